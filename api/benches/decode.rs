@@ -0,0 +1,132 @@
+//! Throughput of decoding messages sent by TWS, from the read buffer to the
+//! parsed frame.
+//!
+//! ```text
+//! cargo bench --bench decode
+//! ```
+// `criterion::black_box` forgets its argument, the frames have to be dropped
+use std::hint::black_box;
+
+use bytes::BytesMut;
+use chrono::{TimeZone, Utc};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use ibkr_rust_api::{codec::{ClientCodec, ServerCodec},
+                    contract::{Contract, SecType},
+                    order::{Action,
+                            Order,
+                            OrderInformation,
+                            OrderState,
+                            OrderStatus,
+                            TimeInForce},
+                    ticker::{Tick,
+                             TickAttribLast,
+                             TickAttribute,
+                             TickByTickAllLast,
+                             TickByTickType,
+                             TickPrice,
+                             TickType},
+                    IBFrame,
+                    ServerVersion};
+use rust_decimal_macros::dec;
+use tokio_util::codec::{Decoder, Encoder};
+
+const SERVER_VERSION: ServerVersion = 176;
+/// The number of messages decoded per iteration.
+const MESSAGES: usize = 1000;
+
+fn contract() -> Contract {
+    Contract {
+        con_id: Some(265_598),
+        symbol: "AAPL".to_string(),
+        sec_type: SecType::Stock,
+        exchange: Some("SMART".to_string()),
+        primary_exchange: Some("NASDAQ".to_string()),
+        currency: "USD".to_string(),
+        local_symbol: Some("AAPL".to_string()),
+        trading_class: Some("NMS".to_string()),
+        ..Default::default()
+    }
+}
+
+fn tick_by_tick() -> IBFrame {
+    IBFrame::Tick(Tick::TickByTickAllLast(TickByTickAllLast {
+        id:                 42,
+        tick_type:          TickByTickType::AllLast,
+        time:               Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+        price:              189.37,
+        size:               100.0,
+        tick_attrib_last:   TickAttribLast::new(false, true),
+        exchange:           "NASDAQ".to_string(),
+        special_conditions: "T I".to_string(),
+    }))
+}
+
+fn tick_price() -> IBFrame {
+    IBFrame::Tick(Tick::Price(TickPrice {
+        id:         42,
+        kind:       TickType::Bid,
+        price:      189.36,
+        size:       Some(300.0),
+        attributes: TickAttribute::new(true, false, false),
+    }))
+}
+
+fn open_order() -> IBFrame {
+    let mut order = Order::limit(
+        contract(),
+        Action::Buy,
+        dec!(100),
+        dec!(189.25),
+        TimeInForce::GoodTillCancel,
+    );
+    order.order_id = Some(17);
+    order.account = Some("DU1234567".to_string());
+    order.order_ref = Some("bench".to_string());
+    IBFrame::OpenOrder(OrderInformation {
+        order,
+        order_state: OrderState {
+            status: OrderStatus::Submitted,
+            perm_id: 1_234_567_890,
+            ..Default::default()
+        },
+    })
+}
+
+/// `MESSAGES` copies of the message of `frame` as received from TWS.
+fn messages(frame: fn() -> IBFrame) -> BytesMut {
+    let mut server = ServerCodec::after_handshake(SERVER_VERSION);
+    let mut buf = BytesMut::new();
+    for _ in 0..MESSAGES {
+        server.encode(frame(), &mut buf).unwrap();
+    }
+    buf
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(MESSAGES as u64));
+    let frames: [(&str, fn() -> IBFrame); 3] = [
+        ("TickByTick", tick_by_tick),
+        ("TickPrice", tick_price),
+        ("OpenOrder", open_order),
+    ];
+    for (name, frame) in frames {
+        let messages = messages(frame);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut client = ClientCodec::new(SERVER_VERSION);
+                let mut buf = messages.clone();
+                let mut decoded = 0;
+                while let Some(frame) = client.decode(&mut buf).unwrap() {
+                    black_box(frame);
+                    decoded += 1;
+                }
+                assert_eq!(MESSAGES, decoded);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -1,12 +1,14 @@
-use std::{fmt, str::Split};
+use std::fmt;
 
+use crossbeam::channel::{self, unbounded, Receiver};
 use rust_decimal::prelude::*;
 use tokio::sync::watch;
 
 use crate::{account_summary_tags::AccountValueKey,
             contract::Contract,
-            ib_frame::{ParseError, ParseIbkrFrame, ParseResult},
-            prelude::{ib_message::{decode, Decodable},
+            ib_frame::{EncodeIbkrFrame, ParseError, ParseIbkrFrame, ParseResult},
+            prelude::{constants::{MIN_SERVER_VER_REALIZED_PNL, MIN_SERVER_VER_UNREALIZED_PNL},
+                      ib_message::{decode, decode_required, Decodable, Encodable, Fields},
                       Incoming,
                       ParseEnumError},
             AccountCode,
@@ -26,7 +28,7 @@ impl ParseIbkrFrame for AccountData {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -36,30 +38,30 @@ impl ParseIbkrFrame for AccountData {
                 it.next(); // skip version
                 tracing::debug!("getting account values");
                 let req_id = decode(it)?;
-                let account = decode(it)?.unwrap();
-                let key = &decode::<String>(it)?.unwrap();
+                let account = decode_required(it)?;
+                let key = &decode_required::<String>(it)?;
                 let key = AccountValueKey::from_str(key)
                     .unwrap_or_else(|_| AccountValueKey::Unknown(key.to_string()));
                 Ok(Self {
                     req_id,
                     account,
                     key,
-                    value: decode(it)?.unwrap(),
+                    value: decode_required(it)?,
                     currency: decode(it)?.unwrap_or_default(),
                 })
             },
             Incoming::AcctValue => {
                 it.next(); // skip version
                 tracing::debug!("getting account values");
-                let key = &decode::<String>(it)?.unwrap();
+                let key = &decode_required::<String>(it)?;
                 let key = AccountValueKey::from_str(key)
                     .unwrap_or_else(|_| AccountValueKey::Unknown(key.to_string()));
                 Ok(Self {
                     req_id: None,
                     key,
-                    value: decode(it)?.unwrap(),
+                    value: decode_required(it)?,
                     currency: decode(it)?.unwrap_or_default(),
-                    account: decode(it)?.unwrap(),
+                    account: decode_required(it)?,
                 })
             },
             _ => Err(ParseError::Incomplete),
@@ -67,6 +69,30 @@ impl ParseIbkrFrame for AccountData {
     }
 }
 
+impl EncodeIbkrFrame for AccountData {
+    fn encode_frame(&self, msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = String::new();
+        match msg_id {
+            Incoming::AccountSummary => {
+                code.push_str("1\0"); // version
+                code.push_str(&self.req_id.encode());
+                code.push_str(&self.account.encode());
+                code.push_str(&self.key.encode());
+                code.push_str(&self.value.encode());
+                code.push_str(&self.currency.encode());
+            },
+            _ => {
+                code.push_str("2\0"); // version
+                code.push_str(&self.key.encode());
+                code.push_str(&self.value.encode());
+                code.push_str(&self.currency.encode());
+                code.push_str(&self.account.encode());
+            },
+        }
+        code
+    }
+}
+
 pub type AccountLastUpdate = TimeStamp;
 
 #[derive(Debug, Clone)]
@@ -112,7 +138,7 @@ pub struct AccountPropertyKey {
 
 type Updating<T> = watch::Receiver<Option<T>>;
 type Sender<T> = watch::Sender<Option<T>>;
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Position {
     pub contract:       Contract,
     pub position:       Option<Decimal>,
@@ -121,6 +147,290 @@ pub struct Position {
     pub average_cost:   Option<Decimal>,
     pub unrealized_pnl: Option<Decimal>,
     pub realized_pnl:   Option<Decimal>,
+    pub account:        Option<AccountCode>,
+    pub model_code:     Option<String>,
+}
+
+impl ParseIbkrFrame for Position {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        match msg_id {
+            Incoming::PortfolioValue => {
+                it.next(); // skip version
+                Ok(Self {
+                    contract:       Contract::try_parse_frame(msg_id, server_version, it)?,
+                    position:       decode(it)?,
+                    market_price:   decode(it)?,
+                    market_value:   decode(it)?,
+                    average_cost:   decode(it)?,
+                    unrealized_pnl: decode(it)?,
+                    realized_pnl:   decode(it)?,
+                    account:        decode(it)?,
+                    model_code:     None,
+                })
+            },
+            Incoming::PositionData => {
+                it.next(); // skip version
+                let account = decode(it)?;
+                Ok(Self {
+                    contract: Contract::try_parse_frame(msg_id, server_version, it)?,
+                    position: decode(it)?,
+                    average_cost: decode(it)?,
+                    account,
+                    model_code: None,
+                    market_price: None,
+                    market_value: None,
+                    unrealized_pnl: None,
+                    realized_pnl: None,
+                })
+            },
+            Incoming::PositionMulti => {
+                // version and req_id are decoded by the caller
+                let account = decode(it)?;
+                Ok(Self {
+                    contract: Contract::try_parse_frame(msg_id, server_version, it)?,
+                    position: decode(it)?,
+                    average_cost: decode(it)?,
+                    model_code: decode(it)?,
+                    account,
+                    market_price: None,
+                    market_value: None,
+                    unrealized_pnl: None,
+                    realized_pnl: None,
+                })
+            },
+            _ => {
+                tracing::error!("Unexpected Message (Position): {msg_id:?}");
+                Err(ParseError::UnexpectedMessage)
+            },
+        }
+    }
+}
+
+impl EncodeIbkrFrame for Position {
+    fn encode_frame(&self, msg_id: Incoming, server_version: ServerVersion) -> String {
+        let mut code = String::new();
+        match msg_id {
+            Incoming::PortfolioValue => {
+                code.push_str("8\0"); // version
+                code.push_str(&self.contract.encode_frame(msg_id, server_version));
+                code.push_str(&self.position.encode());
+                code.push_str(&self.market_price.encode());
+                code.push_str(&self.market_value.encode());
+                code.push_str(&self.average_cost.encode());
+                code.push_str(&self.unrealized_pnl.encode());
+                code.push_str(&self.realized_pnl.encode());
+                code.push_str(&self.account.encode());
+            },
+            Incoming::PositionMulti => {
+                // version and req_id are encoded by the caller
+                code.push_str(&self.account.encode());
+                code.push_str(&self.contract.encode_frame(msg_id, server_version));
+                code.push_str(&self.position.encode());
+                code.push_str(&self.average_cost.encode());
+                code.push_str(&self.model_code.encode());
+            },
+            _ => {
+                code.push_str("3\0"); // version
+                code.push_str(&self.account.encode());
+                code.push_str(&self.contract.encode_frame(msg_id, server_version));
+                code.push_str(&self.position.encode());
+                code.push_str(&self.average_cost.encode());
+            },
+        }
+        code
+    }
+}
+
+/// Update received after subscribing to positions.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum PositionUpdate {
+    /// A single position. `req_id` is only set for positions requested with
+    /// `request_positions_multi`.
+    Position {
+        req_id:   Option<RequestId>,
+        position: Position,
+    },
+    /// All positions of the initial snapshot have been received.
+    End { req_id: Option<RequestId> },
+}
+
+#[derive(Debug, Clone)]
+pub struct PositionTracker {
+    /// positions requested with `request_positions` or
+    /// `request_positions_multi`
+    pub positions: Receiver<PositionUpdate>,
+    /// portfolio updates received after calling `request_account_updates`
+    pub portfolio: Receiver<Position>,
+}
+#[derive(Debug)]
+pub(crate) struct PositionTrackerSender {
+    pub positions_tx: channel::Sender<PositionUpdate>,
+    pub portfolio_tx: channel::Sender<Position>,
+}
+impl PositionTracker {
+    pub(crate) fn new() -> (PositionTrackerSender, Self) {
+        let (positions_tx, positions) = unbounded();
+        let (portfolio_tx, portfolio) = unbounded();
+        (
+            PositionTrackerSender {
+                positions_tx,
+                portfolio_tx,
+            },
+            PositionTracker {
+                positions,
+                portfolio,
+            },
+        )
+    }
+}
+
+/// Daily, unrealized and realized profit and loss of an account and/or model.
+#[derive(Debug, Clone, Copy)]
+pub struct PnL {
+    pub req_id:     RequestId,
+    pub daily:      Option<Decimal>,
+    pub unrealized: Option<Decimal>,
+    pub realized:   Option<Decimal>,
+}
+
+impl ParseIbkrFrame for PnL {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::PnL) {
+            tracing::error!("Unexpected Message (PnL): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        let server_version = server_version.unwrap_or_default();
+        Ok(Self {
+            req_id:     decode_required(it)?,
+            daily:      decode(it)?,
+            unrealized: if server_version >= MIN_SERVER_VER_UNREALIZED_PNL {
+                decode(it)?
+            } else {
+                None
+            },
+            realized:   if server_version >= MIN_SERVER_VER_REALIZED_PNL {
+                decode(it)?
+            } else {
+                None
+            },
+        })
+    }
+}
+
+impl EncodeIbkrFrame for PnL {
+    fn encode_frame(&self, _msg_id: Incoming, server_version: ServerVersion) -> String {
+        let mut code = self.req_id.encode();
+        code.push_str(&self.daily.encode());
+        if server_version >= MIN_SERVER_VER_UNREALIZED_PNL {
+            code.push_str(&self.unrealized.encode());
+        }
+        if server_version >= MIN_SERVER_VER_REALIZED_PNL {
+            code.push_str(&self.realized.encode());
+        }
+        code
+    }
+}
+
+/// Profit and loss of a single position.
+#[derive(Debug, Clone, Copy)]
+pub struct PnLSingle {
+    pub req_id:     RequestId,
+    pub position:   Option<Decimal>,
+    pub daily:      Option<Decimal>,
+    pub unrealized: Option<Decimal>,
+    pub realized:   Option<Decimal>,
+    pub value:      Option<Decimal>,
+}
+
+impl ParseIbkrFrame for PnLSingle {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::PnlSingle) {
+            tracing::error!("Unexpected Message (PnlSingle): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        let server_version = server_version.unwrap_or_default();
+        Ok(Self {
+            req_id:     decode_required(it)?,
+            position:   decode(it)?,
+            daily:      decode(it)?,
+            unrealized: if server_version >= MIN_SERVER_VER_UNREALIZED_PNL {
+                decode(it)?
+            } else {
+                None
+            },
+            realized:   if server_version >= MIN_SERVER_VER_REALIZED_PNL {
+                decode(it)?
+            } else {
+                None
+            },
+            value:      decode(it)?,
+        })
+    }
+}
+
+impl EncodeIbkrFrame for PnLSingle {
+    fn encode_frame(&self, _msg_id: Incoming, server_version: ServerVersion) -> String {
+        let mut code = self.req_id.encode();
+        code.push_str(&self.position.encode());
+        code.push_str(&self.daily.encode());
+        if server_version >= MIN_SERVER_VER_UNREALIZED_PNL {
+            code.push_str(&self.unrealized.encode());
+        }
+        if server_version >= MIN_SERVER_VER_REALIZED_PNL {
+            code.push_str(&self.realized.encode());
+        }
+        code.push_str(&self.value.encode());
+        code
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PnLTracker {
+    /// updates requested with `request_pnl` without a live subscription
+    pub pnl:        Receiver<PnL>,
+    /// updates requested with `request_pnl_single` without a live
+    /// subscription
+    pub pnl_single: Receiver<PnLSingle>,
+}
+#[derive(Debug)]
+pub(crate) struct PnLTrackerSender {
+    pub pnl_tx:        channel::Sender<PnL>,
+    pub pnl_single_tx: channel::Sender<PnLSingle>,
+}
+impl PnLTracker {
+    pub(crate) fn new() -> (PnLTrackerSender, Self) {
+        let (pnl_tx, pnl) = unbounded();
+        let (pnl_single_tx, pnl_single) = unbounded();
+        (
+            PnLTrackerSender {
+                pnl_tx,
+                pnl_single_tx,
+            },
+            PnLTracker { pnl, pnl_single },
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
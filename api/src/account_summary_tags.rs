@@ -1,5 +1,7 @@
 //! Account summary tags
 use strum_macros::{Display, EnumString};
+
+use crate::utils::ib_message::Encodable;
 //==================================================================================================
 /// AccountType — Identifies the IB account structure
 /// NetLiquidation — The basis for determining the price of the assets in your
@@ -411,3 +413,12 @@ pub enum AccountValueKey {
     ///
     Unknown(String),
 }
+
+impl Encodable for AccountValueKey {
+    fn encode(&self) -> String {
+        match self {
+            AccountValueKey::Unknown(key) => key.encode(),
+            key => key.to_string() + "\0",
+        }
+    }
+}
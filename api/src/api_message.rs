@@ -1,6 +1,15 @@
-use chrono::{DateTime, Utc};
+use std::{convert::TryFrom, fmt};
 
-use crate::RequestId;
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::{enums::{constants::{MIN_SERVER_VER_ADVANCED_ORDER_REJECT,
+                                MIN_SERVER_VER_ERROR_TIME},
+                    Incoming},
+            ib_frame::{EncodeIbkrFrame, ParseError, ParseIbkrFrame, ParseResult},
+            utils::ib_message::{decode, Encodable, Fields},
+            RequestId,
+            ServerVersion,
+            TimeStamp};
 
 // #[derive(Clone, Debug)]
 // pub struct TwsApiMessage {
@@ -38,16 +47,9 @@ pub enum TwsApiMessage {
     /// although execution reports and simulated orders will be delayed
     /// until the reset is complete. It is not recommended to operate during
     /// the scheduled reset times.
-    SystemMessage {
-        req_id:  Option<RequestId>,
-        status:  i32,
-        message: Option<String>,
-    },
-    WarningMessage {
-        req_id:  Option<RequestId>,
-        status:  i32,
-        message: Option<String>,
-    },
+    SystemMessage(TwsMessage),
+    /// Warnings and notifications, e.g. about the status of the data farms.
+    WarningMessage(TwsMessage),
 
     /// Client errors are those occurring purely on the TWS API client code and
     /// as such they are never sent by the TWS. They are mostly errors
@@ -56,21 +58,307 @@ pub enum TwsApiMessage {
     /// only a small list of these errors is documented below. To see all
     /// available errors of this type please refer to the
     /// IBApi.EClientErrors class.
-    ClientError {
-        req_id:  Option<RequestId>,
-        status:  i32,
-        message: Option<String>,
-    },
-
-    TwsError {
-        req_id:  Option<RequestId>,
-        status:  i32,
-        message: Option<String>,
-    },
+    ClientError(TwsMessage),
+
+    /// Errors caused by a request, e.g. a rejected order or an unknown
+    /// contract.
+    TwsError(TwsMessage),
 
     ServerTime(DateTime<Utc>),
 }
 
+impl From<TwsMessage> for TwsApiMessage {
+    /// Classifies the message by its code.
+    fn from(message: TwsMessage) -> Self {
+        match message.code.code() {
+            1100..=1300 => TwsApiMessage::SystemMessage(message),
+            501..=599 => TwsApiMessage::ClientError(message),
+            _ if message.severity() <= Severity::Warning => TwsApiMessage::WarningMessage(message),
+            _ => TwsApiMessage::TwsError(message),
+        }
+    }
+}
+
+/// An error or notification message sent by TWS.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TwsMessage {
+    /// the id of the request causing the message, `None` for messages which
+    /// are not related to a request
+    pub req_id:                     Option<RequestId>,
+    pub code:                       TwsErrorCode,
+    pub message:                    Option<String>,
+    /// JSON encoded reject reason of an order, sent by servers with version
+    /// 166 and later
+    pub advanced_order_reject_json: Option<String>,
+    /// the time of the error, sent by servers with version 194 and later
+    pub error_time:                 Option<TimeStamp>,
+}
+
+impl TwsMessage {
+    pub const fn severity(&self) -> Severity { self.code.severity() }
+}
+
+impl fmt::Display for TwsMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "code {}", self.code.code())?;
+        if let Some(req_id) = self.req_id {
+            write!(f, " (request {req_id})")?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ParseIbkrFrame for TwsMessage {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::ErrMsg) {
+            tracing::error!("Unexpected Message (ErrMsg): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        let server_version = server_version.unwrap_or_default();
+        if server_version < MIN_SERVER_VER_ERROR_TIME {
+            it.next(); // skip version
+        }
+        let req_id: i32 = decode(it)?.unwrap_or(-1);
+        let code: i32 = decode(it)?.unwrap_or_default();
+        let message = decode(it)?;
+        let advanced_order_reject_json = if server_version >= MIN_SERVER_VER_ADVANCED_ORDER_REJECT {
+            decode(it)?
+        } else {
+            None
+        };
+        let error_time = if server_version >= MIN_SERVER_VER_ERROR_TIME {
+            decode::<i64>(it)?.and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+        } else {
+            None
+        };
+        Ok(Self {
+            req_id: usize::try_from(req_id).ok(),
+            code: code.into(),
+            message,
+            advanced_order_reject_json,
+            error_time,
+        })
+    }
+}
+
+impl EncodeIbkrFrame for TwsMessage {
+    fn encode_frame(&self, _msg_id: Incoming, server_version: ServerVersion) -> String {
+        let mut code = String::new();
+        if server_version < MIN_SERVER_VER_ERROR_TIME {
+            code.push_str("2\0"); // version
+        }
+        match self.req_id {
+            Some(req_id) => code.push_str(&req_id.encode()),
+            None => code.push_str("-1\0"),
+        }
+        code.push_str(&self.code.code().encode());
+        code.push_str(&self.message.encode());
+        if server_version >= MIN_SERVER_VER_ADVANCED_ORDER_REJECT {
+            code.push_str(&self.advanced_order_reject_json.encode());
+        }
+        if server_version >= MIN_SERVER_VER_ERROR_TIME {
+            code.push_str(&self.error_time.map(|time| time.timestamp_millis()).encode());
+        }
+        code
+    }
+}
+
+/// Severity of a message sent by TWS, ordered from `Info` to `Critical`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum Severity {
+    /// notification, no action needed
+    Info,
+    /// the request was processed, but the result may not be as expected
+    Warning,
+    /// the request failed
+    Error,
+    /// the connection is unusable until the problem is resolved
+    Critical,
+}
+
+macro_rules! tws_error_codes {
+    (
+        $(
+            $(#[$docs:meta])*
+            ($code:expr, $name:ident, $severity:ident);
+        )+
+    ) => {
+        /// Message codes sent by TWS, see
+        /// <https://interactivebrokers.github.io/tws-api/message_codes.html>.
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        pub enum TwsErrorCode {
+            $(
+                $(#[$docs])*
+                $name,
+            )+
+            /// a code without a dedicated variant
+            Other(i32),
+        }
+
+        impl TwsErrorCode {
+            /// Returns the numeric code.
+            pub const fn code(self) -> i32 {
+                match self {
+                    $(TwsErrorCode::$name => $code,)+
+                    TwsErrorCode::Other(code) => code,
+                }
+            }
+
+            /// Returns the severity of the code. Undocumented codes are rated
+            /// by the range they belong to.
+            pub const fn severity(self) -> Severity {
+                match self {
+                    $(TwsErrorCode::$name => Severity::$severity,)+
+                    TwsErrorCode::Other(1100..=1300) => Severity::Critical,
+                    TwsErrorCode::Other(2100..=2199) => Severity::Warning,
+                    TwsErrorCode::Other(_) => Severity::Error,
+                }
+            }
+        }
+
+        impl From<i32> for TwsErrorCode {
+            fn from(code: i32) -> Self {
+                match code {
+                    $($code => TwsErrorCode::$name,)+
+                    code => TwsErrorCode::Other(code),
+                }
+            }
+        }
+    };
+}
+
+tws_error_codes! {
+    /// Max rate of messages per second has been exceeded.
+    (100, MaxMessageRateExceeded, Error);
+    /// Max number of tickers has been reached.
+    (101, MaxTickersReached, Error);
+    /// Duplicate ticker id.
+    (102, DuplicateTickerId, Error);
+    /// Duplicate order id.
+    (103, DuplicateOrderId, Error);
+    /// Can't modify a filled order.
+    (104, CannotModifyFilledOrder, Error);
+    /// Order being modified does not match the original order.
+    (105, OrderModificationMismatch, Error);
+    /// The price does not conform to the minimum price variation.
+    (110, PriceDoesNotConformToMinTick, Error);
+    /// Historical market data service error, e.g. a pacing violation.
+    (162, HistoricalDataServiceError, Error);
+    /// Historical market data service query message.
+    (165, HistoricalDataServiceQuery, Info);
+    /// No security definition has been found for the request.
+    (200, NoSecurityDefinition, Error);
+    /// Order rejected.
+    (201, OrderRejected, Error);
+    /// Order cancelled.
+    (202, OrderCancelled, Info);
+    /// The security is not available or allowed for this account.
+    (203, SecurityNotAllowed, Error);
+    /// Can't find EId with the ticker id.
+    (300, UnknownTickerId, Error);
+    /// Market depth data has been reset.
+    (317, MarketDepthReset, Warning);
+    /// Error validating the request.
+    (321, RequestValidationError, Error);
+    /// Error processing the request.
+    (322, RequestProcessingError, Error);
+    /// The requested market data is not subscribed.
+    (354, MarketDataNotSubscribed, Error);
+    /// No historical data query found for the ticker id.
+    (366, NoHistoricalDataQuery, Error);
+    /// Already connected.
+    (501, AlreadyConnected, Error);
+    /// Couldn't connect to TWS.
+    (502, ConnectFail, Critical);
+    /// The TWS is out of date and must be upgraded.
+    (503, UpdateTws, Critical);
+    /// Not connected.
+    (504, NotConnected, Error);
+    /// Fatal error: unknown message id.
+    (505, UnknownMessageId, Critical);
+    /// Unsupported version.
+    (506, UnsupportedVersion, Critical);
+    /// Bad message length.
+    (507, BadMessageLength, Critical);
+    /// Connectivity between IB and TWS has been lost.
+    (1100, ConnectivityLost, Critical);
+    /// Connectivity between IB and TWS has been restored, data lost. Market
+    /// and account data subscriptions have to be re-submitted.
+    (1101, ConnectivityRestoredDataLost, Warning);
+    /// Connectivity between IB and TWS has been restored, data maintained.
+    (1102, ConnectivityRestored, Info);
+    /// The socket port has been reset and the connection is dropped.
+    (1300, SocketPortReset, Critical);
+    /// New account data requested, the API client has been unsubscribed from
+    /// the account data.
+    (2100, AccountDataUnsubscribed, Warning);
+    /// Unable to subscribe to the account as other clients are subscribed to
+    /// a different account.
+    (2101, AccountSubscriptionRejected, Warning);
+    /// Unable to modify the order as it is being modified by another source.
+    (2102, OrderBeingModified, Warning);
+    /// A market data farm connection is broken.
+    (2103, MarketDataFarmBroken, Warning);
+    /// A market data farm connection is OK.
+    (2104, MarketDataFarmOk, Info);
+    /// A historical data farm connection is broken.
+    (2105, HistoricalDataFarmBroken, Warning);
+    /// A historical data farm connection is OK.
+    (2106, HistoricalDataFarmOk, Info);
+    /// A historical data farm connection is inactive but available upon
+    /// demand.
+    (2107, HistoricalDataFarmInactive, Info);
+    /// A market data farm connection is inactive but available upon demand.
+    (2108, MarketDataFarmInactive, Info);
+    /// Order event warning: the "Outside Regular Trading Hours" attribute is
+    /// ignored.
+    (2109, OrderOutsideRth, Warning);
+    /// Connectivity between TWS and the server is broken.
+    (2110, TwsServerConnectivityBroken, Warning);
+    /// A market data farm is connecting.
+    (2119, MarketDataFarmConnecting, Info);
+    /// The order crosses an existing order of the account.
+    (2137, CrossSideWarning, Warning);
+    /// A security definition data farm connection is broken.
+    (2157, SecDefDataFarmBroken, Warning);
+    /// A security definition data farm connection is OK.
+    (2158, SecDefDataFarmOk, Info);
+    /// Part of the requested market data is not subscribed.
+    (10090, MarketDataPartiallySubscribed, Warning);
+    /// The requested market data is not subscribed, delayed market data is
+    /// displayed.
+    (10167, DelayedMarketData, Warning);
+    /// Delayed market data is not enabled.
+    (10168, DelayedMarketDataNotEnabled, Error);
+    /// Failed to request live updates (disconnected).
+    (10182, LiveUpdatesFailed, Error);
+    /// No market data during a competing live session.
+    (10197, CompetingLiveSession, Warning);
+}
+
+impl TwsErrorCode {
+    /// Returns `true` if TWS sends the code with the id of an order instead
+    /// of a request id, e.g. for rejected or cancelled orders. Order ids and
+    /// request ids are unrelated, such messages must not be routed to the
+    /// request with the same id.
+    pub const fn refers_to_order(self) -> bool {
+        matches!(
+            self.code(),
+            103..=111 | 113..=117 | 135 | 136 | 161 | 201 | 202 | 399 | 404 | 10147..=10149
+        )
+    }
+}
+
 // /// Remember that the TWS API simply connects to a running TWS/IB Gateway
 // /// which most of times will be running on your local network if not in the
 // /// same host as the client application. It is your responsibility to
@@ -544,3 +832,60 @@ pub enum TwsApiMessage {
 // f.write_str("invalid status code") } }
 
 // impl Error for InvalidStatusCode {}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn message(code: i32) -> TwsMessage {
+        TwsMessage {
+            req_id:                     None,
+            code:                       code.into(),
+            message:                    None,
+            advanced_order_reject_json: None,
+            error_time:                 None,
+        }
+    }
+
+    #[test]
+    fn classify_error_codes() {
+        assert_eq!(TwsErrorCode::ConnectivityLost, TwsErrorCode::from(1100));
+        assert_eq!(2158, TwsErrorCode::SecDefDataFarmOk.code());
+        assert_eq!(TwsErrorCode::Other(2199), TwsErrorCode::from(2199));
+        assert_eq!(Severity::Warning, TwsErrorCode::from(2199).severity());
+        assert_eq!(Severity::Error, TwsErrorCode::from(42).severity());
+        assert!(TwsErrorCode::OrderRejected.refers_to_order());
+        assert!(TwsErrorCode::from(399).refers_to_order());
+        assert!(!TwsErrorCode::NoSecurityDefinition.refers_to_order());
+
+        assert!(matches!(
+            TwsApiMessage::from(message(1100)),
+            TwsApiMessage::SystemMessage(TwsMessage {
+                code: TwsErrorCode::ConnectivityLost,
+                ..
+            })
+        ));
+        assert!(matches!(
+            TwsApiMessage::from(message(2104)),
+            TwsApiMessage::WarningMessage(_)
+        ));
+        assert!(matches!(
+            TwsApiMessage::from(message(10167)),
+            TwsApiMessage::WarningMessage(_)
+        ));
+        assert!(matches!(
+            TwsApiMessage::from(message(504)),
+            TwsApiMessage::ClientError(_)
+        ));
+        assert!(matches!(
+            TwsApiMessage::from(message(200)),
+            TwsApiMessage::TwsError(_)
+        ));
+        assert!(matches!(
+            TwsApiMessage::from(message(162)),
+            TwsApiMessage::TwsError(_)
+        ));
+    }
+}
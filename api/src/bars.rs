@@ -1,13 +1,13 @@
 use std::{convert::TryFrom,
           fmt::{Display, Formatter},
-          str::{FromStr, Split}};
+          str::FromStr};
 
-use chrono::{NaiveDate, Utc};
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
 use chrono_tz::Tz;
 
-use crate::{ib_frame::{ParseError, ParseIbkrFrame, ParseResult},
+use crate::{ib_frame::{EncodeIbkrFrame, ParseError, ParseIbkrFrame, ParseResult},
             prelude::{dateparser::Parse,
-                      ib_message::{decode, Decodable, Encodable},
+                      ib_message::{decode_required, preallocate, Decodable, Encodable, Fields},
                       Incoming,
                       ParseEnumError},
             MarketDataValueType,
@@ -47,7 +47,7 @@ impl ParseIbkrFrame for HistoricalSchedule {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -55,37 +55,38 @@ impl ParseIbkrFrame for HistoricalSchedule {
         if !matches!(msg_id, Incoming::HistoricalSchedule) {
             return Err(ParseError::UnexpectedMessage);
         }
-        let id = decode(it)?.unwrap();
-        let start: String = decode(it)?.unwrap();
-        let end: String = decode(it)?.unwrap();
-        let tz: String = decode(it)?.unwrap();
+        let id = decode_required(it)?;
+        let start: String = decode_required(it)?;
+        let end: String = decode_required(it)?;
+        let tz: String = decode_required(it)?;
         let time_zone =
             Tz::from_str(tz.trim()).map_err(|_| ParseError::UnexpectedVariant(tz.clone()))?;
-        let n_sessions = decode(it)?.unwrap();
+        let n_sessions = decode_required(it)?;
 
-        let parse = Parse::new(&Utc, Utc::now().time());
+        let parser = Parse::new(&Utc, Utc::now().time());
+        // the times are local to the time zone of the schedule
+        let parse = |time: &str| {
+            NaiveDateTime::parse_from_str(time, "%Y%m%d-%H:%M:%S")
+                .ok()
+                .and_then(|time| time_zone.from_local_datetime(&time).earliest())
+                .map(|time| time.with_timezone(&Utc))
+                .map_or_else(|| parser.parse(&format!("{time} {tz}")), Ok)
+                .map_err(|_| ParseError::UnexpectedVariant(tz.clone()))
+        };
 
-        let start_date_time = parse
-            .parse(&format!("{start} {tz}"))
-            .map_err(|_| ParseError::UnexpectedVariant(tz.clone()))?;
-        let end_date_time = parse
-            .parse(&format!("{end} {tz}"))
-            .map_err(|_| ParseError::UnexpectedVariant(tz.clone()))?;
-        let mut sessions = Vec::with_capacity(n_sessions);
+        let start_date_time = parse(&start)?;
+        let end_date_time = parse(&end)?;
+        let mut sessions = preallocate(n_sessions);
         for _i in 0..n_sessions {
-            let start_session: String = decode(it)?.unwrap();
-            let end_session: String = decode(it)?.unwrap();
+            let start_session: String = decode_required(it)?;
+            let end_session: String = decode_required(it)?;
             tracing::log::error!("start: {start_session:#?}");
-            let start_date_time_session = parse
-                .parse(&format!("{start_session} {tz}"))
-                .map_err(|_| ParseError::UnexpectedVariant(tz.clone()))?;
-            let end_date_time_session = parse
-                .parse(&format!("{end_session} {tz}"))
-                .map_err(|_| ParseError::UnexpectedVariant(tz.clone()))?;
+            let start_date_time_session = parse(&start_session)?;
+            let end_date_time_session = parse(&end_session)?;
             let session = HistoricalSession {
                 start_date_time: start_date_time_session,
                 end_date_time:   end_date_time_session,
-                ref_date:        decode(it)?.unwrap(),
+                ref_date:        decode_required(it)?,
             };
             sessions.push(session);
         }
@@ -99,6 +100,28 @@ impl ParseIbkrFrame for HistoricalSchedule {
     }
 }
 
+impl EncodeIbkrFrame for HistoricalSchedule {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        // the times are sent in the time zone of the schedule
+        let local = |time: &TimeStamp| {
+            time.with_timezone(&self.time_zone)
+                .format("%Y%m%d-%H:%M:%S")
+                .to_string()
+        };
+        let mut code = self.id.encode();
+        code.push_str(&local(&self.start_date_time).encode());
+        code.push_str(&local(&self.end_date_time).encode());
+        code.push_str(&self.time_zone.name().encode());
+        code.push_str(&self.sessions.len().encode());
+        for session in &self.sessions {
+            code.push_str(&local(&session.start_date_time).encode());
+            code.push_str(&local(&session.end_date_time).encode());
+            code.push_str(&session.ref_date.encode());
+        }
+        code
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct HistoricalSession {
     pub start_date_time: TimeStamp,
@@ -109,7 +132,7 @@ impl ParseIbkrFrame for HistoricalSession {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -118,9 +141,9 @@ impl ParseIbkrFrame for HistoricalSession {
             return Err(ParseError::UnexpectedMessage);
         }
         Ok(Self {
-            start_date_time: decode(it)?.unwrap(),
-            end_date_time:   decode(it)?.unwrap(),
-            ref_date:        decode(it)?.unwrap(),
+            start_date_time: decode_required(it)?,
+            end_date_time:   decode_required(it)?,
+            ref_date:        decode_required(it)?,
         })
     }
 }
@@ -133,7 +156,7 @@ impl ParseIbkrFrame for RealtimeBar {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -142,23 +165,50 @@ impl ParseIbkrFrame for RealtimeBar {
             return Err(ParseError::UnexpectedMessage);
         }
         it.next(); // skip version
-        let id = decode(it)?.unwrap();
+        let id = decode_required(it)?;
         Ok(Self {
             id,
             data: Bar {
-                t_stamp: decode(it)?.unwrap(),
-                open:    decode(it)?.unwrap(),
-                high:    decode(it)?.unwrap(),
-                low:     decode(it)?.unwrap(),
-                close:   decode(it)?.unwrap(),
-                volume:  decode(it)?.unwrap(),
-                wap:     decode(it)?.unwrap(),
-                count:   decode(it)?.unwrap(),
+                t_stamp: decode_required(it)?,
+                open:    decode_required(it)?,
+                high:    decode_required(it)?,
+                low:     decode_required(it)?,
+                close:   decode_required(it)?,
+                volume:  decode_required(it)?,
+                wap:     decode_required(it)?,
+                count:   decode_required(it)?,
             },
         })
     }
 }
 
+impl Encodable for Bar {
+    fn encode(&self) -> String {
+        let mut code = self.t_stamp.encode();
+        code.push_str(&self.open.encode());
+        code.push_str(&self.high.encode());
+        code.push_str(&self.low.encode());
+        code.push_str(&self.close.encode());
+        code.push_str(&self.volume.encode());
+        code.push_str(&self.wap.encode());
+        code.push_str(&self.count.encode());
+        code
+    }
+}
+
+impl EncodeIbkrFrame for RealtimeBar {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = "3\0".to_string(); // version
+        code.push_str(&self.id.encode());
+        code.push_str(&self.data.encode());
+        code
+    }
+}
+
+/// The bars answering a historical data request.
+///
+/// Requests with `keep_up_to_date` set are updated with series of a single
+/// bar, sent as `Incoming::HistoricalDataUpdate`.
 #[derive(Debug, Clone)]
 pub struct HistoricalBars {
     pub id:   RequestId,
@@ -168,29 +218,58 @@ impl ParseIbkrFrame for HistoricalBars {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
     {
         match msg_id {
-            Incoming::HistoricalDataUpdate | Incoming::HistoricalData => {
-                let id = decode(it)?.unwrap();
-                let start_dt = decode(it)?.unwrap();
-                let end_dt = decode(it)?.unwrap();
-                let n_bars = decode(it)?.unwrap();
+            Incoming::HistoricalDataUpdate => {
+                let id = decode_required(it)?;
+                let count = decode_required(it)?;
+                let t_stamp = decode_required(it)?;
+                let open = decode_required(it)?;
+                let close = decode_required(it)?;
+                let high = decode_required(it)?;
+                let low = decode_required(it)?;
+                let wap = decode_required(it)?;
+                let volume = decode_required(it)?;
+                Ok(Self {
+                    id,
+                    data: BarSeries {
+                        start_dt: t_stamp,
+                        end_dt:   t_stamp,
+                        n_bars:   1,
+                        bars:     vec![Bar {
+                            t_stamp,
+                            open,
+                            high,
+                            low,
+                            close,
+                            wap,
+                            volume,
+                            count,
+                        }],
+                    },
+                })
+            },
+            Incoming::HistoricalData => {
+                let id = decode_required(it)?;
+                let start_dt = decode_required(it)?;
+                let end_dt = decode_required(it)?;
+                let n_bars = decode_required(it)?;
                 let data = {
-                    let mut bar_data = Vec::with_capacity(n_bars);
+                    let mut bar_data = preallocate(n_bars);
                     for _i in 0..n_bars {
                         bar_data.push(Bar {
-                            t_stamp: decode(it)?.unwrap(),
-                            open:    decode(it)?.unwrap(),
-                            high:    decode(it)?.unwrap(),
-                            low:     decode(it)?.unwrap(),
-                            close:   decode(it)?.unwrap(),
-                            volume:  decode(it)?.unwrap(),
-                            wap:     decode(it)?.unwrap(),
-                            count:   decode(it)?.unwrap(),
+                            t_stamp: decode_required(it)?,
+                            open:    decode_required(it)?,
+                            high:    decode_required(it)?,
+                            low:     decode_required(it)?,
+                            close:   decode_required(it)?,
+                            volume:  decode_required(it)?,
+                            wap:     decode_required(it)?,
+                            count:   decode_required(it)?,
                         });
                     }
                     bar_data
@@ -209,6 +288,33 @@ impl ParseIbkrFrame for HistoricalBars {
         }
     }
 }
+impl EncodeIbkrFrame for HistoricalBars {
+    fn encode_frame(&self, msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = self.id.encode();
+        if matches!(msg_id, Incoming::HistoricalDataUpdate) {
+            // an update carries a single bar
+            if let Some(bar) = self.data.bars.first() {
+                code.push_str(&bar.count.encode());
+                code.push_str(&bar.t_stamp.encode());
+                code.push_str(&bar.open.encode());
+                code.push_str(&bar.close.encode());
+                code.push_str(&bar.high.encode());
+                code.push_str(&bar.low.encode());
+                code.push_str(&bar.wap.encode());
+                code.push_str(&bar.volume.encode());
+            }
+            return code;
+        }
+        code.push_str(&self.data.start_dt.encode());
+        code.push_str(&self.data.end_dt.encode());
+        code.push_str(&self.data.bars.len().encode());
+        for bar in &self.data.bars {
+            code.push_str(&bar.encode());
+        }
+        code
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum BarSize {
     _1Secs,
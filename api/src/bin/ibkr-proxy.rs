@@ -0,0 +1,292 @@
+//! A transparent proxy between TWS API clients and TWS / IB Gateway.
+//!
+//! The messages of both directions are forwarded unchanged, decoded with the
+//! parsers of the crate and printed with their name, request id and fields.
+//! Optionally all messages are written to a capture file, which can be read
+//! with `ibkr_rust_api::recorder::ReplayReader`.
+//!
+//! ```text
+//! cargo run --features proxy --bin ibkr-proxy -- \
+//!     --upstream 127.0.0.1:4002 --listen 127.0.0.1:4003 --capture session.rec
+//! ```
+//!
+//! The clients then connect to the `--listen` address instead of TWS.
+use std::{error::Error, fmt::Debug, io, path::PathBuf, str::FromStr};
+
+use bytes::{Buf, BytesMut};
+use chrono::Local;
+use clap::Parser;
+use ibkr_rust_api::{codec::{ClientCodec, ServerCodec},
+                    enums::{Incoming, Outgoing},
+                    recorder::{Direction, Recorder},
+                    RequestId};
+use tokio::{io::{AsyncReadExt, AsyncWriteExt},
+            net::{TcpListener, TcpStream}};
+use tokio_util::codec::Decoder;
+
+/// The prefix sent by clients before the supported versions.
+const API_PREFIX: &[u8] = b"API\0";
+
+#[tokio::main]
+pub async fn main() -> std::result::Result<(), Box<dyn Error>> {
+    let opt = Opt::parse();
+    // messages which can not be decoded are logged as warnings
+    tracing_subscriber::fmt()
+        .with_writer(io::stderr)
+        .with_max_level(match opt.verbose {
+            Some(0) | None => tracing::Level::WARN,
+            Some(1) => tracing::Level::INFO,
+            _ => tracing::Level::DEBUG,
+        })
+        .init();
+
+    let recorder = opt.capture.as_ref().map(Recorder::create).transpose()?;
+    let listener = TcpListener::bind(&opt.listen).await?;
+    tracing::info!(listen = %opt.listen, upstream = %opt.upstream, "waiting for clients");
+    let mut sessions = 0;
+    loop {
+        let (client, peer) = listener.accept().await?;
+        sessions += 1;
+        let mut session = Session::new(sessions, opt.pretty, recorder.clone());
+        let upstream = opt.upstream.clone();
+        tokio::spawn(async move {
+            println!("#{} {peer} connected", session.id);
+            match session.run(client, &upstream).await {
+                Ok(()) => println!("#{} closed", session.id),
+                Err(err) => println!("#{} closed: {err}", session.id),
+            }
+        });
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about = None)]
+struct Opt {
+    /// address the clients connect to
+    #[arg(short, long, default_value = "127.0.0.1:4003")]
+    listen: String,
+
+    /// address of TWS or IB Gateway
+    #[arg(short, long, default_value = "127.0.0.1:4002")]
+    upstream: String,
+
+    /// write the messages of all connections to a capture file
+    #[arg(short, long)]
+    capture: Option<PathBuf>,
+
+    /// print the decoded messages over several lines
+    #[arg(short, long)]
+    pretty: bool,
+
+    /// Verbose logging mode (-v 1, -v 2)
+    #[arg(short, long)]
+    verbose: Option<usize>,
+}
+
+/// A client connection and its connection to TWS.
+#[derive(Debug)]
+struct Session {
+    id:            usize,
+    pretty:        bool,
+    recorder:      Option<Recorder>,
+    // the bytes received from the client and TWS, not yet printed
+    outbound:      BytesMut,
+    inbound:       BytesMut,
+    // decodes the commands of the client
+    server_codec:  ServerCodec,
+    // decodes the frames of TWS
+    client_codec:  ClientCodec,
+    // `true` until the "API\0" prefix was received
+    awaiting_init: bool,
+    // `true` while the first message of the client was not printed
+    init_pending:  bool,
+}
+
+impl Session {
+    fn new(id: usize, pretty: bool, recorder: Option<Recorder>) -> Self {
+        Session {
+            id,
+            pretty,
+            recorder,
+            outbound: BytesMut::new(),
+            inbound: BytesMut::new(),
+            server_codec: ServerCodec::new(0),
+            client_codec: ClientCodec::default(),
+            awaiting_init: true,
+            init_pending: false,
+        }
+    }
+
+    /// Forwards the messages between `client` and TWS at `upstream` until
+    /// either side closes the connection.
+    async fn run(&mut self, client: TcpStream, upstream: &str) -> io::Result<()> {
+        let server = TcpStream::connect(upstream).await?;
+        let (mut client_rx, mut client_tx) = client.into_split();
+        let (mut server_rx, mut server_tx) = server.into_split();
+        let mut from_client = [0; 8192];
+        let mut from_server = [0; 8192];
+        loop {
+            tokio::select! {
+                n = client_rx.read(&mut from_client) => {
+                    let n = n?;
+                    if n == 0 {
+                        return Ok(());
+                    }
+                    server_tx.write_all(&from_client[..n]).await?;
+                    self.outbound.extend_from_slice(&from_client[..n]);
+                    self.print_outbound();
+                },
+                n = server_rx.read(&mut from_server) => {
+                    let n = n?;
+                    if n == 0 {
+                        return Ok(());
+                    }
+                    client_tx.write_all(&from_server[..n]).await?;
+                    self.inbound.extend_from_slice(&from_server[..n]);
+                    self.print_inbound();
+                },
+            }
+            if let Some(recorder) = &self.recorder {
+                // the proxy is usually stopped with Ctrl-C, keep the capture complete
+                if let Err(err) = recorder.flush() {
+                    tracing::warn!(cause = %err, "could not write the capture");
+                }
+            }
+        }
+    }
+
+    /// Prints the complete messages received from the client.
+    fn print_outbound(&mut self) {
+        if self.awaiting_init {
+            if self.outbound.len() < API_PREFIX.len() {
+                return;
+            }
+            if self.outbound.starts_with(API_PREFIX) {
+                self.outbound.advance(API_PREFIX.len());
+                self.record(Direction::Outbound, API_PREFIX);
+                self.init_pending = true;
+            } else {
+                // connected after the handshake, e.g. a replayed session
+                self.server_codec = ServerCodec::after_handshake(0);
+            }
+            self.awaiting_init = false;
+        }
+        while let Some(msg) = next_message(&mut self.outbound) {
+            self.record(Direction::Outbound, &msg);
+            let mut src = BytesMut::new();
+            if self.init_pending {
+                self.init_pending = false;
+                src.extend_from_slice(API_PREFIX);
+            }
+            src.extend_from_slice(&msg);
+            let cmd = self.server_codec.decode(&mut src).ok().flatten();
+            let name = message_name::<Outgoing>(&msg).unwrap_or_else(|| "Handshake".to_string());
+            let req_id = cmd.as_ref().and_then(|cmd| cmd.request_id());
+            self.print(
+                "client -> TWS",
+                &name,
+                req_id,
+                cmd.as_ref().map(|cmd| cmd as _),
+                &msg,
+            );
+        }
+    }
+
+    /// Prints the complete messages received from TWS.
+    fn print_inbound(&mut self) {
+        while let Some(msg) = next_message(&mut self.inbound) {
+            self.record(Direction::Inbound, &msg);
+            let known_version = self.client_codec.server_version();
+            let frame = self.client_codec.decode(&mut msg.clone()).ok().flatten();
+            if let (None, Some(server_version)) =
+                (known_version, self.client_codec.server_version())
+            {
+                // the commands following the handshake depend on the version
+                self.server_codec = ServerCodec::after_handshake(server_version);
+            }
+            let name = message_name::<Incoming>(&msg).unwrap_or_else(|| {
+                frame
+                    .as_ref()
+                    .map_or_else(|| "Unknown".to_string(), ToString::to_string)
+            });
+            let req_id = frame.as_ref().and_then(|frame| frame.request_id());
+            self.print(
+                "TWS -> client",
+                &name,
+                req_id,
+                frame.as_ref().map(|frame| frame as _),
+                &msg,
+            );
+        }
+    }
+
+    fn print(
+        &self,
+        direction: &str,
+        name: &str,
+        req_id: Option<RequestId>,
+        decoded: Option<&dyn Debug>,
+        msg: &[u8],
+    ) {
+        let time = Local::now().format("%H:%M:%S%.3f");
+        let req_id = req_id.map(|id| format!(" req_id={id}")).unwrap_or_default();
+        println!("{time} #{} {direction} {name}{req_id}", self.id);
+        match decoded {
+            Some(decoded) if self.pretty => println!("{decoded:#?}"),
+            Some(decoded) => println!("    {decoded:?}"),
+            None => {
+                // show the fields, the cause was logged by the codec
+                let fields = String::from_utf8_lossy(&msg[4..]);
+                let fields = fields.trim_end_matches('\0').replace('\0', "|");
+                println!("    not decoded: {fields}");
+            },
+        }
+    }
+
+    fn record(&self, direction: Direction, msg: &[u8]) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(direction, [msg]);
+        }
+    }
+}
+
+/// Removes the next length prefixed message from `src`, `None` if it was not
+/// received completely yet.
+fn next_message(src: &mut BytesMut) -> Option<BytesMut> {
+    let len = src.get(..4)?;
+    let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    if src.len() < len + 4 {
+        return None;
+    }
+    Some(src.split_to(len + 4))
+}
+
+/// The name and id of the message `msg`, `None` if the first field is not a
+/// message id.
+fn message_name<T: FromStr + Debug>(msg: &[u8]) -> Option<String> {
+    let id = msg[4..].split(|b| *b == 0).next()?;
+    let id = std::str::from_utf8(id).ok()?;
+    let name = id.parse::<T>().ok()?;
+    Some(format!("{name:?}({id})"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_are_split_once_complete() {
+        let mut src = BytesMut::from(&b"\0\0\0\x0549\01\0\0\0\0"[..]);
+        let msg = next_message(&mut src).unwrap();
+        assert_eq!(&b"\0\0\0\x0549\01\0"[..], &msg[..]);
+        assert_eq!(Some("CurrentTime(49)".to_string()), message_name::<Incoming>(&msg));
+        assert!(next_message(&mut src).is_none());
+        assert_eq!(3, src.len());
+    }
+
+    #[test]
+    fn handshake_has_no_message_id() {
+        let msg = b"\0\0\0\x0av100..163\0";
+        assert_eq!(None, message_name::<Outgoing>(msg));
+    }
+}
@@ -7,9 +7,9 @@ use crate::{
     enums::{GenericTickType, MarketDataType, TickType},
     order::ExecutionFilter,
     prelude::TagValue,
-    AccountCode, ClientId, RequestId, Result,
+    AccountCode, ClientId, OrderId, RequestId, Result,
 };
-use crate::{client::ConnectionStatus, order::OrderTracker};
+use crate::order::OrderTracker;
 
 /// Established connection with a Redis server.
 ///
@@ -35,7 +35,7 @@ impl BlockingClient {
 
     /// Checks connection status
     pub fn is_connected(&self) -> bool {
-        matches!(self.inner.conn_state, ConnectionStatus::CONNECTED)
+        self.inner.is_connected()
     }
 }
 
@@ -141,6 +141,21 @@ impl BlockingClient {
             .block_on(self.inner.request_auto_open_orders(auto_bind))
     }
 
+    /// Cancels an active order placed from the same API client ID.
+    pub fn cancel_order(
+        &mut self,
+        order_id: OrderId,
+        manual_order_cancel_time: Option<String>,
+    ) -> Result<()> {
+        self.rt
+            .block_on(self.inner.cancel_order(order_id, manual_order_cancel_time))
+    }
+
+    /// Cancels all open orders.
+    pub fn global_cancel(&mut self) -> Result<()> {
+        self.rt.block_on(self.inner.global_cancel())
+    }
+
     pub fn request_ids(&mut self) -> Result<()> {
         self.rt.block_on(self.inner.request_ids())
     }
@@ -2,17 +2,27 @@
 //!
 //! Provides an async connect and methods for issuing the supported commands.
 use std::{collections::{HashMap, VecDeque},
-          sync::atomic::AtomicUsize};
+          future::Future,
+          net::SocketAddr,
+          sync::{atomic::{AtomicUsize, Ordering},
+                 Arc,
+                 Mutex,
+                 PoisonError},
+          time::Duration};
 
 use chrono::{DateTime, Utc};
 use crossbeam::channel::{unbounded, Receiver, Sender};
-use tokio::{net::{TcpStream, ToSocketAddrs},
-            sync::{broadcast, mpsc}};
-use tracing::{debug, error, info, instrument};
+use tokio::{net::{lookup_host, TcpStream, ToSocketAddrs},
+            sync::{broadcast, mpsc, watch}};
+use tracing::{debug, error, info, instrument, warn};
 
-use crate::{cmd::*,
+use crate::{account::{PnLTracker, PnLTrackerSender, PositionTracker, PositionTrackerSender},
+            cmd::*,
+            frame::Frame,
             ib_frame::IBFrame,
+            news::{HistoricalNewsUpdate, NewsTracker, NewsTrackerSender},
             order::{OrderTracker, OrderTrackerSender},
+            pacing::{HistoricalPacer, HistoricalPacing, PacingMetrics, RateLimit},
             prelude::*,
             reader::Reader,
             shutdown::Shutdown,
@@ -21,8 +31,32 @@ use crate::{cmd::*,
 mod account;
 mod contract_details;
 mod executions;
+mod fundamental_data;
 mod market_data;
+mod news;
+mod options;
 mod orders;
+mod reconnect;
+mod registry;
+mod scanner;
+
+use options::parse_managed_accounts;
+pub use options::{ConnectOptions, ConnectionInfo};
+pub use reconnect::ReconnectPolicy;
+use reconnect::{LiveSubscriptions, Reconnect, SubscriptionKey};
+pub use registry::Subscription;
+use registry::{Registry, RouteKind};
+
+/// Response to a pending request, `Err` if TWS answered the request with an
+/// error message.
+pub type Response<T> = std::result::Result<T, TwsMessage>;
+
+/// The time the streaming subscriptions were requested, by request id.
+pub type SubscriptionTimes = Arc<Mutex<HashMap<RequestId, DateTime<Utc>>>>;
+
+/// Time to wait for a response before a request fails with
+/// `Error::Timeout`.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
 
 #[derive(Debug)]
 pub struct ResponseWithId<T> {
@@ -31,7 +65,7 @@ pub struct ResponseWithId<T> {
 }
 /// Connection status
 #[repr(i32)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ConnectionStatus {
     DISCONNECTED,
     CONNECTING,
@@ -39,14 +73,17 @@ pub enum ConnectionStatus {
     READY,
     REDIRECT,
 }
+/// Requests without a request id, answered in the order they were sent.
 #[derive(Debug)]
 pub enum Request {
     OrderId {
-        sender: mpsc::Sender<Option<OrderId>>,
+        sender: mpsc::UnboundedSender<Response<OrderId>>,
     },
-    RequestWithId {
-        req_id: RequestId,
-        sender: mpsc::Sender<ResponseWithId<ContractDetails>>,
+    ScannerParameters {
+        sender: mpsc::UnboundedSender<Response<String>>,
+    },
+    NewsProviders {
+        sender: mpsc::UnboundedSender<Response<Vec<NewsProvider>>>,
     },
 }
 
@@ -64,7 +101,11 @@ pub enum Request {
 /// the [`connect`](fn@connect) function.
 ///
 /// Requests are issued using the various methods of `Client`.
-#[derive(Debug)]
+///
+/// A `Client` is a cheap handle to the connection, its clones share the
+/// socket, the request ids and the trackers. Each task, e.g. a strategy, the
+/// UI and the risk checks, can work with its own clone without any locking.
+#[derive(Debug, Clone)]
 pub struct Client {
     /// The TCP connection decorated with the redis protocol encoder / decoder
     /// implemented using a buffered `TcpStream`.
@@ -76,12 +117,22 @@ pub struct Client {
     // connection:      Connection,
     writer: Writer,
     pub client_id:                   ClientId,
-    server_version:                  ServerVersion,
-    pub conn_state:                  ConnectionStatus,
-    next_req_id:                     AtomicUsize,
-    #[allow(dead_code)]
-    subscriptions_by_time:           HashMap<RequestId, DateTime<Utc>>,
-    #[allow(dead_code)]
+    conn_state:                      watch::Receiver<ConnectionStatus>,
+    /// server version, connection time and accounts of the connection
+    connection_info:                 watch::Receiver<ConnectionInfo>,
+    /// live subscriptions re-issued by the handler after a reconnect
+    subscriptions:                   LiveSubscriptions,
+    /// routes the answers of the requests to their subscriptions
+    registry:                        Registry,
+    /// queues historical data requests to comply with IB's pacing rules
+    historical_pacer:                Arc<HistoricalPacer>,
+    /// maximum time to wait for a response of TWS
+    request_timeout:                 Duration,
+    /// the next request id, shared by all clones
+    next_req_id:                     Arc<AtomicUsize>,
+    /// subscription times of the streaming requests
+    subscriptions_by_time:           SubscriptionTimes,
+    /// minimum lifetime of a subscription before it may be cancelled
     min_timespan_before_unsubscribe: chrono::Duration,
     /// Broadcasts a shutdown signal to all active connections.
     ///
@@ -93,27 +144,13 @@ pub struct Client {
     /// safe terminal state, and completes the task.
     notify_shutdown:                 broadcast::Sender<()>,
 
-    /// Used as part of the graceful shutdown process to wait for client
-    /// connections to complete processing.
-    ///
-    /// Tokio channels are closed once all `Sender` handles go out of scope.
-    /// When a channel is closed, the receiver receives `None`. This is
-    /// leveraged to detect all connection handlers completing. When a
-    /// connection handler is initialized, it is assigned a clone of
-    /// `shutdown_complete_tx`. When the listener shuts down, it drops the
-    /// sender held by this `shutdown_complete_tx` field. Once all handler tasks
-    /// complete, all clones of the `Sender` are also dropped. This results in
-    /// `shutdown_complete_rx.recv()` completing with `None`. At this point, it
-    /// is safe to exit the server process.
-    #[allow(dead_code)]
-    shutdown_complete_rx: mpsc::Receiver<()>,
-    #[allow(dead_code)]
-    shutdown_complete_tx: mpsc::Sender<()>,
-
     subscribe_handler_tx:       mpsc::Sender<Request>,
     pub order_tracker:          OrderTracker,
+    pub position_tracker:       PositionTracker,
+    pub pnl_tracker:            PnLTracker,
     pub account_tracker:        Receiver<AccountData>,
     pub market_data_tracker:    MarketDataTracker,
+    pub news_tracker:           NewsTracker,
     pub contract_events:        Receiver<ResponseWithId<ContractDetails>>,
     pub account_update_tracker: Receiver<DateTime<Utc>>,
     pub message_tracker:        Receiver<TwsApiMessage>,
@@ -140,45 +177,156 @@ pub struct Client {
 /// }
 /// ```
 pub async fn connect<T: ToSocketAddrs + Send>(addr: T, client_id: ClientId) -> Result<Client> {
-    // The `addr` argument is passed directly to `TcpStream::connect`. This
-    // performs any asynchronous DNS lookup and attempts to establish the TCP
-    // connection. An error at either step returns an error, which is then
-    // bubbled up to the caller of `mini_redis` connect.
-    let socket = TcpStream::connect(addr).await?;
+    connect_with_options(addr, client_id, ConnectOptions::default()).await
+}
 
+/// Establish a connection with TWS / IB Gateway located at `addr`, which is
+/// re-established according to `policy` whenever it is lost, e.g. during the
+/// daily server reset.
+///
+/// After a reconnect the handshake is repeated and all live market data,
+/// market depth, bar, PnL and account subscriptions are re-issued under their
+/// original request ids, so the receivers of the trackers keep working.
+/// Requests still waiting for their response when the connection was lost
+/// fail. The connection state is published on
+/// [`Client::subscribe_connection_status`].
+pub async fn connect_with_reconnect<T: ToSocketAddrs + Send>(
+    addr: T,
+    client_id: ClientId,
+    policy: ReconnectPolicy,
+) -> Result<Client> {
+    connect_with_options(addr, client_id, ConnectOptions::default().reconnect(policy)).await
+}
+
+/// Establish a connection with TWS / IB Gateway located at `addr`, using the
+/// client versions, capabilities and timeouts of `options`.
+///
+/// Fails with `Error::InvalidOptions` if the client versions of `options` are
+/// not supported and with `Error::Timeout` if the connection or the handshake
+/// is not completed in time.
+pub async fn connect_with_options<T: ToSocketAddrs + Send>(
+    addr: T,
+    client_id: ClientId,
+    options: ConnectOptions,
+) -> Result<Client> {
+    options.validate()?;
+    // The `addr` argument is resolved once, a reconnecting handler connects to
+    // the same addresses.
+    let addrs = tokio::time::timeout(options.connect_timeout, lookup_host(addr))
+        .await
+        .map_err(|_| Error::Timeout(options.connect_timeout))??
+        .collect::<Vec<_>>();
+    let socket = open(&addrs, &options).await?;
+
+    let reconnect = options.reconnect_policy.map(|policy| {
+        Reconnect {
+            addrs,
+            client_id,
+            policy,
+            options: options.clone(),
+        }
+    });
+    start(socket, client_id, &options, reconnect).await
+}
+
+/// Opens the TCP connection to the first reachable address of `addrs`.
+async fn open(addrs: &[SocketAddr], options: &ConnectOptions) -> Result<TcpStream> {
+    tokio::time::timeout(options.connect_timeout, TcpStream::connect(addrs))
+        .await
+        .map_err(|_| Error::Timeout(options.connect_timeout))?
+        .map_err(Error::from)
+}
+
+/// Performs the API handshake on a freshly opened `socket` and returns the
+/// reader and writer of the connection together with the server version and
+/// the connection time.
+///
+/// Fails with `Error::Timeout` if TWS does not answer within the handshake
+/// timeout of `options`.
+async fn handshake(
+    socket: TcpStream,
+    client_id: ClientId,
+    options: &ConnectOptions,
+) -> Result<(Writer, Reader, ConnectionInfo)> {
+    tokio::time::timeout(
+        options.handshake_timeout,
+        handshake_inner(socket, client_id, options),
+    )
+    .await
+    .map_err(|_| Error::Timeout(options.handshake_timeout))?
+}
+
+async fn handshake_inner(
+    socket: TcpStream,
+    client_id: ClientId,
+    options: &ConnectOptions,
+) -> Result<(Writer, Reader, ConnectionInfo)> {
     let (recv, trans) = socket.into_split();
     // Initialize the connection state. This allocates read/write buffers to
     // perform redis protocol frame parsing.
-    let mut writer = Writer::new(trans);
-    let mut reader = Reader::new(recv);
+    let writer = Writer::new(trans, options.recorder.clone());
+    let mut reader = Reader::new(recv, options.recorder.clone());
     // initiate handshake
     writer.write_raw(b"API\0").await?;
     let frame = Api::Init {
-        min_client_version: constants::MIN_CLIENT_VER,
-        max_client_version: constants::MAX_CLIENT_VER,
+        min_client_version: options.min_client_version,
+        max_client_version: options.max_client_version,
+        connection_options: options.connection_options.clone(),
     };
     writer.write_frame(&frame.into_frame()).await?;
 
     // Read the response
     let response = reader.read_frame(None).await?;
     debug!("{:?}", response);
-    let (server_version, _connection_time) = match response {
+    let (server_version, connection_time) = match response {
         Some(IBFrame::ServerVersion {
             server_version,
             connection_time,
         }) => (server_version, connection_time),
-        _ => return Err("could not get server version".into()),
+        frame => {
+            return Err(Error::Handshake(format!(
+                "expected the server version, received {frame:?}"
+            )))
+        },
     };
 
-    tracing::error!("Server Version: {}", &server_version);
+    if server_version < options.min_client_version {
+        return Err(Error::Handshake(format!(
+            "server version {server_version} is older than the minimum client version {}",
+            options.min_client_version
+        )));
+    }
+
+    tracing::info!("Server Version: {}", server_version);
     // start API
     let frame = Api::Start {
         client_id,
-        optional_capabilities: None,
+        optional_capabilities: options.optional_capabilities.clone(),
     };
     writer.write_frame(&frame.into_frame()).await?;
 
-    let conn_state = ConnectionStatus::CONNECTED;
+    let info = ConnectionInfo {
+        server_version,
+        connection_time,
+        managed_accounts: None,
+    };
+    Ok((writer, reader, info))
+}
+
+/// Performs the handshake on `socket` and spawns the connection handler.
+async fn start(
+    socket: TcpStream,
+    client_id: ClientId,
+    options: &ConnectOptions,
+    reconnect: Option<Reconnect>,
+) -> Result<Client> {
+    let (writer, reader, info) = handshake(socket, client_id, options).await?;
+    let server_version = info.server_version;
+
+    let (conn_state_tx, conn_state) = watch::channel(ConnectionStatus::CONNECTED);
+    let (connection_info_tx, connection_info) = watch::channel(info);
+    let subscriptions = LiveSubscriptions::default();
+    let registry = Registry::default();
 
     // When the provided `shutdown` future completes, we must send a shutdown
     // message to all active connections. We use a broadcast channel for this
@@ -186,32 +334,39 @@ pub async fn connect<T: ToSocketAddrs + Send>(addr: T, client_id: ClientId) -> R
     // a receiver is needed, the subscribe() method on the sender is used to create
     // one.
     let (notify_shutdown, _) = broadcast::channel(1);
-    let (shutdown_complete_tx, shutdown_complete_rx) = mpsc::channel(1);
     let (subscribe_handler_tx, subscribe_handler_rx) = mpsc::channel(100);
     let (account_tx, account) = unbounded();
     let (contract_tx, contract) = unbounded();
     let (message_tx, message) = unbounded();
     let (account_update_tx, account_update) = unbounded();
     let (market_data_tracker_tx, market_data_tracker) = MarketDataTracker::new();
+    let (news_tracker_tx, news_tracker) = NewsTracker::new();
     let (order_tracker_tx, order_tracker) = OrderTracker::new();
+    let (position_tracker_tx, position_tracker) = PositionTracker::new();
+    let (pnl_tracker_tx, pnl_tracker) = PnLTracker::new();
     // init handler
     let client = Client {
-        writer,
+        writer: writer.clone(),
         // connection,
         client_id,
-        server_version,
         conn_state,
-        next_req_id: AtomicUsize::new(0),
-        subscriptions_by_time: HashMap::new(),
+        connection_info,
+        subscriptions: subscriptions.clone(),
+        registry: registry.clone(),
+        historical_pacer: Arc::new(HistoricalPacer::new(HistoricalPacing::default())),
+        request_timeout: DEFAULT_REQUEST_TIMEOUT,
+        next_req_id: Arc::new(AtomicUsize::new(0)),
+        subscriptions_by_time: Arc::new(Mutex::new(HashMap::new())),
         min_timespan_before_unsubscribe: chrono::Duration::milliseconds(500),
         notify_shutdown,
-        shutdown_complete_tx,
-        shutdown_complete_rx,
         subscribe_handler_tx,
         order_tracker,
+        position_tracker,
+        pnl_tracker,
         account_tracker: account,
         account_update_tracker: account_update,
         market_data_tracker,
+        news_tracker,
         contract_events: contract,
         message_tracker: message,
     };
@@ -220,13 +375,22 @@ pub async fn connect<T: ToSocketAddrs + Send>(addr: T, client_id: ClientId) -> R
         // Process the connection. If an error is encountered, log it.
         if let Err(err) = run(
             reader,
+            writer,
             server_version,
+            conn_state_tx,
+            connection_info_tx,
+            subscriptions,
+            registry,
+            reconnect,
             subscribe_handler_rx,
             test,
             order_tracker_tx,
+            position_tracker_tx,
+            pnl_tracker_tx,
             account_tx,
             account_update_tx,
             market_data_tracker_tx,
+            news_tracker_tx,
             contract_tx,
             message_tx,
         )
@@ -251,14 +415,23 @@ pub async fn connect<T: ToSocketAddrs + Send>(addr: T, client_id: ClientId) -> R
 async fn run(
     //&mut self,
     reader: Reader,
+    writer: Writer,
     server_version: ServerVersion,
+    conn_state_tx: watch::Sender<ConnectionStatus>,
+    connection_info_tx: watch::Sender<ConnectionInfo>,
+    subscriptions: LiveSubscriptions,
+    registry: Registry,
+    reconnect: Option<Reconnect>,
     // socket: tokio::net::tcp::OwnedReadHalf,
     subscribe_handler_rx: mpsc::Receiver<Request>,
     notify_shutdown: broadcast::Receiver<()>,
     order_tracker_tx: OrderTrackerSender,
+    position_tracker_tx: PositionTrackerSender,
+    pnl_tracker_tx: PnLTrackerSender,
     account_tracker_tx: Sender<AccountData>,
     account_update_tracker_tx: Sender<AccountLastUpdate>,
     market_data_tracker_tx: MarketDataTrackerSender,
+    news_tracker_tx: NewsTrackerSender,
     contract_details_events_tx: Sender<ResponseWithId<ContractDetails>>,
     message_events_tx: Sender<TwsApiMessage>,
 ) -> Result<()> {
@@ -274,6 +447,12 @@ async fn run(
         // Initialize the connection state. This allocates read/write
         // buffers to perform redis protocol frame parsing.
         reader, //: Reader::new(socket),
+        writer,
+        conn_state_tx,
+        connection_info_tx,
+        subscriptions,
+        registry,
+        reconnect,
 
         // The connection state needs a handle to the max connections
         // semaphore. When the handler is done processing the
@@ -289,11 +468,15 @@ async fn run(
         //_shutdown_complete: shutdown_complete_tx.clone(),
         subscribe_handler_rx,
         order_id_reqs: VecDeque::new(),
-        requests: HashMap::new(),
+        scanner_parameters_reqs: VecDeque::new(),
+        news_providers_reqs: VecDeque::new(),
         order_tracker_tx,
+        position_tracker_tx,
+        pnl_tracker_tx,
         account_tracker_tx,
         account_update_tracker_tx,
         market_data_tracker_tx,
+        news_tracker_tx,
         contract_details_events_tx,
         message_events_tx,
     };
@@ -311,17 +494,60 @@ async fn run(
 
 impl Client {
     /// Checks connection status
-    pub const fn is_connected(&self) -> bool {
-        matches!(self.conn_state, ConnectionStatus::CONNECTED)
+    pub fn is_connected(&self) -> bool {
+        matches!(self.connection_status(), ConnectionStatus::CONNECTED)
+    }
+
+    /// Returns the current connection status
+    pub fn connection_status(&self) -> ConnectionStatus { *self.conn_state.borrow() }
+
+    /// Returns a receiver which is notified on every connection status
+    /// transition, e.g. `CONNECTED` -> `DISCONNECTED` -> `CONNECTING` ->
+    /// `CONNECTED` while reconnecting.
+    pub fn subscribe_connection_status(&self) -> watch::Receiver<ConnectionStatus> {
+        self.conn_state.clone()
     }
 
     /// Get the server version (important for checking feature flags for
     /// different versions)
-    pub const fn server_version(&self) -> ServerVersion { self.server_version }
+    ///
+    /// The version is negotiated again after a reconnect, all clones see the
+    /// version of the current connection.
+    pub fn server_version(&self) -> ServerVersion { self.connection_info.borrow().server_version }
+
+    /// Sets the rate limit of the messages sent to TWS, defaults to 50
+    /// messages per second.
+    pub async fn set_rate_limit(&self, limit: RateLimit) {
+        self.writer.rate_limiter().set_limit(limit).await;
+    }
+
+    /// Sets the pacing rules of historical data requests, see
+    /// [`HistoricalPacing`] for the defaults.
+    pub async fn set_historical_pacing(&self, pacing: HistoricalPacing) {
+        self.historical_pacer.set_pacing(pacing).await;
+    }
+
+    /// Sets the maximum time to wait for the response of a request, defaults
+    /// to 60 seconds. Requests which receive their response in several
+    /// messages wait at most `timeout` for each of them.
+    ///
+    /// The timeout applies to this handle only, clones created afterwards
+    /// inherit it.
+    #[allow(clippy::missing_const_for_fn)] // `&mut` in const fn is unstable on the pinned toolchain
+    pub fn set_request_timeout(&mut self, timeout: Duration) { self.request_timeout = timeout; }
+
+    /// Returns the number of messages currently held back by the rate limiter
+    /// and the historical data pacing.
+    pub fn pacing_metrics(&self) -> PacingMetrics {
+        PacingMetrics {
+            rate_limited:      self.writer.rate_limiter().waiting(),
+            historical_queued: self.historical_pacer.queued(),
+        }
+    }
 
     /// Sets server logging level
     #[instrument(skip(self))]
-    pub async fn set_server_log_level(&mut self, log_level: ServerLogLevel) -> Result<()> {
+    pub async fn set_server_log_level(&self, log_level: ServerLogLevel) -> Result<()> {
         ////The pub default detail level is ERROR. For more details, see API
         ////        Logging.
         debug!("set_server_log_level -- log_level: {:?}", log_level);
@@ -334,21 +560,42 @@ impl Client {
         Ok(())
     }
 
-    pub fn subscribe_message_updates(&mut self) -> Receiver<TwsApiMessage> {
+    pub fn subscribe_message_updates(&self) -> Receiver<TwsApiMessage> {
         self.message_tracker.clone()
     }
 
     //----------------------------------------------------------------------------------------------
-    /// Gets the connection time
-    // pub fn tws_connection_time(&mut self) -> String {
-    //    //"""Returns the time the API client made a connection to TWS."""
+    /// Returns the server version, the connection time and the managed
+    /// accounts of the current connection.
+    pub fn connection_info(&self) -> ConnectionInfo { self.connection_info.borrow().clone() }
 
-    //    self.conn_time.clone()
-    //}
+    /// Returns the time TWS accepted the current connection, as sent in the
+    /// handshake.
+    pub fn connection_time(&self) -> String {
+        self.connection_info.borrow().connection_time.clone()
+    }
+
+    /// Returns the accounts accessible by the connection.
+    ///
+    /// TWS sends them right after the API was started, the call waits at most
+    /// for the request timeout until they were received.
+    pub async fn managed_accounts(&self) -> Result<Vec<AccountCode>> {
+        let mut info = self.connection_info.clone();
+        let received = async move {
+            info.wait_for(|info| info.managed_accounts.is_some())
+                .await
+                .map(|info| info.managed_accounts.clone().unwrap_or_default())
+        };
+        match tokio::time::timeout(self.request_timeout, received).await {
+            Ok(Ok(accounts)) => Ok(accounts),
+            Ok(Err(_)) => Err(Error::Disconnected),
+            Err(_) => Err(Error::Timeout(self.request_timeout)),
+        }
+    }
 
     //----------------------------------------------------------------------------------------------
     // Request the current time according to TWS or IB Gateway
-    pub async fn request_current_time(&mut self) -> Result<()> {
+    pub async fn request_current_time(&self) -> Result<()> {
         let frame = Api::RequestCurrentTime;
 
         debug!(request = ?frame);
@@ -361,21 +608,116 @@ impl Client {
     //----------------------------------------------------------------------------------------------
     /// Disconnect from TWS
     pub fn disconnect(&self) -> Result<()> {
-        if !self.is_connected() {
+        // the handler keeps listening for the shutdown signal while it
+        // reconnects, so only a terminated handler counts as disconnected
+        if self.notify_shutdown.receiver_count() == 0 {
             info!("Already disconnected...");
             return Ok(());
         }
         error!("Disconnect requested.  Shutting down stream...");
-        let _ = self.notify_shutdown.send(())?;
+        // the handler was checked to be listening above
+        let _ = self.notify_shutdown.send(());
         Ok(())
     }
 
-    fn get_next_req_id(&mut self) -> usize {
-        let req_id = self.next_req_id.get_mut();
-        let id = *req_id;
-        *req_id += 1;
-        id
+    /// Registers a request waiting for its response at the handler.
+    async fn register(&self, request: Request) -> Result<()> {
+        self.subscribe_handler_tx
+            .send(request)
+            .await
+            .map_err(|_| Error::Disconnected)
+    }
+
+    /// Waits for the next `response`, at most for the request timeout.
+    async fn recv_response<T>(
+        &self,
+        response: impl Future<Output = Option<Response<T>>>,
+    ) -> Result<T> {
+        match tokio::time::timeout(self.request_timeout, response).await {
+            Ok(Some(Ok(response))) => Ok(response),
+            Ok(Some(Err(message))) => Err(message.into()),
+            Ok(None) => Err(Error::Disconnected),
+            Err(_) => Err(Error::Timeout(self.request_timeout)),
+        }
+    }
+
+    /// Routes the messages of type `T` answering `req_id` to the returned
+    /// subscription.
+    fn subscribe<T: Send + 'static>(
+        &self,
+        req_id: RequestId,
+        kind: RouteKind,
+    ) -> Result<Subscription<T>> {
+        Subscription::new(&self.registry, req_id, kind)
+    }
+
+    /// Writes `cancel` once `subscription` is dropped, honoring the minimum
+    /// lifetime of the subscription.
+    fn cancel_on_drop<T>(&self, subscription: Subscription<T>, cancel: Frame) -> Subscription<T> {
+        let not_before =
+            tokio::time::Instant::now() + self.unsubscribe_delay(subscription.req_id());
+        subscription.cancel_on_drop(
+            cancel,
+            self.writer.clone(),
+            self.subscriptions.clone(),
+            self.subscriptions_by_time.clone(),
+            not_before,
+        )
+    }
+
+    /// Records the subscription time of the streaming request `req_id`.
+    fn record_subscription_time(&self, req_id: RequestId) {
+        self.subscription_times().insert(req_id, Utc::now());
     }
+
+    fn subscription_times(&self) -> std::sync::MutexGuard<'_, HashMap<RequestId, DateTime<Utc>>> {
+        self.subscriptions_by_time
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns how long the subscription `req_id` has to be kept before it
+    /// may be cancelled.
+    ///
+    /// When unsubscribing symbols immediately after subscribing IB returns an
+    /// error (Can't find EId with tickerId:nnn), so subscriptions are kept at
+    /// least `min_timespan_before_unsubscribe`.
+    fn unsubscribe_delay(&self, req_id: RequestId) -> Duration {
+        self.subscription_times()
+            .get(&req_id)
+            .and_then(|subscribed| {
+                (self.min_timespan_before_unsubscribe - (Utc::now() - *subscribed))
+                    .to_std()
+                    .ok()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Waits until the subscription `req_id` may be cancelled and forgets its
+    /// subscription time.
+    async fn wait_before_unsubscribe(&self, req_id: RequestId) {
+        let delay = self.unsubscribe_delay(req_id);
+        self.subscription_times().remove(&req_id);
+        if delay > Duration::ZERO {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Records a live subscription, so it is re-issued after a reconnect.
+    ///
+    /// `encode` encodes the request for the server version of the connection
+    /// it is re-issued on.
+    fn track_subscription<F>(&self, key: SubscriptionKey, encode: F)
+    where
+        F: Fn(ServerVersion) -> Result<Frame> + Send + Sync + 'static,
+    {
+        self.subscriptions.insert(key, encode);
+    }
+
+    /// Forgets a cancelled subscription.
+    fn untrack_subscription(&self, key: SubscriptionKey) { self.subscriptions.remove(key); }
+
+    fn get_next_req_id(&self) -> usize { self.next_req_id.fetch_add(1, Ordering::Relaxed) }
 }
 /// Per-connection handler. Reads requests from `connection` and applies the
 /// commands to `db`.
@@ -395,7 +737,20 @@ struct Handler {
     /// passed to `Connection::new`, which initializes the associated buffers.
     /// `Connection` allows the handler to operate at the "frame" level and keep
     /// the byte level protocol parsing details encapsulated in `Connection`.
-    reader:         Reader,
+    reader:             Reader,
+    /// Shares the socket with the `Client`, used to re-issue the live
+    /// subscriptions after a reconnect.
+    writer:             Writer,
+    /// publishes the connection status to the `Client`
+    conn_state_tx:      watch::Sender<ConnectionStatus>,
+    /// publishes the connection time and managed accounts to the `Client`
+    connection_info_tx: watch::Sender<ConnectionInfo>,
+    /// live subscriptions recorded by the `Client`
+    subscriptions:      LiveSubscriptions,
+    /// routes the messages with a request id to their subscriptions
+    registry:           Registry,
+    /// re-establish lost connections if set
+    reconnect:          Option<Reconnect>,
 
     /// Max connection semaphore.
     ///
@@ -415,14 +770,15 @@ struct Handler {
     shutdown:      Shutdown,
     /// track order ids request and send the result to the corresponsing
     /// receivers
-    order_id_reqs: VecDeque<mpsc::Sender<Option<OrderId>>>,
+    order_id_reqs: VecDeque<mpsc::UnboundedSender<Response<OrderId>>>,
 
     /// Not used directly. Instead, when `Handler` is dropped...?
     //_shutdown_complete: mpsc::Sender<()>,
     subscribe_handler_rx: mpsc::Receiver<Request>,
-    /// track order details request and send the result to the corresponsing
-    /// receivers
-    requests:             HashMap<usize, mpsc::Sender<ResponseWithId<ContractDetails>>>,
+    /// track scanner parameter and news provider requests and send the result
+    /// to the corresponding receivers
+    scanner_parameters_reqs: VecDeque<mpsc::UnboundedSender<Response<String>>>,
+    news_providers_reqs:     VecDeque<mpsc::UnboundedSender<Response<Vec<NewsProvider>>>>,
 
     // track market data request, send the incomming frames to the corresponding receivers
     // ticker_reqs: HashMap<usize, mpsc::Sender<Option<contract::ContractDetails>>>,
@@ -433,17 +789,125 @@ struct Handler {
     // track tick messages, send incoming frames to the receiver
     market_data_tracker_tx: MarketDataTrackerSender,
 
+    // send news headlines and bulletins to the receivers
+    news_tracker_tx: NewsTrackerSender,
+
     // account_tracker_tx: AccountSender,
     /// send the trade related result to the corLastUpdate
     /// receivers
     order_tracker_tx: OrderTrackerSender,
 
+    /// send positions and portfolio updates to the corresponding receivers
+    position_tracker_tx: PositionTrackerSender,
+
+    /// send profit and loss updates to the corresponding receivers
+    pnl_tracker_tx: PnLTrackerSender,
+
     contract_details_events_tx: Sender<ResponseWithId<ContractDetails>>,
 
     message_events_tx: Sender<TwsApiMessage>,
 }
 
 impl Handler {
+    /// Supervise the connection.
+    ///
+    /// Processes the connection until it is shut down or lost. A lost
+    /// connection is re-established according to the `ReconnectPolicy`, if
+    /// the client was connected with reconnects enabled.
+    #[instrument(level = "debug", skip(self))]
+    async fn run(&mut self) -> Result<()> {
+        loop {
+            let res = self.process_connection().await;
+            self.conn_state_tx
+                .send_replace(ConnectionStatus::DISCONNECTED);
+            res?;
+            if self.shutdown.is_shutdown() || !self.reconnect().await? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Re-establishes a lost connection, waiting between the attempts as
+    /// configured by the `ReconnectPolicy`.
+    ///
+    /// Returns `false` if reconnects are disabled, the attempts are exhausted
+    /// or the shutdown signal was received.
+    async fn reconnect(&mut self) -> Result<bool> {
+        let reconnect = match self.reconnect.clone() {
+            Some(reconnect) => reconnect,
+            None => return Ok(false),
+        };
+        // requests waiting for a response will never be answered, dropping
+        // their senders notifies the waiting callers
+        self.drop_pending_requests();
+
+        let mut attempt = 0;
+        while reconnect.policy.allows(attempt) {
+            let delay = reconnect.policy.delay(attempt);
+            attempt += 1;
+            info!("reconnecting in {:?} (attempt {})", delay, attempt);
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {},
+                _ = self.shutdown.recv() => return Ok(false),
+            }
+
+            self.conn_state_tx
+                .send_replace(ConnectionStatus::CONNECTING);
+            let connection = match open(&reconnect.addrs, &reconnect.options).await {
+                Ok(socket) => handshake(socket, reconnect.client_id, &reconnect.options).await,
+                Err(err) => Err(err),
+            };
+            match connection {
+                Ok((writer, reader, info)) => {
+                    self.writer.replace(writer).await;
+                    self.reader = reader;
+                    self.server_version = info.server_version;
+                    self.connection_info_tx.send_replace(info);
+                    // re-issue the live subscriptions under their request ids,
+                    // encoded for the server version of the new connection
+                    for (key, frame) in self.subscriptions.frames(self.server_version) {
+                        match frame {
+                            Ok(frame) => self.writer.write_frame(&frame).await?,
+                            Err(err) => {
+                                warn!(cause = ?err, "failed to re-issue subscription {:?}", key);
+                                self.subscriptions.remove(key);
+                                if let SubscriptionKey::Request(req_id) = key {
+                                    self.registry.finish(req_id);
+                                }
+                            },
+                        }
+                    }
+                    info!("reconnected after {} attempt(s)", attempt);
+                    self.conn_state_tx.send_replace(ConnectionStatus::CONNECTED);
+                    return Ok(true);
+                },
+                Err(err) => {
+                    warn!(cause = ?err, "reconnect attempt {} failed", attempt);
+                    self.conn_state_tx
+                        .send_replace(ConnectionStatus::DISCONNECTED);
+                },
+            }
+        }
+        error!("giving up to reconnect after {} attempt(s)", attempt);
+        Ok(false)
+    }
+
+    /// Answers the pending request `req_id` with the error `message`.
+    fn fail_pending_request(&self, req_id: RequestId, message: &TwsMessage) {
+        if self.registry.fail(req_id, message) == Some(RouteKind::Live) {
+            // TWS dropped the subscription, it must not be re-issued
+            self.subscriptions.remove(SubscriptionKey::Request(req_id));
+        }
+    }
+
+    /// Drops the senders of all requests waiting for a response.
+    fn drop_pending_requests(&mut self) {
+        self.order_id_reqs.clear();
+        self.scanner_parameters_reqs.clear();
+        self.news_providers_reqs.clear();
+        self.registry.clear_pending();
+    }
+
     /// Process a single connection.
     ///
     /// Request frames are read from the socket and processed. Responses are
@@ -455,24 +919,38 @@ impl Handler {
     /// https://redis.io/topics/pipelining
     ///
     /// When the shutdown signal is received, the connection is processed until
-    /// it reaches a safe state, at which point it is terminated.
-    #[instrument(level = "debug", skip(self))]
-    async fn run(&mut self) -> Result<()> {
+    /// it reaches a safe state, at which point it is terminated. Returns once
+    /// the peer closed the connection or the connection broke.
+    async fn process_connection(&mut self) -> Result<()> {
         // As long as the shutdown signal has not been received, try to read a
         // new request frame.
         while !self.shutdown.is_shutdown() {
             // While reading a request frame, also listen for the shutdown
             // signal.
             let maybe_frame = tokio::select! {
-                res = self.reader.read_frame(Some(self.server_version)) => res?,
+                res = self.reader.read_frame(Some(self.server_version)) => match res {
+                    Ok(Some(frame)) => Some(frame),
+                    // If `None` is returned from `read_frame()` then the peer
+                    // closed the socket.
+                    Ok(None) => {
+                        warn!("connection closed by TWS");
+                        return Ok(());
+                    },
+                    Err(err) => {
+                        error!(cause = ?err, "connection lost");
+                        return Ok(());
+                    },
+                },
                 Some(request) = self.subscribe_handler_rx.recv() => {
                 match request {
                     Request::OrderId {sender } => {
                         self.order_id_reqs.push_back(sender);
                         },
-                    Request::RequestWithId { req_id, sender } => {
-                        debug!("requestwithid:\t{}",req_id);
-                        self.requests.insert(req_id, sender);
+                    Request::ScannerParameters { sender } => {
+                        self.scanner_parameters_reqs.push_back(sender);
+                        },
+                    Request::NewsProviders { sender } => {
+                        self.news_providers_reqs.push_back(sender);
                         },
                     }
                     None
@@ -486,9 +964,6 @@ impl Handler {
                 }
             };
 
-            // If `None` is returned from `read_frame()` then the peer closed
-            // the socket. There is no further work to do and the task can be
-            // terminated.
             if let Some(frame) = maybe_frame {
                 match frame {
                     IBFrame::AccountSummary(data) => {
@@ -499,7 +974,55 @@ impl Handler {
                         self.account_tracker_tx.send(data)?;
                     },
 
-                    IBFrame::PortfolioValue(position) => debug!("got position:\t{:?}", position),
+                    IBFrame::PortfolioValue(position) => {
+                        self.position_tracker_tx.portfolio_tx.send(position)?;
+                    },
+
+                    IBFrame::Position(position) => {
+                        self.position_tracker_tx
+                            .positions_tx
+                            .send(PositionUpdate::Position {
+                                req_id: None,
+                                position,
+                            })?;
+                    },
+
+                    IBFrame::PositionEnd => {
+                        self.position_tracker_tx
+                            .positions_tx
+                            .send(PositionUpdate::End { req_id: None })?;
+                    },
+
+                    IBFrame::PositionMulti { req_id, position } => {
+                        let update = PositionUpdate::Position {
+                            req_id: Some(req_id),
+                            position,
+                        };
+                        if let Some(update) = self.registry.deliver(req_id, update) {
+                            self.position_tracker_tx.positions_tx.send(update)?;
+                        }
+                    },
+
+                    IBFrame::PositionMultiEnd(req_id) => {
+                        let update = PositionUpdate::End {
+                            req_id: Some(req_id),
+                        };
+                        if let Some(update) = self.registry.deliver(req_id, update) {
+                            self.position_tracker_tx.positions_tx.send(update)?;
+                        }
+                    },
+
+                    IBFrame::PnL(pnl) => {
+                        if let Some(pnl) = self.registry.deliver(pnl.req_id, pnl) {
+                            self.pnl_tracker_tx.pnl_tx.send(pnl)?;
+                        }
+                    },
+
+                    IBFrame::PnLSingle(pnl) => {
+                        if let Some(pnl) = self.registry.deliver(pnl.req_id, pnl) {
+                            self.pnl_tracker_tx.pnl_single_tx.send(pnl)?;
+                        }
+                    },
 
                     IBFrame::AccountUpdateTime(time) => {
                         self.account_update_tracker_tx.send(time)?;
@@ -514,8 +1037,8 @@ impl Handler {
 
                     IBFrame::OrderId(id) => {
                         while let Some(sender) = self.order_id_reqs.pop_front() {
-                            sender.send(Some(id)).await?;
-                            // self.order_id_reqs.remove_entry(&_id);
+                            // the caller may have given up waiting already
+                            let _ = sender.send(Ok(id));
                         }
                         self.order_tracker_tx.order_id_tx.send(id)?;
                     },
@@ -525,15 +1048,10 @@ impl Handler {
                         contract_details: details,
                     } => {
                         debug!("got contract details with req_id:\t{}", id);
-                        if let Some(sender) = self.requests.get(&id) {
-                            debug!("got sender for req_id:\t{}", id);
-                            sender
-                                .send(ResponseWithId {
-                                    req_id:   id,
-                                    response: Some(details.clone()),
-                                })
-                                .await?;
-                        }
+                        self.registry.deliver(id, ResponseWithId {
+                            req_id:   id,
+                            response: Some(details.clone()),
+                        });
                         self.contract_details_events_tx.send(ResponseWithId {
                             req_id:   id,
                             response: Some(details.clone()),
@@ -541,19 +1059,72 @@ impl Handler {
                     },
 
                     IBFrame::ContractDetailsEnd(req_id) => {
-                        match self.requests.remove_entry(&req_id) {
-                            Some((_, sender)) => {
-                                sender
-                                    .send(ResponseWithId {
-                                        req_id,
-                                        response: None,
-                                    })
-                                    .await?;
-                            },
-                            None => {
-                                debug!("No pending contract details request for req_id {}", req_id);
-                            },
+                        let end = ResponseWithId::<ContractDetails> {
+                            req_id,
+                            response: None,
                         };
+                        if self.registry.deliver(req_id, end).is_some() {
+                            debug!("No pending contract details request for req_id {}", req_id);
+                        }
+                        self.registry.finish(req_id);
+                    },
+                    IBFrame::SecDefOptParams { req_id, chain } => {
+                        self.registry.deliver(req_id, ResponseWithId {
+                            req_id,
+                            response: Some(chain),
+                        });
+                    },
+                    IBFrame::SecDefOptParamsEnd(req_id) => {
+                        self.registry
+                            .deliver(req_id, ResponseWithId::<OptionChain> {
+                                req_id,
+                                response: None,
+                            });
+                        self.registry.finish(req_id);
+                    },
+                    IBFrame::SymbolSamples {
+                        req_id,
+                        descriptions,
+                    } => {
+                        if self.registry.deliver(req_id, descriptions).is_some() {
+                            debug!("No pending symbol search for req_id {}", req_id);
+                        }
+                    },
+                    IBFrame::NewsProviders(providers) => {
+                        if let Some(sender) = self.news_providers_reqs.pop_front() {
+                            let _ = sender.send(Ok(providers));
+                        }
+                    },
+                    IBFrame::NewsArticle(article) => {
+                        self.registry.deliver(article.req_id, article);
+                    },
+                    IBFrame::HistoricalNews(news) => {
+                        self.registry
+                            .deliver(news.req_id, HistoricalNewsUpdate::Headline(news));
+                    },
+                    IBFrame::HistoricalNewsEnd { req_id, has_more } => {
+                        self.registry
+                            .deliver(req_id, HistoricalNewsUpdate::End { has_more });
+                        self.registry.finish(req_id);
+                    },
+                    IBFrame::TickNews(news) => {
+                        self.news_tracker_tx.tick_news_tx.send(news)?;
+                    },
+                    IBFrame::NewsBulletin(bulletin) => {
+                        self.news_tracker_tx.bulletins_tx.send(bulletin)?;
+                    },
+                    IBFrame::FundamentalData { req_id, data }
+                    | IBFrame::WshMetaData {
+                        req_id,
+                        data_json: data,
+                    }
+                    | IBFrame::WshEventData(WshEventData {
+                        req_id,
+                        data_json: data,
+                    }) => {
+                        if self.registry.deliver(req_id, data).is_some() {
+                            debug!("No pending request for req_id {}", req_id);
+                        }
                     },
                     IBFrame::OpenOrder(order_information) => {
                         let _order_id = order_information.order.order_id;
@@ -596,10 +1167,18 @@ impl Handler {
                     },
                     IBFrame::Tick(tick) => {
                         debug!("got tick: {:#?}", tick);
-                        self.market_data_tracker_tx.tick_by_tick_tx.send(tick)?;
+                        if let Some(tick) = self.registry.deliver(tick.id(), tick) {
+                            self.market_data_tracker_tx.tick_by_tick_tx.send(tick)?;
+                        }
                     },
-                    IBFrame::HistoricalBars(bars) => {
-                        self.market_data_tracker_tx.historical_bars_tx.send(bars)?;
+                    IBFrame::TickSnapshotEnd(req_id) => {
+                        // ends the stream of the snapshot request
+                        self.registry.finish(req_id);
+                    },
+                    IBFrame::HistoricalBars(bars) | IBFrame::HistoricalBarUpdate(bars) => {
+                        if let Some(bars) = self.registry.deliver(bars.id, bars) {
+                            self.market_data_tracker_tx.historical_bars_tx.send(bars)?;
+                        }
                     },
                     IBFrame::HistoricalSchedule(schedule) => {
                         self.market_data_tracker_tx
@@ -607,35 +1186,69 @@ impl Handler {
                             .send(schedule)?;
                     },
                     IBFrame::RealtimeBar(bar) => {
-                        self.market_data_tracker_tx.bars_tx.send(bar)?;
+                        if let Some(bar) = self.registry.deliver(bar.id, bar) {
+                            self.market_data_tracker_tx.bars_tx.send(bar)?;
+                        }
                     },
-                    IBFrame::HistoricalTicks(tick) => {
-                        self.market_data_tracker_tx.historical_ticks_tx.send(tick)?;
+                    IBFrame::HistoricalTicks(ticks) => {
+                        let (req_id, done) = (ticks.id, ticks.done);
+                        match self.registry.deliver(req_id, ticks) {
+                            Some(ticks) => {
+                                self.market_data_tracker_tx
+                                    .historical_ticks_tx
+                                    .send(ticks)?;
+                            },
+                            None if done => {
+                                self.registry.finish(req_id);
+                            },
+                            None => {},
+                        }
                     },
                     IBFrame::HeadTimestamp(timestamp) => {
-                        self.market_data_tracker_tx
-                            .head_timestamp_tx
-                            .send(timestamp)?;
+                        if let Some(timestamp) = self.registry.deliver(timestamp.id, timestamp) {
+                            self.market_data_tracker_tx
+                                .head_timestamp_tx
+                                .send(timestamp)?;
+                        }
                     },
-                    IBFrame::Error {
-                        req_id,
-                        status,
-                        message,
-                    } => {
-                        error!("id:{}\tcode:{}\t{:#?}", req_id, status, message);
-                        let req_id = if req_id < 0 {
-                            None
-                        } else {
-                            Some(req_id as usize)
-                        };
-                        self.message_events_tx.send(TwsApiMessage::TwsError {
-                            req_id,
-                            status,
-                            message,
-                        })?;
+                    IBFrame::ScannerData { req_id, rows } => {
+                        if self.registry.deliver(req_id, rows).is_some() {
+                            debug!("No scanner subscription for req_id {}", req_id);
+                        }
+                    },
+                    IBFrame::ScannerParameters(xml) => {
+                        if let Some(sender) = self.scanner_parameters_reqs.pop_front() {
+                            let _ = sender.send(Ok(xml));
+                        }
+                    },
+                    IBFrame::MarketDepth(update) => {
+                        if let Some(update) = self.registry.deliver(update.id, update) {
+                            self.market_data_tracker_tx.market_depth_tx.send(update)?;
+                        }
+                    },
+                    IBFrame::Error(message) => {
+                        match message.severity() {
+                            Severity::Info => info!("{:?}", message),
+                            Severity::Warning => warn!("{:?}", message),
+                            Severity::Error | Severity::Critical => error!("{:?}", message),
+                        }
+                        if let Some(req_id) = message.req_id {
+                            // errors of orders carry the order id instead of a
+                            // request id
+                            if message.severity() >= Severity::Error
+                                && !message.code.refers_to_order()
+                            {
+                                self.fail_pending_request(req_id, &message);
+                            }
+                        }
+                        self.message_events_tx.send(message.into())?;
                     },
                     // TODO: Implement missing IBFrames
-                    IBFrame::AccountCode(_) => (),
+                    IBFrame::AccountCode(accounts) => {
+                        let accounts = parse_managed_accounts(&accounts);
+                        self.connection_info_tx
+                            .send_modify(|info| info.managed_accounts = Some(accounts));
+                    },
                     IBFrame::OpenOrderEnd => (),
                     IBFrame::ServerVersion {
                         server_version: _,
@@ -1,21 +1,21 @@
 use crossbeam::channel::Receiver;
 use tracing::debug;
 
-use super::Client;
+use super::{Client, RouteKind, Subscription, SubscriptionKey};
 use crate::{
-    account::AccountData,
-    cmd::{RequestAccountSummary, RequestAccountUpdates},
+    account::{AccountData, PnL, PnLSingle, PnLTracker, PositionTracker, PositionUpdate},
+    cmd::{RequestAccountSummary, RequestAccountUpdates, RequestPnl, RequestPositions},
     prelude::AccountLastUpdate,
-    AccountCode, Result,
+    AccountCode, RequestId, Result,
 };
 impl Client {
     // pub fn subscribe_account_updates(self) -> AccountReceiver {
     // self.account_tracker }
-    pub fn subscribe_account_updates(&mut self) -> Receiver<AccountData> {
+    pub fn subscribe_account_updates(&self) -> Receiver<AccountData> {
         self.account_tracker.clone()
     }
 
-    pub fn subscribe_account_last_updates(&mut self) -> Receiver<AccountLastUpdate> {
+    pub fn subscribe_account_last_updates(&self) -> Receiver<AccountLastUpdate> {
         self.account_update_tracker.clone()
     }
 
@@ -32,7 +32,7 @@ impl Client {
     ///   portfolio updates.
     #[tracing::instrument(skip(self))]
     pub async fn request_account_updates(
-        &mut self,
+        &self,
         subscribe: bool,
         account_code: AccountCode,
     ) -> Result<()> {
@@ -40,8 +40,16 @@ impl Client {
 
         debug!(request = ?frame);
 
+        let resubscribe = frame.clone();
         // Write the frame to the socket
         self.writer.write_frame(&frame.into_frame()).await?;
+        if subscribe {
+            self.track_subscription(SubscriptionKey::AccountUpdates, move |_| {
+                Ok(resubscribe.clone().into_frame())
+            });
+        } else {
+            self.untrack_subscription(SubscriptionKey::AccountUpdates);
+        }
         Ok(())
     }
 
@@ -62,7 +70,7 @@ impl Client {
     ///   AccountSummaryTags enum for valid values
     #[tracing::instrument(skip(self))]
     pub async fn request_account_summary(
-        &mut self,
+        &self,
         group_name: String,
         tags: Vec<String>,
     ) -> Result<()> {
@@ -71,13 +79,235 @@ impl Client {
 
         debug!(request = ?frame);
 
+        let resubscribe = frame.clone();
         // Write the frame to the socket
         self.writer.write_frame(&frame.into_frame()).await?;
+        self.track_subscription(SubscriptionKey::Request(req_id), move |_| {
+            Ok(resubscribe.clone().into_frame())
+        });
         Ok(())
     }
+    pub fn subscribe_positions(&self) -> PositionTracker { self.position_tracker.clone() }
+
+    /// Subscribes to position updates for all accessible accounts. All
+    /// positions are sent initially, and then only updates as positions
+    /// change.
+    ///
+    /// The positions are delivered on `PositionTracker::positions`, the end of
+    /// the initial snapshot is marked with `PositionUpdate::End`.
+    #[tracing::instrument(skip(self))]
+    pub async fn request_positions(&self) -> Result<()> {
+        let frame = RequestPositions::Positions;
+
+        debug!(request = ?frame);
+
+        let resubscribe = frame.clone();
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        self.track_subscription(SubscriptionKey::Positions, move |server_version| {
+            resubscribe.clone().into_frame(server_version)
+        });
+        Ok(())
+    }
+
+    /// Cancels a previous position subscription request made with
+    /// request_positions().
+    #[tracing::instrument(skip(self))]
+    pub async fn cancel_positions(&self) -> Result<()> {
+        let frame = RequestPositions::CancelPositions;
+
+        debug!(request = ?frame);
+
+        self.untrack_subscription(SubscriptionKey::Positions);
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    /// Requests position subscription for account and/or model. Initially all
+    /// positions are returned and then updates are returned for any position
+    /// changes in real time.
+    ///
+    /// Returns a stream of the `PositionUpdate`s of the subscription,
+    /// dropping it cancels the subscription.
+    ///
+    /// # Arguments
+    /// * account - If an account Id is provided, only the account's positions
+    ///   belonging to the specified model will be delivered.
+    /// * model_code - The code of the model's positions we are interested in.
+    #[tracing::instrument(skip(self))]
+    pub async fn request_positions_multi(
+        &self,
+        account: AccountCode,
+        model_code: Option<String>,
+    ) -> Result<Subscription<PositionUpdate>> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestPositions::PositionsMulti {
+            req_id,
+            account,
+            model_code,
+        };
+
+        debug!(request = ?frame);
+
+        let subscription = self.subscribe(req_id, RouteKind::Live)?;
+        let resubscribe = frame.clone();
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        self.track_subscription(SubscriptionKey::Request(req_id), move |server_version| {
+            resubscribe.clone().into_frame(server_version)
+        });
+        let cancel =
+            RequestPositions::CancelPositionsMulti { req_id }.into_frame(self.server_version())?;
+        Ok(self.cancel_on_drop(subscription, cancel))
+    }
+
+    /// Cancels positions request for account and/or model.
+    ///
+    /// # Arguments
+    /// * req_id - the request id of the subscription returned by
+    ///   request_positions_multi().
+    #[tracing::instrument(skip(self))]
+    pub async fn cancel_positions_multi(&self, req_id: RequestId) -> Result<()> {
+        let frame = RequestPositions::CancelPositionsMulti { req_id };
+
+        debug!(request = ?frame);
+
+        self.registry.finish(req_id);
+        self.untrack_subscription(SubscriptionKey::Request(req_id));
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    pub fn subscribe_pnl(&self) -> PnLTracker { self.pnl_tracker.clone() }
+
+    /// Creates subscription for real time daily PnL and unrealized PnL updates.
+    ///
+    /// Returns a stream of the updates, dropping it cancels the
+    /// subscription.
+    ///
+    /// # Arguments
+    /// * account - account for which to receive PnL updates
+    /// * model_code - specify to request PnL updates for a specific model
+    #[tracing::instrument(skip(self))]
+    pub async fn request_pnl(
+        &self,
+        account: AccountCode,
+        model_code: Option<String>,
+    ) -> Result<Subscription<PnL>> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestPnl::Pnl {
+            req_id,
+            account,
+            model_code,
+        };
+
+        debug!(request = ?frame);
+
+        let subscription = self.subscribe(req_id, RouteKind::Live)?;
+        let resubscribe = frame.clone();
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        self.track_subscription(SubscriptionKey::Request(req_id), move |server_version| {
+            resubscribe.clone().into_frame(server_version)
+        });
+        let cancel = RequestPnl::CancelPnl { req_id }.into_frame(self.server_version())?;
+        Ok(self.cancel_on_drop(subscription, cancel))
+    }
+
+    /// Cancels subscription for real time updated daily PnL.
+    ///
+    /// # Arguments
+    /// * req_id - the request id of the subscription returned by request_pnl().
+    #[tracing::instrument(skip(self))]
+    pub async fn cancel_pnl(&self, req_id: RequestId) -> Result<()> {
+        let frame = RequestPnl::CancelPnl { req_id };
+
+        debug!(request = ?frame);
+
+        self.registry.finish(req_id);
+        self.untrack_subscription(SubscriptionKey::Request(req_id));
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    /// Requests real time updates for daily PnL of individual positions.
+    ///
+    /// Returns a stream of the updates, dropping it cancels the
+    /// subscription.
+    ///
+    /// # Arguments
+    /// * account - account in which position exists
+    /// * model_code - model in which position exists
+    /// * con_id - contract ID (conId) of contract to receive daily PnL updates
+    ///   for. Note: does not return message if invalid conId is entered
+    #[tracing::instrument(skip(self))]
+    pub async fn request_pnl_single(
+        &self,
+        account: AccountCode,
+        model_code: Option<String>,
+        con_id: i32,
+    ) -> Result<Subscription<PnLSingle>> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestPnl::PnlSingle {
+            req_id,
+            account,
+            model_code,
+            con_id,
+        };
+
+        debug!(request = ?frame);
+
+        let subscription = self.subscribe(req_id, RouteKind::Live)?;
+        let resubscribe = frame.clone();
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        self.track_subscription(SubscriptionKey::Request(req_id), move |server_version| {
+            resubscribe.clone().into_frame(server_version)
+        });
+        let cancel = RequestPnl::CancelPnlSingle { req_id }.into_frame(self.server_version())?;
+        Ok(self.cancel_on_drop(subscription, cancel))
+    }
+
+    /// Cancels real time subscription for a positions daily PnL information.
+    ///
+    /// # Arguments
+    /// * req_id - the request id of the subscription returned by
+    ///   request_pnl_single().
+    #[tracing::instrument(skip(self))]
+    pub async fn cancel_pnl_single(&self, req_id: RequestId) -> Result<()> {
+        let frame = RequestPnl::CancelPnlSingle { req_id };
+
+        debug!(request = ?frame);
+
+        self.registry.finish(req_id);
+        self.untrack_subscription(SubscriptionKey::Request(req_id));
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
     //#[tracing::instrument(skip(self))]
     // pub async fn get_account_summary(
-    //    &mut self,
+    //    &self,
     //    group_name:String,
     //    tags: Vec<String>,
     //) -> Result<Vec<ContractDetails>> {
@@ -1,17 +1,16 @@
 use crossbeam::channel::Receiver;
-use futures::{Stream, StreamExt};
-use tokio::sync::mpsc;
+use futures::Stream;
 use tracing::{debug, instrument};
 
-use super::{Client, Request, ResponseWithId};
-use crate::{cmd::RequestContractDetails,
-            contract::{Contract, ContractDetails},
+use super::{Client, ResponseWithId, RouteKind};
+use crate::{cmd::{RequestContractDetails, RequestMatchingSymbols, RequestSecDefOptParams},
+            contract::{Contract, ContractDescriptionList, ContractDetails, OptionChain, SecType},
             RequestId,
             Result};
 impl Client {
     #[instrument(skip(self))]
     pub async fn request_contract_details(
-        &mut self,
+        &self,
         req_id: RequestId,
         contract: Contract,
     ) -> Result<()> {
@@ -20,16 +19,22 @@ impl Client {
 
         debug!(request = ?frame);
         // Write the frame to the socket
-        self.writer.write_frame(&frame.into_frame()).await?;
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
-    pub fn subscribe_contract_details(&mut self) -> Receiver<ResponseWithId<ContractDetails>> {
+    pub fn subscribe_contract_details(&self) -> Receiver<ResponseWithId<ContractDetails>> {
         self.contract_events.clone()
     }
 
+    /// Requests the details of all contracts matching `contract`.
+    ///
+    /// The stream ends after the last contract, or with an `Error` if TWS
+    /// rejects the request or does not answer in time.
     pub async fn get_contract_details(
-        &mut self,
+        &self,
         req_id: RequestId,
         contract: Contract,
     ) -> impl Stream<Item = Result<ContractDetails>> + '_ {
@@ -38,24 +43,78 @@ impl Client {
         let frame = RequestContractDetails::new(req_id, contract);
         debug!("request id:\t{}", req_id);
         debug!(request = ?frame);
-        // register sender for contract details callback
-        let (rep_tx, mut rep_rx) = mpsc::channel(8);
-        self.subscribe_handler_tx
-            .send(Request::RequestWithId {
-                req_id,
-                sender: rep_tx,
-            })
-            .await?;
+        // route the contract details to this request
+        let mut details =
+            self.subscribe::<ResponseWithId<ContractDetails>>(req_id, RouteKind::UntilEnd)?;
         // Write the frame to the socket
-        self.writer.write_frame(&frame.into_frame()).await?;
-        while let Some(response) = rep_rx.recv().await {
-            if let Some(response) = response.response {
-                tracing::trace!("Received contract details: {:?}", response);
-                yield (response);
-            } else {
-                return;
-            }
+        self.writer.write_frame(&frame.into_frame(self.server_version())?).await?;
+        while let Some(response) = self.recv_response(details.recv()).await?.response {
+            tracing::trace!("Received contract details: {:?}", response);
+            yield (response);
         }
         }
     }
+
+    /// Requests security definition option parameters for viewing a
+    /// contract's option chain.
+    ///
+    /// Returns one `OptionChain` per exchange on which the options are traded.
+    /// Use [`OptionChain::contracts`] to build the option contracts of a
+    /// chain.
+    ///
+    /// # Arguments
+    /// * underlying_symbol - underlying symbol of the option chain
+    /// * fut_fop_exchange - The exchange on which the returned options are
+    ///   trading. Can be set to the empty string "" for all exchanges.
+    /// * underlying_sec_type - The type of the underlying security, i.e. STK
+    /// * underlying_con_id - the contract ID of the underlying security
+    #[instrument(skip(self))]
+    pub async fn request_sec_def_opt_params(
+        &self,
+        underlying_symbol: &str,
+        fut_fop_exchange: &str,
+        underlying_sec_type: SecType,
+        underlying_con_id: i32,
+    ) -> Result<Vec<OptionChain>> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestSecDefOptParams::new(
+            req_id,
+            underlying_symbol.to_string(),
+            fut_fop_exchange.to_string(),
+            underlying_sec_type,
+            underlying_con_id,
+        );
+        debug!(request = ?frame);
+        // route the option chains to this request
+        let mut responses =
+            self.subscribe::<ResponseWithId<OptionChain>>(req_id, RouteKind::UntilEnd)?;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        let mut chains = Vec::new();
+        while let Some(chain) = self.recv_response(responses.recv()).await?.response {
+            chains.push(chain);
+        }
+        Ok(chains)
+    }
+
+    /// Requests contracts whose symbol or company name matches `pattern`.
+    ///
+    /// # Arguments
+    /// * pattern - either start of ticker symbol or (for larger strings)
+    ///   company name
+    #[instrument(skip(self))]
+    pub async fn request_matching_symbols(&self, pattern: &str) -> Result<ContractDescriptionList> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestMatchingSymbols::new(req_id, pattern.to_string());
+        debug!(request = ?frame);
+        // route the symbol samples to this request
+        let mut response = self.subscribe(req_id, RouteKind::Once)?;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        self.recv_response(response.recv()).await
+    }
 }
@@ -22,7 +22,7 @@ impl Client {
     /// NOTE: Time format must be 'yyyymmdd-hh:mm:ss' Eg: '20030702-14:55'
     #[tracing::instrument(skip(self))]
     pub async fn request_executions(
-        &mut self,
+        &self,
         req_id: RequestId,
         exec_filter: Option<ExecutionFilter>,
     ) -> Result<()> {
@@ -0,0 +1,95 @@
+use tracing::debug;
+
+use super::{Client, RouteKind};
+use crate::{cmd::{RequestFundamentalData, RequestWsh},
+            contract::Contract,
+            enums::FundamentalDataType,
+            frame::Frame,
+            wsh::{WshEventData, WshEventDataFilter},
+            RequestId,
+            Result};
+
+impl Client {
+    /// Requests Reuters' fundamental data of a contract and returns the XML
+    /// report.
+    ///
+    /// Dropping the returned future before the report arrived cancels the
+    /// request.
+    ///
+    /// # Arguments
+    /// * contract - this structure contains a description of the contract for
+    ///   which fundamental data is being requested.
+    /// * report_type - the type of report requested
+    #[tracing::instrument(skip(self))]
+    pub async fn request_fundamental_data(
+        &self,
+        contract: Contract,
+        report_type: FundamentalDataType,
+    ) -> Result<String> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestFundamentalData::Request {
+            req_id,
+            contract,
+            report_type,
+            options: Vec::new(),
+        };
+
+        debug!(request = ?frame);
+
+        let cancel = RequestFundamentalData::Cancel { req_id }.into_frame();
+        self.request_payload(req_id, frame.into_frame(), cancel)
+            .await
+    }
+
+    /// Requests the Wall Street Horizon meta data, i.e. the available event
+    /// types and filters, as JSON.
+    ///
+    /// Dropping the returned future before the meta data arrived cancels the
+    /// request.
+    #[tracing::instrument(skip(self))]
+    pub async fn request_wsh_meta_data(&self) -> Result<String> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestWsh::MetaData { req_id };
+
+        debug!(request = ?frame);
+
+        let cancel = RequestWsh::CancelMetaData { req_id }.into_frame(self.server_version())?;
+        self.request_payload(req_id, frame.into_frame(self.server_version())?, cancel)
+            .await
+    }
+
+    /// Requests Wall Street Horizon event data, e.g. earnings dates.
+    ///
+    /// Use [`WshEventData::events`] to decode the returned JSON. Dropping the
+    /// returned future before the event data arrived cancels the request.
+    #[tracing::instrument(skip(self))]
+    pub async fn request_wsh_event_data(&self, filter: WshEventDataFilter) -> Result<WshEventData> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestWsh::EventData { req_id, filter };
+
+        debug!(request = ?frame);
+
+        let cancel = RequestWsh::CancelEventData { req_id }.into_frame(self.server_version())?;
+        let data_json = self
+            .request_payload(req_id, frame.into_frame(self.server_version())?, cancel)
+            .await?;
+        Ok(WshEventData { req_id, data_json })
+    }
+
+    /// Sends `frame` and waits for the text payload answering `req_id`.
+    ///
+    /// `cancel` is written if the caller stops waiting before the answer
+    /// arrived.
+    async fn request_payload(
+        &self,
+        req_id: RequestId,
+        frame: Frame,
+        cancel: Frame,
+    ) -> Result<String> {
+        let response = self.subscribe(req_id, RouteKind::Once)?;
+        // Write the frame to the socket
+        self.writer.write_frame(&frame).await?;
+        let mut response = self.cancel_on_drop(response, cancel);
+        self.recv_response(response.recv()).await
+    }
+}
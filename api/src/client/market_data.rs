@@ -1,12 +1,12 @@
-use chrono::Utc;
-
-use crate::{cmd::request_market_data::*,
+use super::{RouteKind, Subscription, SubscriptionKey};
+use crate::{bars::{HistoricalBars, RealtimeBar},
+            cmd::request_market_data::*,
             prelude::{Client, IntoIbkrFrame},
-            ticker::MarketDataTracker,
+            ticker::{HeadTimestamp, HistoricalTicks, MarketDataTracker, MarketDepthUpdate, Tick},
             Result};
 
 impl Client {
-    pub fn subscribe_market_data_updates(&mut self) -> MarketDataTracker {
+    pub fn subscribe_market_data_updates(&self) -> MarketDataTracker {
         self.market_data_tracker.clone()
     }
 
@@ -19,11 +19,28 @@ impl Client {
     ///   "MidPoint".
     /// * number_of_ticks	- number of ticks.
     /// * ignore_size	- ignore size flag./
+    ///
+    /// Returns a stream of the ticks, dropping it cancels the request.
     #[tracing::instrument(skip(self))]
-    pub async fn request_tick_by_tick_data(&mut self, request: &TickByTickRequest) -> Result<()> {
+    pub async fn request_tick_by_tick_data(
+        &self,
+        request: &TickByTickRequest,
+    ) -> Result<Subscription<Tick>> {
+        let subscription = self.subscribe(request.req_id, RouteKind::Live)?;
+        let frame = request.into_frame(self.server_version())?;
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
-        Ok(())
+        self.writer.write_frame(&frame).await?;
+        self.record_subscription_time(request.req_id);
+        let resubscribe = request.clone();
+        self.track_subscription(
+            SubscriptionKey::Request(request.req_id),
+            move |server_version| resubscribe.into_frame(server_version),
+        );
+        let cancel = CancelTickByTickRequest {
+            req_id: request.req_id,
+        }
+        .into_frame(self.server_version())?;
+        Ok(self.cancel_on_drop(subscription, cancel))
     }
 
     /// Cancel tick by tick data
@@ -31,12 +48,14 @@ impl Client {
     /// # Arguments
     /// * req_id	- The identifier of the original request.
     #[tracing::instrument(skip(self))]
-    pub async fn cancel_tick_by_tick_data(
-        &mut self,
-        request: &CancelTickByTickRequest,
-    ) -> Result<()> {
+    pub async fn cancel_tick_by_tick_data(&self, request: &CancelTickByTickRequest) -> Result<()> {
+        self.registry.finish(request.req_id);
+        self.untrack_subscription(SubscriptionKey::Request(request.req_id));
+        self.wait_before_unsubscribe(request.req_id).await;
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
@@ -60,13 +79,45 @@ impl Client {
     /// * regulatory_snapshot - With the US Value Snapshot Bundle for stocks,
     ///   regulatory snapshots are available for 0.01 USD each.
     /// * mkt_data_options - For internal use only. Use default value XYZ.
+    ///
+    /// Returns a stream of the ticks. A snapshot ends after the last tick,
+    /// otherwise dropping the stream cancels the subscription.
     #[tracing::instrument(skip(self))]
-    pub async fn request_market_data(&mut self, request: &MarketDataRequest) -> Result<()> {
-        self.subscriptions_by_time
-            .insert(request.req_id, Utc::now());
+    pub async fn request_market_data(
+        &self,
+        request: &MarketDataRequest,
+    ) -> Result<Subscription<Tick>> {
+        if request.snapshot {
+            return self.request_market_data_snapshot(request).await;
+        }
+        let subscription = self.subscribe(request.req_id, RouteKind::Live)?;
+        let frame = request.into_frame(self.server_version())?;
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
-        Ok(())
+        self.writer.write_frame(&frame).await?;
+        self.record_subscription_time(request.req_id);
+        let resubscribe = request.clone();
+        self.track_subscription(
+            SubscriptionKey::Request(request.req_id),
+            move |server_version| resubscribe.into_frame(server_version),
+        );
+        let cancel = CancelMarketDataRequest {
+            req_id: request.req_id,
+        }
+        .into_frame(self.server_version())?;
+        Ok(self.cancel_on_drop(subscription, cancel))
+    }
+
+    /// Requests a single snapshot of market data, TWS ends the request itself.
+    async fn request_market_data_snapshot(
+        &self,
+        request: &MarketDataRequest,
+    ) -> Result<Subscription<Tick>> {
+        let subscription = self.subscribe(request.req_id, RouteKind::UntilEnd)?;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
+        Ok(subscription)
     }
 
     /// After calling this function, market data for the specified id will stop
@@ -75,33 +126,40 @@ impl Client {
     /// # Arguments
     /// * req_id - The ID that was specified in the call to req_mkt_data()
     #[tracing::instrument(skip(self))]
-    pub async fn cancel_market_data(&mut self, request: &CancelMarketDataRequest) -> Result<()> {
-        // when unsubscribing symbols immediately after subscribing IB returns an error
-        // (Can't find EId with tickerId:nnn), so we track subscription times to
-        // ensure symbols are not unsubscribed before a minimum time span has elapsed
-        if let Some(subscription_time) = self.subscriptions_by_time.get(&request.req_id) {
-            let time_since_subscription = Utc::now() - *subscription_time;
-            if time_since_subscription < self.min_timespan_before_unsubscribe {
-                let delay = self.min_timespan_before_unsubscribe - time_since_subscription;
-                tokio::time::sleep(std::time::Duration::from_millis(
-                    delay.num_milliseconds() as u64
-                ))
-                .await;
-            }
-            // convert the command into a frame
-            // Write the frame to the socket
-            self.writer.write_frame(&request.into_frame()).await?;
-        }
+    pub async fn cancel_market_data(&self, request: &CancelMarketDataRequest) -> Result<()> {
+        self.registry.finish(request.req_id);
+        self.untrack_subscription(SubscriptionKey::Request(request.req_id));
+        self.wait_before_unsubscribe(request.req_id).await;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
+    /// Requests real time bars.
+    ///
+    /// Returns a stream of the bars, dropping it cancels the request.
     #[tracing::instrument(skip(self))]
-    pub async fn request_realtime_bars(&mut self, request: &RealtimeBarRequest) -> Result<()> {
-        self.subscriptions_by_time
-            .insert(request.req_id, Utc::now());
+    pub async fn request_realtime_bars(
+        &self,
+        request: &RealtimeBarRequest,
+    ) -> Result<Subscription<RealtimeBar>> {
+        let subscription = self.subscribe(request.req_id, RouteKind::Live)?;
+        let frame = request.into_frame(self.server_version())?;
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
-        Ok(())
+        self.writer.write_frame(&frame).await?;
+        self.record_subscription_time(request.req_id);
+        let resubscribe = request.clone();
+        self.track_subscription(
+            SubscriptionKey::Request(request.req_id),
+            move |server_version| resubscribe.into_frame(server_version),
+        );
+        let cancel = CancelRealtimeBars {
+            req_id: request.req_id,
+        }
+        .into_frame(self.server_version())?;
+        Ok(self.cancel_on_drop(subscription, cancel))
     }
 
     /// After calling this function, market data for the specified id will stop
@@ -110,23 +168,14 @@ impl Client {
     /// # Arguments
     /// * req_id - The ID that was specified in the call to req_mkt_data()
     #[tracing::instrument(skip(self))]
-    pub async fn cancel_realtime_bars(&mut self, request: &CancelRealtimeBars) -> Result<()> {
-        // when unsubscribing symbols immediately after subscribing IB returns an error
-        // (Can't find EId with tickerId:nnn), so we track subscription times to
-        // ensure symbols are not unsubscribed before a minimum time span has elapsed
-        if let Some(subscription_time) = self.subscriptions_by_time.get(&request.req_id) {
-            let time_since_subscription = Utc::now() - *subscription_time;
-            if time_since_subscription < self.min_timespan_before_unsubscribe {
-                let delay = self.min_timespan_before_unsubscribe - time_since_subscription;
-                tokio::time::sleep(std::time::Duration::from_millis(
-                    delay.num_milliseconds() as u64
-                ))
-                .await;
-            }
-            // convert the command into a frame
-            // Write the frame to the socket
-            self.writer.write_frame(&request.into_frame()).await?;
-        }
+    pub async fn cancel_realtime_bars(&self, request: &CancelRealtimeBars) -> Result<()> {
+        self.registry.finish(request.req_id);
+        self.untrack_subscription(SubscriptionKey::Request(request.req_id));
+        self.wait_before_unsubscribe(request.req_id).await;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
@@ -143,22 +192,21 @@ impl Client {
     /// * 1 for real-time streaming market data
     /// * 2 for frozen market data
     #[tracing::instrument(skip(self))]
-    pub async fn request_market_data_type(
-        &mut self,
-        request: &MarketDataTypeRequest,
-    ) -> Result<()> {
+    pub async fn request_market_data_type(&self, request: &MarketDataTypeRequest) -> Result<()> {
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
     /// Requests venues for which market data is returned to update_mkt_depth_l2
     /// (those with market makers)
     #[tracing::instrument(level = "debug", skip(self))]
-    pub async fn request_market_depth_exchanges(&mut self) -> Result<()> {
+    pub async fn request_market_depth_exchanges(&self) -> Result<()> {
         // Write the frame to the socket
         self.writer
-            .write_frame(&MarketDepthExchangesRequest.into_frame())
+            .write_frame(&MarketDepthExchangesRequest.into_frame(self.server_version())?)
             .await?;
         Ok(())
     }
@@ -185,11 +233,30 @@ impl Client {
     ///   FALSE!!!!! THERE SEEMS TO BE A BUG ON IB's SIDE AND THEY WILL STOP
     ///   STREAMING DATA IF THIS IS SET TO TRUE
     /// * mkt_depth_options - For internal use only. Use default value XYZ.
+    ///
+    /// Returns a stream of the depth updates, dropping it cancels the
+    /// request.
     #[tracing::instrument(level = "debug", skip(self))]
-    pub async fn request_market_depth(&mut self, request: &MarketDepthRequest) -> Result<()> {
+    pub async fn request_market_depth(
+        &self,
+        request: &MarketDepthRequest,
+    ) -> Result<Subscription<MarketDepthUpdate>> {
+        let subscription = self.subscribe(request.req_id, RouteKind::Live)?;
+        let frame = request.into_frame(self.server_version())?;
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
-        Ok(())
+        self.writer.write_frame(&frame).await?;
+        self.record_subscription_time(request.req_id);
+        let resubscribe = request.clone();
+        self.track_subscription(
+            SubscriptionKey::Request(request.req_id),
+            move |server_version| resubscribe.into_frame(server_version),
+        );
+        let cancel = CancelMarketDepthRequest {
+            req_id:         request.req_id,
+            is_smart_depth: request.is_smart_depth,
+        }
+        .into_frame(self.server_version())?;
+        Ok(self.cancel_on_drop(subscription, cancel))
     }
 
     /// After calling this function, market depth data for the specified id
@@ -199,9 +266,88 @@ impl Client {
     /// * req_id - The ID that was specified in the call to req_mkt_depth().
     //  * is_smart_depth - specifies SMART depth request
     #[tracing::instrument(level = "debug", skip(self))]
-    pub async fn cancel_market_depth(&mut self, request: &CancelMarketDepthRequest) -> Result<()> {
+    pub async fn cancel_market_depth(&self, request: &CancelMarketDepthRequest) -> Result<()> {
+        self.registry.finish(request.req_id);
+        self.untrack_subscription(SubscriptionKey::Request(request.req_id));
+        self.wait_before_unsubscribe(request.req_id).await;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    /// Calculates the implied volatility based on the hypothetical option and
+    /// its underlying prices. The result is delivered as a
+    /// `Tick::TickOptionComputation` on the tick_by_tick channel.
+    ///
+    /// # Arguments
+    /// * req_id - The request id.
+    /// * contract - Describes the contract.
+    /// * option_price - The price of the option.
+    /// * under_price - Price of the underlying.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn calculate_implied_volatility(
+        &self,
+        request: &CalculateImpliedVolatilityRequest,
+    ) -> Result<()> {
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    /// Cancels an option's implied volatility calculation request.
+    ///
+    /// # Arguments
+    /// * req_id - The request id specified in calculate_implied_volatility().
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn cancel_calculate_implied_volatility(
+        &self,
+        request: &CancelCalculateImpliedVolatilityRequest,
+    ) -> Result<()> {
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    /// Calculates an option's price based on the provided volatility and its
+    /// underlying's price. The result is delivered as a
+    /// `Tick::TickOptionComputation` on the tick_by_tick channel.
+    ///
+    /// # Arguments
+    /// * req_id - The request id.
+    /// * contract - Describes the contract.
+    /// * volatility - The volatility.
+    /// * under_price - Price of the underlying.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn calculate_option_price(
+        &self,
+        request: &CalculateOptionPriceRequest,
+    ) -> Result<()> {
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    /// Cancels an option's price calculation request.
+    ///
+    /// # Arguments
+    /// * req_id - The request id specified in calculate_option_price().
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn cancel_calculate_option_price(
+        &self,
+        request: &CancelCalculateOptionPriceRequest,
+    ) -> Result<()> {
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
@@ -268,11 +414,44 @@ impl Client {
     ///     * 2 - dates are returned as a long integer specifying the number of
     ///       seconds since 1/1/1970 GMT.
     /// *chart_options: - For internal use only. Use default value XYZ.
+    ///
+    /// Returns a stream of the bars. Without `keep_up_to_date` it ends after
+    /// the requested bars, otherwise the updates follow until the stream is
+    /// dropped or the request is cancelled with `cancel_historical_data`.
+    /// Requests for the trading
+    /// schedule are answered on `MarketDataTracker::historical_schedule`.
     #[tracing::instrument(level = "debug", skip(self))]
-    pub async fn request_historical_data(&mut self, request: &HistoricalDataRequest) -> Result<()> {
+    pub async fn request_historical_data(
+        &self,
+        request: &HistoricalDataRequest,
+    ) -> Result<Subscription<HistoricalBars>> {
+        let kind = if request.keep_up_to_date {
+            RouteKind::Live
+        } else {
+            RouteKind::Once
+        };
+        let subscription = self.subscribe(request.req_id, kind)?;
+        let frame = request.into_frame(self.server_version())?;
+        // delay the request if it would violate the historical data pacing
+        self.historical_pacer
+            .acquire(request, self.server_version())
+            .await?;
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
-        Ok(())
+        self.writer.write_frame(&frame).await?;
+        if !request.keep_up_to_date {
+            return Ok(subscription);
+        }
+        self.record_subscription_time(request.req_id);
+        let resubscribe = request.clone();
+        self.track_subscription(
+            SubscriptionKey::Request(request.req_id),
+            move |server_version| resubscribe.into_frame(server_version),
+        );
+        let cancel = CancelHistoricalDataRequest {
+            req_id: request.req_id,
+        }
+        .into_frame(self.server_version())?;
+        Ok(self.cancel_on_drop(subscription, cancel))
     }
 
     //----------------------------------------------------------------------------------------------
@@ -284,11 +463,16 @@ impl Client {
     /// * req_id - the id of the original request
     #[tracing::instrument(level = "debug", skip(self))]
     pub async fn cancel_historical_data(
-        &mut self,
+        &self,
         request: &CancelHistoricalDataRequest,
     ) -> Result<()> {
+        self.registry.finish(request.req_id);
+        self.untrack_subscription(SubscriptionKey::Request(request.req_id));
+        self.wait_before_unsubscribe(request.req_id).await;
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
@@ -304,11 +488,25 @@ impl Client {
     // to 2 to obtain it like system time format in seconds ///
     // /// Note that formatData parameter affects intraday bars only
     // /// 1-day bars always return with date in YYYYMMDD format
+    /// Requests the timestamp of the earliest available historical data.
+    ///
+    /// Returns a stream of the single answer, the request is cancelled once
+    /// the stream is dropped.
     #[tracing::instrument(level = "debug", skip(self))]
-    pub async fn request_head_time_stamp(&mut self, request: &HeadTimestampRequest) -> Result<()> {
+    pub async fn request_head_time_stamp(
+        &self,
+        request: &HeadTimestampRequest,
+    ) -> Result<Subscription<HeadTimestamp>> {
+        let subscription = self.subscribe(request.req_id, RouteKind::Once)?;
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
-        Ok(())
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
+        let cancel = CancelHeadTimestampRequest {
+            req_id: request.req_id,
+        }
+        .into_frame(self.server_version())?;
+        Ok(self.cancel_on_drop(subscription, cancel))
     }
 
     // //----------------------------------------------------------------------------------------------
@@ -317,12 +515,12 @@ impl Client {
     // /// # Arguments
     // /// * req_id - the id of the original request
     #[tracing::instrument(level = "debug", skip(self))]
-    pub async fn cancel_head_time_stamp(
-        &mut self,
-        request: &CancelHeadTimestampRequest,
-    ) -> Result<()> {
+    pub async fn cancel_head_time_stamp(&self, request: &CancelHeadTimestampRequest) -> Result<()> {
+        self.registry.finish(request.req_id);
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
+        self.writer
+            .write_frame(&request.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
@@ -336,7 +534,7 @@ impl Client {
     // /// * time_period - period of which data is being requested, e.g. "3 days"
     // #[tracing::instrument(level = "debug", skip(self))]
     // pub fn req_histogram_data(
-    //     &mut self,
+    //     &self,
     //     ticker_id: i32,
     //     contract: &Contract,
     //     use_rth: bool,
@@ -355,7 +553,7 @@ impl Client {
     // ///
     // /// # Arguments
     // /// * req_id - the id of the original request
-    // pub fn cancel_histogram_data(&mut self, ticker_id: i32) -> Result<(),
+    // pub fn cancel_histogram_data(&self, ticker_id: i32) -> Result<(),
     // IBKRApiLibError> { // convert the command into a frame
     // ;
 
@@ -381,13 +579,21 @@ impl Client {
     ///   (0)
     /// * ignore_size - A filter only used when the source price is Bid_Ask
     /// * misc_options - should be defined as null, reserved for internal use
+    ///
+    /// Returns a stream of the ticks, which ends after the last batch.
     #[tracing::instrument(level = "debug", skip(self))]
     pub async fn request_historical_ticks(
-        &mut self,
+        &self,
         request: &HistoricalTicksRequest,
-    ) -> Result<()> {
+    ) -> Result<Subscription<HistoricalTicks>> {
+        let subscription = self.subscribe(request.req_id, RouteKind::UntilEnd)?;
+        let frame = request.into_frame(self.server_version())?;
+        // delay the request if it would violate the historical data pacing
+        self.historical_pacer
+            .acquire(request, self.server_version())
+            .await?;
         // Write the frame to the socket
-        self.writer.write_frame(&request.into_frame()).await?;
-        Ok(())
+        self.writer.write_frame(&frame).await?;
+        Ok(subscription)
     }
 }
@@ -0,0 +1,149 @@
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use super::{Client, Request, RouteKind};
+use crate::{cmd::{RequestNews, TagValue},
+            news::{HistoricalNewsHeadlines,
+                   HistoricalNewsUpdate,
+                   NewsArticle,
+                   NewsProvider,
+                   NewsTracker},
+            Result,
+            TimeStamp};
+
+impl Client {
+    pub fn subscribe_news(&self) -> NewsTracker { self.news_tracker.clone() }
+
+    /// Requests news sources subscribed to by the user.
+    #[tracing::instrument(skip(self))]
+    pub async fn request_news_providers(&self) -> Result<Vec<NewsProvider>> {
+        let frame = RequestNews::Providers;
+
+        debug!(request = ?frame);
+
+        let (rep_tx, mut rep_rx) = mpsc::unbounded_channel();
+        self.register(Request::NewsProviders { sender: rep_tx })
+            .await?;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        self.recv_response(rep_rx.recv()).await
+    }
+
+    /// Requests the body of a news article.
+    ///
+    /// # Arguments
+    /// * provider_code - short code indicating news provider, e.g. FLY
+    /// * article_id - id of the specific article
+    /// * options - reserved for internal use. Should be defined as null.
+    #[tracing::instrument(skip(self))]
+    pub async fn request_news_article(
+        &self,
+        provider_code: &str,
+        article_id: &str,
+        options: Vec<TagValue>,
+    ) -> Result<NewsArticle> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestNews::Article {
+            req_id,
+            provider_code: provider_code.to_string(),
+            article_id: article_id.to_string(),
+            options,
+        };
+
+        debug!(request = ?frame);
+
+        let mut response = self.subscribe(req_id, RouteKind::Once)?;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        self.recv_response(response.recv()).await
+    }
+
+    /// Requests historical news headlines.
+    ///
+    /// # Arguments
+    /// * con_id - contract id of ticker
+    /// * provider_codes - list of provider codes, e.g. ["BRFG", "DJNL"]
+    /// * start - marks the (exclusive) start of the date range
+    /// * end - marks the (inclusive) end of the date range
+    /// * total_results - the maximum number of headlines to fetch (1 - 300)
+    /// * options - reserved for internal use. Should be defined as null.
+    #[tracing::instrument(skip(self))]
+    pub async fn request_historical_news(
+        &self,
+        con_id: i32,
+        provider_codes: Vec<String>,
+        start: Option<TimeStamp>,
+        end: Option<TimeStamp>,
+        total_results: i32,
+        options: Vec<TagValue>,
+    ) -> Result<HistoricalNewsHeadlines> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestNews::Historical {
+            req_id,
+            con_id,
+            provider_codes,
+            start,
+            end,
+            total_results,
+            options,
+        };
+
+        debug!(request = ?frame);
+
+        let mut updates = self.subscribe(req_id, RouteKind::UntilEnd)?;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        let mut headlines = Vec::new();
+        loop {
+            match self.recv_response(updates.recv()).await? {
+                HistoricalNewsUpdate::Headline(news) => headlines.push(news),
+                HistoricalNewsUpdate::End { has_more } => {
+                    return Ok(HistoricalNewsHeadlines {
+                        headlines,
+                        has_more,
+                    });
+                },
+            }
+        }
+    }
+
+    /// Subscribes to IB's news bulletins.
+    ///
+    /// The bulletins are delivered on `NewsTracker::bulletins`.
+    ///
+    /// # Arguments
+    /// * all_messages - if set to true, will return all the existing bulletins
+    ///   for the current day, set to false to receive only the new bulletins.
+    #[tracing::instrument(skip(self))]
+    pub async fn request_news_bulletins(&self, all_messages: bool) -> Result<()> {
+        let frame = RequestNews::Bulletins { all_messages };
+
+        debug!(request = ?frame);
+
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    /// Cancels IB's news bulletin subscription.
+    #[tracing::instrument(skip(self))]
+    pub async fn cancel_news_bulletins(&self) -> Result<()> {
+        let frame = RequestNews::CancelBulletins;
+
+        debug!(request = ?frame);
+
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+}
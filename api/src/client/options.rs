@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+use super::ReconnectPolicy;
+use crate::{enums::constants::{MAX_CLIENT_VER, MIN_CLIENT_VER},
+            recorder::Recorder,
+            AccountCode,
+            Error,
+            Result,
+            ServerVersion};
+
+/// Options of the connection to TWS / IB Gateway, passed to
+/// [`connect_with_options`](fn@super::connect_with_options).
+///
+/// ```no_run
+/// # async fn example() -> ibkr_rust_api::Result<()> {
+/// use std::time::Duration;
+///
+/// use ibkr_rust_api::client::{self, ConnectOptions};
+///
+/// let options = ConnectOptions::default()
+///     .optional_capabilities("MyCapabilities")
+///     .connect_timeout(Duration::from_secs(5));
+/// let client = client::connect_with_options("localhost:4002", 1, options).await?;
+/// # drop(client);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectOptions {
+    pub(crate) min_client_version:    i32,
+    pub(crate) max_client_version:    i32,
+    pub(crate) connection_options:    String,
+    pub(crate) optional_capabilities: Option<String>,
+    pub(crate) connect_timeout:       Duration,
+    pub(crate) handshake_timeout:     Duration,
+    pub(crate) reconnect_policy:      Option<ReconnectPolicy>,
+    pub(crate) recorder:              Option<Recorder>,
+}
+
+impl Default for ConnectOptions {
+    /// The full range of the supported client versions, TWS paces the API
+    /// messages ("+PACEAPI") and the connection is not re-established once
+    /// it was lost. The messages are not recorded.
+    fn default() -> Self {
+        ConnectOptions {
+            min_client_version:    MIN_CLIENT_VER,
+            max_client_version:    MAX_CLIENT_VER,
+            connection_options:    "+PACEAPI".to_string(),
+            optional_capabilities: None,
+            connect_timeout:       Duration::from_secs(10),
+            handshake_timeout:     Duration::from_secs(10),
+            reconnect_policy:      None,
+            recorder:              None,
+        }
+    }
+}
+
+impl ConnectOptions {
+    /// Restricts the client versions offered to TWS, both have to lie
+    /// within `MIN_CLIENT_VER..=MAX_CLIENT_VER`.
+    pub const fn client_versions(mut self, min: i32, max: i32) -> Self {
+        self.min_client_version = min;
+        self.max_client_version = max;
+        self
+    }
+
+    /// Sets the connection options sent in the handshake, e.g. "+PACEAPI".
+    /// An empty string sends no options.
+    pub fn connection_options(mut self, options: impl Into<String>) -> Self {
+        self.connection_options = options.into();
+        self
+    }
+
+    /// Sets the optional capabilities sent when starting the API.
+    pub fn optional_capabilities(mut self, capabilities: impl Into<String>) -> Self {
+        self.optional_capabilities = Some(capabilities.into());
+        self
+    }
+
+    /// Sets the maximum time to wait until the TCP connection is established.
+    pub const fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Sets the maximum time to wait for the server version after the
+    /// handshake was sent.
+    pub const fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Re-establishes the connection according to `policy` whenever it is
+    /// lost, see [`connect_with_reconnect`](fn@super::connect_with_reconnect).
+    pub const fn reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = Some(policy);
+        self
+    }
+
+    /// Records the messages of the connection, including those of later
+    /// reconnects, to the capture of `recorder`.
+    pub fn record(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Checks the client versions are within the range supported by the
+    /// encoders of the crate.
+    pub fn validate(&self) -> Result<()> {
+        let (min, max) = (self.min_client_version, self.max_client_version);
+        if min > max || min < MIN_CLIENT_VER || max > MAX_CLIENT_VER {
+            return Err(Error::InvalidOptions(format!(
+                "client versions {min}..{max} are not within the supported versions \
+                 {MIN_CLIENT_VER}..{MAX_CLIENT_VER}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Information about the connection received during the handshake.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    pub server_version:   ServerVersion,
+    /// the time TWS accepted the connection, e.g. "20240102 09:30:00 EST"
+    pub connection_time:  String,
+    /// the accounts accessible by the connection, `None` until TWS sent them
+    /// after the API was started
+    pub managed_accounts: Option<Vec<AccountCode>>,
+}
+
+/// Splits the comma separated list of the `ManagedAccts` message.
+pub fn parse_managed_accounts(accounts: &str) -> Vec<AccountCode> {
+    accounts
+        .split(',')
+        .map(str::trim)
+        .filter(|account| !account.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn client_versions_are_validated() {
+        assert!(ConnectOptions::default().validate().is_ok());
+        assert!(ConnectOptions::default()
+            .client_versions(MIN_CLIENT_VER, 151)
+            .validate()
+            .is_ok());
+        let error = ConnectOptions::default()
+            .client_versions(100, 176)
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            "invalid connect options: client versions 100..176 are not within the supported \
+             versions 100..163",
+            error.to_string()
+        );
+        assert!(ConnectOptions::default()
+            .client_versions(160, 150)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn managed_accounts_are_split() {
+        assert_eq!(
+            vec!["DU123".to_string(), "DU456".to_string()],
+            parse_managed_accounts("DU123,DU456,")
+        );
+        assert!(parse_managed_accounts("").is_empty());
+    }
+}
@@ -3,45 +3,51 @@ use tracing::{debug, instrument};
 
 use super::{Client, Request};
 use crate::{
-    cmd::{PlaceOrder, RequestOrders},
+    cmd::{CancelOrder, GlobalCancel, PlaceOrder, RequestOrders},
     order::{Order, OrderTracker},
     OrderId, Result,
 };
 impl Client {
-    pub fn subscribe_orders(&mut self) -> OrderTracker {
+    pub fn subscribe_orders(&self) -> OrderTracker {
         self.order_tracker.clone()
     }
 
     #[instrument(skip(self))]
-    pub async fn request_completed_orders(&mut self, api_only: bool) -> Result<()> {
+    pub async fn request_completed_orders(&self, api_only: bool) -> Result<()> {
         let frame = RequestOrders::Completed { api_only };
 
         debug!(request = ?frame);
 
         // Write the frame to the socket
-        self.writer.write_frame(&frame.into_frame()).await?;
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
     #[instrument(skip(self))]
-    pub async fn request_all_open_orders(&mut self) -> Result<()> {
+    pub async fn request_all_open_orders(&self) -> Result<()> {
         let frame = RequestOrders::AllOpen;
 
         debug!(request = ?frame);
 
         // Write the frame to the socket
-        self.writer.write_frame(&frame.into_frame()).await?;
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
     #[instrument(skip(self))]
-    pub async fn request_auto_open_orders(&mut self, auto_bind: bool) -> Result<()> {
+    pub async fn request_auto_open_orders(&self, auto_bind: bool) -> Result<()> {
         let frame = RequestOrders::AutoOpen { auto_bind };
 
         debug!(request = ?frame);
 
         // Write the frame to the socket
-        self.writer.write_frame(&frame.into_frame()).await?;
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
@@ -54,43 +60,87 @@ impl Client {
     ///   placed with an order ID less than or equal to the order ID of a
     ///   previous order an error will occur.
     /// * 'order'   the order
-    pub async fn place_order(&mut self, order_id: OrderId, order: Order) -> Result<()> {
+    pub async fn place_order(&self, order_id: OrderId, order: Order) -> Result<()> {
         let frame = PlaceOrder::new(order_id, order);
 
         debug!(request = ?frame);
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    /// Cancels an active order placed from the same API client ID.
+    ///
+    /// The result of the cancellation is reported as an `OrderStatusUpdate`
+    /// on the `order_status` channel of the `OrderTracker`.
+    ///
+    /// # Arguments
+    ///
+    /// * 'order_id'  -   the order's unique identifier.
+    /// * 'manual_order_cancel_time'    the time the order should be cancelled.
+    ///   `None` cancels the order immediately.
+    #[instrument(skip(self))]
+    pub async fn cancel_order(
+        &self,
+        order_id: OrderId,
+        manual_order_cancel_time: Option<String>,
+    ) -> Result<()> {
+        let frame = CancelOrder::new(order_id, manual_order_cancel_time);
+
+        debug!(request = ?frame);
+
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    /// Cancels all open orders, including those placed directly in TWS or by
+    /// other API clients.
+    ///
+    /// The resulting status changes are reported on the `order_status`
+    /// channel of the `OrderTracker`.
+    #[instrument(skip(self))]
+    pub async fn global_cancel(&self) -> Result<()> {
+        let frame = GlobalCancel;
+
+        debug!(request = ?frame);
+
         // Write the frame to the socket
         self.writer.write_frame(&frame.into_frame()).await?;
         Ok(())
     }
 
     /// Requests the next valid order ID at the current moment.
-    pub async fn request_ids(&mut self) -> Result<()> {
+    pub async fn request_ids(&self) -> Result<()> {
         let frame = RequestOrders::NextOrderId;
 
         debug!(request = ?frame);
 
         // Write the frame to the socket
-        self.writer.write_frame(&frame.into_frame()).await?;
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
         Ok(())
     }
 
     /// Requests the next valid order ID at the current moment.
-    pub async fn get_next_valid_order_id(&mut self) -> Result<OrderId> {
+    pub async fn get_next_valid_order_id(&self) -> Result<OrderId> {
         let frame = RequestOrders::NextOrderId;
 
         debug!(request = ?frame);
 
-        let (rep_tx, mut rep_rx) = mpsc::channel(8);
-        self.subscribe_handler_tx
-            .send(Request::OrderId { sender: rep_tx })
-            .await?;
+        let (rep_tx, mut rep_rx) = mpsc::unbounded_channel();
+        self.register(Request::OrderId { sender: rep_tx }).await?;
         // Write the frame to the socket
-        self.writer.write_frame(&frame.into_frame()).await?;
-        let mut next_order_id: Option<OrderId> = None;
-        while let Some(Some(detail)) = rep_rx.recv().await {
-            tracing::trace!("Received order id: {:?}", detail);
-            next_order_id = Some(detail);
-        }
-        next_order_id.ok_or_else(|| "Returned None".into())
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        let next_order_id = self.recv_response(rep_rx.recv()).await?;
+        tracing::trace!("Received order id: {:?}", next_order_id);
+        Ok(next_order_id)
     }
 }
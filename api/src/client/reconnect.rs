@@ -0,0 +1,181 @@
+use std::{collections::BTreeMap,
+          convert::TryFrom,
+          fmt,
+          net::SocketAddr,
+          sync::{Arc, Mutex, MutexGuard, PoisonError},
+          time::Duration};
+
+use super::ConnectOptions;
+use crate::{frame::Frame, ClientId, RequestId, Result, ServerVersion};
+
+/// Controls how the connection handler reconnects after TWS / IB Gateway
+/// closed the connection, e.g. during the daily server reset.
+///
+/// The delay before the n-th attempt is `initial_delay * multiplier^n`,
+/// capped at `max_delay`. With `max_attempts` set to `None` the handler
+/// retries until the client is shut down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay:     Duration,
+    pub multiplier:    u32,
+    pub max_attempts:  Option<usize>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            initial_delay: Duration::from_secs(1),
+            max_delay:     Duration::from_secs(60),
+            multiplier:    2,
+            max_attempts:  None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Returns the delay before the reconnect attempt `attempt`, starting
+    /// with 0.
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let factor = u32::try_from(attempt)
+            .ok()
+            .and_then(|exp| self.multiplier.checked_pow(exp))
+            .unwrap_or(u32::MAX);
+        self.initial_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Returns `true` if another reconnect attempt is allowed after
+    /// `attempts` failed ones.
+    pub const fn allows(&self, attempts: usize) -> bool {
+        !matches!(self.max_attempts, Some(max) if attempts >= max)
+    }
+}
+
+/// Everything the connection handler needs to re-establish a lost
+/// connection.
+#[derive(Debug, Clone)]
+pub struct Reconnect {
+    /// the resolved addresses of TWS / IB Gateway
+    pub addrs:     Vec<SocketAddr>,
+    pub client_id: ClientId,
+    pub policy:    ReconnectPolicy,
+    /// the options the handshake is repeated with
+    pub options:   ConnectOptions,
+}
+
+/// Identifies a live subscription that has to be re-issued after a
+/// reconnect.
+///
+/// Account updates and positions are not tied to a request id, TWS only
+/// keeps one subscription of each per client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SubscriptionKey {
+    AccountUpdates,
+    Positions,
+    Request(RequestId),
+}
+
+/// Encodes the request of a live subscription for a server version.
+type Encode = Box<dyn Fn(ServerVersion) -> Result<Frame> + Send + Sync>;
+
+/// The requests of all live subscriptions of a client.
+///
+/// The `Client` records a subscription once its request was written and
+/// forgets it when the subscription is cancelled. After a reconnect the
+/// connection handler encodes the recorded requests for the server version
+/// of the new connection and writes them again, so the data keeps flowing
+/// under the original request ids.
+#[derive(Clone, Default)]
+pub struct LiveSubscriptions {
+    requests: Arc<Mutex<BTreeMap<SubscriptionKey, Encode>>>,
+}
+
+impl fmt::Debug for LiveSubscriptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys = self.requests().keys().copied().collect::<Vec<_>>();
+        f.debug_struct("LiveSubscriptions")
+            .field("keys", &keys)
+            .finish()
+    }
+}
+
+impl LiveSubscriptions {
+    fn requests(&self) -> MutexGuard<'_, BTreeMap<SubscriptionKey, Encode>> {
+        self.requests.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn insert<F>(&self, key: SubscriptionKey, encode: F)
+    where
+        F: Fn(ServerVersion) -> Result<Frame> + Send + Sync + 'static,
+    {
+        self.requests().insert(key, Box::new(encode));
+    }
+
+    pub fn remove(&self, key: SubscriptionKey) { self.requests().remove(&key); }
+
+    /// Encodes the recorded requests for `server_version`, account wide
+    /// subscriptions first and the others ordered by request id.
+    pub fn frames(&self, server_version: ServerVersion) -> Vec<(SubscriptionKey, Result<Frame>)> {
+        self.requests()
+            .iter()
+            .map(|(key, encode)| (*key, encode(server_version)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn reconnect_delay_backs_off_up_to_max_delay() {
+        let policy = ReconnectPolicy {
+            max_attempts: Some(3),
+            ..Default::default()
+        };
+        let delays = (0..8).map(|attempt| policy.delay(attempt).as_secs());
+        assert_eq!(vec![1, 2, 4, 8, 16, 32, 60, 60], delays.collect::<Vec<_>>());
+        assert_eq!(Duration::from_secs(60), policy.delay(usize::MAX));
+        assert!(policy.allows(2));
+        assert!(!policy.allows(3));
+        assert!(ReconnectPolicy::default().allows(usize::MAX));
+    }
+
+    #[test]
+    fn live_subscriptions_replay_order() {
+        let subscriptions = LiveSubscriptions::default();
+        let request = |msg: &'static str| {
+            move |server_version| Ok(Frame::Bulk(format!("{} v{}", msg, server_version).into()))
+        };
+        subscriptions.insert(SubscriptionKey::Request(7), request("mkt data 7"));
+        subscriptions.insert(SubscriptionKey::Request(3), request("depth 3"));
+        subscriptions.insert(SubscriptionKey::AccountUpdates, request("account"));
+        subscriptions.insert(SubscriptionKey::Request(5), request("bars 5"));
+        subscriptions.remove(SubscriptionKey::Request(5));
+
+        // the requests are encoded for the server version of the new connection
+        let frames = subscriptions
+            .frames(151)
+            .into_iter()
+            .map(|(key, frame)| {
+                match frame {
+                    Ok(Frame::Bulk(msg)) => (key, msg),
+                    frame => panic!("unexpected frame: {:?}", frame),
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (SubscriptionKey::AccountUpdates, Bytes::from("account v151")),
+                (SubscriptionKey::Request(3), Bytes::from("depth 3 v151")),
+                (SubscriptionKey::Request(7), Bytes::from("mkt data 7 v151"))
+            ],
+            frames
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use std::{any::Any,
+          collections::HashMap,
+          fmt,
+          pin::Pin,
+          sync::{Arc, Mutex, MutexGuard, PoisonError},
+          task::{Context, Poll}};
+
+use futures::Stream;
+use tokio::{sync::mpsc,
+            time::{sleep_until, Instant}};
+use tracing::warn;
+
+use super::{reconnect::{LiveSubscriptions, SubscriptionKey},
+            Response,
+            SubscriptionTimes};
+use crate::{api_message::TwsMessage, frame::Frame, writer::Writer, Error, RequestId};
+
+/// How long a route stays registered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteKind {
+    /// the request is answered by a single message, the route is removed
+    /// after delivering it
+    Once,
+    /// the request is answered by several messages, the route is removed by
+    /// `Registry::finish` once the last one was delivered
+    UntilEnd,
+    /// a live subscription, kept across reconnects until it is cancelled
+    Live,
+}
+
+/// Type erased sender of a route.
+trait Sink: Send {
+    fn as_any(&self) -> &dyn Any;
+    fn fail(&self, message: TwsMessage);
+    fn is_closed(&self) -> bool;
+}
+
+impl<T: Send + 'static> Sink for mpsc::UnboundedSender<Response<T>> {
+    fn as_any(&self) -> &dyn Any { self }
+
+    fn fail(&self, message: TwsMessage) {
+        // the receiver may have been dropped already
+        let _ = self.send(Err(message));
+    }
+
+    fn is_closed(&self) -> bool { mpsc::UnboundedSender::is_closed(self) }
+}
+
+struct Route {
+    kind: RouteKind,
+    sink: Box<dyn Sink>,
+}
+
+/// Correlates the messages of TWS with the request they answer.
+///
+/// Every request id is routed to at most one receiver. The handler offers
+/// the messages carrying a request id to the registry first and falls back
+/// to the global trackers if no route of the message type is registered.
+#[derive(Clone, Default)]
+pub struct Registry {
+    routes: Arc<Mutex<HashMap<RequestId, Route>>>,
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let req_ids = self.routes().keys().copied().collect::<Vec<_>>();
+        f.debug_struct("Registry")
+            .field("req_ids", &req_ids)
+            .finish()
+    }
+}
+
+impl Registry {
+    fn routes(&self) -> MutexGuard<'_, HashMap<RequestId, Route>> {
+        self.routes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Routes the messages of type `T` answering `req_id` to the returned
+    /// receiver.
+    ///
+    /// Fails with `Error::DuplicateRequestId` if another request with the
+    /// same id is still routed, the messages of a request are never taken
+    /// over by another one.
+    #[allow(clippy::significant_drop_tightening)]
+    pub fn register<T: Send + 'static>(
+        &self,
+        req_id: RequestId,
+        kind: RouteKind,
+    ) -> Result<mpsc::UnboundedReceiver<Response<T>>, Error> {
+        let mut routes = self.routes();
+        if matches!(routes.get(&req_id), Some(route) if !route.sink.is_closed()) {
+            return Err(Error::DuplicateRequestId(req_id));
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        routes.insert(req_id, Route {
+            kind,
+            sink: Box::new(sender),
+        });
+        Ok(receiver)
+    }
+
+    /// Delivers `item` to the route of `req_id`.
+    ///
+    /// Returns the item if no route of type `T` is registered for `req_id`.
+    pub fn deliver<T: Send + 'static>(&self, req_id: RequestId, item: T) -> Option<T> {
+        let mut routes = self.routes();
+        let route = match routes.get(&req_id) {
+            Some(route) => route,
+            None => return Some(item),
+        };
+        let sender = match route
+            .sink
+            .as_any()
+            .downcast_ref::<mpsc::UnboundedSender<Response<T>>>()
+        {
+            Some(sender) => sender,
+            None => return Some(item),
+        };
+        // a failed send means the receiver is gone, the message is discarded
+        if sender.send(Ok(item)).is_err() || route.kind == RouteKind::Once {
+            routes.remove(&req_id);
+        }
+        None
+    }
+
+    /// Removes the route of `req_id`, which ends the stream of its receiver.
+    pub fn finish(&self, req_id: RequestId) -> bool { self.routes().remove(&req_id).is_some() }
+
+    /// Answers the request `req_id` with the error `message` and removes its
+    /// route.
+    pub fn fail(&self, req_id: RequestId, message: &TwsMessage) -> Option<RouteKind> {
+        let route = self.routes().remove(&req_id)?;
+        route.sink.fail(message.clone());
+        Some(route.kind)
+    }
+
+    /// Removes the route of `req_id` if its receiver was dropped or closed.
+    ///
+    /// Returns `false` if the route was already removed, i.e. the request is
+    /// complete, failed or was cancelled.
+    pub fn remove_closed(&self, req_id: RequestId) -> bool {
+        let mut routes = self.routes();
+        let closed = matches!(routes.get(&req_id), Some(route) if route.sink.is_closed());
+        if closed {
+            routes.remove(&req_id);
+        }
+        closed
+    }
+
+    /// Removes the routes of all requests waiting for a response, the live
+    /// subscriptions are kept.
+    pub fn clear_pending(&self) {
+        self.routes()
+            .retain(|_, route| route.kind == RouteKind::Live);
+    }
+}
+
+/// The message sent to TWS when a subscription is dropped.
+#[derive(Debug)]
+struct Cancel {
+    frame:              Frame,
+    writer:             Writer,
+    subscriptions:      LiveSubscriptions,
+    /// the subscription time is forgotten once the subscription is dropped
+    subscription_times: SubscriptionTimes,
+    /// TWS rejects cancelling a subscription right after it was requested,
+    /// the cancel message is held back until then
+    not_before:         Instant,
+}
+
+/// A stream of the messages TWS sends in answer to a single request.
+///
+/// The stream ends once the request is complete, after TWS rejected it with
+/// an error or when the connection was lost. Dropping the subscription stops
+/// the delivery of its messages, the messages of other requests are not
+/// affected. Subscriptions of streaming data also cancel their request at TWS
+/// when they are dropped.
+#[derive(Debug)]
+#[must_use = "dropping a subscription stops the delivery of its messages"]
+pub struct Subscription<T> {
+    req_id:   RequestId,
+    receiver: mpsc::UnboundedReceiver<Response<T>>,
+    registry: Registry,
+    cancel:   Option<Cancel>,
+}
+
+impl<T: Send + 'static> Subscription<T> {
+    /// Registers a route of `req_id` at `registry`.
+    pub(crate) fn new(
+        registry: &Registry,
+        req_id: RequestId,
+        kind: RouteKind,
+    ) -> Result<Self, Error> {
+        Ok(Subscription {
+            req_id,
+            receiver: registry.register(req_id, kind)?,
+            registry: registry.clone(),
+            cancel: None,
+        })
+    }
+}
+
+impl<T> Subscription<T> {
+    /// Writes `frame` to cancel the request at TWS once the subscription is
+    /// dropped, but not before `not_before`.
+    ///
+    /// Requests which are complete, failed or were cancelled explicitly are
+    /// not cancelled again.
+    pub(crate) fn cancel_on_drop(
+        mut self,
+        frame: Frame,
+        writer: Writer,
+        subscriptions: LiveSubscriptions,
+        subscription_times: SubscriptionTimes,
+        not_before: Instant,
+    ) -> Self {
+        self.cancel = Some(Cancel {
+            frame,
+            writer,
+            subscriptions,
+            subscription_times,
+            not_before,
+        });
+        self
+    }
+
+    /// The request id the subscription was registered for.
+    pub const fn req_id(&self) -> RequestId { self.req_id }
+
+    /// Receives the next response, `None` once the stream ended.
+    pub(crate) async fn recv(&mut self) -> Option<Response<T>> { self.receiver.recv().await }
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver
+            .poll_recv(cx)
+            .map(|response| response.map(|response| response.map_err(Error::from)))
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        self.receiver.close();
+        let pending = self.registry.remove_closed(self.req_id);
+        if let Some(cancel) = self.cancel.take() {
+            cancel
+                .subscriptions
+                .remove(SubscriptionKey::Request(self.req_id));
+            cancel
+                .subscription_times
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(&self.req_id);
+            if !pending {
+                return;
+            }
+            let req_id = self.req_id;
+            match tokio::runtime::Handle::try_current() {
+                Ok(runtime) => {
+                    runtime.spawn(async move {
+                        sleep_until(cancel.not_before).await;
+                        if let Err(err) = cancel.writer.write_frame(&cancel.frame).await {
+                            warn!(cause = ?err, "failed to cancel request {}", req_id);
+                        }
+                    });
+                },
+                Err(_) => warn!("no runtime to cancel request {}", req_id),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use chrono::Utc;
+    use futures::StreamExt;
+    use pretty_assertions::assert_eq;
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::api_message::TwsErrorCode;
+
+    fn message(req_id: RequestId) -> TwsMessage {
+        TwsMessage {
+            req_id:                     Some(req_id),
+            code:                       TwsErrorCode::NoSecurityDefinition,
+            message:                    Some("No security definition has been found".to_string()),
+            advanced_order_reject_json: None,
+            error_time:                 None,
+        }
+    }
+
+    #[tokio::test]
+    async fn messages_are_routed_by_request_id() {
+        let registry = Registry::default();
+        let mut first = Subscription::<u32>::new(&registry, 1, RouteKind::Live).unwrap();
+        let mut second = Subscription::<u32>::new(&registry, 2, RouteKind::Once).unwrap();
+
+        assert_eq!(None, registry.deliver(1, 10_u32));
+        assert_eq!(None, registry.deliver(2, 20_u32));
+        assert_eq!(None, registry.deliver(1, 11_u32));
+        // unknown request ids and other message types are handed back
+        assert_eq!(Some(30_u32), registry.deliver(3, 30_u32));
+        assert_eq!(Some("text"), registry.deliver(1, "text"));
+        // the one-shot route was removed after its answer
+        assert_eq!(Some(21_u32), registry.deliver(2, 21_u32));
+
+        assert_eq!(10, first.next().await.unwrap().unwrap());
+        assert_eq!(11, first.next().await.unwrap().unwrap());
+        assert_eq!(20, second.next().await.unwrap().unwrap());
+        assert!(second.next().await.is_none());
+
+        drop(first);
+        assert_eq!(Some(12_u32), registry.deliver(1, 12_u32));
+    }
+
+    #[tokio::test]
+    async fn errors_are_routed_to_the_pending_request() {
+        let registry = Registry::default();
+        let mut pending = Subscription::<String>::new(&registry, 7, RouteKind::UntilEnd).unwrap();
+        let mut live = Subscription::<String>::new(&registry, 8, RouteKind::Live).unwrap();
+
+        assert_eq!(None, registry.fail(3, &message(3)));
+        assert_eq!(Some(RouteKind::UntilEnd), registry.fail(7, &message(7)));
+        let error = pending.next().await.unwrap().unwrap_err();
+        assert_eq!(
+            "request failed with code 200 (request 7): No security definition has been found",
+            error.to_string()
+        );
+        // the route was removed, the caller does not wait forever
+        assert!(pending.next().await.is_none());
+
+        registry.clear_pending();
+        assert_eq!(None, registry.deliver(8, "live".to_string()));
+        assert_eq!("live", live.next().await.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn request_ids_are_not_taken_over() {
+        let registry = Registry::default();
+        let mut first = Subscription::<u32>::new(&registry, 5, RouteKind::Live).unwrap();
+        assert!(matches!(
+            Subscription::<u32>::new(&registry, 5, RouteKind::Once),
+            Err(Error::DuplicateRequestId(5))
+        ));
+        assert_eq!(None, registry.deliver(5, 50_u32));
+        assert_eq!(50, first.next().await.unwrap().unwrap());
+
+        // the id can be used again once the first request is gone
+        drop(first);
+        assert!(Subscription::<u32>::new(&registry, 5, RouteKind::Once).is_ok());
+    }
+
+    #[test]
+    fn only_pending_requests_are_cancelled_on_drop() {
+        let registry = Registry::default();
+        let mut live = registry.register::<u32>(1, RouteKind::Live).unwrap();
+        let _done = registry.register::<u32>(2, RouteKind::UntilEnd).unwrap();
+
+        // the receiver is still open
+        assert!(!registry.remove_closed(1));
+        live.close();
+        assert!(registry.remove_closed(1));
+        // a completed request does not have to be cancelled
+        assert!(registry.finish(2));
+        assert!(!registry.remove_closed(2));
+    }
+
+    #[tokio::test]
+    async fn dropped_subscriptions_forget_their_subscription_time() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let socket = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (_, write_half) = socket.into_split();
+        let registry = Registry::default();
+        let subscription_times = SubscriptionTimes::default();
+        subscription_times.lock().unwrap().insert(4, Utc::now());
+
+        let subscription = Subscription::<u32>::new(&registry, 4, RouteKind::Live)
+            .unwrap()
+            .cancel_on_drop(
+                Frame::Bulk(Bytes::from("cancel")),
+                Writer::new(write_half, None),
+                LiveSubscriptions::default(),
+                subscription_times.clone(),
+                Instant::now(),
+            );
+        drop(subscription);
+        assert!(subscription_times.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use super::{Client, Request, RouteKind, Subscription};
+use crate::{cmd::{RequestScanner, TagValue},
+            scanner::{ScannerData, ScannerSubscription},
+            RequestId,
+            Result};
+
+impl Client {
+    /// Starts a subscription to market scan results based on the provided
+    /// parameters.
+    ///
+    /// Returns a stream of scan results. Every item holds the complete result
+    /// of one scan, ordered by rank. The stream ends once TWS rejects the
+    /// subscription or the connection is closed. Dropping the stream cancels
+    /// the subscription.
+    ///
+    /// # Arguments
+    /// * subscription - summary of the scanner subscription including its
+    ///   filters.
+    /// * subscription_options - For internal use only.
+    #[tracing::instrument(skip(self))]
+    pub async fn request_scanner_subscription(
+        &self,
+        subscription: ScannerSubscription,
+        subscription_options: Vec<TagValue>,
+    ) -> Result<Subscription<Vec<ScannerData>>> {
+        let req_id = self.get_next_req_id();
+        let frame = RequestScanner::Subscription {
+            req_id,
+            subscription,
+            subscription_options,
+        };
+
+        debug!(request = ?frame);
+
+        // route the scan results to the subscription
+        let subscription = self.subscribe(req_id, RouteKind::Live)?;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        let cancel =
+            RequestScanner::CancelSubscription { req_id }.into_frame(self.server_version())?;
+        Ok(self.cancel_on_drop(subscription, cancel))
+    }
+
+    /// Cancels a scanner subscription.
+    ///
+    /// # Arguments
+    /// * req_id - the request id of the subscription returned by
+    ///   request_scanner_subscription().
+    #[tracing::instrument(skip(self))]
+    pub async fn cancel_scanner_subscription(&self, req_id: RequestId) -> Result<()> {
+        let frame = RequestScanner::CancelSubscription { req_id };
+
+        debug!(request = ?frame);
+
+        self.registry.finish(req_id);
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        Ok(())
+    }
+
+    /// Requests an XML string that describes all possible scanner queries.
+    #[tracing::instrument(skip(self))]
+    pub async fn request_scanner_parameters(&self) -> Result<String> {
+        let frame = RequestScanner::Parameters;
+
+        debug!(request = ?frame);
+
+        let (rep_tx, mut rep_rx) = mpsc::unbounded_channel();
+        self.register(Request::ScannerParameters { sender: rep_tx })
+            .await?;
+        // Write the frame to the socket
+        self.writer
+            .write_frame(&frame.into_frame(self.server_version())?)
+            .await?;
+        self.recv_response(rep_rx.recv()).await
+    }
+}
@@ -1,10 +1,12 @@
 use bytes::Bytes;
 
+use super::ParseIbkrCommand;
 use crate::{
     enums::{Outgoing, ServerLogLevel},
     frame::Frame,
-    utils::ib_message::{Encodable, IBMessage},
-    ClientId,
+    ib_frame::{ParseError, ParseResult},
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    ClientId, ServerVersion,
 };
 
 const VERSION: i32 = 1;
@@ -22,6 +24,8 @@ pub enum Api {
     Init {
         min_client_version: i32,
         max_client_version: i32,
+        /// options of the connection, e.g. "+PACEAPI"
+        connection_options: String,
     },
     Start {
         client_id: ClientId,
@@ -38,13 +42,17 @@ impl Api {
             Api::Init {
                 min_client_version,
                 max_client_version,
+                connection_options,
             } => {
                 let mut frame = Frame::array();
                 // frame.push_bulk(Bytes::from("API\0".as_bytes()));
                 let mut valid_versions = min_client_version.to_string();
                 valid_versions.push_str("..");
                 valid_versions.push_str(&max_client_version.to_string());
-                valid_versions.push_str(" +PACEAPI");
+                if !connection_options.is_empty() {
+                    valid_versions.push(' ');
+                    valid_versions.push_str(&connection_options);
+                }
                 let msg = valid_versions.as_str().to_ib_message().unwrap();
                 frame.push_bulk(Bytes::from(msg));
                 frame
@@ -80,3 +88,39 @@ impl Api {
         }
     }
 }
+
+impl Api {
+    /// Decodes the version range sent after the "API\0" prefix, the inverse
+    /// of the encoding of `Api::Init`.
+    pub(crate) fn parse_init(versions: &str) -> ParseResult<Api> {
+        let (range, connection_options) = versions.split_once(' ').unwrap_or((versions, ""));
+        let invalid = || ParseError::InvalidValue(versions.to_string());
+        let (min, max) = range.split_once("..").ok_or_else(invalid)?;
+        Ok(Api::Init {
+            min_client_version: min.parse().map_err(|_| invalid())?,
+            max_client_version: max.parse().map_err(|_| invalid())?,
+            connection_options: connection_options.to_string(),
+        })
+    }
+}
+
+impl ParseIbkrCommand for Api {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        it.next(); // skip version
+        match msg_id {
+            Outgoing::StartApi => Ok(Api::Start {
+                client_id: decode_required(it)?,
+                optional_capabilities: decode(it)?,
+            }),
+            Outgoing::SetServerLoglevel => Ok(Api::SetServerLoglevel {
+                log_level: decode_required(it)?,
+            }),
+            Outgoing::ReqCurrentTime => Ok(Api::RequestCurrentTime),
+            _ => Err(ParseError::UnexpectedMessage),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use bytes::Bytes;
+
+use super::ParseIbkrCommand;
+use crate::{
+    enums::Outgoing,
+    frame::Frame,
+    ib_frame::{ParseError, ParseResult},
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    OrderId, Result, ServerVersion,
+};
+
+const VERSION: i32 = 1;
+
+/// Cancels an active order placed from the same API client ID.
+///
+/// Note: API clients cannot cancel individual orders placed by other clients.
+/// Only `GlobalCancel` is available.
+///
+/// # Arguments
+/// * order_id - The order ID that was specified previously in the call to
+///   placeOrder().
+/// * manual_order_cancel_time - Specify the time the order should be
+///   cancelled. An empty string will cancel the order immediately. Servers
+///   without support for manual order times only accept an empty string.
+#[derive(Debug)]
+pub struct CancelOrder {
+    pub order_id:                 OrderId,
+    pub manual_order_cancel_time: Option<String>,
+}
+
+impl CancelOrder {
+    pub const fn new(order_id: OrderId, manual_order_cancel_time: Option<String>) -> CancelOrder {
+        CancelOrder {
+            order_id,
+            manual_order_cancel_time,
+        }
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `CancelOrder` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
+        if matches!(&self.manual_order_cancel_time, Some(time) if !time.is_empty()) {
+            MinServerVersion::ManualOrderTime.check(server_version)?;
+        }
+        let mut msg = Outgoing::CancelOrder.encode();
+        msg.push_str(&VERSION.encode());
+        msg.push_str(&self.order_id.encode());
+        if MinServerVersion::ManualOrderTime.is_supported(server_version) {
+            msg.push_str(&self.manual_order_cancel_time.encode());
+        }
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+/// Cancels all open orders, including those placed directly in TWS or by
+/// other API clients.
+#[derive(Debug, Copy, Clone)]
+pub struct GlobalCancel;
+
+impl GlobalCancel {
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `GlobalCancel` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut msg = Outgoing::ReqGlobalCancel.encode();
+        msg.push_str(&VERSION.encode());
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Frame::Bulk(Bytes::from(msg))
+    }
+}
+
+impl ParseIbkrCommand for CancelOrder {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        if !matches!(msg_id, Outgoing::CancelOrder) {
+            return Err(ParseError::UnexpectedMessage);
+        }
+        it.next(); // skip version
+        let order_id = decode_required(it)?;
+        let manual_order_cancel_time =
+            if MinServerVersion::ManualOrderTime.is_supported(server_version) {
+                decode(it)?
+            } else {
+                None
+            };
+        Ok(CancelOrder {
+            order_id,
+            manual_order_cancel_time,
+        })
+    }
+}
+
+impl ParseIbkrCommand for GlobalCancel {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        _it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        if !matches!(msg_id, Outgoing::ReqGlobalCancel) {
+            return Err(ParseError::UnexpectedMessage);
+        }
+        Ok(GlobalCancel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::Error;
+
+    fn bytes(frame: Frame) -> Bytes {
+        match frame {
+            Frame::Bulk(bytes) => bytes,
+            Frame::Array(_) => panic!("unexpected frame"),
+        }
+    }
+
+    fn message(fields: &str) -> Vec<u8> { fields.to_ib_message().unwrap() }
+
+    #[test]
+    fn encode_cancel_order() {
+        let frame = CancelOrder::new(7, None).into_frame(168).unwrap();
+        assert_eq!(message("4\01\07\0"), bytes(frame).as_ref());
+
+        // the manual order cancel time follows the order id on newer servers
+        let frame = CancelOrder::new(7, None).into_frame(169).unwrap();
+        assert_eq!(message("4\01\07\0\0"), bytes(frame).as_ref());
+        let frame = CancelOrder::new(7, Some("20240102 10:00:00 UTC".to_string()))
+            .into_frame(169)
+            .unwrap();
+        assert_eq!(
+            message("4\01\07\020240102 10:00:00 UTC\0"),
+            bytes(frame).as_ref()
+        );
+
+        assert!(matches!(
+            CancelOrder::new(7, Some("20240102 10:00:00 UTC".to_string())).into_frame(168),
+            Err(Error::Unsupported {
+                feature:        MinServerVersion::ManualOrderTime,
+                server_version: 168,
+            })
+        ));
+    }
+
+    #[test]
+    fn encode_global_cancel() {
+        assert_eq!(
+            message("58\01\0"),
+            bytes(GlobalCancel.into_frame()).as_ref()
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use bytes::Bytes;
+
+use super::*;
+use crate::{
+    enums::Outgoing,
+    frame::Frame,
+    ib_frame::ParseResult,
+    utils::ib_message::{Encodable, Fields, IBMessage},
+    RequestId, ServerVersion,
+};
+
+/// A command sent by a client to the server.
+///
+/// This is the decoded form of the messages written by the client, it is used
+/// by servers and proxies to interpret the requests of their clients.
+/// Messages without a dedicated command type are kept as `Other`.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum Command {
+    Api(Api),
+    CancelOrder(CancelOrder),
+    GlobalCancel(GlobalCancel),
+    PlaceOrder(PlaceOrder),
+    RequestAccountSummary(RequestAccountSummary),
+    RequestAccountUpdates(RequestAccountUpdates),
+    RequestContractDetails(RequestContractDetails),
+    RequestExecutions(RequestExecutions),
+    RequestFundamentalData(RequestFundamentalData),
+    RequestMatchingSymbols(RequestMatchingSymbols),
+    RequestSecDefOptParams(RequestSecDefOptParams),
+    RequestOrders(RequestOrders),
+    RequestPositions(RequestPositions),
+    RequestNews(RequestNews),
+    RequestPnl(RequestPnl),
+    RequestScanner(RequestScanner),
+    RequestWsh(RequestWsh),
+    MarketData(MarketDataRequest),
+    CancelMarketData(CancelMarketDataRequest),
+    MarketDataType(MarketDataTypeRequest),
+    MarketDepth(MarketDepthRequest),
+    CancelMarketDepth(CancelMarketDepthRequest),
+    MarketDepthExchanges(MarketDepthExchangesRequest),
+    HeadTimestamp(HeadTimestampRequest),
+    CancelHeadTimestamp(CancelHeadTimestampRequest),
+    HistoricalData(HistoricalDataRequest),
+    CancelHistoricalData(CancelHistoricalDataRequest),
+    HistoricalTicks(HistoricalTicksRequest),
+    TickByTick(TickByTickRequest),
+    CancelTickByTick(CancelTickByTickRequest),
+    RealtimeBars(RealtimeBarRequest),
+    CancelRealtimeBars(CancelRealtimeBars),
+    CalculateImpliedVolatility(CalculateImpliedVolatilityRequest),
+    CancelCalculateImpliedVolatility(CancelCalculateImpliedVolatilityRequest),
+    CalculateOptionPrice(CalculateOptionPriceRequest),
+    CancelCalculateOptionPrice(CancelCalculateOptionPriceRequest),
+    /// A message without a dedicated command type, `fields` holds the fields
+    /// following the message id.
+    Other {
+        msg_id: Outgoing,
+        fields: Vec<String>,
+    },
+}
+
+impl Command {
+    /// Decodes the fields following the message id `msg_id` of a message
+    /// sent to a server with version `server_version`.
+    pub(crate) fn parse(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Command> {
+        fn parse<T: ParseIbkrCommand>(
+            msg_id: Outgoing,
+            server_version: ServerVersion,
+            it: &mut Fields<'_>,
+        ) -> ParseResult<T> {
+            T::try_parse_command(msg_id, server_version, it)
+        }
+
+        let sv = server_version;
+        let cmd = match msg_id {
+            Outgoing::StartApi | Outgoing::SetServerLoglevel | Outgoing::ReqCurrentTime => {
+                Command::Api(parse(msg_id, sv, it)?)
+            },
+            Outgoing::CancelOrder => Command::CancelOrder(parse(msg_id, sv, it)?),
+            Outgoing::ReqGlobalCancel => Command::GlobalCancel(parse(msg_id, sv, it)?),
+            Outgoing::PlaceOrder => Command::PlaceOrder(parse(msg_id, sv, it)?),
+            Outgoing::ReqAccountSummary => Command::RequestAccountSummary(parse(msg_id, sv, it)?),
+            Outgoing::ReqAcctData => Command::RequestAccountUpdates(parse(msg_id, sv, it)?),
+            Outgoing::ReqContractData => Command::RequestContractDetails(parse(msg_id, sv, it)?),
+            Outgoing::ReqExecutions => Command::RequestExecutions(parse(msg_id, sv, it)?),
+            Outgoing::ReqFundamentalData | Outgoing::CancelFundamentalData => {
+                Command::RequestFundamentalData(parse(msg_id, sv, it)?)
+            },
+            Outgoing::ReqMatchingSymbols => Command::RequestMatchingSymbols(parse(msg_id, sv, it)?),
+            Outgoing::ReqSecDefOptParams => Command::RequestSecDefOptParams(parse(msg_id, sv, it)?),
+            Outgoing::ReqAllOpenOrders
+            | Outgoing::ReqAutoOpenOrders
+            | Outgoing::ReqOpenOrders
+            | Outgoing::ReqCompletedOrders
+            | Outgoing::ReqIds => Command::RequestOrders(parse(msg_id, sv, it)?),
+            Outgoing::ReqPositions
+            | Outgoing::CancelPositions
+            | Outgoing::ReqPositionsMulti
+            | Outgoing::CancelPositionsMulti => Command::RequestPositions(parse(msg_id, sv, it)?),
+            Outgoing::ReqNewsProviders
+            | Outgoing::ReqNewsArticle
+            | Outgoing::ReqHistoricalNews
+            | Outgoing::ReqNewsBulletins
+            | Outgoing::CancelNewsBulletins => Command::RequestNews(parse(msg_id, sv, it)?),
+            Outgoing::ReqPnl
+            | Outgoing::CancelPnl
+            | Outgoing::ReqPnlSingle
+            | Outgoing::CancelPnlSingle => Command::RequestPnl(parse(msg_id, sv, it)?),
+            Outgoing::ReqScannerSubscription
+            | Outgoing::CancelScannerSubscription
+            | Outgoing::ReqScannerParameters => Command::RequestScanner(parse(msg_id, sv, it)?),
+            Outgoing::ReqWshMetaData
+            | Outgoing::CancelWshMetaData
+            | Outgoing::ReqWshEventData
+            | Outgoing::CancelWshEventData => Command::RequestWsh(parse(msg_id, sv, it)?),
+            Outgoing::ReqMktData => Command::MarketData(parse(msg_id, sv, it)?),
+            Outgoing::CancelMktData => Command::CancelMarketData(parse(msg_id, sv, it)?),
+            Outgoing::ReqMarketDataType => Command::MarketDataType(parse(msg_id, sv, it)?),
+            Outgoing::ReqMktDepth => Command::MarketDepth(parse(msg_id, sv, it)?),
+            Outgoing::CancelMktDepth => Command::CancelMarketDepth(parse(msg_id, sv, it)?),
+            Outgoing::ReqMktDepthExchanges => Command::MarketDepthExchanges(parse(msg_id, sv, it)?),
+            Outgoing::ReqHeadTimestamp => Command::HeadTimestamp(parse(msg_id, sv, it)?),
+            Outgoing::CancelHeadTimestamp => Command::CancelHeadTimestamp(parse(msg_id, sv, it)?),
+            Outgoing::ReqHistoricalData => Command::HistoricalData(parse(msg_id, sv, it)?),
+            Outgoing::CancelHistoricalData => Command::CancelHistoricalData(parse(msg_id, sv, it)?),
+            Outgoing::ReqHistoricalTicks => Command::HistoricalTicks(parse(msg_id, sv, it)?),
+            Outgoing::ReqTickByTickData => Command::TickByTick(parse(msg_id, sv, it)?),
+            Outgoing::CancelTickByTickData => Command::CancelTickByTick(parse(msg_id, sv, it)?),
+            Outgoing::ReqRealTimeBars => Command::RealtimeBars(parse(msg_id, sv, it)?),
+            Outgoing::CancelRealTimeBars => Command::CancelRealtimeBars(parse(msg_id, sv, it)?),
+            Outgoing::ReqCalcImpliedVolat => {
+                Command::CalculateImpliedVolatility(parse(msg_id, sv, it)?)
+            },
+            Outgoing::CancelCalcImpliedVolat => {
+                Command::CancelCalculateImpliedVolatility(parse(msg_id, sv, it)?)
+            },
+            Outgoing::ReqCalcOptionPrice => Command::CalculateOptionPrice(parse(msg_id, sv, it)?),
+            Outgoing::CancelCalcOptionPrice => {
+                Command::CancelCalculateOptionPrice(parse(msg_id, sv, it)?)
+            },
+            _ => Command::Other {
+                msg_id,
+                fields: it.map(str::to_string).collect(),
+            },
+        };
+        Ok(cmd)
+    }
+
+    /// The id of the request, `None` for commands without one.
+    pub const fn request_id(&self) -> Option<RequestId> {
+        let req_id = match self {
+            Command::RequestAccountSummary(cmd) => cmd.req_id,
+            Command::RequestContractDetails(cmd) => cmd.req_id,
+            Command::RequestExecutions(cmd) => cmd.req_id,
+            Command::RequestMatchingSymbols(cmd) => cmd.req_id,
+            Command::RequestSecDefOptParams(cmd) => cmd.req_id,
+            Command::RequestFundamentalData(
+                RequestFundamentalData::Request { req_id, .. }
+                | RequestFundamentalData::Cancel { req_id },
+            )
+            | Command::RequestPositions(
+                RequestPositions::PositionsMulti { req_id, .. }
+                | RequestPositions::CancelPositionsMulti { req_id },
+            )
+            | Command::RequestNews(
+                RequestNews::Article { req_id, .. } | RequestNews::Historical { req_id, .. },
+            )
+            | Command::RequestPnl(
+                RequestPnl::Pnl { req_id, .. }
+                | RequestPnl::CancelPnl { req_id }
+                | RequestPnl::PnlSingle { req_id, .. }
+                | RequestPnl::CancelPnlSingle { req_id },
+            )
+            | Command::RequestScanner(
+                RequestScanner::Subscription { req_id, .. }
+                | RequestScanner::CancelSubscription { req_id },
+            )
+            | Command::RequestWsh(
+                RequestWsh::MetaData { req_id }
+                | RequestWsh::CancelMetaData { req_id }
+                | RequestWsh::EventData { req_id, .. }
+                | RequestWsh::CancelEventData { req_id },
+            ) => *req_id,
+            Command::MarketData(cmd) => cmd.req_id,
+            Command::CancelMarketData(cmd) => cmd.req_id,
+            Command::MarketDepth(cmd) => cmd.req_id,
+            Command::CancelMarketDepth(cmd) => cmd.req_id,
+            Command::HeadTimestamp(cmd) => cmd.req_id,
+            Command::CancelHeadTimestamp(cmd) => cmd.req_id,
+            Command::HistoricalData(cmd) => cmd.req_id,
+            Command::CancelHistoricalData(cmd) => cmd.req_id,
+            Command::HistoricalTicks(cmd) => cmd.req_id,
+            Command::TickByTick(cmd) => cmd.req_id,
+            Command::CancelTickByTick(cmd) => cmd.req_id,
+            Command::RealtimeBars(cmd) => cmd.req_id,
+            Command::CancelRealtimeBars(cmd) => cmd.req_id,
+            Command::CalculateImpliedVolatility(cmd) => cmd.req_id,
+            Command::CancelCalculateImpliedVolatility(cmd) => cmd.req_id,
+            Command::CalculateOptionPrice(cmd) => cmd.req_id,
+            Command::CancelCalculateOptionPrice(cmd) => cmd.req_id,
+            _ => return None,
+        };
+        Some(req_id)
+    }
+
+    /// Converts the command into an equivalent `Frame`, encoded as expected by
+    /// servers with version `server_version`.
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> crate::Result<Frame> {
+        let sv = server_version;
+        match self {
+            Command::Api(cmd) => Ok(cmd.into_frame()),
+            Command::CancelOrder(cmd) => cmd.into_frame(sv),
+            Command::GlobalCancel(cmd) => Ok(cmd.into_frame()),
+            Command::PlaceOrder(cmd) => cmd.into_frame(sv),
+            Command::RequestAccountSummary(cmd) => Ok(cmd.into_frame()),
+            Command::RequestAccountUpdates(cmd) => Ok(cmd.into_frame()),
+            Command::RequestContractDetails(cmd) => cmd.into_frame(sv),
+            Command::RequestExecutions(cmd) => Ok(cmd.into_frame()),
+            Command::RequestFundamentalData(cmd) => Ok(cmd.into_frame()),
+            Command::RequestMatchingSymbols(cmd) => cmd.into_frame(sv),
+            Command::RequestSecDefOptParams(cmd) => cmd.into_frame(sv),
+            Command::RequestOrders(cmd) => cmd.into_frame(sv),
+            Command::RequestPositions(cmd) => cmd.into_frame(sv),
+            Command::RequestNews(cmd) => cmd.into_frame(sv),
+            Command::RequestPnl(cmd) => cmd.into_frame(sv),
+            Command::RequestScanner(cmd) => cmd.into_frame(sv),
+            Command::RequestWsh(cmd) => cmd.into_frame(sv),
+            Command::MarketData(cmd) => cmd.into_frame(sv),
+            Command::CancelMarketData(cmd) => cmd.into_frame(sv),
+            Command::MarketDataType(cmd) => cmd.into_frame(sv),
+            Command::MarketDepth(cmd) => cmd.into_frame(sv),
+            Command::CancelMarketDepth(cmd) => cmd.into_frame(sv),
+            Command::MarketDepthExchanges(cmd) => cmd.into_frame(sv),
+            Command::HeadTimestamp(cmd) => cmd.into_frame(sv),
+            Command::CancelHeadTimestamp(cmd) => cmd.into_frame(sv),
+            Command::HistoricalData(cmd) => cmd.into_frame(sv),
+            Command::CancelHistoricalData(cmd) => cmd.into_frame(sv),
+            Command::HistoricalTicks(cmd) => cmd.into_frame(sv),
+            Command::TickByTick(cmd) => cmd.into_frame(sv),
+            Command::CancelTickByTick(cmd) => cmd.into_frame(sv),
+            Command::RealtimeBars(cmd) => cmd.into_frame(sv),
+            Command::CancelRealtimeBars(cmd) => cmd.into_frame(sv),
+            Command::CalculateImpliedVolatility(cmd) => cmd.into_frame(sv),
+            Command::CancelCalculateImpliedVolatility(cmd) => cmd.into_frame(sv),
+            Command::CalculateOptionPrice(cmd) => cmd.into_frame(sv),
+            Command::CancelCalculateOptionPrice(cmd) => cmd.into_frame(sv),
+            Command::Other { msg_id, fields } => {
+                let mut msg = msg_id.encode();
+                for field in fields {
+                    msg.push_str(&field.encode());
+                }
+                let msg = msg.as_str().to_ib_message().unwrap();
+                Ok(Frame::Bulk(Bytes::from(msg)))
+            },
+        }
+    }
+}
@@ -10,19 +10,59 @@ pub(crate) mod request_market_data;
 pub use request_market_data::*;
 mod request_contract_details;
 pub use request_contract_details::RequestContractDetails;
+mod request_fundamental_data;
+pub use request_fundamental_data::RequestFundamentalData;
+mod request_matching_symbols;
+pub use request_matching_symbols::RequestMatchingSymbols;
+mod request_sec_def_opt_params;
+pub use request_sec_def_opt_params::RequestSecDefOptParams;
 mod place_order;
 pub use place_order::PlaceOrder;
+mod cancel_order;
+pub use cancel_order::{CancelOrder, GlobalCancel};
 pub mod request_executions;
 pub use request_executions::RequestExecutions;
 mod request_orders;
 pub use request_orders::RequestOrders;
+mod request_positions;
+pub use request_positions::RequestPositions;
+mod request_news;
+pub use request_news::RequestNews;
+mod request_pnl;
+pub use request_pnl::RequestPnl;
+mod request_scanner;
+pub use request_scanner::RequestScanner;
+mod request_wsh;
+pub use request_wsh::RequestWsh;
+mod command;
+pub use command::Command;
+
+use crate::{enums::Outgoing, ib_frame::ParseResult, utils::ib_message::Fields, ServerVersion};
 
 pub(crate) trait IntoIbkrFrame {
     /// Converts the command into an equivalent `Frame`.
     ///
     /// This is called by the client when encoding a command
-    /// to send to the server.
+    /// to send to the server. The fields are encoded as expected by servers
+    /// with version `server_version`, commands depending on a feature the
+    /// server does not support fail with `Error::Unsupported`.
 
     #[allow(clippy::wrong_self_convention)]
-    fn into_frame(&self) -> crate::frame::Frame;
+    fn into_frame(
+        &self,
+        server_version: ServerVersion,
+    ) -> crate::Result<crate::frame::Frame>;
+}
+
+/// The inverse of `into_frame`, used to decode the commands sent by a client.
+pub(crate) trait ParseIbkrCommand {
+    /// Decodes the fields following the message id `msg_id` of a command
+    /// sent to a server with version `server_version`.
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized;
 }
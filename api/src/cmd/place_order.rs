@@ -1,14 +1,17 @@
 use bytes::Bytes;
 
+use super::ParseIbkrCommand;
 use crate::{
     enums::Outgoing,
     frame::Frame,
+    ib_frame::{ParseError, ParseResult},
     order::Order,
-    utils::ib_message::{Encodable, IBMessage},
-    OrderId,
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode_required, Encodable, Fields, IBMessage},
+    OrderId, Result, ServerVersion,
 };
 
-// const VERSION: i32 = 45;
+const VERSION: i32 = 45;
 
 /// Call this function to download all details for a particular
 /// underlying. The contract details will be received via the contractDetails()
@@ -21,8 +24,8 @@ use crate::{
 /// * contract - The summary description of the contract being looked up.
 #[derive(Debug)]
 pub struct PlaceOrder {
-    order_id: OrderId,
-    order: Order,
+    pub order_id: OrderId,
+    pub order: Order,
 }
 
 impl PlaceOrder {
@@ -36,14 +39,77 @@ impl PlaceOrder {
 
     /// Converts the command into an equivalent `Frame`.
     ///
-    /// This is called by the client when encoding a `RequestMarketData` command
+    /// This is called by the client when encoding a `PlaceOrder` command
     /// to send to the server.
-    pub(crate) fn into_frame(self) -> Frame {
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
         let mut msg = Outgoing::PlaceOrder.encode();
-        // msg.push_str(&VERSION.encode());
+        if !MinServerVersion::OrderContainer.is_supported(server_version) {
+            msg.push_str(&VERSION.encode());
+        }
         msg.push_str(&self.order_id.encode());
-        msg.push_str(&self.order.encode());
+        msg.push_str(&self.order.encode(server_version)?);
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+impl ParseIbkrCommand for PlaceOrder {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        if !matches!(msg_id, Outgoing::PlaceOrder) {
+            return Err(ParseError::UnexpectedMessage);
+        }
+        if !MinServerVersion::OrderContainer.is_supported(server_version) {
+            it.next(); // skip version
+        }
+        Ok(PlaceOrder {
+            order_id: decode_required(it)?,
+            order: Order::decode(it, server_version)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::{contract::Contract, order::Action, Error};
+
+    fn fields(frame: &Frame) -> Vec<String> {
+        match frame {
+            Frame::Bulk(msg) => {
+                String::from_utf8_lossy(&msg[4..])
+                    .split('\0')
+                    .map(str::to_string)
+                    .collect()
+            },
+            Frame::Array(_) => panic!("unexpected frame"),
+        }
+    }
+
+    #[test]
+    fn order_fields_depend_on_server_version() {
+        let mut order = Order::market(Contract::default(), Action::Buy, dec!(100));
+        let old = fields(&PlaceOrder::new(1, order.clone()).into_frame(144).unwrap());
+        let new = fields(&PlaceOrder::new(1, order.clone()).into_frame(151).unwrap());
+        // servers before 145 expect the message version
+        assert_eq!(["3", "45", "1"], old[..3]);
+        assert_eq!(["3", "1"], new[..2]);
+        // oms container, discretionary up to limit price and price management algo
+        assert_eq!(old.len() - 1 + 3, new.len());
+
+        order.cash_qty = Some(dec!(1000));
+        assert!(matches!(
+            PlaceOrder::new(1, order).into_frame(110),
+            Err(Error::Unsupported {
+                feature: MinServerVersion::CashQty,
+                ..
+            })
+        ));
     }
 }
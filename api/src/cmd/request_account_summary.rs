@@ -1,10 +1,12 @@
 use bytes::Bytes;
 
+use super::ParseIbkrCommand;
 use crate::{
     enums::Outgoing,
     frame::Frame,
-    utils::ib_message::{Encodable, IBMessage},
-    RequestId,
+    ib_frame::{ParseError, ParseResult},
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    RequestId, ServerVersion,
 };
 
 const VERSION: i32 = 1;
@@ -83,11 +85,11 @@ const VERSION: i32 = 1;
 ///         * - $LEDGER:ALL — Single flag to relay all cash balance tags* in all
 ///           currencies.
 ///         */
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RequestAccountSummary {
-    req_id: RequestId,
-    group_name: String,
-    tags: Vec<String>,
+    pub req_id: RequestId,
+    pub group_name: String,
+    pub tags: Vec<String>,
 }
 
 impl RequestAccountSummary {
@@ -117,3 +119,28 @@ impl RequestAccountSummary {
         Frame::Bulk(Bytes::from(msg))
     }
 }
+
+impl ParseIbkrCommand for RequestAccountSummary {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        if !matches!(msg_id, Outgoing::ReqAccountSummary) {
+            return Err(ParseError::UnexpectedMessage);
+        }
+        it.next(); // skip version
+        let req_id = decode_required(it)?;
+        let group_name = decode(it)?.unwrap_or_default();
+        let tags: String = decode(it)?.unwrap_or_default();
+        Ok(RequestAccountSummary {
+            req_id,
+            group_name,
+            tags: tags
+                .split(',')
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect(),
+        })
+    }
+}
@@ -1,17 +1,19 @@
 use bytes::Bytes;
 
+use super::ParseIbkrCommand;
 use crate::{
     enums::Outgoing,
     frame::Frame,
-    utils::ib_message::{Encodable, IBMessage},
-    AccountCode,
+    ib_frame::{ParseError, ParseResult},
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    AccountCode, ServerVersion,
 };
 const VERSION: i32 = 2;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RequestAccountUpdates {
-    subscribe: bool,
-    account_code: AccountCode,
+    pub subscribe: bool,
+    pub account_code: AccountCode,
 }
 /// Subscribes to a specific account's information and
 /// portfolio.
@@ -61,3 +63,20 @@ impl RequestAccountUpdates {
         Frame::Bulk(Bytes::from(msg))
     }
 }
+
+impl ParseIbkrCommand for RequestAccountUpdates {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        if !matches!(msg_id, Outgoing::ReqAcctData) {
+            return Err(ParseError::UnexpectedMessage);
+        }
+        it.next(); // skip version
+        Ok(RequestAccountUpdates {
+            subscribe: decode_required(it)?,
+            account_code: decode(it)?.unwrap_or_default(),
+        })
+    }
+}
@@ -1,11 +1,14 @@
 use bytes::Bytes;
 
+use super::ParseIbkrCommand;
 use crate::{
     contract,
     enums::Outgoing,
     frame::Frame,
-    utils::ib_message::{Encodable, IBMessage},
-    RequestId,
+    ib_frame::{ParseError, ParseResult},
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    RequestId, Result, ServerVersion,
 };
 const VERSION: i32 = 8;
 
@@ -20,8 +23,8 @@ const VERSION: i32 = 8;
 /// * contract - The summary description of the contract being looked up.
 #[derive(Debug)]
 pub struct RequestContractDetails {
-    req_id: RequestId,
-    contract: contract::Contract,
+    pub req_id: RequestId,
+    pub contract: contract::Contract,
 }
 
 impl RequestContractDetails {
@@ -37,12 +40,37 @@ impl RequestContractDetails {
     ///
     /// This is called by the client when encoding a `RequestMarketData` command
     /// to send to the server.
-    pub(crate) fn into_frame(self) -> Frame {
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
+        if self.contract.issuer_id.is_some() {
+            MinServerVersion::BondIssuerid.check(server_version)?;
+        }
         let mut msg = Outgoing::ReqContractData.encode();
         msg.push_str(&VERSION.encode());
         msg.push_str(&self.req_id.encode());
         msg.push_str(&self.contract.encode());
+        if MinServerVersion::BondIssuerid.is_supported(server_version) {
+            msg.push_str(&self.contract.issuer_id.encode());
+        }
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+impl ParseIbkrCommand for RequestContractDetails {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        if !matches!(msg_id, Outgoing::ReqContractData) {
+            return Err(ParseError::UnexpectedMessage);
+        }
+        it.next(); // skip version
+        let req_id = decode_required(it)?;
+        let mut contract = contract::Contract::decode(it)?;
+        if MinServerVersion::BondIssuerid.is_supported(server_version) {
+            contract.issuer_id = decode(it)?;
+        }
+        Ok(RequestContractDetails { req_id, contract })
     }
 }
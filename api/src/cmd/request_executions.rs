@@ -1,18 +1,20 @@
 use bytes::Bytes;
 
+use super::ParseIbkrCommand;
 use crate::{
     enums::Outgoing,
     frame::Frame,
+    ib_frame::{ParseError, ParseResult},
     order::ExecutionFilter,
-    utils::ib_message::{Encodable, IBMessage},
-    RequestId,
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    RequestId, ServerVersion,
 };
 
 const VERSION: i32 = 3;
 #[derive(Debug)]
 pub struct RequestExecutions {
-    req_id: RequestId,
-    filter: Option<ExecutionFilter>,
+    pub req_id: RequestId,
+    pub filter: Option<ExecutionFilter>,
 }
 
 impl RequestExecutions {
@@ -50,3 +52,38 @@ impl RequestExecutions {
         Frame::Bulk(Bytes::from(msg))
     }
 }
+
+impl ParseIbkrCommand for RequestExecutions {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        if !matches!(msg_id, Outgoing::ReqExecutions) {
+            return Err(ParseError::UnexpectedMessage);
+        }
+        it.next(); // skip version
+        let req_id = decode_required(it)?;
+        let filter = ExecutionFilter {
+            client_id: decode(it)?,
+            account_code: decode(it)?.unwrap_or_default(),
+            time: decode(it)?.unwrap_or_default(),
+            symbol: decode(it)?.unwrap_or_default(),
+            sec_type: decode(it)?.unwrap_or_default(),
+            exchange: decode(it)?.unwrap_or_default(),
+            side: decode(it)?,
+        };
+        // a missing filter is sent as empty fields
+        let empty = filter.client_id.is_none()
+            && filter.account_code.is_empty()
+            && filter.time.is_empty()
+            && filter.symbol.is_empty()
+            && filter.sec_type.is_empty()
+            && filter.exchange.is_empty()
+            && filter.side.is_none();
+        Ok(RequestExecutions {
+            req_id,
+            filter: if empty { None } else { Some(filter) },
+        })
+    }
+}
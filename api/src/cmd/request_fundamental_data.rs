@@ -0,0 +1,96 @@
+use bytes::Bytes;
+
+use super::{request_market_data::decode_tag_value_list, ParseIbkrCommand, TagValue};
+use crate::{
+    contract::Contract,
+    enums::{FundamentalDataType, Outgoing},
+    frame::Frame,
+    ib_frame::{ParseError, ParseResult},
+    utils::ib_message::{decode_required, Encodable, Fields, IBMessage},
+    RequestId, ServerVersion,
+};
+
+const VERSION: i32 = 2;
+const CANCEL_VERSION: i32 = 1;
+
+/// Requests (or cancels) Reuters' fundamental data of a contract.
+///
+/// # Arguments
+/// * req_id - the ID of the data request. Ensures that responses are matched to
+///   requests if several requests are in process.
+/// * contract - this structure contains a description of the contract for which
+///   fundamental data is being requested.
+/// * report_type - the type of report requested
+/// * options - for internal use only.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum RequestFundamentalData {
+    Request {
+        req_id:      RequestId,
+        contract:    Contract,
+        report_type: FundamentalDataType,
+        options:     Vec<TagValue>,
+    },
+    Cancel {
+        req_id: RequestId,
+    },
+}
+
+impl RequestFundamentalData {
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `RequestFundamentalData`
+    /// command to send to the server.
+    pub(crate) fn into_frame(self) -> Frame {
+        let msg = match self {
+            RequestFundamentalData::Request {
+                req_id,
+                contract,
+                report_type,
+                options,
+            } => {
+                let mut msg = Outgoing::ReqFundamentalData.encode();
+                msg.push_str(&VERSION.encode());
+                msg.push_str(&req_id.encode());
+                msg.push_str(&contract.encode_for_fundamental_data());
+                msg.push_str(&report_type.encode());
+                let options_str = options
+                    .iter()
+                    .map(|x| format!("{}={};", x.tag, x.value))
+                    .collect::<String>();
+                msg.push_str(&options_str.encode());
+                msg
+            },
+            RequestFundamentalData::Cancel { req_id } => {
+                let mut msg = Outgoing::CancelFundamentalData.encode();
+                msg.push_str(&CANCEL_VERSION.encode());
+                msg.push_str(&req_id.encode());
+                msg
+            },
+        };
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Frame::Bulk(Bytes::from(msg))
+    }
+}
+
+impl ParseIbkrCommand for RequestFundamentalData {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        it.next(); // skip version
+        match msg_id {
+            Outgoing::ReqFundamentalData => Ok(RequestFundamentalData::Request {
+                req_id:      decode_required(it)?,
+                contract:    Contract::decode_for_fundamental_data(it)?,
+                report_type: decode_required(it)?,
+                options:     decode_tag_value_list(it)?,
+            }),
+            Outgoing::CancelFundamentalData => Ok(RequestFundamentalData::Cancel {
+                req_id: decode_required(it)?,
+            }),
+            _ => Err(ParseError::UnexpectedMessage),
+        }
+    }
+}
@@ -2,16 +2,19 @@ use std::{fmt::{Display, Formatter},
           str::FromStr};
 
 use bytes::Bytes;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
 
-use super::IntoIbkrFrame;
+use super::{IntoIbkrFrame, ParseIbkrCommand};
 use crate::{contract::Contract,
             enums::{MarketDataType, Outgoing},
             frame::Frame,
+            ib_frame::{ParseError, ParseResult},
             prelude::{ib_message::Decodable, BarSize, Duration, ParseEnumError},
             ticker::{GenericTickType, TickByTickType},
-            utils::ib_message::{Encodable, IBMessage},
+            server_versions::MinServerVersion,
+            utils::ib_message::{decode, decode_required, decode_tag_values, Encodable, Fields, IBMessage},
             RequestId,
+            ServerVersion,
             TimeStamp};
 
 /// Call this function to request market data. The market data
@@ -33,7 +36,7 @@ use crate::{contract::Contract,
 /// * regulatory_snapshot - With the US Value Snapshot Bundle for stocks,
 ///   regulatory snapshots are available for 0.01 USD each.
 /// * mkt_data_options - For internal use only. Use default value XYZ.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MarketDataRequest {
     pub req_id:            RequestId,
     pub contract:          Contract,
@@ -44,29 +47,32 @@ pub struct MarketDataRequest {
 }
 
 impl IntoIbkrFrame for MarketDataRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, server_version: ServerVersion) -> crate::Result<Frame> {
+        if self.regulatory {
+            MinServerVersion::ReqSmartComponents.check(server_version)?;
+        }
         let version: i32 = 11;
         let mut msg = Outgoing::ReqMktData.encode();
         msg.push_str(&version.encode()); // version
         msg.push_str(&self.req_id.encode());
         msg.push_str(&self.contract.encode_for_ticker());
         msg.push_str("0\0");
-        for i in &self.generic_tick_list {
-            msg.push_str(&i.encode());
-            msg.push(',');
-        }
-        if let Some(gen_tick) = self.generic_tick_list.last() {
-            msg.push_str(&gen_tick.encode());
-        }
-        // TODO: generic tick list
-        msg.push('\0'); // generic tick data
+        let generic_tick_list = self
+            .generic_tick_list
+            .iter()
+            .map(Encodable::encode)
+            .collect::<Vec<_>>()
+            .join(",");
+        msg.push_str(&generic_tick_list.encode());
         msg.push_str(&self.snapshot.encode());
-        msg.push_str(&self.regulatory.encode());
+        if MinServerVersion::ReqSmartComponents.is_supported(server_version) {
+            msg.push_str(&self.regulatory.encode());
+        }
         // TODO: MarketData Options
         // current doc says this part if for "internal use only" -> won't support it
         msg.push('\0');
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// Cancel the request
@@ -78,11 +84,12 @@ pub struct CancelHeadTimestampRequest {
     pub req_id: RequestId,
 }
 impl IntoIbkrFrame for CancelHeadTimestampRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, server_version: ServerVersion) -> crate::Result<Frame> {
+        MinServerVersion::CancelHeadtimestamp.check(server_version)?;
         let mut msg = Outgoing::CancelHeadTimestamp.encode();
         msg.push_str(&self.req_id.encode());
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// Used if an internet disconnect has occurred or the results of a query
@@ -96,11 +103,11 @@ pub struct CancelHistoricalDataRequest {
     pub req_id: RequestId,
 }
 impl IntoIbkrFrame for CancelHistoricalDataRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, _server_version: ServerVersion) -> crate::Result<Frame> {
         let mut msg = Outgoing::CancelHistoricalData.encode();
         msg.push_str(&self.req_id.encode());
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// After calling this function, market depth data for the specified id
@@ -115,11 +122,11 @@ pub struct CancelMarketDepthRequest {
     pub is_smart_depth: bool,
 }
 impl IntoIbkrFrame for CancelMarketDepthRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, _server_version: ServerVersion) -> crate::Result<Frame> {
         let mut msg = Outgoing::CancelMktDepth.encode();
         msg.push_str(&self.req_id.encode());
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// After calling this function, market data for the specified id will stop
@@ -132,11 +139,11 @@ pub struct CancelMarketDataRequest {
     pub req_id: RequestId,
 }
 impl IntoIbkrFrame for CancelMarketDataRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, _server_version: ServerVersion) -> crate::Result<Frame> {
         let mut msg = Outgoing::CancelMktData.encode();
         msg.push_str(&self.req_id.encode());
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// Cancel tick by tick data
@@ -148,11 +155,12 @@ pub struct CancelTickByTickRequest {
     pub req_id: RequestId,
 }
 impl IntoIbkrFrame for CancelTickByTickRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, server_version: ServerVersion) -> crate::Result<Frame> {
+        MinServerVersion::TickByTick.check(server_version)?;
         let mut msg = Outgoing::CancelTickByTickData.encode();
         msg.push_str(&self.req_id.encode());
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 // /// Returns the timestamp of earliest available historical data for a
@@ -175,7 +183,11 @@ pub struct HeadTimestampRequest {
     pub format_date:  IntradayBarDateFormat,
 }
 impl IntoIbkrFrame for HeadTimestampRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, server_version: ServerVersion) -> crate::Result<Frame> {
+        MinServerVersion::ReqHeadTimestamp.check(server_version)?;
+        if self.what_to_show == HistoricalDataType::Schedule {
+            MinServerVersion::HistoricalSchedule.check(server_version)?;
+        }
         // let version: i32 = 6;
         let mut msg = Outgoing::ReqHeadTimestamp.encode();
         // msg.push_str(&version.encode()); // version
@@ -187,7 +199,7 @@ impl IntoIbkrFrame for HeadTimestampRequest {
         msg.push_str(&self.what_to_show.encode());
         msg.push_str(&self.format_date.encode());
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// Requests contracts' historical data. When requesting historical data, a
@@ -264,10 +276,19 @@ pub struct HistoricalDataRequest {
     pub chart_options:    Vec<TagValue>,
 }
 impl IntoIbkrFrame for HistoricalDataRequest {
-    fn into_frame(&self) -> Frame {
-        // let version: i32 = 6;
+    fn into_frame(&self, server_version: ServerVersion) -> crate::Result<Frame> {
+        let keep_up_to_date = MinServerVersion::SyntRealtimeBars;
+        if self.keep_up_to_date {
+            keep_up_to_date.check(server_version)?;
+        }
+        if self.what_to_show == HistoricalDataType::Schedule {
+            MinServerVersion::HistoricalSchedule.check(server_version)?;
+        }
+        let version: i32 = 6;
         let mut msg = Outgoing::ReqHistoricalData.encode();
-        // msg.push_str(&version.encode()); // version
+        if !keep_up_to_date.is_supported(server_version) {
+            msg.push_str(&version.encode()); // version
+        }
         msg.push_str(&self.req_id.encode());
         msg.push_str(&self.contract.encode_for_ticker());
         msg.push_str(&self.contract.include_expired.encode());
@@ -286,7 +307,9 @@ impl IntoIbkrFrame for HistoricalDataRequest {
         msg.push_str(&self.use_rth.encode());
         msg.push_str(&self.what_to_show.encode());
         msg.push_str(&self.format_date.encode());
-        msg.push_str(&self.keep_up_to_date.encode());
+        if keep_up_to_date.is_supported(server_version) {
+            msg.push_str(&self.keep_up_to_date.encode());
+        }
         let chart_options_str = self
             .chart_options
             .iter()
@@ -295,7 +318,7 @@ impl IntoIbkrFrame for HistoricalDataRequest {
         msg.push_str(&chart_options_str.encode());
         msg.push('\0');
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// Requests historical Time&Sales data for an instrument.
@@ -313,7 +336,7 @@ impl IntoIbkrFrame for HistoricalDataRequest {
 /// * use_rth - Data from regular trading hours (1), or all available hours (0)
 /// * ignore_size - A filter only used when the source price is Bid_Ask
 /// * misc_options - should be defined as null, reserved for internal use
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HistoricalTicksRequest {
     pub req_id:          RequestId,
     pub contract:        Contract,
@@ -325,7 +348,8 @@ pub struct HistoricalTicksRequest {
     pub misc_options:    Vec<TagValue>,
 }
 impl IntoIbkrFrame for HistoricalTicksRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, server_version: ServerVersion) -> crate::Result<Frame> {
+        MinServerVersion::HistoricalTicks.check(server_version)?;
         // let version: i32 = 6;
         let mut msg = Outgoing::ReqHistoricalTicks.encode();
         // msg.push_str(&version.encode()); // version
@@ -363,7 +387,7 @@ impl IntoIbkrFrame for HistoricalTicksRequest {
         msg.push_str(&misc_options_str.encode());
         msg.push('\0');
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// The API can receive frozen market data from Trader
@@ -383,13 +407,13 @@ pub struct MarketDataTypeRequest {
     pub market_data_type: MarketDataType,
 }
 impl IntoIbkrFrame for MarketDataTypeRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, _server_version: ServerVersion) -> crate::Result<Frame> {
         let version: i32 = 1;
         let mut msg = Outgoing::ReqMarketDataType.encode();
         msg.push_str(&version.encode());
         msg.push_str(&self.market_data_type.encode());
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// Call this function to request market depth for a specific
@@ -414,7 +438,7 @@ impl IntoIbkrFrame for MarketDataTypeRequest {
 ///   STREAMING DATA IF THIS IS SET TO TRUE
 /// * mkt_depth_options - For internal use only. Use default value XYZ.
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MarketDepthRequest {
     pub req_id:            RequestId,
     pub contract:          Contract,
@@ -424,19 +448,24 @@ pub struct MarketDepthRequest {
 }
 
 impl IntoIbkrFrame for MarketDepthRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, server_version: ServerVersion) -> crate::Result<Frame> {
+        if self.is_smart_depth {
+            MinServerVersion::SmartDepth.check(server_version)?;
+        }
         let version: i32 = 5;
         let mut msg = Outgoing::ReqMktDepth.encode();
         msg.push_str(&version.encode()); // version
         msg.push_str(&self.req_id.encode());
-        msg.push_str(&self.contract.encode_for_ticker());
+        msg.push_str(&self.contract.encode_for_market_depth(server_version));
         msg.push_str(&self.num_rows.encode());
-        msg.push_str(&self.is_smart_depth.encode());
+        if MinServerVersion::SmartDepth.is_supported(server_version) {
+            msg.push_str(&self.is_smart_depth.encode());
+        }
         // send mkt_depth_options parameter
         // current doc says this part if for "internal use only" -> won't support it
         msg.push('\0');
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// Requests venues for which market data is returned to update_mkt_depth_l2
@@ -444,10 +473,11 @@ impl IntoIbkrFrame for MarketDepthRequest {
 #[derive(Debug, Clone, Copy)]
 pub struct MarketDepthExchangesRequest;
 impl IntoIbkrFrame for MarketDepthExchangesRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, server_version: ServerVersion) -> crate::Result<Frame> {
+        MinServerVersion::ReqMktDepthExchanges.check(server_version)?;
         let msg = Outgoing::ReqMktDepthExchanges.encode();
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 /// Request tick by tick data
@@ -459,7 +489,7 @@ impl IntoIbkrFrame for MarketDepthExchangesRequest {
 ///   "MidPoint".
 /// * number_of_ticks    - number of ticks.
 /// * ignore_size    - ignore size flag./
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TickByTickRequest {
     pub req_id:          RequestId,
     pub contract:        Contract,
@@ -468,16 +498,23 @@ pub struct TickByTickRequest {
     pub ignore_size:     bool,
 }
 impl IntoIbkrFrame for TickByTickRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, server_version: ServerVersion) -> crate::Result<Frame> {
+        MinServerVersion::TickByTick.check(server_version)?;
+        let ignore_size = MinServerVersion::TickByTickIgnoreSize;
+        if self.number_of_ticks != 0 || self.ignore_size {
+            ignore_size.check(server_version)?;
+        }
         let mut msg = Outgoing::ReqTickByTickData.encode();
         // msg.push_str(&VERSION.encode()); // version
         msg.push_str(&self.req_id.encode());
         msg.push_str(&self.contract.encode_for_ticker());
         msg.push_str(&self.tick_type.encode());
-        msg.push_str(&self.number_of_ticks.encode());
-        msg.push_str(&self.ignore_size.encode());
+        if ignore_size.is_supported(server_version) {
+            msg.push_str(&self.number_of_ticks.encode());
+            msg.push_str(&self.ignore_size.encode());
+        }
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 // //----------------------------------------------------------------------------------------------
@@ -541,7 +578,7 @@ pub enum HistoricalTickDateTime {
 // historical data request: no more than 60 API queries in more than 600 seconds. Real time bars
 // subscriptions are also included in the calculation of the number of Level 1 market data
 // subscriptions allowed in an account.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RealtimeBarRequest {
     pub req_id:                 RequestId,
     pub contract:               Contract,
@@ -551,7 +588,7 @@ pub struct RealtimeBarRequest {
     pub real_time_bars_options: Vec<TagValue>,
 }
 impl IntoIbkrFrame for RealtimeBarRequest {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, _server_version: ServerVersion) -> crate::Result<Frame> {
         let version: i32 = 3;
 
         let mut msg = Outgoing::ReqRealTimeBars.encode();
@@ -574,7 +611,7 @@ impl IntoIbkrFrame for RealtimeBarRequest {
         msg.push_str(&options_str.encode());
         msg.push('\0');
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
 
@@ -588,15 +625,529 @@ pub struct CancelRealtimeBars {
     pub req_id: RequestId,
 }
 impl IntoIbkrFrame for CancelRealtimeBars {
-    fn into_frame(&self) -> Frame {
+    fn into_frame(&self, _server_version: ServerVersion) -> crate::Result<Frame> {
         let version: i32 = 1;
         let mut msg = Outgoing::CancelRealTimeBars.encode();
         msg.push_str(&version.encode());
         msg.push_str(&self.req_id.encode());
         let msg = msg.as_str().to_ib_message().unwrap();
-        Frame::Bulk(Bytes::from(msg))
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+//----------------------------------------------------------------------------------------------
+/// Calculates the implied volatility based on the hypothetical option and its
+/// underlying prices.
+///
+/// The calculation will be returned as a `Tick::TickOptionComputation` with
+/// tick type `CustOptionComputation`.
+///
+/// # Arguments
+/// * req_id - The request id.
+/// * contract - Describes the contract.
+/// * option_price - The price of the option.
+/// * under_price - Price of the underlying.
+/// * options - Implied volatility options.
+#[derive(Debug, Clone)]
+pub struct CalculateImpliedVolatilityRequest {
+    pub req_id:       RequestId,
+    pub contract:     Contract,
+    pub option_price: f64,
+    pub under_price:  f64,
+    pub options:      Vec<TagValue>,
+}
+impl IntoIbkrFrame for CalculateImpliedVolatilityRequest {
+    fn into_frame(&self, _server_version: ServerVersion) -> crate::Result<Frame> {
+        let version: i32 = 3;
+        let mut msg = Outgoing::ReqCalcImpliedVolat.encode();
+        msg.push_str(&version.encode());
+        msg.push_str(&self.req_id.encode());
+        msg.push_str(&self.contract.encode_for_ticker());
+        msg.push_str(&self.option_price.encode());
+        msg.push_str(&self.under_price.encode());
+        let options_str = self
+            .options
+            .iter()
+            .map(|x| format!("{}={};", x.tag, x.value))
+            .collect::<String>();
+        msg.push_str(&options_str.encode());
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+/// Calculates an option's price based on the provided volatility and its
+/// underlying's price.
+///
+/// The calculation will be returned as a `Tick::TickOptionComputation` with
+/// tick type `CustOptionComputation`.
+///
+/// # Arguments
+/// * req_id - The request id.
+/// * contract - Describes the contract.
+/// * volatility - The volatility.
+/// * under_price - Price of the underlying.
+/// * options - Option price options.
+#[derive(Debug, Clone)]
+pub struct CalculateOptionPriceRequest {
+    pub req_id:      RequestId,
+    pub contract:    Contract,
+    pub volatility:  f64,
+    pub under_price: f64,
+    pub options:     Vec<TagValue>,
+}
+impl IntoIbkrFrame for CalculateOptionPriceRequest {
+    fn into_frame(&self, _server_version: ServerVersion) -> crate::Result<Frame> {
+        let version: i32 = 3;
+        let mut msg = Outgoing::ReqCalcOptionPrice.encode();
+        msg.push_str(&version.encode());
+        msg.push_str(&self.req_id.encode());
+        msg.push_str(&self.contract.encode_for_ticker());
+        msg.push_str(&self.volatility.encode());
+        msg.push_str(&self.under_price.encode());
+        let options_str = self
+            .options
+            .iter()
+            .map(|x| format!("{}={};", x.tag, x.value))
+            .collect::<String>();
+        msg.push_str(&options_str.encode());
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
     }
 }
+
+/// Cancels an option's implied volatility calculation request.
+///
+/// # Arguments
+/// * req_id - The request id specified in
+///   `CalculateImpliedVolatilityRequest`.
+#[derive(Debug, Clone, Copy)]
+pub struct CancelCalculateImpliedVolatilityRequest {
+    pub req_id: RequestId,
+}
+impl IntoIbkrFrame for CancelCalculateImpliedVolatilityRequest {
+    fn into_frame(&self, _server_version: ServerVersion) -> crate::Result<Frame> {
+        let version: i32 = 1;
+        let mut msg = Outgoing::CancelCalcImpliedVolat.encode();
+        msg.push_str(&version.encode());
+        msg.push_str(&self.req_id.encode());
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+/// Cancels an option's price calculation request.
+///
+/// # Arguments
+/// * req_id - The request id specified in `CalculateOptionPriceRequest`.
+#[derive(Debug, Clone, Copy)]
+pub struct CancelCalculateOptionPriceRequest {
+    pub req_id: RequestId,
+}
+impl IntoIbkrFrame for CancelCalculateOptionPriceRequest {
+    fn into_frame(&self, _server_version: ServerVersion) -> crate::Result<Frame> {
+        let version: i32 = 1;
+        let mut msg = Outgoing::CancelCalcOptionPrice.encode();
+        msg.push_str(&version.encode());
+        msg.push_str(&self.req_id.encode());
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+/// Decodes a list of tag values encoded as "tag1=value1;tag2=value2;".
+pub(super) fn decode_tag_value_list(it: &mut Fields<'_>) -> ParseResult<Vec<TagValue>> {
+    Ok(decode_tag_values(it)?
+        .into_iter()
+        .map(|(tag, value)| TagValue::new(tag, value))
+        .collect())
+}
+
+/// Decodes a date time formatted as "yyyyMMdd-HH:mm:ss" (UTC), the format
+/// used by the historical data requests.
+fn decode_date_time(it: &mut Fields<'_>) -> ParseResult<Option<TimeStamp>> {
+    match decode::<String>(it)? {
+        Some(date_time) => {
+            let date_time = NaiveDateTime::parse_from_str(&date_time, "%Y%m%d-%H:%M:%S")
+                .map_err(|_| ParseError::InvalidValue(date_time))?;
+            Ok(Some(Utc.from_utc_datetime(&date_time)))
+        },
+        None => Ok(None),
+    }
+}
+
+/// Checks that a command decoded with a single message id was sent with it.
+const fn expect_msg_id(msg_id: Outgoing, expected: Outgoing) -> ParseResult<()> {
+    if msg_id as i32 == expected as i32 {
+        Ok(())
+    } else {
+        Err(ParseError::UnexpectedMessage)
+    }
+}
+
+impl ParseIbkrCommand for MarketDataRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqMktData)?;
+        it.next(); // skip version
+        let req_id = decode_required(it)?;
+        let contract = Contract::decode_for_ticker(it)?;
+        it.next(); // skip combo legs
+        let generic_tick_list = decode::<String>(it)?
+            .unwrap_or_default()
+            .split(',')
+            .filter(|tick| !tick.is_empty())
+            .map(|tick| {
+                tick.parse()
+                    .map_err(|_| ParseError::InvalidValue(tick.to_string()))
+            })
+            .collect::<ParseResult<_>>()?;
+        let snapshot = decode_required(it)?;
+        let regulatory = if MinServerVersion::ReqSmartComponents.is_supported(server_version) {
+            decode_required(it)?
+        } else {
+            false
+        };
+        Ok(MarketDataRequest {
+            req_id,
+            contract,
+            generic_tick_list,
+            snapshot,
+            regulatory,
+            additional_data: Vec::new(),
+        })
+    }
+}
+
+impl ParseIbkrCommand for CancelHeadTimestampRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::CancelHeadTimestamp)?;
+        Ok(CancelHeadTimestampRequest {
+            req_id: decode_required(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for CancelHistoricalDataRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::CancelHistoricalData)?;
+        Ok(CancelHistoricalDataRequest {
+            req_id: decode_required(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for CancelMarketDepthRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::CancelMktDepth)?;
+        // the smart depth flag is not sent
+        Ok(CancelMarketDepthRequest {
+            req_id:         decode_required(it)?,
+            is_smart_depth: false,
+        })
+    }
+}
+
+impl ParseIbkrCommand for CancelMarketDataRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::CancelMktData)?;
+        Ok(CancelMarketDataRequest {
+            req_id: decode_required(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for CancelTickByTickRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::CancelTickByTickData)?;
+        Ok(CancelTickByTickRequest {
+            req_id: decode_required(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for HeadTimestampRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqHeadTimestamp)?;
+        let req_id = decode_required(it)?;
+        let mut contract = Contract::decode_for_ticker(it)?;
+        contract.include_expired = decode(it)?;
+        Ok(HeadTimestampRequest {
+            req_id,
+            contract,
+            use_rth: decode_required(it)?,
+            what_to_show: decode_required(it)?,
+            format_date: decode_required(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for HistoricalDataRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqHistoricalData)?;
+        let keep_up_to_date = MinServerVersion::SyntRealtimeBars.is_supported(server_version);
+        if !keep_up_to_date {
+            it.next(); // skip version
+        }
+        let req_id = decode_required(it)?;
+        let mut contract = Contract::decode_for_ticker(it)?;
+        contract.include_expired = decode(it)?;
+        let end_date_time = decode_date_time(it)?
+            .ok_or_else(|| ParseError::InvalidValue("end date time".to_string()))?;
+        Ok(HistoricalDataRequest {
+            req_id,
+            contract,
+            end_date_time,
+            bar_size_setting: decode_required(it)?,
+            duration: decode_required(it)?,
+            use_rth: decode_required(it)?,
+            what_to_show: decode_required(it)?,
+            format_date: decode_required(it)?,
+            keep_up_to_date: keep_up_to_date && decode_required(it)?,
+            chart_options: decode_tag_value_list(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for HistoricalTicksRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqHistoricalTicks)?;
+        let req_id = decode_required(it)?;
+        let mut contract = Contract::decode_for_ticker(it)?;
+        contract.include_expired = decode(it)?;
+        let start = decode_date_time(it)?;
+        let end = decode_date_time(it)?;
+        let date_time = match (start, end) {
+            (Some(start), _) => HistoricalTickDateTime::Start(start),
+            (None, Some(end)) => HistoricalTickDateTime::End(end),
+            (None, None) => {
+                return Err(ParseError::InvalidValue("start or end date time".to_string()))
+            },
+        };
+        Ok(HistoricalTicksRequest {
+            req_id,
+            contract,
+            date_time,
+            number_of_ticks: decode_required(it)?,
+            what_to_show: decode_required(it)?,
+            use_rth: decode_required(it)?,
+            ignore_size: decode_required(it)?,
+            misc_options: decode_tag_value_list(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for MarketDataTypeRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqMarketDataType)?;
+        it.next(); // skip version
+        Ok(MarketDataTypeRequest {
+            market_data_type: decode_required(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for MarketDepthRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqMktDepth)?;
+        it.next(); // skip version
+        let req_id = decode_required(it)?;
+        let contract = Contract::decode_for_market_depth(it, server_version)?;
+        let num_rows = decode_required(it)?;
+        let is_smart_depth = if MinServerVersion::SmartDepth.is_supported(server_version) {
+            decode_required(it)?
+        } else {
+            false
+        };
+        Ok(MarketDepthRequest {
+            req_id,
+            contract,
+            num_rows,
+            is_smart_depth,
+            mkt_depth_options: Vec::new(),
+        })
+    }
+}
+
+impl ParseIbkrCommand for MarketDepthExchangesRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        _it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqMktDepthExchanges)?;
+        Ok(MarketDepthExchangesRequest)
+    }
+}
+
+impl ParseIbkrCommand for TickByTickRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqTickByTickData)?;
+        let req_id = decode_required(it)?;
+        let contract = Contract::decode_for_ticker(it)?;
+        let tick_type = decode(it)?.unwrap_or(TickByTickType::NA);
+        let (number_of_ticks, ignore_size) =
+            if MinServerVersion::TickByTickIgnoreSize.is_supported(server_version) {
+                (decode_required(it)?, decode_required(it)?)
+            } else {
+                (0, false)
+            };
+        Ok(TickByTickRequest {
+            req_id,
+            contract,
+            tick_type,
+            number_of_ticks,
+            ignore_size,
+        })
+    }
+}
+
+impl ParseIbkrCommand for RealtimeBarRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqRealTimeBars)?;
+        it.next(); // skip version
+        let req_id = decode_required(it)?;
+        let contract = Contract::decode_for_ticker(it)?;
+        it.next(); // skip bar size, only 5 second bars are supported
+        Ok(RealtimeBarRequest {
+            req_id,
+            contract,
+            bar_size: BarSize::_5Secs,
+            what_to_show: decode_required(it)?,
+            use_rth: decode_required(it)?,
+            real_time_bars_options: decode_tag_value_list(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for CancelRealtimeBars {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::CancelRealTimeBars)?;
+        it.next(); // skip version
+        Ok(CancelRealtimeBars {
+            req_id: decode_required(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for CalculateImpliedVolatilityRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqCalcImpliedVolat)?;
+        it.next(); // skip version
+        Ok(CalculateImpliedVolatilityRequest {
+            req_id:       decode_required(it)?,
+            contract:     Contract::decode_for_ticker(it)?,
+            option_price: decode_required(it)?,
+            under_price:  decode_required(it)?,
+            options:      decode_tag_value_list(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for CalculateOptionPriceRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::ReqCalcOptionPrice)?;
+        it.next(); // skip version
+        Ok(CalculateOptionPriceRequest {
+            req_id:      decode_required(it)?,
+            contract:    Contract::decode_for_ticker(it)?,
+            volatility:  decode_required(it)?,
+            under_price: decode_required(it)?,
+            options:     decode_tag_value_list(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for CancelCalculateImpliedVolatilityRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::CancelCalcImpliedVolat)?;
+        it.next(); // skip version
+        Ok(CancelCalculateImpliedVolatilityRequest {
+            req_id: decode_required(it)?,
+        })
+    }
+}
+
+impl ParseIbkrCommand for CancelCalculateOptionPriceRequest {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        expect_msg_id(msg_id, Outgoing::CancelCalcOptionPrice)?;
+        it.next(); // skip version
+        Ok(CancelCalculateOptionPriceRequest {
+            req_id: decode_required(it)?,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 /// format_date - Determines the date format applied to returned bars. validd
 /// values include:
@@ -777,7 +1328,7 @@ impl Display for HistoricalDataType {
 }
 
 impl Decodable for HistoricalDataType {}
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MarketDepthOperation {
     Insert = 0,
     Update = 1,
@@ -800,7 +1351,13 @@ impl FromStr for MarketDepthOperation {
 
 impl Decodable for MarketDepthOperation {}
 
-#[derive(Debug, Clone, Copy)]
+impl Encodable for MarketDepthOperation {
+    fn encode(&self) -> String { (*self as i32).encode() }
+}
+
+/// The side of the book of a market depth update, TWS sends 0 for the ask and
+/// 1 for the bid side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DepthSide {
     Bid,
     Ask,
@@ -811,11 +1368,76 @@ impl FromStr for DepthSide {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "0" => Ok(DepthSide::Bid),
-            "1" => Ok(DepthSide::Ask),
+            "0" => Ok(DepthSide::Ask),
+            "1" => Ok(DepthSide::Bid),
             &_ => Err(ParseEnumError),
         }
     }
 }
 
 impl Decodable for DepthSide {}
+
+impl Encodable for DepthSide {
+    fn encode(&self) -> String {
+        match self {
+            DepthSide::Ask => "0\0",
+            DepthSide::Bid => "1\0",
+        }
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::Error;
+
+    fn fields(frame: &Frame) -> Vec<String> {
+        match frame {
+            Frame::Bulk(msg) => {
+                String::from_utf8_lossy(&msg[4..])
+                    .split('\0')
+                    .map(str::to_string)
+                    .collect()
+            },
+            Frame::Array(_) => panic!("unexpected frame"),
+        }
+    }
+
+    #[test]
+    fn tick_by_tick_fields_depend_on_server_version() {
+        let mut request = TickByTickRequest {
+            req_id:          1,
+            contract:        Contract {
+                symbol: "AAPL".to_string(),
+                ..Default::default()
+            },
+            tick_type:       TickByTickType::Last,
+            number_of_ticks: 0,
+            ignore_size:     false,
+        };
+        let old = fields(&request.into_frame(137).unwrap());
+        let new = fields(&request.into_frame(140).unwrap());
+        // number of ticks and ignore size
+        assert_eq!(old.len() + 2, new.len());
+        assert_eq!(["0", "0"], new[new.len() - 3..new.len() - 1]);
+
+        request.ignore_size = true;
+        assert!(matches!(
+            request.into_frame(139),
+            Err(Error::Unsupported {
+                feature:        MinServerVersion::TickByTickIgnoreSize,
+                server_version: 139,
+            })
+        ));
+        assert!(matches!(
+            request.into_frame(136),
+            Err(Error::Unsupported {
+                feature: MinServerVersion::TickByTick,
+                ..
+            })
+        ));
+    }
+}
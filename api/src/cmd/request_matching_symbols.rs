@@ -0,0 +1,58 @@
+use bytes::Bytes;
+
+use super::ParseIbkrCommand;
+use crate::{
+    enums::Outgoing,
+    frame::Frame,
+    ib_frame::{ParseError, ParseResult},
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    RequestId, Result, ServerVersion,
+};
+
+/// Requests matching stock symbols.
+///
+/// # Arguments
+/// * req_id - id to specify the request
+/// * pattern - either start of ticker symbol or (for larger strings) company
+///   name
+#[derive(Debug)]
+pub struct RequestMatchingSymbols {
+    pub req_id:  RequestId,
+    pub pattern: String,
+}
+
+impl RequestMatchingSymbols {
+    pub const fn new(req_id: RequestId, pattern: String) -> RequestMatchingSymbols {
+        RequestMatchingSymbols { req_id, pattern }
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `RequestMatchingSymbols`
+    /// command to send to the server.
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
+        MinServerVersion::ReqMatchingSymbols.check(server_version)?;
+        let mut msg = Outgoing::ReqMatchingSymbols.encode();
+        msg.push_str(&self.req_id.encode());
+        msg.push_str(&self.pattern.encode());
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+impl ParseIbkrCommand for RequestMatchingSymbols {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        if !matches!(msg_id, Outgoing::ReqMatchingSymbols) {
+            return Err(ParseError::UnexpectedMessage);
+        }
+        Ok(RequestMatchingSymbols {
+            req_id:  decode_required(it)?,
+            pattern: decode(it)?.unwrap_or_default(),
+        })
+    }
+}
@@ -0,0 +1,195 @@
+use bytes::Bytes;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+
+use super::{request_market_data::decode_tag_value_list, ParseIbkrCommand, TagValue};
+use crate::{
+    enums::Outgoing,
+    frame::Frame,
+    ib_frame::{ParseError, ParseResult},
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    RequestId, Result, ServerVersion, TimeStamp,
+};
+
+const VERSION: i32 = 1;
+
+/// Requests news providers, articles, headlines and bulletins.
+///
+/// * `Providers` - Requests news sources subscribed to by the user.
+/// * `Article` - Requests the body of a news article.
+/// * `Historical` - Requests historical news headlines of a contract. The
+///   provider codes are joined with '+', e.g. "BRFG+DJNL".
+/// * `Bulletins` - Subscribes to IB's news bulletins. With `all_messages` all
+///   existing bulletins of the current day are sent first.
+#[derive(Debug, Clone)]
+pub enum RequestNews {
+    Providers,
+    Article {
+        req_id:        RequestId,
+        provider_code: String,
+        article_id:    String,
+        options:       Vec<TagValue>,
+    },
+    Historical {
+        req_id:         RequestId,
+        con_id:         i32,
+        provider_codes: Vec<String>,
+        start:          Option<TimeStamp>,
+        end:            Option<TimeStamp>,
+        total_results:  i32,
+        options:        Vec<TagValue>,
+    },
+    Bulletins {
+        all_messages: bool,
+    },
+    CancelBulletins,
+}
+
+impl RequestNews {
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `RequestNews` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
+        let msg = match self {
+            RequestNews::Providers => {
+                MinServerVersion::ReqNewsProviders.check(server_version)?;
+                Outgoing::ReqNewsProviders.encode()
+            },
+            RequestNews::Article {
+                req_id,
+                provider_code,
+                article_id,
+                options,
+            } => {
+                MinServerVersion::ReqNewsArticle.check(server_version)?;
+                let mut msg = Outgoing::ReqNewsArticle.encode();
+                msg.push_str(&req_id.encode());
+                msg.push_str(&provider_code.encode());
+                msg.push_str(&article_id.encode());
+                if MinServerVersion::NewsQueryOrigins.is_supported(server_version) {
+                    let options_str = options
+                        .iter()
+                        .map(|x| format!("{}={};", x.tag, x.value))
+                        .collect::<String>();
+                    msg.push_str(&options_str.encode());
+                }
+                msg
+            },
+            RequestNews::Historical {
+                req_id,
+                con_id,
+                provider_codes,
+                start,
+                end,
+                total_results,
+                options,
+            } => {
+                MinServerVersion::ReqHistoricalNews.check(server_version)?;
+                let mut msg = Outgoing::ReqHistoricalNews.encode();
+                msg.push_str(&req_id.encode());
+                msg.push_str(&con_id.encode());
+                msg.push_str(&provider_codes.join("+").encode());
+                msg.push_str(
+                    &start
+                        .map(|start| start.format("%Y-%m-%d %H:%M:%S.0").to_string())
+                        .encode(),
+                );
+                msg.push_str(
+                    &end.map(|end| end.format("%Y-%m-%d %H:%M:%S.0").to_string())
+                        .encode(),
+                );
+                msg.push_str(&total_results.encode());
+                if MinServerVersion::NewsQueryOrigins.is_supported(server_version) {
+                    let options_str = options
+                        .iter()
+                        .map(|x| format!("{}={};", x.tag, x.value))
+                        .collect::<String>();
+                    msg.push_str(&options_str.encode());
+                }
+                msg
+            },
+            RequestNews::Bulletins { all_messages } => {
+                let mut msg = Outgoing::ReqNewsBulletins.encode();
+                msg.push_str(&VERSION.encode());
+                msg.push_str(&all_messages.encode());
+                msg
+            },
+            RequestNews::CancelBulletins => {
+                let mut msg = Outgoing::CancelNewsBulletins.encode();
+                msg.push_str(&VERSION.encode());
+                msg
+            },
+        };
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+/// Decodes a date time formatted as "yyyy-MM-dd HH:mm:ss.0".
+fn decode_date_time(it: &mut Fields<'_>) -> ParseResult<Option<TimeStamp>> {
+    match decode::<String>(it)? {
+        Some(date_time) => {
+            let date_time = NaiveDateTime::parse_from_str(&date_time, "%Y-%m-%d %H:%M:%S%.f")
+                .map_err(|_| ParseError::InvalidValue(date_time))?;
+            Ok(Some(Utc.from_utc_datetime(&date_time)))
+        },
+        None => Ok(None),
+    }
+}
+
+impl ParseIbkrCommand for RequestNews {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        let query_origins = MinServerVersion::NewsQueryOrigins.is_supported(server_version);
+        match msg_id {
+            Outgoing::ReqNewsProviders => Ok(RequestNews::Providers),
+            Outgoing::ReqNewsArticle => {
+                Ok(RequestNews::Article {
+                    req_id:        decode_required(it)?,
+                    provider_code: decode(it)?.unwrap_or_default(),
+                    article_id:    decode(it)?.unwrap_or_default(),
+                    options:       if query_origins {
+                        decode_tag_value_list(it)?
+                    } else {
+                        Vec::new()
+                    },
+                })
+            },
+            Outgoing::ReqHistoricalNews => {
+                let req_id = decode_required(it)?;
+                let con_id = decode_required(it)?;
+                let provider_codes = decode::<String>(it)?
+                    .unwrap_or_default()
+                    .split('+')
+                    .filter(|code| !code.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                Ok(RequestNews::Historical {
+                    req_id,
+                    con_id,
+                    provider_codes,
+                    start: decode_date_time(it)?,
+                    end: decode_date_time(it)?,
+                    total_results: decode_required(it)?,
+                    options: if query_origins {
+                        decode_tag_value_list(it)?
+                    } else {
+                        Vec::new()
+                    },
+                })
+            },
+            Outgoing::ReqNewsBulletins => {
+                it.next(); // skip version
+                Ok(RequestNews::Bulletins {
+                    all_messages: decode_required(it)?,
+                })
+            },
+            Outgoing::CancelNewsBulletins => Ok(RequestNews::CancelBulletins),
+            _ => Err(ParseError::UnexpectedMessage),
+        }
+    }
+}
@@ -1,9 +1,13 @@
 use bytes::Bytes;
 
+use super::ParseIbkrCommand;
 use crate::{
     enums::Outgoing,
     frame::Frame,
-    utils::ib_message::{Encodable, IBMessage},
+    ib_frame::{ParseError, ParseResult},
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode_required, Encodable, Fields, IBMessage},
+    Result, ServerVersion,
 };
 
 const VERSION: i32 = 1;
@@ -22,8 +26,8 @@ impl RequestOrders {
     ///
     /// This is called by the client when encoding a `RequestMarketData` command
     /// to send to the server.
-    pub(crate) fn into_frame(self) -> Frame {
-        match self {
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
+        let frame = match self {
             RequestOrders::AllOpen => {
                 let mut msg = Outgoing::ReqAllOpenOrders.encode();
                 msg.push_str(&VERSION.encode()); // version
@@ -38,6 +42,7 @@ impl RequestOrders {
                 Frame::Bulk(Bytes::from(msg))
             },
             RequestOrders::Completed { api_only } => {
+                MinServerVersion::CompletedOrders.check(server_version)?;
                 let mut msg = Outgoing::ReqCompletedOrders.encode();
                 // msg.push_str(&VERSION.encode()); // version
                 msg.push_str(&api_only.encode()); // version
@@ -58,6 +63,31 @@ impl RequestOrders {
                 let msg = msg.as_str().to_ib_message().unwrap();
                 Frame::Bulk(Bytes::from(msg))
             },
+        };
+        Ok(frame)
+    }
+}
+
+impl ParseIbkrCommand for RequestOrders {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        match msg_id {
+            Outgoing::ReqAllOpenOrders => Ok(RequestOrders::AllOpen),
+            Outgoing::ReqAutoOpenOrders => {
+                it.next(); // skip version
+                Ok(RequestOrders::AutoOpen {
+                    auto_bind: decode_required(it)?,
+                })
+            },
+            Outgoing::ReqCompletedOrders => Ok(RequestOrders::Completed {
+                api_only: decode_required(it)?,
+            }),
+            Outgoing::ReqOpenOrders => Ok(RequestOrders::Open),
+            Outgoing::ReqIds => Ok(RequestOrders::NextOrderId),
+            _ => Err(ParseError::UnexpectedMessage),
         }
     }
 }
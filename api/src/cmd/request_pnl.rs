@@ -0,0 +1,123 @@
+use bytes::Bytes;
+
+use super::ParseIbkrCommand;
+use crate::{
+    enums::Outgoing,
+    frame::Frame,
+    ib_frame::{ParseError, ParseResult},
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    AccountCode, RequestId, Result, ServerVersion,
+};
+
+/// Subscribes to (or cancels) profit and loss updates.
+///
+/// * `Pnl` - Creates subscription for real time daily PnL and unrealized PnL
+///   updates of an account and/or model.
+/// * `PnlSingle` - Requests real time updates for daily PnL of individual
+///   positions.
+#[derive(Debug, Clone)]
+pub enum RequestPnl {
+    Pnl {
+        req_id:     RequestId,
+        account:    AccountCode,
+        model_code: Option<String>,
+    },
+    CancelPnl {
+        req_id: RequestId,
+    },
+    PnlSingle {
+        req_id:     RequestId,
+        account:    AccountCode,
+        model_code: Option<String>,
+        con_id:     i32,
+    },
+    CancelPnlSingle {
+        req_id: RequestId,
+    },
+}
+
+impl RequestPnl {
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `RequestPnl` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
+        MinServerVersion::Pnl.check(server_version)?;
+        let msg = match self {
+            RequestPnl::Pnl {
+                req_id,
+                account,
+                model_code,
+            } => {
+                let mut msg = Outgoing::ReqPnl.encode();
+                msg.push_str(&req_id.encode());
+                msg.push_str(&account.encode());
+                msg.push_str(&model_code.encode());
+                msg
+            },
+            RequestPnl::CancelPnl { req_id } => {
+                let mut msg = Outgoing::CancelPnl.encode();
+                msg.push_str(&req_id.encode());
+                msg
+            },
+            RequestPnl::PnlSingle {
+                req_id,
+                account,
+                model_code,
+                con_id,
+            } => {
+                let mut msg = Outgoing::ReqPnlSingle.encode();
+                msg.push_str(&req_id.encode());
+                msg.push_str(&account.encode());
+                msg.push_str(&model_code.encode());
+                msg.push_str(&con_id.encode());
+                msg
+            },
+            RequestPnl::CancelPnlSingle { req_id } => {
+                let mut msg = Outgoing::CancelPnlSingle.encode();
+                msg.push_str(&req_id.encode());
+                msg
+            },
+        };
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+impl ParseIbkrCommand for RequestPnl {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        match msg_id {
+            Outgoing::ReqPnl => {
+                Ok(RequestPnl::Pnl {
+                    req_id:     decode_required(it)?,
+                    account:    decode(it)?.unwrap_or_default(),
+                    model_code: decode(it)?,
+                })
+            },
+            Outgoing::CancelPnl => {
+                Ok(RequestPnl::CancelPnl {
+                    req_id: decode_required(it)?,
+                })
+            },
+            Outgoing::ReqPnlSingle => {
+                Ok(RequestPnl::PnlSingle {
+                    req_id:     decode_required(it)?,
+                    account:    decode(it)?.unwrap_or_default(),
+                    model_code: decode(it)?,
+                    con_id:     decode_required(it)?,
+                })
+            },
+            Outgoing::CancelPnlSingle => {
+                Ok(RequestPnl::CancelPnlSingle {
+                    req_id: decode_required(it)?,
+                })
+            },
+            _ => Err(ParseError::UnexpectedMessage),
+        }
+    }
+}
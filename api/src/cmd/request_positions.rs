@@ -0,0 +1,104 @@
+use bytes::Bytes;
+
+use super::ParseIbkrCommand;
+use crate::{
+    enums::Outgoing,
+    frame::Frame,
+    ib_frame::{ParseError, ParseResult},
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    AccountCode, RequestId, Result, ServerVersion,
+};
+
+const VERSION: i32 = 1;
+
+/// Subscribes to (or cancels) position updates.
+///
+/// * `Positions` - Subscribes to position updates for all accessible accounts.
+///   All positions sent initially, and then only updates as positions change.
+/// * `PositionsMulti` - Requests position subscription for account and/or
+///   model. Initially all positions are returned and then updates are returned
+///   for any position changes in real time.
+#[derive(Debug, Clone)]
+pub enum RequestPositions {
+    Positions,
+    CancelPositions,
+    PositionsMulti {
+        req_id:     RequestId,
+        account:    AccountCode,
+        model_code: Option<String>,
+    },
+    CancelPositionsMulti {
+        req_id: RequestId,
+    },
+}
+
+impl RequestPositions {
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `RequestPositions` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
+        let msg = match self {
+            RequestPositions::Positions => {
+                let mut msg = Outgoing::ReqPositions.encode();
+                msg.push_str(&VERSION.encode());
+                msg
+            },
+            RequestPositions::CancelPositions => {
+                let mut msg = Outgoing::CancelPositions.encode();
+                msg.push_str(&VERSION.encode());
+                msg
+            },
+            RequestPositions::PositionsMulti {
+                req_id,
+                account,
+                model_code,
+            } => {
+                MinServerVersion::ModelsSupport.check(server_version)?;
+                let mut msg = Outgoing::ReqPositionsMulti.encode();
+                msg.push_str(&VERSION.encode());
+                msg.push_str(&req_id.encode());
+                msg.push_str(&account.encode());
+                msg.push_str(&model_code.encode());
+                msg
+            },
+            RequestPositions::CancelPositionsMulti { req_id } => {
+                MinServerVersion::ModelsSupport.check(server_version)?;
+                let mut msg = Outgoing::CancelPositionsMulti.encode();
+                msg.push_str(&VERSION.encode());
+                msg.push_str(&req_id.encode());
+                msg
+            },
+        };
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+impl ParseIbkrCommand for RequestPositions {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        it.next(); // skip version
+        match msg_id {
+            Outgoing::ReqPositions => Ok(RequestPositions::Positions),
+            Outgoing::CancelPositions => Ok(RequestPositions::CancelPositions),
+            Outgoing::ReqPositionsMulti => {
+                Ok(RequestPositions::PositionsMulti {
+                    req_id:     decode_required(it)?,
+                    account:    decode(it)?.unwrap_or_default(),
+                    model_code: decode(it)?,
+                })
+            },
+            Outgoing::CancelPositionsMulti => {
+                Ok(RequestPositions::CancelPositionsMulti {
+                    req_id: decode_required(it)?,
+                })
+            },
+            _ => Err(ParseError::UnexpectedMessage),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use bytes::Bytes;
+
+use super::{request_market_data::decode_tag_value_list, ParseIbkrCommand, TagValue};
+use crate::{
+    enums::Outgoing,
+    frame::Frame,
+    ib_frame::{ParseError, ParseResult},
+    scanner::ScannerSubscription,
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode_required, Encodable, Fields, IBMessage},
+    RequestId, Result, ServerVersion,
+};
+
+const VERSION: i32 = 1;
+const SUBSCRIPTION_VERSION: i32 = 4;
+
+/// Starts (or cancels) a market scanner subscription.
+///
+/// * `Subscription` - Starts a subscription to market scan results based on the
+///   provided parameters. The results are delivered periodically until the
+///   subscription is cancelled.
+/// * `Parameters` - Requests an XML string that describes all possible scanner
+///   queries.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum RequestScanner {
+    Subscription {
+        req_id:               RequestId,
+        subscription:         ScannerSubscription,
+        subscription_options: Vec<TagValue>,
+    },
+    CancelSubscription {
+        req_id: RequestId,
+    },
+    Parameters,
+}
+
+impl RequestScanner {
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `RequestScanner` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
+        let generic_opts = MinServerVersion::ScannerGenericOpts.is_supported(server_version);
+        let msg = match self {
+            RequestScanner::Subscription {
+                req_id,
+                subscription,
+                subscription_options,
+            } => {
+                if !subscription.filter_options.is_empty() {
+                    MinServerVersion::ScannerGenericOpts.check(server_version)?;
+                }
+                let mut msg = Outgoing::ReqScannerSubscription.encode();
+                if !generic_opts {
+                    msg.push_str(&SUBSCRIPTION_VERSION.encode());
+                }
+                msg.push_str(&req_id.encode());
+                msg.push_str(&subscription.encode());
+                if generic_opts {
+                    let filter_options_str = subscription
+                        .filter_options
+                        .iter()
+                        .map(|x| format!("{}={};", x.tag, x.value))
+                        .collect::<String>();
+                    msg.push_str(&filter_options_str.encode());
+                }
+                let options_str = subscription_options
+                    .iter()
+                    .map(|x| format!("{}={};", x.tag, x.value))
+                    .collect::<String>();
+                msg.push_str(&options_str.encode());
+                msg
+            },
+            RequestScanner::CancelSubscription { req_id } => {
+                let mut msg = Outgoing::CancelScannerSubscription.encode();
+                msg.push_str(&VERSION.encode());
+                msg.push_str(&req_id.encode());
+                msg
+            },
+            RequestScanner::Parameters => {
+                let mut msg = Outgoing::ReqScannerParameters.encode();
+                msg.push_str(&VERSION.encode());
+                msg
+            },
+        };
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+impl ParseIbkrCommand for RequestScanner {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        let generic_opts = MinServerVersion::ScannerGenericOpts.is_supported(server_version);
+        match msg_id {
+            Outgoing::ReqScannerSubscription => {
+                if !generic_opts {
+                    it.next(); // skip version
+                }
+                let req_id = decode_required(it)?;
+                let mut subscription = ScannerSubscription::decode(it)?;
+                if generic_opts {
+                    subscription.filter_options = decode_tag_value_list(it)?;
+                }
+                Ok(RequestScanner::Subscription {
+                    req_id,
+                    subscription,
+                    subscription_options: decode_tag_value_list(it)?,
+                })
+            },
+            Outgoing::CancelScannerSubscription => {
+                it.next(); // skip version
+                Ok(RequestScanner::CancelSubscription {
+                    req_id: decode_required(it)?,
+                })
+            },
+            Outgoing::ReqScannerParameters => Ok(RequestScanner::Parameters),
+            _ => Err(ParseError::UnexpectedMessage),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn encode_scanner_subscription() {
+        let subscription = ScannerSubscription::new("STK", "STK.US.MAJOR", "TOP_PERC_GAIN")
+            .number_of_rows(5)
+            .above_price(2.5)
+            .filter(TagValue::new("priceAbove".to_string(), "5".to_string()));
+        let frame = RequestScanner::Subscription {
+            req_id: 3,
+            subscription,
+            subscription_options: Vec::new(),
+        }
+        .into_frame(151)
+        .unwrap();
+        let expected = [
+            "22\0",                                  // msg id
+            "3\0",                                   // req id
+            "5\0STK\0STK.US.MAJOR\0TOP_PERC_GAIN\0", // rows, instrument, location, scan
+            "2.5\0\0\0\0\0\0\0\0\0\0\0\0\0",         // price .. coupon rate filters
+            "0\0\0\0\0",                             // exclude convertible .. stock type
+            "priceAbove=5;\0",                       // filter options
+            "\0",                                    // subscription options
+        ]
+        .concat();
+        let expected = expected.as_str().to_ib_message().unwrap();
+        match frame {
+            Frame::Bulk(bytes) => assert_eq!(expected.as_slice(), bytes.as_ref()),
+            _ => panic!("unexpected frame"),
+        }
+    }
+}
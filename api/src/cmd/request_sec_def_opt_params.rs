@@ -0,0 +1,84 @@
+use bytes::Bytes;
+
+use super::ParseIbkrCommand;
+use crate::{
+    contract::SecType,
+    enums::Outgoing,
+    frame::Frame,
+    ib_frame::{ParseError, ParseResult},
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    RequestId, Result, ServerVersion,
+};
+
+/// Requests security definition option parameters for viewing a contract's
+/// option chain.
+///
+/// # Arguments
+/// * req_id - the ID chosen for the request
+/// * underlying_symbol - underlying symbol of the option chain
+/// * fut_fop_exchange - The exchange on which the returned options are trading.
+///   Can be set to the empty string "" for all exchanges.
+/// * underlying_sec_type - The type of the underlying security, i.e. STK
+/// * underlying_con_id - the contract ID of the underlying security
+#[derive(Debug)]
+pub struct RequestSecDefOptParams {
+    pub req_id:              RequestId,
+    pub underlying_symbol:   String,
+    pub fut_fop_exchange:    String,
+    pub underlying_sec_type: SecType,
+    pub underlying_con_id:   i32,
+}
+
+impl RequestSecDefOptParams {
+    pub const fn new(
+        req_id: RequestId,
+        underlying_symbol: String,
+        fut_fop_exchange: String,
+        underlying_sec_type: SecType,
+        underlying_con_id: i32,
+    ) -> RequestSecDefOptParams {
+        RequestSecDefOptParams {
+            req_id,
+            underlying_symbol,
+            fut_fop_exchange,
+            underlying_sec_type,
+            underlying_con_id,
+        }
+    }
+
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `RequestSecDefOptParams`
+    /// command to send to the server.
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
+        MinServerVersion::SecDefOptParamsReq.check(server_version)?;
+        let mut msg = Outgoing::ReqSecDefOptParams.encode();
+        msg.push_str(&self.req_id.encode());
+        msg.push_str(&self.underlying_symbol.encode());
+        msg.push_str(&self.fut_fop_exchange.encode());
+        msg.push_str(&self.underlying_sec_type.encode());
+        msg.push_str(&self.underlying_con_id.encode());
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+impl ParseIbkrCommand for RequestSecDefOptParams {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        _server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        if !matches!(msg_id, Outgoing::ReqSecDefOptParams) {
+            return Err(ParseError::UnexpectedMessage);
+        }
+        Ok(RequestSecDefOptParams {
+            req_id:              decode_required(it)?,
+            underlying_symbol:   decode(it)?.unwrap_or_default(),
+            fut_fop_exchange:    decode(it)?.unwrap_or_default(),
+            underlying_sec_type: decode(it)?.unwrap_or_default(),
+            underlying_con_id:   decode_required(it)?,
+        })
+    }
+}
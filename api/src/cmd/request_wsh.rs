@@ -0,0 +1,128 @@
+use bytes::Bytes;
+
+use super::ParseIbkrCommand;
+use crate::{
+    enums::Outgoing,
+    frame::Frame,
+    ib_frame::{ParseError, ParseResult},
+    server_versions::MinServerVersion,
+    utils::ib_message::{decode, decode_required, Encodable, Fields, IBMessage},
+    wsh::WshEventDataFilter,
+    RequestId, Result, ServerVersion,
+};
+
+/// Requests (or cancels) Wall Street Horizon data.
+///
+/// * `MetaData` - Requests the available event types and filters.
+/// * `EventData` - Requests the events selected by the filter.
+#[derive(Debug, Clone)]
+pub enum RequestWsh {
+    MetaData {
+        req_id: RequestId,
+    },
+    CancelMetaData {
+        req_id: RequestId,
+    },
+    EventData {
+        req_id: RequestId,
+        filter: WshEventDataFilter,
+    },
+    CancelEventData {
+        req_id: RequestId,
+    },
+}
+
+impl RequestWsh {
+    /// Converts the command into an equivalent `Frame`.
+    ///
+    /// This is called by the client when encoding a `RequestWsh` command
+    /// to send to the server.
+    pub(crate) fn into_frame(self, server_version: ServerVersion) -> Result<Frame> {
+        MinServerVersion::WsheCalendar.check(server_version)?;
+        let msg = match self {
+            RequestWsh::MetaData { req_id } => {
+                let mut msg = Outgoing::ReqWshMetaData.encode();
+                msg.push_str(&req_id.encode());
+                msg
+            },
+            RequestWsh::CancelMetaData { req_id } => {
+                let mut msg = Outgoing::CancelWshMetaData.encode();
+                msg.push_str(&req_id.encode());
+                msg
+            },
+            RequestWsh::EventData { req_id, filter } => {
+                let filters = MinServerVersion::WshEventDataFilters;
+                let filters_date = MinServerVersion::WshEventDataFiltersDate;
+                if filter.filter.is_some()
+                    || filter.fill_watchlist
+                    || filter.fill_portfolio
+                    || filter.fill_competitors
+                {
+                    filters.check(server_version)?;
+                }
+                if filter.start_date.is_some()
+                    || filter.end_date.is_some()
+                    || filter.total_limit.is_some()
+                {
+                    filters_date.check(server_version)?;
+                }
+                let mut msg = Outgoing::ReqWshEventData.encode();
+                msg.push_str(&req_id.encode());
+                msg.push_str(&filter.con_id.encode());
+                if filters.is_supported(server_version) {
+                    msg.push_str(&filter.filter.encode());
+                    msg.push_str(&filter.fill_watchlist.encode());
+                    msg.push_str(&filter.fill_portfolio.encode());
+                    msg.push_str(&filter.fill_competitors.encode());
+                }
+                if filters_date.is_supported(server_version) {
+                    msg.push_str(&filter.start_date.encode());
+                    msg.push_str(&filter.end_date.encode());
+                    msg.push_str(&filter.total_limit.encode());
+                }
+                msg
+            },
+            RequestWsh::CancelEventData { req_id } => {
+                let mut msg = Outgoing::CancelWshEventData.encode();
+                msg.push_str(&req_id.encode());
+                msg
+            },
+        };
+        let msg = msg.as_str().to_ib_message().unwrap();
+        Ok(Frame::Bulk(Bytes::from(msg)))
+    }
+}
+
+impl ParseIbkrCommand for RequestWsh {
+    fn try_parse_command(
+        msg_id: Outgoing,
+        server_version: ServerVersion,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self> {
+        let req_id = decode_required(it)?;
+        match msg_id {
+            Outgoing::ReqWshMetaData => Ok(RequestWsh::MetaData { req_id }),
+            Outgoing::CancelWshMetaData => Ok(RequestWsh::CancelMetaData { req_id }),
+            Outgoing::ReqWshEventData => {
+                let mut filter = WshEventDataFilter {
+                    con_id: decode(it)?,
+                    ..Default::default()
+                };
+                if MinServerVersion::WshEventDataFilters.is_supported(server_version) {
+                    filter.filter = decode(it)?;
+                    filter.fill_watchlist = decode_required(it)?;
+                    filter.fill_portfolio = decode_required(it)?;
+                    filter.fill_competitors = decode_required(it)?;
+                }
+                if MinServerVersion::WshEventDataFiltersDate.is_supported(server_version) {
+                    filter.start_date = decode(it)?;
+                    filter.end_date = decode(it)?;
+                    filter.total_limit = decode(it)?;
+                }
+                Ok(RequestWsh::EventData { req_id, filter })
+            },
+            Outgoing::CancelWshEventData => Ok(RequestWsh::CancelEventData { req_id }),
+            _ => Err(ParseError::UnexpectedMessage),
+        }
+    }
+}
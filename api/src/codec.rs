@@ -0,0 +1,1926 @@
+//! Encoders and decoders of the TWS API protocol for use with
+//! `tokio_util::codec`.
+//!
+//! [`ClientCodec`] is the client side of a connection: it encodes
+//! [`Command`]s and decodes the [`IBFrame`]s sent by TWS. [`ServerCodec`] is
+//! the inverse, it decodes the commands of a client and encodes frames. Both
+//! are meant to build fake servers and proxies with the types of the crate:
+//!
+//! ```no_run
+//! use futures::{SinkExt, StreamExt};
+//! use ibkr_rust_api::{cmd::{Api, Command},
+//!                     codec::ClientCodec};
+//! use tokio::net::TcpStream;
+//! use tokio_util::codec::Framed;
+//!
+//! # async fn run() -> ibkr_rust_api::Result<()> {
+//! let socket = TcpStream::connect("127.0.0.1:4002").await?;
+//! let mut framed = Framed::new(socket, ClientCodec::default());
+//! framed
+//!     .send(Command::Api(Api::Init {
+//!         min_client_version: 100,
+//!         max_client_version: 163,
+//!         connection_options: String::new(),
+//!     }))
+//!     .await?;
+//! // the first frame is the server version
+//! let server_version = framed.next().await;
+//! # Ok(())
+//! # }
+//! ```
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{cmd::{Api, Command},
+            enums::Outgoing,
+            ib_frame::{IBFrame, ParseError, ParseResult},
+            utils::ib_message::{Fields, IBMessage},
+            Error,
+            ServerVersion};
+
+/// The prefix sent by clients before the supported versions.
+const API_PREFIX: &[u8] = b"API\0";
+
+/// The client side of a connection, see the [module documentation](self).
+///
+/// The server version is taken from the first frame received, commands other
+/// than `Api::Init` can only be encoded once it is known.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ClientCodec {
+    server_version: Option<ServerVersion>,
+}
+
+impl ClientCodec {
+    /// Creates a codec for a connection where the handshake already happened.
+    pub const fn new(server_version: ServerVersion) -> Self {
+        ClientCodec {
+            server_version: Some(server_version),
+        }
+    }
+
+    /// The version of the server, once it was received.
+    pub const fn server_version(&self) -> Option<ServerVersion> { self.server_version }
+}
+
+impl Decoder for ClientCodec {
+    type Error = Error;
+    type Item = IBFrame;
+
+    /// Decodes the next frame, messages which can not be decoded are logged
+    /// and skipped.
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<IBFrame>, Error> {
+        loop {
+            let msg = match split_message(src)? {
+                Some(msg) => msg,
+                None => return Ok(None),
+            };
+            match self.server_version {
+                None => {
+                    // the handshake can not continue without the server version
+                    let frame = IBFrame::parse_server_version_message(&msg[4..])?;
+                    if let IBFrame::ServerVersion { server_version, .. } = frame {
+                        self.server_version = Some(server_version);
+                    }
+                    return Ok(Some(frame));
+                },
+                Some(server_version) => {
+                    match IBFrame::parse_message(&msg[4..], Some(server_version)) {
+                        Ok(frame) => return Ok(Some(frame)),
+                        Err(err) => tracing::warn!(cause = %err, "skipping message"),
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl Encoder<Command> for ClientCodec {
+    type Error = Error;
+
+    fn encode(&mut self, cmd: Command, dst: &mut BytesMut) -> Result<(), Error> {
+        let frame = match cmd {
+            Command::Api(init @ Api::Init { .. }) => {
+                dst.extend_from_slice(API_PREFIX);
+                init.into_frame()
+            },
+            cmd => {
+                let server_version = self
+                    .server_version
+                    .ok_or(ParseError::MissingServerVersion)?;
+                cmd.into_frame(server_version)?
+            },
+        };
+        for part in frame.parts() {
+            dst.extend_from_slice(part);
+        }
+        Ok(())
+    }
+}
+
+/// The server side of a connection, see the [module documentation](self).
+///
+/// Frames are encoded as sent by a server with the version of the codec,
+/// encoding an `IBFrame::ServerVersion` changes it.
+#[derive(Debug, Clone, Copy)]
+pub struct ServerCodec {
+    server_version:     ServerVersion,
+    // `true` once the "API\0" prefix and the client versions were received
+    handshake_received: bool,
+}
+
+impl ServerCodec {
+    /// Creates a codec for a server with version `server_version`, expecting
+    /// the handshake of the client first.
+    pub const fn new(server_version: ServerVersion) -> Self {
+        ServerCodec {
+            server_version,
+            handshake_received: false,
+        }
+    }
+
+    /// Creates a codec for a connection where the handshake already happened.
+    pub const fn after_handshake(server_version: ServerVersion) -> Self {
+        ServerCodec {
+            server_version,
+            handshake_received: true,
+        }
+    }
+
+    /// The version the frames are encoded for.
+    pub const fn server_version(&self) -> ServerVersion { self.server_version }
+}
+
+impl Decoder for ServerCodec {
+    type Error = Error;
+    type Item = Command;
+
+    /// Decodes the next command, messages which can not be decoded are logged
+    /// and skipped.
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Command>, Error> {
+        if !self.handshake_received {
+            if src.len() < API_PREFIX.len() {
+                return Ok(None);
+            }
+            if !src.starts_with(API_PREFIX) {
+                return Err(Error::Handshake("expected the API prefix".to_string()));
+            }
+            if let Err(err) = IBFrame::message_len(&src[API_PREFIX.len()..]) {
+                return match err {
+                    ParseError::Incomplete => Ok(None),
+                    err => Err(err.into()),
+                };
+            }
+            src.advance(API_PREFIX.len());
+            let msg = match split_message(src)? {
+                Some(msg) => msg,
+                None => return Ok(None),
+            };
+            self.handshake_received = true;
+            let versions = String::from_utf8_lossy(&msg[4..]);
+            return Ok(Some(Command::Api(Api::parse_init(&versions)?)));
+        }
+        loop {
+            let msg = match split_message(src)? {
+                Some(msg) => msg,
+                None => return Ok(None),
+            };
+            match parse_command(&msg[4..], self.server_version) {
+                Ok(cmd) => return Ok(Some(cmd)),
+                Err(err) => tracing::warn!(cause = %err, "skipping message"),
+            }
+        }
+    }
+}
+
+impl Encoder<IBFrame> for ServerCodec {
+    type Error = Error;
+
+    fn encode(&mut self, frame: IBFrame, dst: &mut BytesMut) -> Result<(), Error> {
+        if let IBFrame::ServerVersion { server_version, .. } = frame {
+            self.server_version = server_version;
+        }
+        let msg = frame.encode(self.server_version)?;
+        let msg = msg
+            .as_str()
+            .to_ib_message()
+            .map_err(|_| ParseError::InvalidValue(format!("message length {}", msg.len())))?;
+        dst.extend_from_slice(&msg);
+        Ok(())
+    }
+}
+
+/// Removes the next length prefixed message from `src`, `None` if it was not
+/// received completely yet.
+fn split_message(src: &mut BytesMut) -> Result<Option<BytesMut>, Error> {
+    match IBFrame::message_len(src) {
+        Ok(len) => Ok(Some(src.split_to(len))),
+        Err(ParseError::Incomplete) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Decodes the message `msg` (without the length prefix) sent by a client.
+fn parse_command(msg: &[u8], server_version: ServerVersion) -> ParseResult<Command> {
+    let msg = String::from_utf8_lossy(msg);
+    // every field is terminated by '\0'
+    let msg = msg.strip_suffix('\0').unwrap_or(&msg);
+    let mut it = Fields::new(msg);
+    let msg_id = it.next().unwrap_or_default();
+    let msg_id: Outgoing = msg_id
+        .parse()
+        .map_err(|_| ParseError::UnknownMessageId(msg_id.to_string()))?;
+    Command::parse(msg_id, server_version, &mut it)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use chrono_tz::Tz;
+    use proptest::{collection::vec, option, prelude::*, sample::select, strategy::LazyJust};
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::{account::{AccountData, PnL, PnLSingle, Position},
+                account_summary_tags::AccountValueKey,
+                api_message::TwsMessage,
+                bars::{Bar,
+                       BarSeries,
+                       BarSize,
+                       Duration,
+                       HistoricalBars,
+                       HistoricalSchedule,
+                       HistoricalSession,
+                       RealtimeBar},
+                cmd::*,
+                contract::{Contract,
+                           ContractDescription,
+                           ContractDetails,
+                           OptionChain,
+                           OptionRight,
+                           SecIdType,
+                           SecType},
+                enums::{FundamentalDataType, MarketDataType, ServerLogLevel},
+                news::{HistoricalNews,
+                       NewsArticle,
+                       NewsArticleType,
+                       NewsBulletin,
+                       NewsBulletinType,
+                       NewsProvider,
+                       TickNews},
+                order::{Action,
+                        CommissionReport,
+                        Execution,
+                        ExecutionFilter,
+                        Order,
+                        OrderInformation,
+                        OrderState,
+                        OrderStatus,
+                        OrderStatusUpdate,
+                        OrderType,
+                        Side,
+                        TimeInForce},
+                scanner::{ScannerData, ScannerSubscription},
+                ticker::{GenericTickType,
+                         HeadTimestamp,
+                         HistoricalBidAsk,
+                         HistoricalLast,
+                         HistoricalTick,
+                         HistoricalTicks,
+                         HistoricalTimeAndSales,
+                         MarketDepthUpdate,
+                         Tick,
+                         TickAttribBidAsk,
+                         TickAttribLast,
+                         TickAttribute,
+                         TickByTickAllLast,
+                         TickByTickBidAsk,
+                         TickByTickMidPoint,
+                         TickByTickType,
+                         TickGeneric,
+                         TickOptionComputation,
+                         TickPrice,
+                         TickSize,
+                         TickString,
+                         TickType},
+                wsh::{WshEventData, WshEventDataFilter},
+                RequestId,
+                TimeStamp};
+
+    fn server_version() -> impl Strategy<Value = ServerVersion> { select(vec![151, 163, 176]) }
+
+    fn text() -> impl Strategy<Value = String> { "[A-Za-z0-9]{1,8}" }
+
+    fn opt_text() -> impl Strategy<Value = Option<String>> { option::of(text()) }
+
+    fn req_id() -> impl Strategy<Value = RequestId> { 0..100_000usize }
+
+    fn id() -> impl Strategy<Value = i32> { 0..1_000_000i32 }
+
+    fn price() -> impl Strategy<Value = f64> { (0..10_000_000i64).prop_map(|v| v as f64 / 100.0) }
+
+    fn decimal() -> impl Strategy<Value = Decimal> {
+        (0..10_000_000i64).prop_map(|v| Decimal::new(v, 2))
+    }
+
+    fn quantity() -> impl Strategy<Value = Decimal> { (1..10_000i64).prop_map(Decimal::from) }
+
+    fn timestamp() -> impl Strategy<Value = TimeStamp> {
+        (1_500_000_000i64..1_900_000_000).prop_map(|secs| Utc.timestamp_opt(secs, 0).unwrap())
+    }
+
+    fn date() -> impl Strategy<Value = NaiveDate> {
+        (2000..2040i32, 1..=12u32, 1..=28u32)
+            .prop_map(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d).unwrap())
+    }
+
+    fn tag_values() -> impl Strategy<Value = Vec<TagValue>> {
+        vec(
+            (text(), text()).prop_map(|(tag, value)| TagValue::new(tag, value)),
+            0..3,
+        )
+    }
+
+    fn contract() -> BoxedStrategy<Contract> {
+        let sec_types = vec![
+            SecType::Stock,
+            SecType::Option,
+            SecType::Future,
+            SecType::Index,
+            SecType::Forex,
+            SecType::Bond,
+            SecType::Cfd,
+            SecType::Crypto,
+        ];
+        (
+            (
+                option::of(1..1_000_000i32),
+                text(),
+                select(sec_types),
+                text(),
+            ),
+            (
+                option::of("20[23][0-9]{3}"),
+                option::of(decimal()),
+                option::of(select(vec![OptionRight::Put, OptionRight::Call])),
+                option::of(quantity()),
+            ),
+            (opt_text(), opt_text(), opt_text(), opt_text()),
+            (
+                option::of(select(vec![SecIdType::Isin, SecIdType::Cusip])),
+                opt_text(),
+            ),
+        )
+            .prop_map(
+                |(
+                    (con_id, symbol, sec_type, currency),
+                    (last_trade_date_or_contract_month, strike, right, multiplier),
+                    (exchange, primary_exchange, local_symbol, trading_class),
+                    (sec_id_type, sec_id),
+                )| {
+                    Contract {
+                        con_id,
+                        symbol,
+                        sec_type,
+                        last_trade_date_or_contract_month,
+                        strike,
+                        right,
+                        multiplier,
+                        exchange,
+                        currency,
+                        local_symbol,
+                        primary_exchange,
+                        trading_class,
+                        sec_id_type,
+                        sec_id,
+                        ..Default::default()
+                    }
+                },
+            )
+            .boxed()
+    }
+
+    fn contract_details() -> BoxedStrategy<ContractDetails> {
+        (
+            contract(),
+            (opt_text(), option::of(price()), opt_text(), opt_text()),
+            (option::of(id()), opt_text(), opt_text(), opt_text()),
+            option::of(vec((text(), text()), 1..3)),
+        )
+            .prop_map(
+                |(
+                    contract,
+                    (market_name, min_tick, order_types, valid_exchanges),
+                    (under_con_id, long_name, industry, timezone_id),
+                    sec_id_list,
+                )| {
+                    ContractDetails {
+                        contract,
+                        market_name,
+                        min_tick,
+                        order_types,
+                        valid_exchanges,
+                        under_con_id,
+                        long_name,
+                        industry,
+                        timezone_id,
+                        sec_id_list,
+                        ..Default::default()
+                    }
+                },
+            )
+            .boxed()
+    }
+
+    fn position() -> BoxedStrategy<Position> {
+        (
+            contract(),
+            (
+                option::of(decimal()),
+                option::of(decimal()),
+                option::of(decimal()),
+            ),
+            (
+                option::of(decimal()),
+                option::of(decimal()),
+                option::of(decimal()),
+            ),
+            (opt_text(), opt_text()),
+        )
+            .prop_map(
+                |(
+                    contract,
+                    (position, market_price, market_value),
+                    (average_cost, unrealized_pnl, realized_pnl),
+                    (account, model_code),
+                )| {
+                    Position {
+                        contract,
+                        position,
+                        market_price,
+                        market_value,
+                        average_cost,
+                        unrealized_pnl,
+                        realized_pnl,
+                        account,
+                        model_code,
+                    }
+                },
+            )
+            .boxed()
+    }
+
+    fn account_data(req_id: Option<RequestId>) -> impl Strategy<Value = AccountData> {
+        (text(), text(), text(), text()).prop_map(move |(account, key, value, currency)| {
+            AccountData {
+                req_id,
+                account,
+                key: key.parse().unwrap_or(AccountValueKey::Unknown(key)),
+                value,
+                currency,
+            }
+        })
+    }
+
+    fn order() -> BoxedStrategy<Order> {
+        let order_types = vec![OrderType::Limit, OrderType::Market, OrderType::Stop];
+        let tifs = vec![
+            TimeInForce::Day,
+            TimeInForce::GoodTillCancel,
+            TimeInForce::ImmediateOrCancel,
+        ];
+        (
+            contract(),
+            (
+                select(vec![Action::Buy, Action::Sell]),
+                quantity(),
+                select(order_types),
+            ),
+            (
+                option::of(decimal()),
+                option::of(decimal()),
+                option::of(select(tifs)),
+            ),
+            (opt_text(), opt_text(), any::<bool>(), any::<bool>()),
+        )
+            .prop_map(
+                |(
+                    contract,
+                    (action, total_qty, order_type),
+                    (lmt_price, aux_price, tif),
+                    (account, order_ref, transmit, outside_rth),
+                )| {
+                    Order {
+                        contract,
+                        action,
+                        total_qty,
+                        order_type,
+                        lmt_price,
+                        aux_price,
+                        tif,
+                        account,
+                        order_ref,
+                        transmit,
+                        outside_rth,
+                        ..Default::default()
+                    }
+                },
+            )
+            .boxed()
+    }
+
+    fn order_information() -> BoxedStrategy<OrderInformation> {
+        let statuses = vec![
+            OrderStatus::PreSubmitted,
+            OrderStatus::Submitted,
+            OrderStatus::Cancelled,
+            OrderStatus::Filled,
+        ];
+        (
+            order(),
+            id(),
+            select(statuses),
+            (option::of(decimal()), opt_text(), opt_text()),
+        )
+            .prop_map(
+                |(order, perm_id, status, (commission, commission_currency, warning_text))| {
+                    OrderInformation {
+                        order,
+                        order_state: OrderState {
+                            perm_id,
+                            status,
+                            commission,
+                            commission_currency,
+                            warning_text,
+                            ..Default::default()
+                        },
+                    }
+                },
+            )
+            .boxed()
+    }
+
+    fn tick_attribute() -> impl Strategy<Value = TickAttribute> {
+        (any::<bool>(), any::<bool>(), any::<bool>())
+            .prop_map(|(auto, past, pre)| TickAttribute::new(auto, past, pre))
+    }
+
+    fn bar() -> impl Strategy<Value = Bar> {
+        (
+            timestamp(),
+            (price(), price(), price(), price()),
+            (
+                price(),
+                (0..1_000_000u32).prop_map(f64::from),
+                0..10_000isize,
+            ),
+        )
+            .prop_map(
+                |(t_stamp, (open, high, low, close), (wap, volume, count))| {
+                    Bar {
+                        t_stamp,
+                        open,
+                        high,
+                        low,
+                        close,
+                        wap,
+                        volume,
+                        count,
+                    }
+                },
+            )
+    }
+
+    fn tick() -> BoxedStrategy<Tick> {
+        let price_kinds = vec![TickType::Bid, TickType::Ask, TickType::Last, TickType::High];
+        let size_kinds = vec![TickType::BidSize, TickType::AskSize, TickType::Volume];
+        let option_kinds = vec![TickType::BidOptionComputation, TickType::ModelOption];
+        let greek = || option::of((-100..100i32).prop_map(|v| f64::from(v) / 100.0));
+        prop_oneof![
+            (
+                req_id(),
+                select(price_kinds),
+                price(),
+                option::of(price()),
+                tick_attribute()
+            )
+                .prop_map(|(id, kind, price, size, attributes)| {
+                    Tick::Price(TickPrice {
+                        id,
+                        kind,
+                        price,
+                        size,
+                        attributes,
+                    })
+                }),
+            (req_id(), select(size_kinds), price())
+                .prop_map(|(id, kind, size)| Tick::Size(TickSize { id, kind, size })),
+            (req_id(), opt_text()).prop_map(|(id, val)| {
+                Tick::String(TickString {
+                    id,
+                    kind: TickType::LastExch,
+                    val,
+                })
+            }),
+            (req_id(), price()).prop_map(|(id, val)| {
+                Tick::Generic(TickGeneric {
+                    id,
+                    kind: TickType::Halted,
+                    val,
+                })
+            }),
+            (
+                req_id(),
+                select(option_kinds),
+                option::of(0..2i32),
+                (
+                    option::of(price()),
+                    greek(),
+                    option::of(price()),
+                    option::of(price())
+                ),
+                (greek(), greek(), greek(), option::of(price())),
+            )
+                .prop_map(
+                    |(
+                        id,
+                        kind,
+                        tick_attrib,
+                        (implied_vol, delta, opt_price, pv_dividend),
+                        (gamma, vega, theta, und_price),
+                    )| {
+                        Tick::TickOptionComputation(TickOptionComputation {
+                            id,
+                            kind,
+                            tick_attrib,
+                            implied_vol,
+                            delta,
+                            opt_price,
+                            pv_dividend,
+                            gamma,
+                            vega,
+                            theta,
+                            und_price,
+                        })
+                    },
+                ),
+            (
+                req_id(),
+                select(vec![TickByTickType::Last, TickByTickType::AllLast]),
+                timestamp(),
+                (price(), price(), any::<bool>(), any::<bool>()),
+                (text(), text()),
+            )
+                .prop_map(
+                    |(
+                        id,
+                        tick_type,
+                        time,
+                        (price, size, past_limit, unreported),
+                        (exchange, special_conditions),
+                    )| {
+                        Tick::TickByTickAllLast(TickByTickAllLast {
+                            id,
+                            tick_type,
+                            time,
+                            price,
+                            size,
+                            tick_attrib_last: TickAttribLast {
+                                past_limit,
+                                unreported,
+                            },
+                            exchange,
+                            special_conditions,
+                        })
+                    },
+                ),
+            (
+                req_id(),
+                timestamp(),
+                (price(), price(), price(), price()),
+                (any::<bool>(), any::<bool>()),
+            )
+                .prop_map(
+                    |(
+                        id,
+                        time,
+                        (bid_price, ask_price, bid_size, ask_size),
+                        (bid_past_low, ask_past_high),
+                    )| {
+                        Tick::TickByTickBidAsk(TickByTickBidAsk {
+                            id,
+                            time,
+                            bid_price,
+                            ask_price,
+                            bid_size,
+                            ask_size,
+                            tick_attrib_bid_ask: TickAttribBidAsk {
+                                bid_past_low,
+                                ask_past_high,
+                            },
+                        })
+                    },
+                ),
+            (req_id(), timestamp(), price()).prop_map(|(id, time, mid_point)| {
+                Tick::TickByTickMidPoint(TickByTickMidPoint {
+                    id,
+                    time,
+                    mid_point,
+                })
+            }),
+        ]
+        .boxed()
+    }
+
+    fn historical_ticks() -> BoxedStrategy<HistoricalTicks> {
+        let ticks = prop_oneof![
+            vec(
+                (timestamp(), price(), price()).prop_map(|(time, price, size)| {
+                    HistoricalTimeAndSales::Tick(HistoricalTick { time, price, size })
+                }),
+                0..3
+            ),
+            vec(
+                (
+                    timestamp(),
+                    tick_attribute(),
+                    (price(), price(), price(), price())
+                )
+                    .prop_map(
+                        |(time, attributes, (price_bid, price_ask, size_bid, size_ask))| {
+                            HistoricalTimeAndSales::BidAsk(HistoricalBidAsk {
+                                time,
+                                attributes,
+                                price_bid,
+                                price_ask,
+                                size_bid,
+                                size_ask,
+                            })
+                        }
+                    ),
+                0..3
+            ),
+            vec(
+                (
+                    timestamp(),
+                    tick_attribute(),
+                    price(),
+                    price(),
+                    text(),
+                    opt_text()
+                )
+                    .prop_map(
+                        |(time, attributes, price, size, exchange, special_conditions)| {
+                            HistoricalTimeAndSales::Last(HistoricalLast {
+                                time,
+                                attributes,
+                                price,
+                                size,
+                                exchange,
+                                special_conditions,
+                            })
+                        }
+                    ),
+                0..3
+            ),
+        ];
+        (req_id(), ticks, any::<bool>())
+            .prop_map(|(id, ticks, done)| HistoricalTicks { id, ticks, done })
+            .boxed()
+    }
+
+    fn market_depth() -> BoxedStrategy<MarketDepthUpdate> {
+        (
+            (req_id(), 0..20usize, opt_text()),
+            select(vec![
+                MarketDepthOperation::Insert,
+                MarketDepthOperation::Update,
+                MarketDepthOperation::Remove,
+            ]),
+            select(vec![DepthSide::Bid, DepthSide::Ask]),
+            (price(), price(), any::<bool>()),
+        )
+            .prop_map(
+                |((id, position, market_maker), operation, side, (price, size, smart))| {
+                    MarketDepthUpdate {
+                        id,
+                        position,
+                        // smart depth is only sent with the level 2 updates
+                        is_smart_depth: smart && market_maker.is_some(),
+                        market_maker,
+                        operation,
+                        side,
+                        price,
+                        size,
+                    }
+                },
+            )
+            .boxed()
+    }
+
+    fn account_frame() -> BoxedStrategy<IBFrame> {
+        prop_oneof![
+            text().prop_map(IBFrame::AccountCode),
+            account_data(None).prop_map(IBFrame::AccountValue),
+            req_id()
+                .prop_flat_map(|req_id| account_data(Some(req_id)))
+                .prop_map(IBFrame::AccountSummary),
+            text().prop_map(IBFrame::AccountUpdateEnd),
+            timestamp().prop_map(IBFrame::AccountUpdateTime),
+            (
+                req_id(),
+                option::of(decimal()),
+                option::of(decimal()),
+                option::of(decimal())
+            )
+                .prop_map(|(req_id, daily, unrealized, realized)| {
+                    IBFrame::PnL(PnL {
+                        req_id,
+                        daily,
+                        unrealized,
+                        realized,
+                    })
+                }),
+            (
+                req_id(),
+                option::of(decimal()),
+                option::of(decimal()),
+                option::of(decimal()),
+                option::of(decimal()),
+                option::of(decimal())
+            )
+                .prop_map(|(req_id, position, daily, unrealized, realized, value)| {
+                    IBFrame::PnLSingle(PnLSingle {
+                        req_id,
+                        position,
+                        daily,
+                        unrealized,
+                        realized,
+                        value,
+                    })
+                }),
+            position().prop_map(IBFrame::PortfolioValue),
+            position().prop_map(IBFrame::Position),
+            LazyJust::new(|| IBFrame::PositionEnd),
+            (req_id(), position())
+                .prop_map(|(req_id, position)| IBFrame::PositionMulti { req_id, position }),
+            req_id().prop_map(IBFrame::PositionMultiEnd),
+        ]
+        .boxed()
+    }
+
+    fn contract_frame() -> BoxedStrategy<IBFrame> {
+        let option_chain = (
+            text(),
+            id(),
+            text(),
+            option::of(quantity()),
+            vec(date(), 0..3),
+            vec(quantity(), 0..3),
+        )
+            .prop_map(
+                |(exchange, underlying_con_id, trading_class, multiplier, expirations, strikes)| {
+                    let mut chain = OptionChain {
+                        exchange,
+                        underlying_con_id,
+                        trading_class,
+                        multiplier,
+                        expirations,
+                        strikes,
+                    };
+                    chain.expirations.sort();
+                    chain.expirations.dedup();
+                    chain.strikes.sort();
+                    chain.strikes.dedup();
+                    chain
+                },
+            );
+        let description = (contract(), option::of(vec("[A-Z]{3}", 0..3))).prop_map(
+            |(contract, derivative_sec_types_list)| {
+                ContractDescription {
+                    contract,
+                    derivative_sec_types_list,
+                }
+            },
+        );
+        prop_oneof![
+            (req_id(), contract_details()).prop_map(|(req_id, contract_details)| {
+                IBFrame::ContractDetails {
+                    req_id,
+                    contract_details,
+                }
+            }),
+            req_id().prop_map(IBFrame::ContractDetailsEnd),
+            (req_id(), text()).prop_map(|(req_id, data)| IBFrame::FundamentalData { req_id, data }),
+            (req_id(), option_chain)
+                .prop_map(|(req_id, chain)| IBFrame::SecDefOptParams { req_id, chain }),
+            req_id().prop_map(IBFrame::SecDefOptParamsEnd),
+            req_id().prop_map(IBFrame::TickSnapshotEnd),
+            (req_id(), vec(description, 0..3)).prop_map(|(req_id, descriptions)| {
+                IBFrame::SymbolSamples {
+                    req_id,
+                    descriptions,
+                }
+            }),
+            (
+                req_id(),
+                vec(
+                    (
+                        0..50i32,
+                        contract_details(),
+                        opt_text(),
+                        opt_text(),
+                        opt_text()
+                    ),
+                    0..3
+                )
+            )
+                .prop_map(|(req_id, rows)| {
+                    IBFrame::ScannerData {
+                        req_id,
+                        rows: rows
+                            .into_iter()
+                            .map(
+                                |(rank, contract_details, distance, benchmark, projection)| {
+                                    ScannerData {
+                                        rank,
+                                        contract_details,
+                                        distance,
+                                        benchmark,
+                                        projection,
+                                    }
+                                },
+                            )
+                            .collect(),
+                    }
+                }),
+            text().prop_map(IBFrame::ScannerParameters),
+        ]
+        .boxed()
+    }
+
+    fn order_frame() -> BoxedStrategy<IBFrame> {
+        let statuses = vec![
+            OrderStatus::PendingSubmit,
+            OrderStatus::Submitted,
+            OrderStatus::Cancelled,
+            OrderStatus::Filled,
+        ];
+        let execution = (
+            (text(), timestamp(), text(), opt_text()),
+            (
+                select(vec![Side::Buy, Side::Sell]),
+                decimal(),
+                decimal(),
+                id(),
+            ),
+            (0..100i32, id(), contract(), 0..2i32),
+            (decimal(), decimal(), opt_text(), opt_text()),
+            (option::of(decimal()), opt_text(), option::of(0..4i32)),
+        )
+            .prop_map(
+                |(
+                    (exec_id, time, acct_number, exchange),
+                    (side, shares, price, perm_id),
+                    (client_id, order_id, contract, liquidation),
+                    (cum_qty, avg_price, order_ref, ev_rule),
+                    (ev_multiplier, model_code, last_liquidity),
+                )| {
+                    Execution {
+                        exec_id,
+                        time,
+                        acct_number,
+                        exchange,
+                        side,
+                        shares,
+                        price,
+                        perm_id,
+                        client_id,
+                        order_id,
+                        contract,
+                        liquidation,
+                        cum_qty,
+                        avg_price,
+                        order_ref,
+                        ev_rule,
+                        ev_multiplier,
+                        model_code,
+                        last_liquidity,
+                    }
+                },
+            );
+        prop_oneof![
+            order_information().prop_map(IBFrame::OpenOrder),
+            order_information().prop_map(IBFrame::CompletedOrder),
+            LazyJust::new(|| IBFrame::OpenOrderEnd),
+            id().prop_map(IBFrame::OrderId),
+            (
+                (id(), select(statuses), decimal(), decimal()),
+                (decimal(), id(), id(), decimal()),
+                (0..100usize, opt_text())
+            )
+                .prop_map(
+                    |(
+                        (order_id, status, filled, remaining),
+                        (avg_fill_price, perm_id, parent_id, last_fill_price),
+                        (client_id, why_held),
+                    )| {
+                        IBFrame::OrderStatus(OrderStatusUpdate {
+                            order_id,
+                            status,
+                            filled,
+                            remaining,
+                            avg_fill_price,
+                            perm_id,
+                            parent_id,
+                            last_fill_price,
+                            client_id,
+                            why_held,
+                        })
+                    }
+                ),
+            execution.prop_map(IBFrame::Execution),
+            (
+                text(),
+                decimal(),
+                text(),
+                option::of(decimal()),
+                option::of(decimal()),
+                option::of(20_000_101..20_401_231i32)
+            )
+                .prop_map(
+                    |(exec_id, commission, currency, realized_pnl, yield_amount, date)| {
+                        IBFrame::CommissionReport(CommissionReport {
+                            exec_id,
+                            commission,
+                            currency,
+                            realized_pnl,
+                            yield_amount,
+                            yield_redemption_date: date,
+                        })
+                    }
+                ),
+        ]
+        .boxed()
+    }
+
+    fn market_data_frame() -> BoxedStrategy<IBFrame> {
+        let schedule = (
+            req_id(),
+            timestamp(),
+            timestamp(),
+            select(vec![Tz::America__New_York, Tz::Europe__Berlin, Tz::UTC]),
+            vec((timestamp(), timestamp(), date()), 0..3),
+        )
+            .prop_map(
+                |(id, start_date_time, end_date_time, time_zone, sessions)| {
+                    HistoricalSchedule {
+                        id,
+                        start_date_time,
+                        end_date_time,
+                        time_zone,
+                        sessions: sessions
+                            .into_iter()
+                            .map(|(start_date_time, end_date_time, ref_date)| {
+                                HistoricalSession {
+                                    start_date_time,
+                                    end_date_time,
+                                    ref_date,
+                                }
+                            })
+                            .collect(),
+                    }
+                },
+            );
+        prop_oneof![
+            tick().prop_map(IBFrame::Tick),
+            historical_ticks().prop_map(IBFrame::HistoricalTicks),
+            market_depth().prop_map(IBFrame::MarketDepth),
+            (req_id(), timestamp()).prop_map(|(id, timestamp)| {
+                IBFrame::HeadTimestamp(HeadTimestamp { id, timestamp })
+            }),
+            (req_id(), timestamp(), timestamp(), vec(bar(), 0..3)).prop_map(
+                |(id, start_dt, end_dt, bars)| {
+                    IBFrame::HistoricalBars(HistoricalBars {
+                        id,
+                        data: BarSeries {
+                            start_dt,
+                            end_dt,
+                            n_bars: bars.len(),
+                            bars,
+                        },
+                    })
+                }
+            ),
+            (req_id(), bar()).prop_map(|(id, bar)| {
+                IBFrame::HistoricalBarUpdate(HistoricalBars {
+                    id,
+                    data: BarSeries {
+                        start_dt: bar.t_stamp,
+                        end_dt:   bar.t_stamp,
+                        n_bars:   1,
+                        bars:     vec![bar],
+                    },
+                })
+            }),
+            schedule.prop_map(IBFrame::HistoricalSchedule),
+            (req_id(), bar()).prop_map(|(id, data)| IBFrame::RealtimeBar(RealtimeBar { id, data })),
+        ]
+        .boxed()
+    }
+
+    fn misc_frame() -> BoxedStrategy<IBFrame> {
+        prop_oneof![
+            timestamp().prop_map(IBFrame::CurrentTime),
+            (
+                option::of(req_id()),
+                100..3000i32,
+                opt_text(),
+                opt_text(),
+                option::of(timestamp())
+            )
+                .prop_map(|(req_id, code, message, json, error_time)| {
+                    IBFrame::Error(TwsMessage {
+                        req_id,
+                        code: code.into(),
+                        message,
+                        advanced_order_reject_json: json,
+                        error_time,
+                    })
+                }),
+            (
+                req_id(),
+                select(vec![NewsArticleType::Text, NewsArticleType::Binary]),
+                text()
+            )
+                .prop_map(|(req_id, article_type, article_text)| {
+                    IBFrame::NewsArticle(NewsArticle {
+                        req_id,
+                        article_type,
+                        article_text,
+                    })
+                }),
+            (
+                id(),
+                select(vec![
+                    NewsBulletinType::Regular,
+                    NewsBulletinType::ExchangeUnavailable,
+                    NewsBulletinType::ExchangeAvailable,
+                ]),
+                text(),
+                text()
+            )
+                .prop_map(|(msg_id, msg_type, message, origin_exchange)| {
+                    IBFrame::NewsBulletin(NewsBulletin {
+                        msg_id,
+                        msg_type,
+                        message,
+                        origin_exchange,
+                    })
+                }),
+            vec(
+                (text(), text()).prop_map(|(code, name)| NewsProvider { code, name }),
+                0..3
+            )
+            .prop_map(IBFrame::NewsProviders),
+            (req_id(), timestamp(), text(), text(), text()).prop_map(
+                |(req_id, time, provider_code, article_id, headline)| {
+                    IBFrame::HistoricalNews(HistoricalNews {
+                        req_id,
+                        time,
+                        provider_code,
+                        article_id,
+                        headline,
+                    })
+                }
+            ),
+            (req_id(), any::<bool>())
+                .prop_map(|(req_id, has_more)| IBFrame::HistoricalNewsEnd { req_id, has_more }),
+            (req_id(), timestamp(), text(), text(), text(), opt_text()).prop_map(
+                |(req_id, time, provider_code, article_id, headline, extra_data)| {
+                    IBFrame::TickNews(TickNews {
+                        req_id,
+                        time,
+                        provider_code,
+                        article_id,
+                        headline,
+                        extra_data,
+                    })
+                }
+            ),
+            (req_id(), text())
+                .prop_map(|(req_id, data_json)| IBFrame::WshMetaData { req_id, data_json }),
+            (req_id(), text()).prop_map(|(req_id, data_json)| {
+                IBFrame::WshEventData(WshEventData { req_id, data_json })
+            }),
+        ]
+        .boxed()
+    }
+
+    fn frame() -> BoxedStrategy<IBFrame> {
+        prop_oneof![
+            account_frame(),
+            contract_frame(),
+            order_frame(),
+            market_data_frame(),
+            misc_frame(),
+        ]
+        .boxed()
+    }
+
+    fn api_command() -> BoxedStrategy<Command> {
+        let log_levels = vec![
+            ServerLogLevel::System,
+            ServerLogLevel::Error,
+            ServerLogLevel::Warning,
+            ServerLogLevel::Information,
+            ServerLogLevel::Detail,
+        ];
+        prop_oneof![
+            (0..100i32, opt_text()).prop_map(|(client_id, optional_capabilities)| {
+                Api::Start {
+                    client_id,
+                    optional_capabilities,
+                }
+            }),
+            select(log_levels).prop_map(|log_level| Api::SetServerLoglevel { log_level }),
+            LazyJust::new(|| Api::RequestCurrentTime),
+        ]
+        .prop_map(Command::Api)
+        .boxed()
+    }
+
+    fn account_command() -> BoxedStrategy<Command> {
+        let filter = (
+            option::of(0..100i32),
+            (text(), text(), text()),
+            (
+                text(),
+                text(),
+                option::of(select(vec![Side::Buy, Side::Sell])),
+            ),
+        )
+            .prop_map(
+                |(client_id, (account_code, time, symbol), (sec_type, exchange, side))| {
+                    ExecutionFilter {
+                        client_id,
+                        account_code,
+                        time,
+                        symbol,
+                        sec_type,
+                        exchange,
+                        side,
+                    }
+                },
+            );
+        prop_oneof![
+            (req_id(), text(), vec(text(), 0..3)).prop_map(|(req_id, group, tags)| {
+                Command::RequestAccountSummary(RequestAccountSummary::new(req_id, group, tags))
+            }),
+            (any::<bool>(), text()).prop_map(|(subscribe, account)| {
+                Command::RequestAccountUpdates(RequestAccountUpdates::new(subscribe, account))
+            }),
+            (req_id(), option::of(filter)).prop_map(|(req_id, filter)| {
+                Command::RequestExecutions(RequestExecutions::new(req_id, filter))
+            }),
+            prop_oneof![
+                LazyJust::new(|| RequestPositions::Positions),
+                LazyJust::new(|| RequestPositions::CancelPositions),
+                (req_id(), text(), opt_text()).prop_map(|(req_id, account, model_code)| {
+                    RequestPositions::PositionsMulti {
+                        req_id,
+                        account,
+                        model_code,
+                    }
+                }),
+                req_id().prop_map(|req_id| RequestPositions::CancelPositionsMulti { req_id }),
+            ]
+            .prop_map(Command::RequestPositions),
+            prop_oneof![
+                (req_id(), text(), opt_text()).prop_map(|(req_id, account, model_code)| {
+                    RequestPnl::Pnl {
+                        req_id,
+                        account,
+                        model_code,
+                    }
+                }),
+                req_id().prop_map(|req_id| RequestPnl::CancelPnl { req_id }),
+                (req_id(), text(), opt_text(), id()).prop_map(
+                    |(req_id, account, model_code, con_id)| {
+                        RequestPnl::PnlSingle {
+                            req_id,
+                            account,
+                            model_code,
+                            con_id,
+                        }
+                    }
+                ),
+                req_id().prop_map(|req_id| RequestPnl::CancelPnlSingle { req_id }),
+            ]
+            .prop_map(Command::RequestPnl),
+        ]
+        .boxed()
+    }
+
+    fn order_command() -> BoxedStrategy<Command> {
+        prop_oneof![
+            (
+                id(),
+                option::of("20[23][0-9]{5} [0-9]{2}:[0-9]{2}:[0-9]{2}")
+            )
+                .prop_map(|(order_id, time)| {
+                    Command::CancelOrder(CancelOrder::new(order_id, time))
+                }),
+            LazyJust::new(|| Command::GlobalCancel(GlobalCancel)),
+            (id(), order()).prop_map(|(order_id, order)| {
+                Command::PlaceOrder(PlaceOrder::new(order_id, order))
+            }),
+            prop_oneof![
+                LazyJust::new(|| RequestOrders::AllOpen),
+                any::<bool>().prop_map(|auto_bind| RequestOrders::AutoOpen { auto_bind }),
+                LazyJust::new(|| RequestOrders::Open),
+                any::<bool>().prop_map(|api_only| RequestOrders::Completed { api_only }),
+                LazyJust::new(|| RequestOrders::NextOrderId),
+            ]
+            .prop_map(Command::RequestOrders),
+        ]
+        .boxed()
+    }
+
+    fn contract_command() -> BoxedStrategy<Command> {
+        let report_types = vec![
+            FundamentalDataType::Snapshot,
+            FundamentalDataType::FinSummary,
+            FundamentalDataType::Ratios,
+            FundamentalDataType::FinStatements,
+            FundamentalDataType::Estimates,
+        ];
+        let subscription = (
+            (option::of(1..50i32), text(), text(), text()),
+            (option::of(price()), option::of(price()), option::of(id())),
+            (opt_text(), any::<bool>(), opt_text()),
+            tag_values(),
+        )
+            .prop_map(
+                |(
+                    (number_of_rows, instrument, location_code, scan_code),
+                    (above_price, below_price, above_volume),
+                    (moody_rating_above, exclude_convertible, stock_type_filter),
+                    filter_options,
+                )| {
+                    ScannerSubscription {
+                        number_of_rows,
+                        instrument,
+                        location_code,
+                        scan_code,
+                        above_price,
+                        below_price,
+                        above_volume,
+                        moody_rating_above,
+                        exclude_convertible,
+                        stock_type_filter,
+                        filter_options,
+                        ..Default::default()
+                    }
+                },
+            );
+        let wsh_filter = (
+            option::of(id()),
+            opt_text(),
+            (any::<bool>(), any::<bool>(), any::<bool>()),
+            (
+                option::of("20[23][0-9]{5}"),
+                option::of("20[23][0-9]{5}"),
+                option::of(1..100i32),
+            ),
+        )
+            .prop_map(
+                |(
+                    con_id,
+                    filter,
+                    (fill_watchlist, fill_portfolio, fill_competitors),
+                    (start_date, end_date, total_limit),
+                )| {
+                    WshEventDataFilter {
+                        con_id,
+                        filter,
+                        fill_watchlist,
+                        fill_portfolio,
+                        fill_competitors,
+                        start_date,
+                        end_date,
+                        total_limit,
+                    }
+                },
+            );
+        prop_oneof![
+            (req_id(), contract()).prop_map(|(req_id, contract)| {
+                Command::RequestContractDetails(RequestContractDetails::new(req_id, contract))
+            }),
+            prop_oneof![
+                (req_id(), contract(), select(report_types), tag_values()).prop_map(
+                    |(req_id, contract, report_type, options)| {
+                        RequestFundamentalData::Request {
+                            req_id,
+                            contract,
+                            report_type,
+                            options,
+                        }
+                    }
+                ),
+                req_id().prop_map(|req_id| RequestFundamentalData::Cancel { req_id }),
+            ]
+            .prop_map(Command::RequestFundamentalData),
+            (req_id(), text()).prop_map(|(req_id, pattern)| {
+                Command::RequestMatchingSymbols(RequestMatchingSymbols::new(req_id, pattern))
+            }),
+            (req_id(), text(), text(), id()).prop_map(|(req_id, symbol, exchange, con_id)| {
+                Command::RequestSecDefOptParams(RequestSecDefOptParams::new(
+                    req_id,
+                    symbol,
+                    exchange,
+                    SecType::Stock,
+                    con_id,
+                ))
+            }),
+            prop_oneof![
+                (req_id(), subscription, tag_values()).prop_map(
+                    |(req_id, subscription, subscription_options)| {
+                        RequestScanner::Subscription {
+                            req_id,
+                            subscription,
+                            subscription_options,
+                        }
+                    }
+                ),
+                req_id().prop_map(|req_id| RequestScanner::CancelSubscription { req_id }),
+                LazyJust::new(|| RequestScanner::Parameters),
+            ]
+            .prop_map(Command::RequestScanner),
+            prop_oneof![
+                req_id().prop_map(|req_id| RequestWsh::MetaData { req_id }),
+                req_id().prop_map(|req_id| RequestWsh::CancelMetaData { req_id }),
+                (req_id(), wsh_filter)
+                    .prop_map(|(req_id, filter)| RequestWsh::EventData { req_id, filter }),
+                req_id().prop_map(|req_id| RequestWsh::CancelEventData { req_id }),
+            ]
+            .prop_map(Command::RequestWsh),
+            prop_oneof![
+                LazyJust::new(|| RequestNews::Providers),
+                (req_id(), text(), text(), tag_values()).prop_map(
+                    |(req_id, provider_code, article_id, options)| {
+                        RequestNews::Article {
+                            req_id,
+                            provider_code,
+                            article_id,
+                            options,
+                        }
+                    }
+                ),
+                (
+                    (req_id(), id(), vec(text(), 0..3)),
+                    (option::of(timestamp()), option::of(timestamp())),
+                    (1..300i32, tag_values())
+                )
+                    .prop_map(
+                        |(
+                            (req_id, con_id, provider_codes),
+                            (start, end),
+                            (total_results, options),
+                        )| {
+                            RequestNews::Historical {
+                                req_id,
+                                con_id,
+                                provider_codes,
+                                start,
+                                end,
+                                total_results,
+                                options,
+                            }
+                        }
+                    ),
+                any::<bool>().prop_map(|all_messages| RequestNews::Bulletins { all_messages }),
+                LazyJust::new(|| RequestNews::CancelBulletins),
+            ]
+            .prop_map(Command::RequestNews),
+        ]
+        .boxed()
+    }
+
+    fn market_data_command() -> BoxedStrategy<Command> {
+        let what_to_show = || {
+            select(vec![
+                HistoricalDataType::Trades,
+                HistoricalDataType::Midpoint,
+                HistoricalDataType::BidAsk,
+            ])
+        };
+        let use_rth = || {
+            select(vec![
+                UseRegularTradingHoursOnly::DontUse,
+                UseRegularTradingHoursOnly::Use,
+            ])
+        };
+        let format_date = || {
+            select(vec![
+                IntradayBarDateFormat::YYYYMMDD,
+                IntradayBarDateFormat::UnixEpochSeconds,
+            ])
+        };
+        let generic_ticks = vec(
+            select(vec![
+                GenericTickType::ShortableData,
+                GenericTickType::OptionImpliedVol,
+                GenericTickType::AuctionData,
+            ]),
+            0..3,
+        );
+        let historical_data = (
+            (req_id(), contract(), option::of(any::<bool>()), timestamp()),
+            (
+                select(vec![BarSize::_1Secs, BarSize::_5Mins, BarSize::_1Day]),
+                select(vec![
+                    Duration::Seconds(1800),
+                    Duration::Day(3),
+                    Duration::Year(1),
+                ]),
+                use_rth(),
+            ),
+            (what_to_show(), format_date(), any::<bool>(), tag_values()),
+        )
+            .prop_map(
+                |(
+                    (req_id, mut contract, include_expired, end_date_time),
+                    (bar_size_setting, duration, use_rth),
+                    (what_to_show, format_date, keep_up_to_date, chart_options),
+                )| {
+                    contract.include_expired = include_expired;
+                    HistoricalDataRequest {
+                        req_id,
+                        contract,
+                        end_date_time,
+                        duration,
+                        bar_size_setting,
+                        what_to_show,
+                        use_rth,
+                        format_date,
+                        keep_up_to_date,
+                        chart_options,
+                    }
+                },
+            );
+        let historical_ticks = (
+            (req_id(), contract(), any::<bool>(), timestamp()),
+            (1..1000i32, what_to_show(), use_rth(), 0..2i32, tag_values()),
+        )
+            .prop_map(
+                |(
+                    (req_id, contract, start, date_time),
+                    (number_of_ticks, what_to_show, use_rth, ignore_size, misc_options),
+                )| {
+                    HistoricalTicksRequest {
+                        req_id,
+                        contract,
+                        date_time: if start {
+                            HistoricalTickDateTime::Start(date_time)
+                        } else {
+                            HistoricalTickDateTime::End(date_time)
+                        },
+                        number_of_ticks,
+                        what_to_show,
+                        use_rth,
+                        ignore_size,
+                        misc_options,
+                    }
+                },
+            );
+        prop_oneof![
+            (
+                req_id(),
+                contract(),
+                generic_ticks,
+                any::<bool>(),
+                any::<bool>()
+            )
+                .prop_map(
+                    |(req_id, contract, generic_tick_list, snapshot, regulatory)| {
+                        Command::MarketData(MarketDataRequest {
+                            req_id,
+                            contract,
+                            generic_tick_list,
+                            snapshot,
+                            regulatory,
+                            additional_data: Vec::new(),
+                        })
+                    }
+                ),
+            req_id()
+                .prop_map(|req_id| Command::CancelMarketData(CancelMarketDataRequest { req_id })),
+            select(vec![MarketDataType::RealTime, MarketDataType::Delayed]).prop_map(
+                |market_data_type| {
+                    Command::MarketDataType(MarketDataTypeRequest { market_data_type })
+                }
+            ),
+            (req_id(), contract(), 1..20i32, any::<bool>()).prop_map(
+                |(req_id, contract, num_rows, is_smart_depth)| {
+                    Command::MarketDepth(MarketDepthRequest {
+                        req_id,
+                        contract,
+                        num_rows,
+                        is_smart_depth,
+                        mkt_depth_options: Vec::new(),
+                    })
+                }
+            ),
+            req_id().prop_map(|req_id| {
+                Command::CancelMarketDepth(CancelMarketDepthRequest {
+                    req_id,
+                    is_smart_depth: false,
+                })
+            }),
+            LazyJust::new(|| Command::MarketDepthExchanges(MarketDepthExchangesRequest)),
+            (
+                req_id(),
+                contract(),
+                what_to_show(),
+                use_rth(),
+                format_date()
+            )
+                .prop_map(|(req_id, contract, what_to_show, use_rth, format_date)| {
+                    Command::HeadTimestamp(HeadTimestampRequest {
+                        req_id,
+                        contract,
+                        what_to_show,
+                        use_rth,
+                        format_date,
+                    })
+                }),
+            req_id().prop_map(|req_id| {
+                Command::CancelHeadTimestamp(CancelHeadTimestampRequest { req_id })
+            }),
+            historical_data.prop_map(Command::HistoricalData),
+            req_id().prop_map(|req_id| {
+                Command::CancelHistoricalData(CancelHistoricalDataRequest { req_id })
+            }),
+            historical_ticks.prop_map(Command::HistoricalTicks),
+            (
+                req_id(),
+                contract(),
+                select(vec![
+                    TickByTickType::Last,
+                    TickByTickType::AllLast,
+                    TickByTickType::BidAsk,
+                    TickByTickType::MidPoint,
+                ]),
+                0..1000i32,
+                any::<bool>()
+            )
+                .prop_map(
+                    |(req_id, contract, tick_type, number_of_ticks, ignore_size)| {
+                        Command::TickByTick(TickByTickRequest {
+                            req_id,
+                            contract,
+                            tick_type,
+                            number_of_ticks,
+                            ignore_size,
+                        })
+                    }
+                ),
+            req_id()
+                .prop_map(|req_id| Command::CancelTickByTick(CancelTickByTickRequest { req_id })),
+            (
+                req_id(),
+                contract(),
+                what_to_show(),
+                use_rth(),
+                tag_values()
+            )
+                .prop_map(
+                    |(req_id, contract, what_to_show, use_rth, real_time_bars_options)| {
+                        Command::RealtimeBars(RealtimeBarRequest {
+                            req_id,
+                            contract,
+                            bar_size: BarSize::_5Secs,
+                            what_to_show,
+                            use_rth,
+                            real_time_bars_options,
+                        })
+                    }
+                ),
+            req_id().prop_map(|req_id| Command::CancelRealtimeBars(CancelRealtimeBars { req_id })),
+            (req_id(), contract(), price(), price(), tag_values()).prop_map(
+                |(req_id, contract, option_price, under_price, options)| {
+                    Command::CalculateImpliedVolatility(CalculateImpliedVolatilityRequest {
+                        req_id,
+                        contract,
+                        option_price,
+                        under_price,
+                        options,
+                    })
+                }
+            ),
+            req_id().prop_map(|req_id| {
+                Command::CancelCalculateImpliedVolatility(CancelCalculateImpliedVolatilityRequest {
+                    req_id,
+                })
+            }),
+            (req_id(), contract(), price(), price(), tag_values()).prop_map(
+                |(req_id, contract, volatility, under_price, options)| {
+                    Command::CalculateOptionPrice(CalculateOptionPriceRequest {
+                        req_id,
+                        contract,
+                        volatility,
+                        under_price,
+                        options,
+                    })
+                }
+            ),
+            req_id().prop_map(|req_id| {
+                Command::CancelCalculateOptionPrice(CancelCalculateOptionPriceRequest { req_id })
+            }),
+        ]
+        .boxed()
+    }
+
+    fn command() -> BoxedStrategy<Command> {
+        prop_oneof![
+            api_command(),
+            account_command(),
+            order_command(),
+            contract_command(),
+            market_data_command(),
+            (
+                select(vec![
+                    Outgoing::CancelAccountSummary,
+                    Outgoing::ReqManagedAccts
+                ]),
+                vec(text(), 0..3)
+            )
+                .prop_map(|(msg_id, fields)| Command::Other { msg_id, fields }),
+        ]
+        .boxed()
+    }
+
+    /// Encodes `frame` with the server codec and decodes it with the client
+    /// codec, the decoded frame has to be encoded to the same message.
+    fn frame_round_trip(
+        frame: IBFrame,
+        server_version: ServerVersion,
+    ) -> Result<(), TestCaseError> {
+        let mut server = ServerCodec::after_handshake(server_version);
+        let mut client = ClientCodec::new(server_version);
+        let mut buf = BytesMut::new();
+        server.encode(frame, &mut buf).unwrap();
+        let encoded = buf.clone();
+        let decoded = client.decode(&mut buf).unwrap();
+        prop_assert!(buf.is_empty());
+        let decoded = decoded.ok_or_else(|| {
+            TestCaseError::fail(format!(
+                "could not decode {:?}",
+                String::from_utf8_lossy(&encoded)
+            ))
+        })?;
+        let mut reencoded = BytesMut::new();
+        server.encode(decoded, &mut reencoded).unwrap();
+        prop_assert_eq!(
+            String::from_utf8_lossy(&encoded),
+            String::from_utf8_lossy(&reencoded)
+        );
+        Ok(())
+    }
+
+    /// Encodes `cmd` with the client codec and decodes it with the server
+    /// codec, the decoded command has to be encoded to the same message.
+    fn command_round_trip(
+        cmd: Command,
+        server_version: ServerVersion,
+    ) -> Result<(), TestCaseError> {
+        let mut server = ServerCodec::after_handshake(server_version);
+        let mut client = ClientCodec::new(server_version);
+        let mut buf = BytesMut::new();
+        match client.encode(cmd, &mut buf) {
+            // the command is not supported by the server
+            Err(Error::Unsupported { .. }) => return Ok(()),
+            res => res.unwrap(),
+        }
+        let encoded = buf.clone();
+        let decoded = server.decode(&mut buf).unwrap();
+        prop_assert!(buf.is_empty());
+        let decoded = decoded.ok_or_else(|| {
+            TestCaseError::fail(format!(
+                "could not decode {:?}",
+                String::from_utf8_lossy(&encoded)
+            ))
+        })?;
+        let mut reencoded = BytesMut::new();
+        client.encode(decoded, &mut reencoded).unwrap();
+        prop_assert_eq!(
+            String::from_utf8_lossy(&encoded),
+            String::from_utf8_lossy(&reencoded)
+        );
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn frames_round_trip(frame in frame(), server_version in server_version()) {
+            frame_round_trip(frame, server_version)?;
+        }
+
+        #[test]
+        fn commands_round_trip(cmd in command(), server_version in server_version()) {
+            command_round_trip(cmd, server_version)?;
+        }
+
+        #[test]
+        fn handshake_round_trip(
+            min_client_version in 100..150i32,
+            max_client_version in 150..200i32,
+            connection_options in "(\\+[A-Z]{4})?",
+            server_version in server_version(),
+            connection_time in "20[0-9]{6} [0-9]{2}:[0-9]{2}:[0-9]{2} EST",
+        ) {
+            let mut client = ClientCodec::default();
+            let mut server = ServerCodec::new(server_version);
+            let mut buf = BytesMut::new();
+            client
+                .encode(
+                    Command::Api(Api::Init {
+                        min_client_version,
+                        max_client_version,
+                        connection_options: connection_options.clone(),
+                    }),
+                    &mut buf,
+                )
+                .unwrap();
+            match server.decode(&mut buf).unwrap() {
+                Some(Command::Api(Api::Init {
+                    min_client_version: min,
+                    max_client_version: max,
+                    connection_options: options,
+                })) => {
+                    prop_assert_eq!(min_client_version, min);
+                    prop_assert_eq!(max_client_version, max);
+                    prop_assert_eq!(connection_options, options);
+                },
+                cmd => prop_assert!(false, "unexpected command {:?}", cmd),
+            }
+            server
+                .encode(
+                    IBFrame::ServerVersion {
+                        server_version,
+                        connection_time: connection_time.clone(),
+                    },
+                    &mut buf,
+                )
+                .unwrap();
+            match client.decode(&mut buf).unwrap() {
+                Some(IBFrame::ServerVersion {
+                    server_version: version,
+                    connection_time: time,
+                }) => {
+                    prop_assert_eq!(server_version, version);
+                    prop_assert_eq!(connection_time, time);
+                },
+                frame => prop_assert!(false, "unexpected frame {:?}", frame),
+            }
+            prop_assert_eq!(Some(server_version), client.server_version());
+        }
+    }
+
+    #[test]
+    fn decoders_wait_for_complete_messages() {
+        let mut server = ServerCodec::after_handshake(163);
+        let mut client = ClientCodec::new(163);
+        let mut buf = BytesMut::new();
+        client
+            .encode(Command::Api(Api::RequestCurrentTime), &mut buf)
+            .unwrap();
+        let mut partial = buf.split_to(buf.len() - 1);
+        assert!(server.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(buf);
+        assert!(matches!(
+            server.decode(&mut partial).unwrap(),
+            Some(Command::Api(Api::RequestCurrentTime))
+        ));
+
+        let mut buf = BytesMut::new();
+        server.encode(IBFrame::OrderId(7), &mut buf).unwrap();
+        let mut partial = buf.split_to(3);
+        assert!(client.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(buf);
+        assert!(matches!(
+            client.decode(&mut partial).unwrap(),
+            Some(IBFrame::OrderId(7))
+        ));
+    }
+
+    #[test]
+    fn commands_need_the_server_version() {
+        let mut client = ClientCodec::default();
+        let result = client.encode(Command::Api(Api::RequestCurrentTime), &mut BytesMut::new());
+        assert!(matches!(
+            result,
+            Err(Error::Parse(ParseError::MissingServerVersion))
+        ));
+    }
+}
@@ -1,16 +1,18 @@
 use std::{fmt::{Display, Formatter},
-          str::{FromStr, Split}};
+          ops::RangeBounds,
+          str::FromStr};
 
-use chrono::{DateTime, NaiveDateTime, TimeZone};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone};
 use chrono_tz::{Tz, US, UTC};
 use rust_decimal::prelude::*;
 
 // #[cfg(feature = "serde")]
 // use serde::{Deserialize, Serialize};
 use crate::{enums::*,
-            ib_frame::{ParseError, ParseIbkrFrame, ParseResult},
+            ib_frame::{EncodeIbkrFrame, ParseError, ParseIbkrFrame, ParseResult},
             order::{ComboAction, OptionOpenClose, ShortSaleSlot},
-            prelude::ib_message::{decode, Decodable},
+            prelude::ib_message::{decode, decode_required, preallocate, Decodable, Fields},
+            server_versions::MinServerVersion,
             utils::ib_message::Encodable,
             ServerVersion};
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -87,7 +89,6 @@ impl Encodable for Contract {
         code.push_str(&self.include_expired.encode());
         code.push_str(&self.sec_id_type.encode());
         code.push_str(&self.sec_id.encode());
-        code.push_str(&self.issuer_id.encode());
         code
     }
 }
@@ -96,7 +97,7 @@ impl ParseIbkrFrame for Contract {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -105,13 +106,13 @@ impl ParseIbkrFrame for Contract {
             Incoming::ContractData => {
                 tracing::debug!("decode ContractData");
                 Ok(Self {
-                    symbol: decode(it)?.unwrap(),
-                    sec_type: decode(it)?.unwrap(),
+                    symbol: decode_required(it)?,
+                    sec_type: decode_required(it)?,
                     last_trade_date_or_contract_month: decode(it)?,
                     strike: decode(it)?,
                     right: decode(it)?,
                     exchange: decode(it)?,
-                    currency: decode(it)?.unwrap(),
+                    currency: decode_required(it)?,
                     local_symbol: decode(it)?,
                     // issuer_id: decode(it)?,
                     ..Default::default()
@@ -120,17 +121,19 @@ impl ParseIbkrFrame for Contract {
             Incoming::OpenOrder
             | Incoming::CompletedOrder
             | Incoming::PortfolioValue
+            | Incoming::PositionData
+            | Incoming::PositionMulti
             | Incoming::ExecutionData => {
                 Ok(Contract {
                     con_id: decode(it)?,
-                    symbol: decode(it)?.unwrap(),
-                    sec_type: decode(it)?.unwrap(),
+                    symbol: decode_required(it)?,
+                    sec_type: decode_required(it)?,
                     last_trade_date_or_contract_month: decode(it)?,
                     strike: decode(it)?,
                     right: decode(it)?,
                     multiplier: decode(it)?,
                     exchange: decode(it)?,
-                    currency: decode(it)?.unwrap(),
+                    currency: decode_required(it)?,
                     local_symbol: decode(it)?,
                     trading_class: decode(it)?,
                     ..Default::default()
@@ -141,6 +144,35 @@ impl ParseIbkrFrame for Contract {
     }
 }
 
+impl EncodeIbkrFrame for Contract {
+    fn encode_frame(&self, msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = String::new();
+        if matches!(msg_id, Incoming::ContractData) {
+            code.push_str(&self.symbol.encode());
+            code.push_str(&self.sec_type.encode());
+            code.push_str(&self.last_trade_date_or_contract_month.encode());
+            code.push_str(&self.strike.encode());
+            code.push_str(&self.right.encode());
+            code.push_str(&self.exchange.encode());
+            code.push_str(&self.currency.encode());
+            code.push_str(&self.local_symbol.encode());
+        } else {
+            code.push_str(&self.con_id.encode());
+            code.push_str(&self.symbol.encode());
+            code.push_str(&self.sec_type.encode());
+            code.push_str(&self.last_trade_date_or_contract_month.encode());
+            code.push_str(&self.strike.encode());
+            code.push_str(&self.right.encode());
+            code.push_str(&self.multiplier.encode());
+            code.push_str(&self.exchange.encode());
+            code.push_str(&self.currency.encode());
+            code.push_str(&self.local_symbol.encode());
+            code.push_str(&self.trading_class.encode());
+        }
+        code
+    }
+}
+
 impl Contract {
     pub fn encode_for_order(&self) -> String {
         let mut code = String::new();
@@ -178,6 +210,107 @@ impl Contract {
         code
     }
 
+    /// Encodes the contract of a market depth request, servers before
+    /// version 149 do not expect the primary exchange.
+    pub fn encode_for_market_depth(&self, server_version: ServerVersion) -> String {
+        let mut code = String::new();
+        code.push_str(&self.con_id.encode());
+        code.push_str(&self.symbol.encode());
+        code.push_str(&self.sec_type.encode());
+        code.push_str(&self.last_trade_date_or_contract_month.encode());
+        code.push_str(&self.strike.encode());
+        code.push_str(&self.right.encode());
+        code.push_str(&self.multiplier.encode());
+        code.push_str(&self.exchange.encode());
+        if MinServerVersion::MktDepthPrimExchange.is_supported(server_version) {
+            code.push_str(&self.primary_exchange.encode());
+        }
+        code.push_str(&self.currency.encode());
+        code.push_str(&self.local_symbol.encode());
+        code.push_str(&self.trading_class.encode());
+        code
+    }
+
+    pub fn encode_for_fundamental_data(&self) -> String {
+        let mut code = String::new();
+        code.push_str(&self.con_id.encode());
+        code.push_str(&self.symbol.encode());
+        code.push_str(&self.sec_type.encode());
+        code.push_str(&self.exchange.encode());
+        code.push_str(&self.primary_exchange.encode());
+        code.push_str(&self.currency.encode());
+        code.push_str(&self.local_symbol.encode());
+        code
+    }
+
+    /// Decodes the fields shared by the contracts of the requests, the
+    /// primary exchange is left out by some of them.
+    fn decode_for_request(it: &mut Fields<'_>, primary_exchange: bool) -> ParseResult<Contract> {
+        let mut contract = Contract {
+            con_id: decode(it)?,
+            symbol: decode(it)?.unwrap_or_default(),
+            sec_type: decode(it)?.unwrap_or_default(),
+            last_trade_date_or_contract_month: decode(it)?,
+            strike: decode(it)?,
+            right: decode(it)?,
+            multiplier: decode(it)?,
+            exchange: decode(it)?,
+            ..Default::default()
+        };
+        if primary_exchange {
+            contract.primary_exchange = decode(it)?;
+        }
+        contract.currency = decode(it)?.unwrap_or_default();
+        contract.local_symbol = decode(it)?;
+        contract.trading_class = decode(it)?;
+        Ok(contract)
+    }
+
+    /// The inverse of `encode`.
+    pub(crate) fn decode(it: &mut Fields<'_>) -> ParseResult<Contract> {
+        let mut contract = Contract::decode_for_ticker(it)?;
+        contract.include_expired = decode(it)?;
+        contract.sec_id_type = decode(it)?;
+        contract.sec_id = decode(it)?;
+        Ok(contract)
+    }
+
+    /// The inverse of `encode_for_order`.
+    pub(crate) fn decode_for_order(it: &mut Fields<'_>) -> ParseResult<Contract> {
+        let mut contract = Contract::decode_for_ticker(it)?;
+        contract.sec_id_type = decode(it)?;
+        contract.sec_id = decode(it)?;
+        Ok(contract)
+    }
+
+    /// The inverse of `encode_for_ticker`.
+    pub(crate) fn decode_for_ticker(it: &mut Fields<'_>) -> ParseResult<Contract> {
+        Contract::decode_for_request(it, true)
+    }
+
+    /// The inverse of `encode_for_market_depth`.
+    pub(crate) fn decode_for_market_depth(
+        it: &mut Fields<'_>,
+        server_version: ServerVersion,
+    ) -> ParseResult<Contract> {
+        let primary_exchange = MinServerVersion::MktDepthPrimExchange.is_supported(server_version);
+        Contract::decode_for_request(it, primary_exchange)
+    }
+
+    /// The inverse of `encode_for_fundamental_data`.
+    pub(crate) fn decode_for_fundamental_data(it: &mut Fields<'_>) -> ParseResult<Contract> {
+        Ok(Contract {
+            con_id: decode(it)?,
+            symbol: decode(it)?.unwrap_or_default(),
+            sec_type: decode(it)?.unwrap_or_default(),
+            exchange: decode(it)?,
+            primary_exchange: decode(it)?,
+            currency: decode(it)?.unwrap_or_default(),
+            local_symbol: decode(it)?,
+            ..Default::default()
+        })
+    }
+
     pub fn encode_for_hist_data(&self) -> String {
         let mut code = String::new();
         code.push_str(&self.con_id.encode());
@@ -275,7 +408,7 @@ impl ParseIbkrFrame for ContractDetails {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -317,9 +450,9 @@ impl ParseIbkrFrame for ContractDetails {
                 let sec_id_list_count: Option<usize> = decode(it)?;
                 details.sec_id_list = match sec_id_list_count {
                     Some(count) => {
-                        let mut sec_ids: Vec<(String, String)> = Vec::with_capacity(count);
+                        let mut sec_ids: Vec<(String, String)> = preallocate(count);
                         for _i in 0..count {
-                            sec_ids.push((decode(it)?.unwrap(), decode(it)?.unwrap()));
+                            sec_ids.push((decode_required(it)?, decode_required(it)?));
                         }
                         Some(sec_ids)
                     },
@@ -338,6 +471,53 @@ impl ParseIbkrFrame for ContractDetails {
         }
     }
 }
+impl EncodeIbkrFrame for ContractDetails {
+    fn encode_frame(&self, msg_id: Incoming, server_version: ServerVersion) -> String {
+        let contract = &self.contract;
+        let mut code = contract.encode_frame(msg_id, server_version);
+        code.push_str(&self.market_name.encode());
+        code.push_str(&contract.trading_class.encode());
+        if server_version >= 183 {
+            code.push('\0');
+        }
+        code.push_str(&contract.con_id.encode());
+        code.push_str(&self.min_tick.encode());
+        code.push_str(&contract.multiplier.encode());
+        code.push_str(&self.order_types.encode());
+        code.push_str(&self.valid_exchanges.encode());
+        code.push_str(&self.price_magnifier.encode());
+        code.push_str(&self.under_con_id.encode());
+        code.push_str(&self.long_name.encode());
+        code.push_str(&contract.primary_exchange.encode());
+        code.push_str(&self.contract_month.encode());
+        code.push_str(&self.industry.encode());
+        code.push_str(&self.category.encode());
+        code.push_str(&self.subcategory.encode());
+        code.push_str(&self.timezone_id.encode());
+        code.push_str(&self.trading_hours.encode());
+        code.push_str(&self.liquid_hours.encode());
+        code.push_str(&self.ev_rule.encode());
+        code.push_str(&self.ev_multiplier.encode());
+        match &self.sec_id_list {
+            Some(sec_ids) => {
+                code.push_str(&sec_ids.len().encode());
+                for (tag, value) in sec_ids {
+                    code.push_str(&tag.encode());
+                    code.push_str(&value.encode());
+                }
+            },
+            None => code.push('\0'),
+        }
+        code.push_str(&self.agg_group.encode());
+        code.push_str(&self.under_symbol.encode());
+        code.push_str(&self.under_sec_type.encode());
+        code.push_str(&self.market_rule_ids.encode());
+        code.push_str(&self.real_expiration_date.encode());
+        code.push_str(&self.stock_type.encode());
+        code
+    }
+}
+
 impl ContractDetails {
     pub fn liquid_hours(&self) -> Option<Vec<(DateTime<Tz>, DateTime<Tz>)>> {
         let liq_hours_it = self.liquid_hours.as_ref()?.split(';');
@@ -348,19 +528,19 @@ impl ContractDetails {
             } else {
                 let mut hours_it = liq_hours.split('-');
                 let open_dt =
-                    NaiveDateTime::parse_from_str(hours_it.next()?, "%Y%m%d:%H%M").unwrap();
+                    NaiveDateTime::parse_from_str(hours_it.next()?, "%Y%m%d:%H%M").ok()?;
                 let close_dt =
-                    NaiveDateTime::parse_from_str(hours_it.next()?, "%Y%m%d:%H%M").unwrap();
+                    NaiveDateTime::parse_from_str(hours_it.next()?, "%Y%m%d:%H%M").ok()?;
                 if let Some(tz) = &self.timezone_id {
                     if tz.contains("EST") {
                         ret.push((
-                            US::Eastern.from_local_datetime(&open_dt).unwrap(),
-                            US::Eastern.from_local_datetime(&close_dt).unwrap(),
+                            US::Eastern.from_local_datetime(&open_dt).earliest()?,
+                            US::Eastern.from_local_datetime(&close_dt).earliest()?,
                         ));
                     } else {
                         ret.push((
-                            UTC.from_local_datetime(&open_dt).unwrap(),
-                            UTC.from_local_datetime(&close_dt).unwrap(),
+                            UTC.from_local_datetime(&open_dt).earliest()?,
+                            UTC.from_local_datetime(&close_dt).earliest()?,
                         ));
                     }
                 }
@@ -369,11 +549,211 @@ impl ContractDetails {
         Some(ret)
     }
 }
-#[allow(dead_code)]
+/// Option parameters of an underlying on a single exchange.
+///
+/// Returned by `Client::request_sec_def_opt_params`, one per exchange the
+/// options are traded on. `expirations` and `strikes` are sorted ascending.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OptionChain {
+    pub exchange:          String,
+    pub underlying_con_id: i32,
+    pub trading_class:     String,
+    pub multiplier:        Option<Decimal>,
+    pub expirations:       Vec<NaiveDate>,
+    pub strikes:           Vec<Decimal>,
+}
+
+impl ParseIbkrFrame for OptionChain {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        _server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::SecurityDefinitionOptionParameter) {
+            tracing::error!("Unexpected Message (OptionChain): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        let exchange = decode(it)?.unwrap_or_default();
+        let underlying_con_id = decode(it)?.unwrap_or_default();
+        let trading_class = decode(it)?.unwrap_or_default();
+        let multiplier = decode(it)?;
+        let expiration_count: usize = decode(it)?.unwrap_or_default();
+        let mut expirations = preallocate(expiration_count);
+        for _ in 0..expiration_count {
+            expirations.push(decode_required(it)?);
+        }
+        expirations.sort_unstable();
+        let strike_count: usize = decode(it)?.unwrap_or_default();
+        let mut strikes = preallocate(strike_count);
+        for _ in 0..strike_count {
+            strikes.push(decode_required(it)?);
+        }
+        strikes.sort_unstable();
+        Ok(Self {
+            exchange,
+            underlying_con_id,
+            trading_class,
+            multiplier,
+            expirations,
+            strikes,
+        })
+    }
+}
+
+impl EncodeIbkrFrame for OptionChain {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = self.exchange.encode();
+        code.push_str(&self.underlying_con_id.encode());
+        code.push_str(&self.trading_class.encode());
+        code.push_str(&self.multiplier.encode());
+        code.push_str(&self.expirations.len().encode());
+        for expiration in &self.expirations {
+            code.push_str(&expiration.encode());
+        }
+        code.push_str(&self.strikes.len().encode());
+        for strike in &self.strikes {
+            code.push_str(&strike.encode());
+        }
+        code
+    }
+}
+
+impl OptionChain {
+    /// Expands the chain into call and put contracts.
+    ///
+    /// Only expirations within `expirations` and strikes within
+    /// `underlying_price * (1 ± moneyness)` are included, e.g. a moneyness of
+    /// `0.1` keeps the strikes within 10% of the underlying price. The
+    /// contracts are ordered by expiration, strike and right.
+    pub fn contracts<R: RangeBounds<NaiveDate>>(
+        &self,
+        symbol: &str,
+        expirations: R,
+        underlying_price: Decimal,
+        moneyness: Decimal,
+    ) -> Vec<Contract> {
+        let min_strike = underlying_price * (Decimal::ONE - moneyness);
+        let max_strike = underlying_price * (Decimal::ONE + moneyness);
+        let strikes: Vec<Decimal> = self
+            .strikes
+            .iter()
+            .copied()
+            .filter(|strike| (min_strike..=max_strike).contains(strike))
+            .collect();
+        let mut contracts = Vec::new();
+        for expiration in self
+            .expirations
+            .iter()
+            .filter(|exp| expirations.contains(exp))
+        {
+            for strike in &strikes {
+                for right in [OptionRight::Call, OptionRight::Put] {
+                    contracts.push(Contract {
+                        symbol: symbol.to_string(),
+                        sec_type: SecType::Option,
+                        last_trade_date_or_contract_month: Some(
+                            expiration.format("%Y%m%d").to_string(),
+                        ),
+                        strike: Some(*strike),
+                        right: Some(right),
+                        multiplier: self.multiplier,
+                        exchange: Some(self.exchange.clone()),
+                        trading_class: Some(self.trading_class.clone()),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        contracts
+    }
+}
+
+/// A contract matching a symbol search, see
+/// `Client::request_matching_symbols`.
 #[derive(Debug, Clone)]
 pub struct ContractDescription {
-    contract:                  Option<Contract>,
-    derivative_sec_types_list: Option<Vec<String>>,
+    pub contract:                  Contract,
+    /// Security types of the derivatives available for the contract, e.g.
+    /// `OPT`, `FUT` or `WAR`.
+    pub derivative_sec_types_list: Option<Vec<String>>,
+}
+
+impl ParseIbkrFrame for ContractDescription {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::SymbolSamples) {
+            tracing::error!("Unexpected Message (ContractDescription): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        let con_id = decode(it)?;
+        let symbol = decode(it)?.unwrap_or_default();
+        let sec_type = decode(it)?.unwrap_or_default();
+        let primary_exchange = decode(it)?;
+        let currency = decode(it)?.unwrap_or_default();
+        let derivative_sec_types_count: Option<usize> = decode(it)?;
+        let derivative_sec_types_list = match derivative_sec_types_count {
+            Some(count) => {
+                let mut sec_types = preallocate(count);
+                for _ in 0..count {
+                    sec_types.push(decode_required(it)?);
+                }
+                Some(sec_types)
+            },
+            None => None,
+        };
+        let issuer_id =
+            if server_version.unwrap_or_default() >= constants::MIN_SERVER_VER_BOND_ISSUERID {
+                let _description: Option<String> = decode(it)?;
+                decode(it)?
+            } else {
+                None
+            };
+        Ok(Self {
+            contract: Contract {
+                con_id,
+                symbol,
+                sec_type,
+                primary_exchange,
+                currency,
+                issuer_id,
+                ..Default::default()
+            },
+            derivative_sec_types_list,
+        })
+    }
+}
+
+impl EncodeIbkrFrame for ContractDescription {
+    fn encode_frame(&self, _msg_id: Incoming, server_version: ServerVersion) -> String {
+        let mut code = self.contract.con_id.encode();
+        code.push_str(&self.contract.symbol.encode());
+        code.push_str(&self.contract.sec_type.encode());
+        code.push_str(&self.contract.primary_exchange.encode());
+        code.push_str(&self.contract.currency.encode());
+        match &self.derivative_sec_types_list {
+            Some(sec_types) => {
+                code.push_str(&sec_types.len().encode());
+                for sec_type in sec_types {
+                    code.push_str(&sec_type.encode());
+                }
+            },
+            None => code.push('\0'),
+        }
+        if server_version >= constants::MIN_SERVER_VER_BOND_ISSUERID {
+            code.push('\0'); // description
+            code.push_str(&self.contract.issuer_id.encode());
+        }
+        code
+    }
 }
 
 pub type ContractDescriptionList = Vec<ContractDescription>;
@@ -392,6 +772,17 @@ pub enum SecType {
     Commodity,
     News,
     MutualFund,
+    Cfd,
+    Crypto,
+    ContinuousFuture,
+    DutchWarrant,
+    Forward,
+    Bill,
+    Fixed,
+    StockLoan,
+    Basket,
+    InterCommodityUnit,
+    InterCommoditySpread,
 }
 
 impl Encodable for SecType {
@@ -409,6 +800,17 @@ impl Encodable for SecType {
             SecType::Commodity => "CMDTY\0",
             SecType::News => "NEWS\0",
             SecType::MutualFund => "FUND\0",
+            SecType::Cfd => "CFD\0",
+            SecType::Crypto => "CRYPTO\0",
+            SecType::ContinuousFuture => "CONTFUT\0",
+            SecType::DutchWarrant => "IOPT\0",
+            SecType::Forward => "FWD\0",
+            SecType::Bill => "BILL\0",
+            SecType::Fixed => "FIXED\0",
+            SecType::StockLoan => "SLB\0",
+            SecType::Basket => "BSK\0",
+            SecType::InterCommodityUnit => "ICU\0",
+            SecType::InterCommoditySpread => "ICS\0",
         }
         .to_string()
     }
@@ -434,6 +836,17 @@ impl FromStr for SecType {
             "CMDTY" => SecType::Commodity,
             "NEWS" => SecType::News,
             "FUND" => SecType::MutualFund,
+            "CFD" => SecType::Cfd,
+            "CRYPTO" => SecType::Crypto,
+            "CONTFUT" => SecType::ContinuousFuture,
+            "IOPT" => SecType::DutchWarrant,
+            "FWD" => SecType::Forward,
+            "BILL" => SecType::Bill,
+            "FIXED" => SecType::Fixed,
+            "SLB" => SecType::StockLoan,
+            "BSK" => SecType::Basket,
+            "ICU" => SecType::InterCommodityUnit,
+            "ICS" => SecType::InterCommoditySpread,
             &_ => return Err(ParseEnumError),
         };
         Ok(res)
@@ -511,3 +924,47 @@ impl FromStr for SecIdType {
         }
     }
 }
+
+impl Decodable for SecIdType {}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn option_chain_contracts() {
+        let chain = OptionChain {
+            exchange:          "SMART".to_string(),
+            underlying_con_id: 265598,
+            trading_class:     "AAPL".to_string(),
+            multiplier:        Some(dec!(100)),
+            expirations:       vec![
+                NaiveDate::from_ymd_opt(2024, 1, 19).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 2, 16).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 3, 15).unwrap(),
+            ],
+            strikes:           vec![dec!(80), dec!(95), dec!(100), dec!(105), dec!(120)],
+        };
+        let contracts = chain.contracts(
+            "AAPL",
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap()..,
+            dec!(100),
+            dec!(0.1),
+        );
+        assert_eq!(12, contracts.len());
+        assert!(contracts
+            .iter()
+            .all(|c| c.sec_type == SecType::Option && c.symbol == "AAPL"));
+        assert_eq!(
+            Some("20240216".to_string()),
+            contracts[0].last_trade_date_or_contract_month
+        );
+        assert_eq!(Some(dec!(95)), contracts[0].strike);
+        assert_eq!(Some(OptionRight::Call), contracts[0].right);
+        assert_eq!(Some(OptionRight::Put), contracts[1].right);
+        assert_eq!(Some(dec!(105)), contracts[11].strike);
+    }
+}
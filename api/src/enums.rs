@@ -7,8 +7,9 @@ use num_traits::FromPrimitive;
 use crate::utils::ib_message::{Decodable, Encodable};
 
 pub mod constants {
+    pub use crate::server_versions::*;
+
     pub const CLIENT_VERSION: i32 = 66;
-    pub const MIN_SERVER_VER_PRICE_MGMT_ALGO: i32 = 151;
     pub const MIN_CLIENT_VER: i32 = 100;
     pub const MAX_CLIENT_VER: i32 = 163; // MIN_SERVER_VER_PRICE_MGMT_ALGO;
     pub const UNSET_INTEGER: i32 = std::i32::MAX;
@@ -152,7 +153,14 @@ impl FromStr for Incoming {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+impl Encodable for Incoming {
+    fn encode(&self) -> String {
+        let ord = *self as i32;
+        ord.to_string() + "\0"
+    }
+}
+
+#[derive(Debug, FromPrimitive, Copy, Clone)]
 pub enum Outgoing {
     // outgoing message IDs
     ReqMktData                 = 1,
@@ -230,6 +238,10 @@ pub enum Outgoing {
     ReqTickByTickData          = 97,
     CancelTickByTickData       = 98,
     ReqCompletedOrders         = 99,
+    ReqWshMetaData             = 100,
+    CancelWshMetaData          = 101,
+    ReqWshEventData            = 102,
+    CancelWshEventData         = 103,
 }
 
 impl Encodable for Outgoing {
@@ -239,6 +251,18 @@ impl Encodable for Outgoing {
     }
 }
 
+impl FromStr for Outgoing {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ord = match s.parse::<i32>() {
+            Ok(n) => n,
+            Err(_) => return Err(ParseEnumError),
+        };
+        FromPrimitive::from_i32(ord).ok_or(ParseEnumError)
+    }
+}
+
 use std::fmt::Display;
 #[derive(Debug, Clone, Copy)]
 pub struct ParseEnumError;
@@ -309,6 +333,24 @@ impl Encodable for FundamentalDataType {
     }
 }
 
+impl FromStr for FundamentalDataType {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let res = match s {
+            "ReportSnapShot" => FundamentalDataType::Snapshot,
+            "ReportsFinSummary" => FundamentalDataType::FinSummary,
+            "ReportRatios" => FundamentalDataType::Ratios,
+            "ReportsFinStatements" => FundamentalDataType::FinStatements,
+            "RESC" => FundamentalDataType::Estimates,
+            &_ => return Err(ParseEnumError),
+        };
+        Ok(res)
+    }
+}
+
+impl Decodable for FundamentalDataType {}
+
 // #[derive(Debug, Clone, Copy)]
 // /// use regular trading hours only, 1 for yes or 0 for no
 // pub enum UseRegularTradingHoursOnly {
@@ -0,0 +1,111 @@
+use std::{io, time::Duration};
+
+use crate::{api_message::{TwsErrorCode, TwsMessage},
+            ib_frame::ParseError,
+            server_versions::MinServerVersion,
+            RequestId,
+            ServerVersion};
+
+/// Error returned by the functions of the crate.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// reading from or writing to the socket failed
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// the options passed to `connect_with_options` are not supported
+    #[error("invalid connect options: {0}")]
+    InvalidOptions(String),
+    /// TWS / IB Gateway did not complete the API handshake
+    #[error("handshake failed: {0}")]
+    Handshake(String),
+    /// a message of TWS could not be decoded
+    #[error("could not parse message: {0}")]
+    Parse(#[from] ParseError),
+    /// TWS answered the request with an error message
+    #[error("request failed with {0}")]
+    Tws(TwsMessage),
+    /// TWS did not answer within the request timeout
+    #[error("no response within {0:?}")]
+    Timeout(Duration),
+    /// the connection was closed before the response was received
+    #[error("connection closed")]
+    Disconnected,
+    /// another request with the same id is still waiting for its response
+    /// or subscribed
+    #[error("request id {0} is already in use")]
+    DuplicateRequestId(RequestId),
+    /// TWS rejected the request because too many requests were sent
+    #[error("pacing violation: {0}")]
+    PacingViolation(TwsMessage),
+    /// the request needs a newer version of TWS / IB Gateway
+    #[error(
+        "{feature} requires server version {}, the server has version {server_version}",
+        .feature.version()
+    )]
+    Unsupported {
+        feature:        MinServerVersion,
+        server_version: ServerVersion,
+    },
+}
+
+impl From<TwsMessage> for Error {
+    /// Separates pacing violations from the other errors of TWS.
+    fn from(message: TwsMessage) -> Self {
+        let pacing = match message.code {
+            TwsErrorCode::MaxMessageRateExceeded => true,
+            TwsErrorCode::HistoricalDataServiceError | TwsErrorCode::Other(420) => {
+                matches!(&message.message, Some(text) if text.to_lowercase().contains("pacing violation"))
+            },
+            _ => false,
+        };
+        if pacing {
+            Error::PacingViolation(message)
+        } else {
+            Error::Tws(message)
+        }
+    }
+}
+
+impl<T> From<crossbeam::channel::SendError<T>> for Error {
+    /// The receivers of a tracker are gone once all `Client` handles were
+    /// dropped.
+    fn from(_: crossbeam::channel::SendError<T>) -> Self { Error::Disconnected }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn message(code: i32, text: &str) -> TwsMessage {
+        TwsMessage {
+            req_id:                     Some(1),
+            code:                       code.into(),
+            message:                    Some(text.to_string()),
+            advanced_order_reject_json: None,
+            error_time:                 None,
+        }
+    }
+
+    #[test]
+    fn pacing_violations_are_classified() {
+        let violation = message(
+            162,
+            "Historical Market Data Service error message:Pacing violation",
+        );
+        assert!(matches!(Error::from(violation), Error::PacingViolation(_)));
+        assert!(matches!(
+            Error::from(message(
+                100,
+                "Max rate of messages per second has been exceeded"
+            )),
+            Error::PacingViolation(_)
+        ));
+        let error = Error::from(message(162, "HMDS query returned no data"));
+        assert_eq!(
+            "request failed with code 162 (request 1): HMDS query returned no data",
+            error.to_string()
+        );
+    }
+}
@@ -24,7 +24,7 @@ pub enum Error {
     Incomplete,
 
     /// Invalid message encoding
-    Other(crate::Error),
+    Other(Box<dyn std::error::Error + Send + Sync>),
 }
 
 impl Frame {
@@ -46,6 +46,14 @@ impl Frame {
         }
     }
 
+    /// The bytes of the frame in the order they are written to the socket.
+    pub(crate) fn parts(&self) -> Vec<&[u8]> {
+        match self {
+            Frame::Bulk(val) => vec![&val[..]],
+            Frame::Array(val) => val.iter().flat_map(Frame::parts).collect(),
+        }
+    }
+
     // /// Push an "integer" frame into the array. `self` must be an Array frame.
     // ///
     // /// # Panics
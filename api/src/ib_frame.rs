@@ -1,24 +1,33 @@
-use std::{convert::TryInto, io::Cursor, marker::Sized, str::Split, string::FromUtf8Error};
+use std::{convert::TryInto, io::Cursor, marker::Sized, string::FromUtf8Error};
 
-use bytes::Buf;
 use chrono::{DateTime, TimeZone, Timelike, Utc};
 use derive_more::From;
 
-use crate::{account::{AccountData, AccountLastUpdate, Position},
+use crate::{account::{AccountData, AccountLastUpdate, PnL, PnLSingle, Position},
+            api_message::TwsMessage,
             bars::{HistoricalBars, RealtimeBar},
             contract,
-            contract::Contract,
             enums::*,
+            news::{HistoricalNews, NewsArticle, NewsBulletin, NewsProvider, TickNews},
             order::{CommissionReport, Execution, OrderInformation, OrderStatusUpdate},
             prelude::HistoricalSchedule,
+            scanner::ScannerData,
             ticker::{HeadTimestamp,
                      HistoricalTicks,
+                     MarketDepthUpdate,
                      Tick,
                      TickGeneric,
+                     TickOptionComputation,
                      TickPrice,
                      TickSize,
                      TickString},
-            utils::ib_message::{decode, IbDecodeError},
+            utils::ib_message::{decode,
+                                decode_required,
+                                preallocate,
+                                Encodable,
+                                Fields,
+                                IbDecodeError},
+            wsh::WshEventData,
             AccountCode,
             OrderId,
             RequestId,
@@ -35,7 +44,13 @@ pub enum ParseError {
     Incomplete,
 
     #[error("Invalid message encoding")]
-    Other(crate::prelude::Error),
+    Other(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("Unknown message id: '{}'", _0)]
+    UnknownMessageId(String),
+
+    #[error("Invalid value: '{}'", _0)]
+    InvalidValue(String),
 
     #[error("Unexpected variant: {}", _0)]
     UnexpectedVariant(String),
@@ -51,6 +66,9 @@ pub enum ParseError {
 }
 pub type ParseResult<T, E = ParseError> = Result<T, E>;
 
+/// The maximum length of a message, as enforced by TWS.
+const MAX_MSG_LEN: usize = 0x00FF_FFFF;
+
 pub trait FromIbkrFrame {
     /// Converts the command into an equivalent `Frame`.
     ///
@@ -58,7 +76,7 @@ pub trait FromIbkrFrame {
     /// to send to the server.
 
     #[allow(clippy::wrong_self_convention)]
-    fn try_into_frame(msg_id: Incoming, it: Split<&str>) -> ParseResult<IBFrame>
+    fn try_into_frame(msg_id: Incoming, it: Fields<'_>) -> ParseResult<IBFrame>
     where
         Self: Sized;
 }
@@ -73,12 +91,19 @@ pub trait ParseIbkrFrame {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized;
 }
 
+/// The inverse of `ParseIbkrFrame`, used to encode messages as sent by TWS.
+pub trait EncodeIbkrFrame {
+    /// Encodes the fields `ParseIbkrFrame::try_parse_frame` decodes for the
+    /// message `msg_id` of servers with version `server_version`.
+    fn encode_frame(&self, msg_id: Incoming, server_version: ServerVersion) -> String;
+}
+
 // TODO: split into separate structs to implement parse
 #[derive(Debug, strum_macros::Display, From)]
 pub enum IBFrame {
@@ -108,22 +133,44 @@ pub enum IBFrame {
 
     CurrentTime(TimeStamp),
 
-    Error {
-        req_id:  i32,
-        status:  i32,
-        message: Option<String>,
-    },
+    Error(TwsMessage),
 
     Execution(Execution),
 
+    #[from(ignore)]
+    FundamentalData {
+        req_id: RequestId,
+        data:   String,
+    },
+
     HeadTimestamp(HeadTimestamp),
 
     HistoricalBars(HistoricalBars),
 
+    /// the latest bar of a historical data request with `keep_up_to_date`
+    #[from(ignore)]
+    HistoricalBarUpdate(HistoricalBars),
+
     HistoricalSchedule(HistoricalSchedule),
 
     HistoricalTicks(HistoricalTicks),
 
+    MarketDepth(MarketDepthUpdate),
+
+    NewsArticle(NewsArticle),
+
+    NewsBulletin(NewsBulletin),
+
+    NewsProviders(Vec<NewsProvider>),
+
+    HistoricalNews(HistoricalNews),
+
+    #[from(ignore)]
+    HistoricalNewsEnd {
+        req_id:   RequestId,
+        has_more: bool,
+    },
+
     #[from(ignore)]
     NotImplemented,
 
@@ -137,66 +184,128 @@ pub enum IBFrame {
 
     OrderStatus(OrderStatusUpdate),
 
+    PnL(PnL),
+
+    PnLSingle(PnLSingle),
+
     PortfolioValue(Position),
 
+    #[from(ignore)]
+    Position(Position),
+
+    #[from(ignore)]
+    PositionEnd,
+
+    PositionMulti {
+        req_id:   RequestId,
+        position: Position,
+    },
+
+    #[from(ignore)]
+    PositionMultiEnd(RequestId),
+
     RealtimeBar(RealtimeBar),
 
+    TickNews(TickNews),
+
+    #[from(ignore)]
+    WshMetaData {
+        req_id:    RequestId,
+        data_json: String,
+    },
+
+    WshEventData(WshEventData),
+
+    #[from(ignore)]
+    ScannerData {
+        req_id: RequestId,
+        rows:   Vec<ScannerData>,
+    },
+
+    #[from(ignore)]
+    ScannerParameters(String),
+
+    SecDefOptParams {
+        req_id: RequestId,
+        chain:  contract::OptionChain,
+    },
+
+    #[from(ignore)]
+    SecDefOptParamsEnd(RequestId),
+
+    SymbolSamples {
+        req_id:       RequestId,
+        descriptions: contract::ContractDescriptionList,
+    },
+
     ServerVersion {
         server_version:  i32,
         connection_time: String,
     },
 
     Tick(Tick),
+
+    /// the last tick of a market data snapshot was sent
+    #[from(ignore)]
+    TickSnapshotEnd(RequestId),
 }
 
 impl IBFrame {
-    /// Checks if an entire message can be decoded from `src`
-    pub fn check(src: &mut Cursor<&[u8]>) -> ParseResult<()> {
-        if !src.has_remaining() {
-            return Err(ParseError::Incomplete);
+    /// Returns the length of the first message in `src`, including its length
+    /// prefix, `ParseError::Incomplete` until it was received completely.
+    pub fn message_len(src: &[u8]) -> ParseResult<usize> {
+        let msg_size = u32::from_be_bytes(header(src, 0)?) as usize;
+        if msg_size > MAX_MSG_LEN {
+            return Err(ParseError::InvalidValue(format!(
+                "message length {msg_size}"
+            )));
         }
-        if src.get_ref().len() < 4 {
+        if src.len() < msg_size + 4 {
             return Err(ParseError::Incomplete);
         }
+        Ok(msg_size + 4)
+    }
+
+    /// Checks if an entire message can be decoded from `src`
+    pub fn check(src: &mut Cursor<&[u8]>) -> ParseResult<()> {
         let start = src.position() as usize;
-        let headbuf: [u8; 4] = src.get_ref()[0..4].try_into().expect("Incomplete");
-        let msg_size = u32::from_be_bytes(headbuf) as usize;
-        tracing::debug!("message: {:?} with size {:?}", headbuf, msg_size);
-        let end = src.get_ref().len() - 1;
-        tracing::debug!("start: {:?} end: {:?} msg_size: {:?}", start, end, msg_size);
-        if end < (start + msg_size + 3) {
-            tracing::warn!(
-                "incomplete message! expectiong len: {}, msg_size: {}",
-                end,
-                (start + msg_size + 3)
-            );
-            return Err(ParseError::Incomplete);
-        }
-        src.set_position((start + msg_size + 4) as u64);
+        let len = Self::message_len(src.get_ref().get(start..).unwrap_or_default())?;
+        src.set_position((start + len) as u64);
         Ok(())
     }
 
-    #[allow(clippy::cognitive_complexity)]
+    /// Parses the message at the position of `src` and advances it to the
+    /// following message.
     pub fn parse(
         src: &mut Cursor<&[u8]>,
         server_version: Option<ServerVersion>,
     ) -> ParseResult<IBFrame> {
-        let msg = read(src)?;
-        let utf8msg = String::from_utf8_lossy(msg);
-        tracing::debug!("trying to parse message: {:?}", utf8msg);
-        #[allow(clippy::single_char_pattern)]
-        let mut it = utf8msg.split("\0");
-        let msg_id: Incoming = it
-            .next()
-            .unwrap()
+        Self::parse_message(read(src)?, server_version)
+    }
+
+    /// Parses the message `msg`, without its length prefix.
+    ///
+    /// The fields are borrowed from `msg`, only the values of the frame are
+    /// allocated.
+    #[allow(clippy::cognitive_complexity)]
+    pub fn parse_message(
+        msg: &[u8],
+        server_version: Option<ServerVersion>,
+    ) -> ParseResult<IBFrame> {
+        // borrowed unless TWS sent invalid UTF-8
+        let msg = String::from_utf8_lossy(msg);
+        tracing::trace!("trying to parse message: {:?}", msg);
+        let mut it = Fields::new(&msg);
+        let msg_id = it.next().unwrap_or_default();
+        let msg_id: Incoming = msg_id
             .parse()
-            .expect("Could not parse message type.");
-        tracing::debug!("incoming message: {:?}", msg_id);
+            .map_err(|_| ParseError::UnknownMessageId(msg_id.to_string()))?;
+        tracing::trace!("incoming message: {:?}", msg_id);
         match msg_id {
             Incoming::ManagedAccts => {
                 // skip version
                 it.next();
-                let code = decode::<String>(&mut it)?.unwrap();
+                let code = decode_required::<String>(&mut it)?;
                 Ok(code.into())
             },
             Incoming::AccountSummary => {
@@ -217,13 +326,13 @@ impl IBFrame {
 
             Incoming::AcctDownloadEnd => {
                 it.next(); // skip version
-                let code = decode::<String>(&mut it)?.unwrap();
+                let code = decode_required::<String>(&mut it)?;
                 Ok(IBFrame::AccountUpdateEnd(code))
             },
 
             Incoming::AcctUpdateTime => {
                 it.next(); // skip version
-                let time = decode::<String>(&mut it)?.unwrap();
+                let time = decode_required::<String>(&mut it)?;
                 let mut time = time.split(':');
                 if let (Some(hour), Some(minute)) = (
                     time.next().and_then(|h| h.parse().ok()),
@@ -231,40 +340,71 @@ impl IBFrame {
                 ) {
                     let dt = chrono::Local::now()
                         .with_hour(hour)
-                        .unwrap()
-                        .with_minute(minute)
-                        .unwrap()
-                        .with_second(0)
-                        .unwrap()
-                        .with_nanosecond(0)
-                        .unwrap();
+                        .and_then(|dt| dt.with_minute(minute))
+                        .and_then(|dt| dt.with_second(0))
+                        .and_then(|dt| dt.with_nanosecond(0))
+                        .ok_or(ParseError::InvalidValue(format!("{hour}:{minute}")))?;
                     Ok(IBFrame::AccountUpdateTime(DateTime::from(dt)))
                 } else {
                     Err(ParseError::Incomplete)
                 }
             },
             Incoming::PortfolioValue => {
-                let _version: i32 = decode(&mut it)?.unwrap();
-                Ok(IBFrame::PortfolioValue(Position {
-                    contract:       Contract::try_parse_frame(msg_id, server_version, &mut it)?,
-                    position:       decode(&mut it)?,
-                    market_price:   decode(&mut it)?,
-                    market_value:   decode(&mut it)?,
-                    average_cost:   decode(&mut it)?,
-                    unrealized_pnl: decode(&mut it)?,
-                    realized_pnl:   decode(&mut it)?,
-                }))
+                Ok(IBFrame::PortfolioValue(Position::try_parse_frame(
+                    msg_id,
+                    server_version,
+                    &mut it,
+                )?))
+            },
+            Incoming::PnL => {
+                Ok(IBFrame::PnL(PnL::try_parse_frame(
+                    msg_id,
+                    server_version,
+                    &mut it,
+                )?))
+            },
+            Incoming::PnlSingle => {
+                Ok(IBFrame::PnLSingle(PnLSingle::try_parse_frame(
+                    msg_id,
+                    server_version,
+                    &mut it,
+                )?))
+            },
+            Incoming::PositionData => {
+                Ok(IBFrame::Position(Position::try_parse_frame(
+                    msg_id,
+                    server_version,
+                    &mut it,
+                )?))
+            },
+            Incoming::PositionEnd => {
+                it.next(); // skip version
+                Ok(IBFrame::PositionEnd)
+            },
+            Incoming::PositionMulti => {
+                it.next(); // skip version
+                let req_id = decode_required(&mut it)?;
+                Ok(IBFrame::PositionMulti {
+                    req_id,
+                    position: Position::try_parse_frame(msg_id, server_version, &mut it)?,
+                })
+            },
+            Incoming::PositionMultiEnd => {
+                it.next(); // skip version
+                Ok(IBFrame::PositionMultiEnd(decode_required(&mut it)?))
             },
             Incoming::CurrentTime => {
                 it.next(); // skip version
-                let unix_time: i64 = decode(&mut it)?.unwrap();
+                let unix_time: i64 = decode_required(&mut it)?;
                 Ok(IBFrame::CurrentTime(
-                    Utc.timestamp_opt(unix_time, 0).unwrap(),
+                    Utc.timestamp_opt(unix_time, 0)
+                        .single()
+                        .ok_or_else(|| ParseError::InvalidValue(unix_time.to_string()))?,
                 ))
             },
             Incoming::ContractData => {
                 tracing::debug!("decode ContractData");
-                let req_id: usize = decode(&mut it)?.unwrap();
+                let req_id: usize = decode_required(&mut it)?;
                 let details =
                     contract::ContractDetails::try_parse_frame(msg_id, server_version, &mut it)?;
                 Ok(IBFrame::ContractDetails {
@@ -274,11 +414,126 @@ impl IBFrame {
             },
             Incoming::ContractDataEnd => {
                 it.next(); // skip version
-                Ok(IBFrame::ContractDetailsEnd(decode(&mut it)?.unwrap()))
+                Ok(IBFrame::ContractDetailsEnd(decode_required(&mut it)?))
+            },
+            Incoming::ScannerData => {
+                it.next(); // skip version
+                let req_id = decode_required(&mut it)?;
+                let count: usize = decode(&mut it)?.unwrap_or_default();
+                let mut rows = preallocate(count);
+                for _ in 0..count {
+                    rows.push(ScannerData::try_parse_frame(
+                        msg_id,
+                        server_version,
+                        &mut it,
+                    )?);
+                }
+                Ok(IBFrame::ScannerData { req_id, rows })
+            },
+            Incoming::ScannerParameters => {
+                it.next(); // skip version
+                Ok(IBFrame::ScannerParameters(
+                    decode(&mut it)?.unwrap_or_default(),
+                ))
+            },
+            Incoming::SecurityDefinitionOptionParameter => {
+                let req_id = decode_required(&mut it)?;
+                Ok(IBFrame::SecDefOptParams {
+                    req_id,
+                    chain: contract::OptionChain::try_parse_frame(msg_id, server_version, &mut it)?,
+                })
+            },
+            Incoming::SecurityDefinitionOptionParameterEnd => {
+                Ok(IBFrame::SecDefOptParamsEnd(decode_required(&mut it)?))
+            },
+            Incoming::TickSnapshotEnd => {
+                it.next(); // skip version
+                Ok(IBFrame::TickSnapshotEnd(decode_required(&mut it)?))
+            },
+            Incoming::SymbolSamples => {
+                let req_id = decode_required(&mut it)?;
+                let count: usize = decode(&mut it)?.unwrap_or_default();
+                let mut descriptions = preallocate(count);
+                for _ in 0..count {
+                    descriptions.push(contract::ContractDescription::try_parse_frame(
+                        msg_id,
+                        server_version,
+                        &mut it,
+                    )?);
+                }
+                Ok(IBFrame::SymbolSamples {
+                    req_id,
+                    descriptions,
+                })
+            },
+            Incoming::NewsBulletins => {
+                Ok(IBFrame::NewsBulletin(NewsBulletin::try_parse_frame(
+                    msg_id,
+                    server_version,
+                    &mut it,
+                )?))
+            },
+            Incoming::NewsProviders => {
+                let count: usize = decode(&mut it)?.unwrap_or_default();
+                let mut providers = preallocate(count);
+                for _ in 0..count {
+                    providers.push(NewsProvider::try_parse_frame(
+                        msg_id,
+                        server_version,
+                        &mut it,
+                    )?);
+                }
+                Ok(IBFrame::NewsProviders(providers))
+            },
+            Incoming::NewsArticle => {
+                Ok(IBFrame::NewsArticle(NewsArticle::try_parse_frame(
+                    msg_id,
+                    server_version,
+                    &mut it,
+                )?))
+            },
+            Incoming::TickNews => {
+                Ok(IBFrame::TickNews(TickNews::try_parse_frame(
+                    msg_id,
+                    server_version,
+                    &mut it,
+                )?))
+            },
+            Incoming::HistoricalNews => {
+                Ok(IBFrame::HistoricalNews(HistoricalNews::try_parse_frame(
+                    msg_id,
+                    server_version,
+                    &mut it,
+                )?))
+            },
+            Incoming::HistoricalNewsEnd => {
+                Ok(IBFrame::HistoricalNewsEnd {
+                    req_id:   decode_required(&mut it)?,
+                    has_more: decode(&mut it)?.unwrap_or_default(),
+                })
+            },
+            Incoming::FundamentalData => {
+                it.next(); // skip version
+                Ok(IBFrame::FundamentalData {
+                    req_id: decode_required(&mut it)?,
+                    data:   decode(&mut it)?.unwrap_or_default(),
+                })
+            },
+            Incoming::WshMetaData => {
+                Ok(IBFrame::WshMetaData {
+                    req_id:    decode_required(&mut it)?,
+                    data_json: decode(&mut it)?.unwrap_or_default(),
+                })
+            },
+            Incoming::WshEventData => {
+                Ok(IBFrame::WshEventData(WshEventData {
+                    req_id:    decode_required(&mut it)?,
+                    data_json: decode(&mut it)?.unwrap_or_default(),
+                }))
             },
             Incoming::NextValidId => {
                 it.next(); // skip version
-                Ok(IBFrame::OrderId(decode(&mut it)?.unwrap()))
+                Ok(IBFrame::OrderId(decode_required(&mut it)?))
             },
             Incoming::OpenOrderEnd => {
                 tracing::debug!("open order end!");
@@ -349,6 +604,12 @@ impl IBFrame {
                 ))
             },
 
+            Incoming::TickOptionComputation => {
+                Ok(IBFrame::Tick(
+                    TickOptionComputation::try_parse_frame(msg_id, server_version, &mut it)?.into(),
+                ))
+            },
+
             Incoming::TickByTick => {
                 Ok(IBFrame::Tick(Tick::try_parse_frame(
                     msg_id,
@@ -366,11 +627,9 @@ impl IBFrame {
             },
 
             Incoming::HistoricalDataUpdate => {
-                Ok(IBFrame::HistoricalBars(HistoricalBars::try_parse_frame(
-                    msg_id,
-                    server_version,
-                    &mut it,
-                )?))
+                Ok(IBFrame::HistoricalBarUpdate(
+                    HistoricalBars::try_parse_frame(msg_id, server_version, &mut it)?,
+                ))
             },
 
             Incoming::HistoricalSchedule => {
@@ -379,8 +638,8 @@ impl IBFrame {
 
             Incoming::HeadTimestamp => {
                 Ok(IBFrame::HeadTimestamp(HeadTimestamp {
-                    id:        decode(&mut it)?.unwrap(),
-                    timestamp: decode(&mut it)?.unwrap(),
+                    id:        decode_required(&mut it)?,
+                    timestamp: decode_required(&mut it)?,
                 }))
             },
 
@@ -394,26 +653,243 @@ impl IBFrame {
                 )?))
             },
 
+            Incoming::MarketDepth | Incoming::MarketDepthL2 => {
+                Ok(IBFrame::MarketDepth(MarketDepthUpdate::try_parse_frame(
+                    msg_id,
+                    server_version,
+                    &mut it,
+                )?))
+            },
+
             Incoming::ErrMsg => {
-                it.next(); // skip version
-                Ok(IBFrame::Error {
-                    req_id:  decode(&mut it)?.unwrap(),
-                    status:  decode(&mut it)?.unwrap(),
-                    message: decode(&mut it)?,
-                })
+                Ok(IBFrame::Error(TwsMessage::try_parse_frame(
+                    msg_id,
+                    server_version,
+                    &mut it,
+                )?))
             },
             _ => Ok(IBFrame::NotImplemented),
         }
     }
 
-    // TODO resolve unwrap() !!!
+    /// The id of the message the frame is sent with.
+    ///
+    /// Frames parsed from several messages are sent with the most common
+    /// one, e.g. `HistoricalBars` with `HistoricalData`.
+    pub fn msg_id(&self) -> Option<Incoming> {
+        let msg_id = match self {
+            IBFrame::AccountCode(_) => Incoming::ManagedAccts,
+            IBFrame::AccountSummary(_) => Incoming::AccountSummary,
+            IBFrame::AccountUpdateEnd(_) => Incoming::AcctDownloadEnd,
+            IBFrame::AccountUpdateTime(_) => Incoming::AcctUpdateTime,
+            IBFrame::AccountValue(_) => Incoming::AcctValue,
+            IBFrame::CommissionReport(_) => Incoming::CommissionReport,
+            IBFrame::CompletedOrder(_) => Incoming::CompletedOrder,
+            IBFrame::ContractDetails { .. } => Incoming::ContractData,
+            IBFrame::ContractDetailsEnd(_) => Incoming::ContractDataEnd,
+            IBFrame::CurrentTime(_) => Incoming::CurrentTime,
+            IBFrame::Error(_) => Incoming::ErrMsg,
+            IBFrame::Execution(_) => Incoming::ExecutionData,
+            IBFrame::FundamentalData { .. } => Incoming::FundamentalData,
+            IBFrame::HeadTimestamp(_) => Incoming::HeadTimestamp,
+            IBFrame::HistoricalBars(_) => Incoming::HistoricalData,
+            IBFrame::HistoricalBarUpdate(_) => Incoming::HistoricalDataUpdate,
+            IBFrame::HistoricalSchedule(_) => Incoming::HistoricalSchedule,
+            IBFrame::HistoricalTicks(ticks) => ticks.msg_id(),
+            IBFrame::MarketDepth(update) => update.msg_id(),
+            IBFrame::NewsArticle(_) => Incoming::NewsArticle,
+            IBFrame::NewsBulletin(_) => Incoming::NewsBulletins,
+            IBFrame::NewsProviders(_) => Incoming::NewsProviders,
+            IBFrame::HistoricalNews(_) => Incoming::HistoricalNews,
+            IBFrame::HistoricalNewsEnd { .. } => Incoming::HistoricalNewsEnd,
+            IBFrame::OpenOrder(_) => Incoming::OpenOrder,
+            IBFrame::OpenOrderEnd => Incoming::OpenOrderEnd,
+            IBFrame::OrderId(_) => Incoming::NextValidId,
+            IBFrame::OrderStatus(_) => Incoming::OrderStatus,
+            IBFrame::PnL(_) => Incoming::PnL,
+            IBFrame::PnLSingle(_) => Incoming::PnlSingle,
+            IBFrame::PortfolioValue(_) => Incoming::PortfolioValue,
+            IBFrame::Position(_) => Incoming::PositionData,
+            IBFrame::PositionEnd => Incoming::PositionEnd,
+            IBFrame::PositionMulti { .. } => Incoming::PositionMulti,
+            IBFrame::PositionMultiEnd(_) => Incoming::PositionMultiEnd,
+            IBFrame::RealtimeBar(_) => Incoming::RealTimeBars,
+            IBFrame::TickNews(_) => Incoming::TickNews,
+            IBFrame::WshMetaData { .. } => Incoming::WshMetaData,
+            IBFrame::WshEventData(_) => Incoming::WshEventData,
+            IBFrame::ScannerData { .. } => Incoming::ScannerData,
+            IBFrame::ScannerParameters(_) => Incoming::ScannerParameters,
+            IBFrame::SecDefOptParams { .. } => Incoming::SecurityDefinitionOptionParameter,
+            IBFrame::SecDefOptParamsEnd(_) => Incoming::SecurityDefinitionOptionParameterEnd,
+            IBFrame::SymbolSamples { .. } => Incoming::SymbolSamples,
+            IBFrame::Tick(tick) => tick.msg_id(),
+            IBFrame::TickSnapshotEnd(_) => Incoming::TickSnapshotEnd,
+            IBFrame::NotImplemented | IBFrame::ServerVersion { .. } => return None,
+        };
+        Some(msg_id)
+    }
+
+    /// The id of the request the frame answers, `None` for frames not sent
+    /// in reply to a request with an id.
+    pub const fn request_id(&self) -> Option<RequestId> {
+        let req_id = match self {
+            IBFrame::AccountSummary(data) => return data.req_id,
+            IBFrame::Error(msg) => return msg.req_id,
+            IBFrame::ContractDetails { req_id, .. }
+            | IBFrame::FundamentalData { req_id, .. }
+            | IBFrame::HistoricalNewsEnd { req_id, .. }
+            | IBFrame::PositionMulti { req_id, .. }
+            | IBFrame::WshMetaData { req_id, .. }
+            | IBFrame::ScannerData { req_id, .. }
+            | IBFrame::SecDefOptParams { req_id, .. }
+            | IBFrame::SymbolSamples { req_id, .. }
+            | IBFrame::ContractDetailsEnd(req_id)
+            | IBFrame::PositionMultiEnd(req_id)
+            | IBFrame::SecDefOptParamsEnd(req_id)
+            | IBFrame::TickSnapshotEnd(req_id) => *req_id,
+            IBFrame::HeadTimestamp(timestamp) => timestamp.id,
+            IBFrame::HistoricalBars(bars) | IBFrame::HistoricalBarUpdate(bars) => bars.id,
+            IBFrame::HistoricalSchedule(schedule) => schedule.id,
+            IBFrame::HistoricalTicks(ticks) => ticks.id,
+            IBFrame::MarketDepth(update) => update.id,
+            IBFrame::NewsArticle(article) => article.req_id,
+            IBFrame::HistoricalNews(news) => news.req_id,
+            IBFrame::PnL(pnl) => pnl.req_id,
+            IBFrame::PnLSingle(pnl) => pnl.req_id,
+            IBFrame::RealtimeBar(bar) => bar.id,
+            IBFrame::TickNews(news) => news.req_id,
+            IBFrame::WshEventData(data) => data.req_id,
+            IBFrame::Tick(tick) => tick.id(),
+            _ => return None,
+        };
+        Some(req_id)
+    }
+
+    /// Encodes the frame as sent by servers with version `server_version`,
+    /// the inverse of `IBFrame::parse`.
+    ///
+    /// The result lacks the length prefix, see `IBMessage::to_ib_message`.
+    /// `ServerVersion` is encoded as the first message of the handshake.
+    pub fn encode(&self, server_version: ServerVersion) -> ParseResult<String> {
+        if let IBFrame::ServerVersion {
+            server_version,
+            connection_time,
+        } = self
+        {
+            return Ok(server_version.encode() + &connection_time.encode());
+        }
+        let msg_id = self
+            .msg_id()
+            .ok_or_else(|| ParseError::UnexpectedVariant(self.to_string()))?;
+        let sv = server_version;
+        let mut code = msg_id.encode();
+        let fields = match self {
+            IBFrame::AccountCode(code) => "1\0".to_string() + &code.encode(),
+            IBFrame::AccountUpdateEnd(code) => "1\0".to_string() + &code.encode(),
+            IBFrame::AccountUpdateTime(time) => {
+                let time = time.with_timezone(&chrono::Local).format("%H:%M");
+                "1\0".to_string() + &time.to_string().encode()
+            },
+            IBFrame::AccountSummary(data) | IBFrame::AccountValue(data) => {
+                data.encode_frame(msg_id, sv)
+            },
+            IBFrame::CommissionReport(report) => report.encode_frame(msg_id, sv),
+            IBFrame::CompletedOrder(order) | IBFrame::OpenOrder(order) => {
+                order.encode_frame(msg_id, sv)
+            },
+            IBFrame::ContractDetails {
+                req_id,
+                contract_details,
+            } => req_id.encode() + &contract_details.encode_frame(msg_id, sv),
+            IBFrame::ContractDetailsEnd(req_id)
+            | IBFrame::PositionMultiEnd(req_id)
+            | IBFrame::TickSnapshotEnd(req_id) => "1\0".to_string() + &req_id.encode(),
+            IBFrame::CurrentTime(time) => "1\0".to_string() + &time.encode(),
+            IBFrame::Error(message) => message.encode_frame(msg_id, sv),
+            IBFrame::Execution(execution) => execution.encode_frame(msg_id, sv),
+            IBFrame::FundamentalData { req_id, data } => {
+                "1\0".to_string() + &req_id.encode() + &data.encode()
+            },
+            IBFrame::HeadTimestamp(head) => head.id.encode() + &head.timestamp.encode(),
+            IBFrame::HistoricalBars(bars) | IBFrame::HistoricalBarUpdate(bars) => {
+                bars.encode_frame(msg_id, sv)
+            },
+            IBFrame::HistoricalSchedule(schedule) => schedule.encode_frame(msg_id, sv),
+            IBFrame::HistoricalTicks(ticks) => ticks.encode_frame(msg_id, sv),
+            IBFrame::MarketDepth(update) => update.encode_frame(msg_id, sv),
+            IBFrame::NewsArticle(article) => article.encode_frame(msg_id, sv),
+            IBFrame::NewsBulletin(bulletin) => bulletin.encode_frame(msg_id, sv),
+            IBFrame::NewsProviders(providers) => {
+                let mut fields = providers.len().encode();
+                for provider in providers {
+                    fields.push_str(&provider.encode_frame(msg_id, sv));
+                }
+                fields
+            },
+            IBFrame::HistoricalNews(news) => news.encode_frame(msg_id, sv),
+            IBFrame::HistoricalNewsEnd { req_id, has_more } => {
+                req_id.encode() + &has_more.encode()
+            },
+            IBFrame::OpenOrderEnd | IBFrame::PositionEnd => "1\0".to_string(),
+            IBFrame::OrderId(order_id) => "1\0".to_string() + &order_id.encode(),
+            IBFrame::OrderStatus(status) => status.encode_frame(msg_id, sv),
+            IBFrame::PnL(pnl) => pnl.encode_frame(msg_id, sv),
+            IBFrame::PnLSingle(pnl) => pnl.encode_frame(msg_id, sv),
+            IBFrame::PortfolioValue(position) | IBFrame::Position(position) => {
+                position.encode_frame(msg_id, sv)
+            },
+            IBFrame::PositionMulti { req_id, position } => {
+                "1\0".to_string() + &req_id.encode() + &position.encode_frame(msg_id, sv)
+            },
+            IBFrame::RealtimeBar(bar) => bar.encode_frame(msg_id, sv),
+            IBFrame::TickNews(news) => news.encode_frame(msg_id, sv),
+            IBFrame::WshMetaData { req_id, data_json } => req_id.encode() + &data_json.encode(),
+            IBFrame::WshEventData(data) => data.req_id.encode() + &data.data_json.encode(),
+            IBFrame::ScannerData { req_id, rows } => {
+                let mut fields = "3\0".to_string() + &req_id.encode() + &rows.len().encode();
+                for row in rows {
+                    fields.push_str(&row.encode_frame(msg_id, sv));
+                }
+                fields
+            },
+            IBFrame::ScannerParameters(xml) => "1\0".to_string() + &xml.encode(),
+            IBFrame::SecDefOptParams { req_id, chain } => {
+                req_id.encode() + &chain.encode_frame(msg_id, sv)
+            },
+            IBFrame::SecDefOptParamsEnd(req_id) => req_id.encode(),
+            IBFrame::SymbolSamples {
+                req_id,
+                descriptions,
+            } => {
+                let mut fields = req_id.encode() + &descriptions.len().encode();
+                for description in descriptions {
+                    fields.push_str(&description.encode_frame(msg_id, sv));
+                }
+                fields
+            },
+            IBFrame::Tick(tick) => tick.encode_frame(msg_id, sv),
+            IBFrame::NotImplemented | IBFrame::ServerVersion { .. } => {
+                return Err(ParseError::UnexpectedVariant(self.to_string()));
+            },
+        };
+        code.push_str(&fields);
+        Ok(code)
+    }
+
     // TODO use DateTime instead of String
     pub fn parse_server_version(msg: &mut Cursor<&[u8]>) -> ParseResult<IBFrame> {
-        let msg = read(msg)?;
-        let utf8msg = String::from_utf8_lossy(msg);
-        let mut it = utf8msg.split('\0');
-        let server_version = it.next().unwrap().parse().unwrap();
-        let connection_time = it.next().unwrap().parse().unwrap();
+        Self::parse_server_version_message(read(msg)?)
+    }
+
+    /// Parses the first message of TWS `msg`, without its length prefix.
+    pub fn parse_server_version_message(msg: &[u8]) -> ParseResult<IBFrame> {
+        let msg = String::from_utf8_lossy(msg);
+        let mut it = Fields::new(&msg);
+        let server_version = it.next().unwrap_or_default();
+        let server_version = server_version
+            .parse()
+            .map_err(|_| ParseError::InvalidValue(server_version.to_string()))?;
+        let connection_time = it.next().ok_or(ParseError::Incomplete)?.to_string();
         Ok(IBFrame::ServerVersion {
             server_version,
             connection_time,
@@ -424,18 +900,21 @@ impl IBFrame {
 fn read<'a>(src: &mut Cursor<&'a [u8]>) -> ParseResult<&'a [u8]> {
     // Scan the bytes directly
     let start = src.position() as usize;
-    // Scan to the second to last byte
-    let end = src.get_ref().len() - 1;
-    if (end - start) < 3 {
-        return Err(ParseError::Incomplete);
-    }
-
-    let headbuf: [u8; 4] = src.get_ref()[start..start + 4]
-        .try_into()
-        .expect("Incomplete");
+    let headbuf = header(src.get_ref(), start)?;
     let msg_size = u32::from_be_bytes(headbuf) as usize;
+    let msg = src
+        .get_ref()
+        .get(start + 4..start + 4 + msg_size)
+        .ok_or(ParseError::Incomplete)?;
     src.set_position((start + 4 + msg_size) as u64);
-    Ok(&src.get_ref()[start + 4..start + 4 + msg_size])
+    Ok(msg)
+}
+
+/// Returns the length prefix of the message starting at `start`.
+fn header(src: &[u8], start: usize) -> ParseResult<[u8; 4]> {
+    src.get(start..start + 4)
+        .and_then(|header| header.try_into().ok())
+        .ok_or(ParseError::Incomplete)
 }
 
 #[cfg(test)]
@@ -443,6 +922,268 @@ mod tests {
     use std::io::Cursor;
 
     use super::*;
+    use crate::{api_message::{Severity, TwsErrorCode},
+                contract::SecType,
+                order_book::DepthSnapshot,
+                prelude::DepthSide,
+                ticker::TickType,
+                utils::ib_message::IBMessage};
+
+    /// Parses the message with the fields `msg` sent by a server with version
+    /// `server_version`.
+    fn parse(msg: &str, server_version: ServerVersion) -> IBFrame {
+        let msg = msg.to_ib_message().unwrap();
+        IBFrame::parse(&mut Cursor::new(msg.as_slice()), Some(server_version)).unwrap()
+    }
+
+    /// Parses `msg` like `parse` and evaluates `value` with the bindings of
+    /// `frame`, any other frame fails the test.
+    macro_rules! parse_frame {
+        ($msg:expr, $server_version:expr, $frame:pat => $value:expr) => {
+            match parse($msg, $server_version) {
+                $frame => $value,
+                frame => panic!("unexpected frame: {:?}", frame),
+            }
+        };
+    }
+
+    #[test]
+    fn parse_position_data() {
+        let position = parse_frame!(
+            "61\03\0DU123\0265598\0AAPL\0STK\0\00\0?\0\0NASDAQ\0USD\0AAPL\0NMS\0100\0150.25\0",
+            163,
+            IBFrame::Position(position) => position
+        );
+        assert_eq!(Some("DU123".to_string()), position.account);
+        assert_eq!(Some(265598), position.contract.con_id);
+        assert_eq!("AAPL", position.contract.symbol);
+        assert_eq!(Some(rust_decimal_macros::dec!(100)), position.position);
+        assert_eq!(
+            Some(rust_decimal_macros::dec!(150.25)),
+            position.average_cost
+        );
+    }
+
+    #[test]
+    fn parse_pnl_single() {
+        let pnl = parse_frame!(
+            "95\07\0100\012.5\0-3.25\01.75\015025\0",
+            163,
+            IBFrame::PnLSingle(pnl) => pnl
+        );
+        assert_eq!(7, pnl.req_id);
+        assert_eq!(Some(rust_decimal_macros::dec!(100)), pnl.position);
+        assert_eq!(Some(rust_decimal_macros::dec!(12.5)), pnl.daily);
+        assert_eq!(Some(rust_decimal_macros::dec!(-3.25)), pnl.unrealized);
+        assert_eq!(Some(rust_decimal_macros::dec!(1.75)), pnl.realized);
+        assert_eq!(Some(rust_decimal_macros::dec!(15025)), pnl.value);
+    }
+
+    #[test]
+    fn parse_scanner_data() {
+        let msg = [
+            "20\03\05\02\0",
+            "0\0265598\0AAPL\0STK\0\00\0\0SMART\0USD\0AAPL\0NMS\0AAPL\0\0\0\0\0",
+            "1\08314\0IBM\0STK\0\00\0\0SMART\0USD\0IBM\0NMS\0IBM\00.5\0\0\0\0",
+        ]
+        .concat();
+        let (req_id, rows) = parse_frame!(
+            &msg,
+            163,
+            IBFrame::ScannerData { req_id, rows } => (req_id, rows)
+        );
+        assert_eq!(5, req_id);
+        assert_eq!(2, rows.len());
+        assert_eq!(0, rows[0].rank);
+        assert_eq!("AAPL", rows[0].contract_details.contract.symbol);
+        assert_eq!(Some(8314), rows[1].contract_details.contract.con_id);
+        assert_eq!(
+            Some("NMS".to_string()),
+            rows[1].contract_details.market_name
+        );
+        assert_eq!(Some("0.5".to_string()), rows[1].distance);
+    }
+
+    #[test]
+    fn parse_sec_def_opt_params() {
+        let (req_id, chain) = parse_frame!(
+            "75\04\0SMART\0265598\0AAPL\0100\02\020240216\020240119\03\0105\095\0100\0",
+            163,
+            IBFrame::SecDefOptParams { req_id, chain } => (req_id, chain)
+        );
+        assert_eq!(4, req_id);
+        assert_eq!("SMART", chain.exchange);
+        assert_eq!(265598, chain.underlying_con_id);
+        assert_eq!(Some(rust_decimal_macros::dec!(100)), chain.multiplier);
+        assert_eq!(
+            vec![
+                chrono::NaiveDate::from_ymd_opt(2024, 1, 19).unwrap(),
+                chrono::NaiveDate::from_ymd_opt(2024, 2, 16).unwrap(),
+            ],
+            chain.expirations
+        );
+        assert_eq!(3, chain.strikes.len());
+        assert_eq!(rust_decimal_macros::dec!(95), chain.strikes[0]);
+    }
+
+    #[test]
+    fn parse_symbol_samples() {
+        let (req_id, descriptions) = parse_frame!(
+            "79\09\02\0265598\0AAPL\0STK\0NASDAQ\0USD\02\0OPT\0WAR\0\
+             12345\0AAPL\0CFD\0SMART\0USD\00\0",
+            163,
+            IBFrame::SymbolSamples { req_id, descriptions } => (req_id, descriptions)
+        );
+        assert_eq!(9, req_id);
+        assert_eq!(2, descriptions.len());
+        let contract = &descriptions[0].contract;
+        assert_eq!(Some(265598), contract.con_id);
+        assert_eq!(Some("NASDAQ".to_string()), contract.primary_exchange);
+        assert_eq!(SecType::Stock, contract.sec_type);
+        assert_eq!(
+            Some(vec!["OPT".to_string(), "WAR".to_string()]),
+            descriptions[0].derivative_sec_types_list
+        );
+        assert_eq!(Some(12345), descriptions[1].contract.con_id);
+        assert_eq!("AAPL", descriptions[1].contract.symbol);
+        assert_eq!(SecType::Cfd, descriptions[1].contract.sec_type);
+    }
+
+    #[test]
+    fn parse_tick_news() {
+        let news = parse_frame!(
+            "84\01\01700000000123\0BRFG\0BRFG$0a1b2c\0Apple beats estimates\0A:800015\0",
+            163,
+            IBFrame::TickNews(news) => news
+        );
+        assert_eq!(1, news.req_id);
+        assert_eq!(1_700_000_000_123, news.time.timestamp_millis());
+        assert_eq!("BRFG", news.provider_code);
+        assert_eq!("BRFG$0a1b2c", news.article_id);
+        assert_eq!("Apple beats estimates", news.headline);
+        assert_eq!(Some("A:800015".to_string()), news.extra_data);
+    }
+
+    #[test]
+    fn parse_error_message() {
+        let message = parse_frame!(
+            "4\02\0-1\02104\0Market data farm connection is OK:usfarm\0",
+            163,
+            IBFrame::Error(message) => message
+        );
+        assert_eq!(None, message.req_id);
+        assert_eq!(TwsErrorCode::MarketDataFarmOk, message.code);
+        assert_eq!(Severity::Info, message.severity());
+        assert_eq!(
+            Some("Market data farm connection is OK:usfarm".to_string()),
+            message.message
+        );
+        assert_eq!(None, message.advanced_order_reject_json);
+
+        let message = parse_frame!(
+            "4\07\0201\0Order rejected\0{\"rejectReason\":\"x\"}\01700000000123\0",
+            194,
+            IBFrame::Error(message) => message
+        );
+        assert_eq!(Some(7), message.req_id);
+        assert_eq!(TwsErrorCode::OrderRejected, message.code);
+        assert_eq!(
+            Some(r#"{"rejectReason":"x"}"#.to_string()),
+            message.advanced_order_reject_json
+        );
+        assert_eq!(
+            Some(1_700_000_000_123),
+            message.error_time.map(|time| time.timestamp_millis())
+        );
+    }
+
+    #[test]
+    fn parse_historical_news() {
+        let news = parse_frame!(
+            "86\02\02023-11-14 22:13:20.0\0DJNL\0DJNL$1\0Headline\0",
+            163,
+            IBFrame::HistoricalNews(news) => news
+        );
+        assert_eq!(2, news.req_id);
+        assert_eq!(
+            Utc.with_ymd_and_hms(2023, 11, 14, 22, 13, 20).unwrap(),
+            news.time
+        );
+        assert_eq!("DJNL$1", news.article_id);
+        assert!(matches!(
+            parse("87\02\01\0", 163),
+            IBFrame::HistoricalNewsEnd {
+                req_id:   2,
+                has_more: true,
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_market_depth() {
+        // the best ask and bid of a reqMktDepth subscription with smart depth
+        let ask = parse_frame!(
+            "12\01\03\00\00\00\0189.45\0300\0",
+            163,
+            IBFrame::MarketDepth(update) => update
+        );
+        let bid = parse_frame!(
+            "13\01\03\00\0NSDQ\00\01\0189.41\0200\01\0",
+            163,
+            IBFrame::MarketDepth(update) => update
+        );
+        assert_eq!(DepthSide::Ask, ask.side);
+        assert_eq!(DepthSide::Bid, bid.side);
+        assert_eq!(Some("NSDQ".to_string()), bid.market_maker);
+        assert!(bid.is_smart_depth);
+
+        let mut book = DepthSnapshot::default();
+        book.apply(&ask);
+        book.apply(&bid);
+        assert_eq!(1, book.bids.len());
+        assert_eq!(189.41, book.bids[0].price);
+        assert_eq!(1, book.asks.len());
+        assert_eq!(189.45, book.asks[0].price);
+    }
+
+    #[test]
+    fn parse_historical_data_update() {
+        // reqId, barCount, date, open, close, high, low, WAP, volume
+        let bars = parse_frame!(
+            "90\01010\012\01704205800\0137.1\0137.2\0137.3\0137.0\0137.15\0500\0",
+            163,
+            IBFrame::HistoricalBarUpdate(bars) => bars
+        );
+        assert_eq!(1010, bars.id);
+        assert_eq!(1, bars.data.bars.len());
+        let bar = bars.data.bars[0];
+        assert_eq!(1_704_205_800, bar.t_stamp.timestamp());
+        assert_eq!(137.1, bar.open);
+        assert_eq!(137.2, bar.close);
+        assert_eq!(137.3, bar.high);
+        assert_eq!(137.0, bar.low);
+        assert_eq!(137.15, bar.wap);
+        assert_eq!(500.0, bar.volume);
+        assert_eq!(12, bar.count);
+    }
+
+    #[test]
+    fn parse_tick_option_computation() {
+        let tick = parse_frame!(
+            "21\01\013\01\00.25\0-2\01.5\0-1\00.01\00.2\0-0.05\0100\0",
+            163,
+            IBFrame::Tick(Tick::TickOptionComputation(tick)) => tick
+        );
+        assert_eq!(1, tick.id);
+        assert!(matches!(tick.kind, TickType::ModelOption));
+        assert_eq!(Some(1), tick.tick_attrib);
+        assert_eq!(Some(0.25), tick.implied_vol);
+        assert_eq!(None, tick.delta);
+        assert_eq!(Some(1.5), tick.opt_price);
+        assert_eq!(None, tick.pv_dividend);
+        assert_eq!(Some(-0.05), tick.theta);
+        assert_eq!(Some(100.0), tick.und_price);
+    }
 
     #[test]
     #[ignore]
@@ -498,4 +1239,42 @@ mod tests {
         let open_position = IBFrame::parse(&mut buff, Some(183));
         tracing::warn!("parse open position: {:#?}", open_position);
     }
+
+    #[test]
+    fn malformed_messages_do_not_panic() {
+        let payloads = [
+            "",
+            "\0",
+            "1",
+            "1\0",
+            "x\0y\0z\0",
+            "-1\0-1\0-1\0-1\0-1\0",
+            "99\025:99\0",
+            "1\09999999999999999999\0",
+            "1\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\0",
+            "3\01\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+        ];
+        for msg_id in 0..=110 {
+            for payload in payloads {
+                let msg = format!("{msg_id}\0{payload}");
+                let msg = msg.as_str().to_ib_message().unwrap();
+                for len in 0..=msg.len() {
+                    // truncated messages keep their length prefix
+                    let mut buff = Cursor::new(&msg[..len]);
+                    let _ = IBFrame::check(&mut buff);
+                    let mut buff = Cursor::new(&msg[..len]);
+                    let _ = IBFrame::parse(&mut buff, Some(163));
+                }
+            }
+        }
+        // an unknown message id is an error
+        let msg = "9999\0".to_ib_message().unwrap();
+        let mut buff = Cursor::new(msg.as_slice());
+        assert!(matches!(
+            IBFrame::parse(&mut buff, Some(163)),
+            Err(ParseError::UnknownMessageId(_))
+        ));
+        let mut buff = Cursor::new(&[0xff_u8, 0xff, 0xff, 0xff, 0][..]);
+        assert!(IBFrame::check(&mut buff).is_err());
+    }
 }
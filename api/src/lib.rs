@@ -30,16 +30,27 @@ pub mod api_message;
 pub mod bars;
 pub mod client;
 pub mod cmd;
+pub mod codec;
 pub mod contract;
 pub mod enums;
+mod error;
 mod frame;
 mod ib_frame;
+#[cfg(any(test, feature = "mock_tws"))]
+pub mod mock_tws;
+pub mod news;
 pub mod order;
+pub mod order_book;
+pub mod pacing;
 mod reader;
+pub mod recorder;
+pub mod scanner;
+pub mod server_versions;
 mod shutdown;
 pub mod ticker;
 mod utils;
 mod writer;
+pub mod wsh;
 
 pub type ClientId = i32;
 pub type ServerVersion = i32;
@@ -52,26 +63,17 @@ pub mod prelude {
     #[doc(hidden)]
     pub use crate::{
         account::*, account_summary_tags::*, api_message::*, bars::*, client::*, cmd::*,
-        contract::*, enums::*, order::*, ticker::*, utils::*, AccountCode, ClientId, Error,
-        OrderId, RequestId, Result, TimeStamp,
+        contract::*, enums::*, news::*, order::*, order_book::*, pacing::*, scanner::*,
+        server_versions::MinServerVersion, ticker::*, utils::*, wsh::*, AccountCode, ClientId,
+        Error, OrderId, RequestId, Result, TimeStamp,
     };
 }
 // use parse::{Parse, ParseError};
 
-/// Error returned by most functions.
-///
-/// When writing a real application, one might want to consider a specialized
-/// error handling crate or defining an error type as an `enum` of causes.
-/// However, for our example, using a boxed `std::error::Error` is sufficient.
-///
-/// For performance reasons, boxing is avoided in any hot path. For example, in
-/// `parse`, a custom error `enum` is defined. This is because the error is hit
-/// and handled during normal execution when a partial frame is received on a
-/// socket. `std::error::Error` is implemented for `parse::Error` which allows
-/// it to be converted to `Box<dyn std::error::Error>`.
-pub type Error = Box<dyn std::error::Error + Send + Sync>;
+pub use error::Error;
+pub use ib_frame::{IBFrame, ParseError};
 
-/// A specialized `Result` type for mini-redis operations.
+/// A specialized `Result` type for the operations of the crate.
 ///
 /// This is defined as a convenience.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,475 @@
+//! In-process mock of TWS / IB Gateway for offline tests.
+//!
+//! [`MockTws`] listens on a local port, performs the `API\0` + version
+//! handshake and answers `StartApi` with `NextValidId` and `ManagedAccts`.
+//! The responses to all other requests are scripted per outgoing message id
+//! with [`MockTws::on`], requests without a script are only recorded.
+//!
+//! ```no_run
+//! # async fn example() -> ibkr_rust_api::Result<()> {
+//! use ibkr_rust_api::{client,
+//!                     enums::Outgoing,
+//!                     mock_tws::{Message, MockTws}};
+//!
+//! let tws = MockTws::start().await?;
+//! tws.on(Outgoing::ReqMktData, |request| {
+//!     let req_id = request.field(1).unwrap_or_default();
+//!     vec![Message::tick_price(req_id.parse().unwrap(), 4, 101.5, 100)]
+//! });
+//! let client = client::connect(tws.addr(), 1).await?;
+//! # drop(client);
+//! # Ok(())
+//! # }
+//! ```
+use std::{collections::HashMap,
+          io,
+          net::{Ipv4Addr, SocketAddr},
+          sync::{Arc, Mutex, MutexGuard, PoisonError}};
+
+use tokio::{io::{AsyncReadExt, AsyncWriteExt},
+            net::{TcpListener, TcpStream},
+            sync::mpsc,
+            task::JoinHandle};
+use tracing::{debug, warn};
+
+use crate::{enums::{constants::MAX_CLIENT_VER, Incoming, Outgoing},
+            utils::ib_message::IBMessage,
+            AccountCode,
+            OrderId,
+            RequestId,
+            ServerVersion};
+
+/// A request received by the mock, split into its fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    /// the outgoing message id of the request
+    pub msg_id: i32,
+    /// the fields following the message id
+    pub fields: Vec<String>,
+}
+
+impl Request {
+    /// Returns `true` if the request has the message id of `msg_id`.
+    pub const fn is(&self, msg_id: Outgoing) -> bool { self.msg_id == msg_id as i32 }
+
+    /// Returns the field at `index`, counting from the field after the
+    /// message id, e.g. the version of most requests.
+    pub fn field(&self, index: usize) -> Option<&str> { self.fields.get(index).map(String::as_str) }
+}
+
+/// A message sent by the mock, encoded for server versions up to
+/// `MAX_CLIENT_VER`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    fields: Vec<String>,
+}
+
+impl Message {
+    /// Starts a message with the incoming message id `msg_id`.
+    pub fn new(msg_id: Incoming) -> Self {
+        Message {
+            fields: vec![(msg_id as i32).to_string()],
+        }
+    }
+
+    /// Appends a field, unset values are sent as empty fields "".
+    #[must_use]
+    pub fn field(mut self, value: impl ToString) -> Self {
+        self.fields.push(value.to_string());
+        self
+    }
+
+    /// Appends all `values` as fields.
+    #[must_use]
+    pub fn fields<T: ToString>(mut self, values: impl IntoIterator<Item = T>) -> Self {
+        self.fields
+            .extend(values.into_iter().map(|value| value.to_string()));
+        self
+    }
+
+    pub fn next_valid_id(order_id: OrderId) -> Self {
+        Message::new(Incoming::NextValidId).field(1).field(order_id)
+    }
+
+    pub fn managed_accounts(accounts: &[AccountCode]) -> Self {
+        Message::new(Incoming::ManagedAccts)
+            .field(1)
+            .field(accounts.join(","))
+    }
+
+    /// An error message of TWS, `req_id` is `None` for messages not related
+    /// to a request.
+    pub fn error(req_id: Option<RequestId>, code: i32, message: &str) -> Self {
+        let req_id = req_id.map_or_else(|| "-1".to_string(), |req_id| req_id.to_string());
+        Message::new(Incoming::ErrMsg)
+            .field(2)
+            .field(req_id)
+            .field(code)
+            .field(message)
+    }
+
+    /// A price tick of the type `tick_type`, e.g. 4 for the last price.
+    pub fn tick_price(req_id: RequestId, tick_type: i32, price: f64, size: i64) -> Self {
+        Message::new(Incoming::TickPrice)
+            .field(6)
+            .field(req_id)
+            .field(tick_type)
+            .field(price)
+            .field(size)
+            .field(0)
+    }
+
+    /// A size tick of the type `tick_type`, e.g. 5 for the last size.
+    pub fn tick_size(req_id: RequestId, tick_type: i32, size: i64) -> Self {
+        Message::new(Incoming::TickSize)
+            .field(6)
+            .field(req_id)
+            .field(tick_type)
+            .field(size)
+    }
+
+    /// The status of the order `order_id`, e.g. "Submitted" or "Filled".
+    pub fn order_status(
+        order_id: OrderId,
+        status: &str,
+        filled: f64,
+        remaining: f64,
+        avg_fill_price: f64,
+    ) -> Self {
+        Message::new(Incoming::OrderStatus)
+            .field(order_id)
+            .field(status)
+            .field(filled)
+            .field(remaining)
+            .field(avg_fill_price)
+            .fields([0, 0])
+            .field(avg_fill_price)
+            .field(0)
+            .field("")
+    }
+
+    pub fn contract_data_end(req_id: RequestId) -> Self {
+        Message::new(Incoming::ContractDataEnd)
+            .field(1)
+            .field(req_id)
+    }
+
+    /// Encodes the message with its length prefix.
+    fn encode(&self) -> Vec<u8> {
+        let msg = self
+            .fields
+            .iter()
+            .map(|field| format!("{field}\0"))
+            .collect::<String>();
+        // the fields of a test message are far below 4 GB
+        msg.as_str().to_ib_message().unwrap_or_default()
+    }
+}
+
+type Responder = Box<dyn FnMut(&Request) -> Vec<Message> + Send>;
+
+struct State {
+    server_version:   ServerVersion,
+    connection_time:  String,
+    next_order_id:    OrderId,
+    managed_accounts: Vec<AccountCode>,
+    responders:       HashMap<i32, Responder>,
+    requests:         Vec<Request>,
+    /// the senders of the messages pushed to the open connections
+    connections:      Vec<mpsc::UnboundedSender<Message>>,
+    tasks:            Vec<JoinHandle<()>>,
+}
+
+/// Mock of TWS / IB Gateway listening on a local port.
+///
+/// The mock accepts any number of connections, the scripted responses are
+/// shared by all of them. Dropping the mock closes the listener and all open
+/// connections.
+pub struct MockTws {
+    addr:     SocketAddr,
+    state:    Arc<Mutex<State>>,
+    listener: JoinHandle<()>,
+}
+
+impl std::fmt::Debug for MockTws {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockTws").field("addr", &self.addr).finish()
+    }
+}
+
+impl MockTws {
+    /// Starts listening on a free port of the loopback interface.
+    ///
+    /// The mock answers with the server version `MAX_CLIENT_VER`, the next
+    /// valid order id 1 and the account "DU1234567".
+    pub async fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State {
+            server_version:   MAX_CLIENT_VER,
+            connection_time:  "20240102 09:30:00 EST".to_string(),
+            next_order_id:    1,
+            managed_accounts: vec!["DU1234567".to_string()],
+            responders:       HashMap::new(),
+            requests:         Vec::new(),
+            connections:      Vec::new(),
+            tasks:            Vec::new(),
+        }));
+        let accept_state = Arc::clone(&state);
+        let listener = tokio::spawn(async move {
+            while let Ok((socket, peer)) = listener.accept().await {
+                debug!("mock TWS accepted {}", peer);
+                let (sender, receiver) = mpsc::unbounded_channel();
+                let state = Arc::clone(&accept_state);
+                let task = tokio::spawn(async move {
+                    if let Err(err) = serve(socket, &state, receiver).await {
+                        warn!(cause = ?err, "mock TWS connection failed");
+                    }
+                });
+                let mut state = lock(&accept_state);
+                state.connections.push(sender);
+                state.tasks.push(task);
+            }
+        });
+        Ok(MockTws {
+            addr,
+            state,
+            listener,
+        })
+    }
+
+    /// The address the mock listens on.
+    pub const fn addr(&self) -> SocketAddr { self.addr }
+
+    /// Sets the server version answered in the handshake of the following
+    /// connections, capped at the maximum version offered by the client.
+    pub fn set_server_version(&self, server_version: ServerVersion) {
+        lock(&self.state).server_version = server_version;
+    }
+
+    /// Sets the next valid order id sent after the API was started and in
+    /// answer to `ReqIds`.
+    pub fn set_next_order_id(&self, order_id: OrderId) {
+        lock(&self.state).next_order_id = order_id;
+    }
+
+    /// Sets the accounts sent after the API was started.
+    pub fn set_managed_accounts(&self, accounts: &[&str]) {
+        lock(&self.state).managed_accounts = accounts.iter().map(ToString::to_string).collect();
+    }
+
+    /// Answers every request with the message id `msg_id` with the messages
+    /// returned by `responder`, replacing a previous script of `msg_id`.
+    pub fn on<F>(&self, msg_id: Outgoing, responder: F)
+    where
+        F: FnMut(&Request) -> Vec<Message> + Send + 'static,
+    {
+        lock(&self.state)
+            .responders
+            .insert(msg_id as i32, Box::new(responder));
+    }
+
+    /// Sends `message` to all open connections, e.g. ticks of a running
+    /// subscription.
+    pub fn push(&self, message: &Message) {
+        lock(&self.state)
+            .connections
+            .retain(|sender| sender.send(message.clone()).is_ok());
+    }
+
+    /// Returns the requests received so far, without the handshake.
+    pub fn requests(&self) -> Vec<Request> { lock(&self.state).requests.clone() }
+
+    /// Closes all open connections, e.g. to test reconnects.
+    pub fn drop_connections(&self) {
+        let mut state = lock(&self.state);
+        state.connections.clear();
+        for task in state.tasks.drain(..) {
+            task.abort();
+        }
+    }
+}
+
+impl Drop for MockTws {
+    fn drop(&mut self) {
+        self.listener.abort();
+        self.drop_connections();
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Reads a length prefixed message, `None` if the peer closed the socket.
+async fn read_message(socket: &mut TcpStream) -> io::Result<Option<Vec<String>>> {
+    let mut len = [0_u8; 4];
+    match socket.read_exact(&mut len).await {
+        Ok(_) => {},
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let mut body = vec![0_u8; u32::from_be_bytes(len) as usize];
+    socket.read_exact(&mut body).await?;
+    let body = String::from_utf8_lossy(&body);
+    let mut fields = body
+        .split('\0')
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    // every field is terminated by a NUL
+    if fields.last().map(String::is_empty) == Some(true) {
+        fields.pop();
+    }
+    Ok(Some(fields))
+}
+
+/// Performs the handshake and answers the requests of a single connection.
+async fn serve(
+    mut socket: TcpStream,
+    state: &Mutex<State>,
+    mut pushed: mpsc::UnboundedReceiver<Message>,
+) -> io::Result<()> {
+    let mut prefix = [0_u8; 4];
+    socket.read_exact(&mut prefix).await?;
+    if &prefix != b"API\0" {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "expected the API prefix",
+        ));
+    }
+    let versions = read_message(&mut socket)
+        .await?
+        .unwrap_or_default()
+        .concat();
+    let max_version = versions
+        .split(' ')
+        .next()
+        .and_then(|range| range.rsplit("..").next())
+        .and_then(|max| max.trim_start_matches('v').parse::<ServerVersion>().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid client versions {versions:?}"),
+            )
+        })?;
+    let handshake = {
+        let state = lock(state);
+        Message {
+            fields: vec![
+                state.server_version.min(max_version).to_string(),
+                state.connection_time.clone(),
+            ],
+        }
+    };
+    socket.write_all(&handshake.encode()).await?;
+
+    loop {
+        tokio::select! {
+            request = read_message(&mut socket) => {
+                let mut fields = match request? {
+                    Some(fields) if !fields.is_empty() => fields,
+                    Some(_) => continue,
+                    None => return Ok(()),
+                };
+                let msg_id = fields.remove(0).parse().unwrap_or_default();
+                let request = Request { msg_id, fields };
+                debug!(?request, "mock TWS received");
+                for message in respond(state, request) {
+                    socket.write_all(&message.encode()).await?;
+                }
+            },
+            Some(message) = pushed.recv() => socket.write_all(&message.encode()).await?,
+        }
+    }
+}
+
+/// Records `request` and returns its scripted or default responses.
+fn respond(state: &Mutex<State>, request: Request) -> Vec<Message> {
+    let mut state = lock(state);
+    let mut messages = Vec::new();
+    if request.is(Outgoing::StartApi) {
+        messages.push(Message::next_valid_id(state.next_order_id));
+        messages.push(Message::managed_accounts(&state.managed_accounts));
+    }
+    match state.responders.get_mut(&request.msg_id) {
+        Some(responder) => messages.extend(responder(&request)),
+        None if request.is(Outgoing::ReqIds) => {
+            messages.push(Message::next_valid_id(state.next_order_id));
+        },
+        None => {},
+    }
+    state.requests.push(request);
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{client, cmd::MarketDataRequest, contract::Contract, ticker::Tick};
+
+    #[tokio::test]
+    async fn handshake_and_scripted_responses() {
+        let tws = MockTws::start().await.unwrap();
+        tws.set_managed_accounts(&["DU1", "DU2"]);
+        tws.set_next_order_id(42);
+        tws.on(Outgoing::ReqContractData, |request| {
+            let req_id = request.field(1).unwrap_or_default().parse().unwrap();
+            vec![Message::error(Some(req_id), 200, "No security definition")]
+        });
+
+        let client = client::connect(tws.addr(), 7).await.unwrap();
+        assert_eq!(MAX_CLIENT_VER, client.server_version());
+        assert_eq!("20240102 09:30:00 EST", client.connection_time());
+        assert_eq!(
+            vec!["DU1".to_string(), "DU2".to_string()],
+            client.managed_accounts().await.unwrap()
+        );
+        assert_eq!(42, client.get_next_valid_order_id().await.unwrap());
+
+        let details = client.get_contract_details(3, Contract::default()).await;
+        futures::pin_mut!(details);
+        let error = details.next().await.unwrap();
+        assert_eq!(
+            "request failed with code 200 (request 3): No security definition",
+            error.unwrap_err().to_string()
+        );
+
+        let requests = tws.requests();
+        assert!(requests[0].is(Outgoing::StartApi));
+        assert_eq!(Some("7"), requests[0].field(1));
+        assert!(requests
+            .iter()
+            .any(|request| request.is(Outgoing::ReqContractData)));
+    }
+
+    #[tokio::test]
+    async fn pushed_messages_reach_the_subscription() {
+        let tws = MockTws::start().await.unwrap();
+        tws.set_server_version(151);
+        tws.on(Outgoing::ReqMktData, |request| {
+            let req_id = request.field(1).unwrap_or_default().parse().unwrap();
+            vec![Message::tick_price(req_id, 4, 101.5, 100)]
+        });
+        let client = client::connect(tws.addr(), 1).await.unwrap();
+        assert_eq!(151, client.server_version());
+
+        let request = MarketDataRequest {
+            req_id:            5,
+            contract:          Contract::default(),
+            generic_tick_list: vec![],
+            snapshot:          false,
+            regulatory:        false,
+            additional_data:   vec![],
+        };
+        let mut ticks = client.request_market_data(&request).await.unwrap();
+        let tick = ticks.next().await.unwrap().unwrap();
+        assert!(matches!(tick, Tick::Price(price) if price.id == 5));
+
+        tws.push(&Message::tick_size(5, 8, 1200));
+        let tick = ticks.next().await.unwrap().unwrap();
+        assert!(matches!(tick, Tick::Size(size) if size.id == 5));
+    }
+}
@@ -0,0 +1,352 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use crossbeam::channel::{unbounded, Receiver, Sender};
+
+use crate::{ib_frame::{EncodeIbkrFrame, ParseError, ParseIbkrFrame, ParseResult},
+            prelude::{ib_message::{decode,
+                                   decode_required,
+                                   Decodable,
+                                   Encodable,
+                                   Fields,
+                                   IbDecodeError},
+                      Incoming,
+                      ParseEnumError},
+            RequestId,
+            ServerVersion,
+            TimeStamp};
+
+#[derive(Debug, Clone)]
+pub struct NewsTracker {
+    /// headlines of market data subscriptions with generic tick 292
+    pub tick_news: Receiver<TickNews>,
+    /// bulletins requested with `request_news_bulletins`
+    pub bulletins: Receiver<NewsBulletin>,
+}
+#[derive(Debug)]
+pub(crate) struct NewsTrackerSender {
+    pub tick_news_tx: Sender<TickNews>,
+    pub bulletins_tx: Sender<NewsBulletin>,
+}
+impl NewsTracker {
+    pub(crate) fn new() -> (NewsTrackerSender, Self) {
+        let (tick_news_tx, tick_news) = unbounded();
+        let (bulletins_tx, bulletins) = unbounded();
+        (
+            NewsTrackerSender {
+                tick_news_tx,
+                bulletins_tx,
+            },
+            NewsTracker {
+                tick_news,
+                bulletins,
+            },
+        )
+    }
+}
+
+/// A news provider the user is subscribed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewsProvider {
+    pub code: String,
+    pub name: String,
+}
+
+impl ParseIbkrFrame for NewsProvider {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        _server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::NewsProviders) {
+            tracing::error!("Unexpected Message (NewsProviders): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        Ok(Self {
+            code: decode(it)?.unwrap_or_default(),
+            name: decode(it)?.unwrap_or_default(),
+        })
+    }
+}
+
+impl EncodeIbkrFrame for NewsProvider {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = self.code.encode();
+        code.push_str(&self.name.encode());
+        code
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewsBulletinType {
+    /// Regular news bulletin
+    Regular,
+    /// Exchange no longer available for trading
+    ExchangeUnavailable,
+    /// Exchange is available for trading
+    ExchangeAvailable,
+}
+
+impl FromStr for NewsBulletinType {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(NewsBulletinType::Regular),
+            "2" => Ok(NewsBulletinType::ExchangeUnavailable),
+            "3" => Ok(NewsBulletinType::ExchangeAvailable),
+            &_ => Err(ParseEnumError),
+        }
+    }
+}
+impl Decodable for NewsBulletinType {}
+
+impl Encodable for NewsBulletinType {
+    fn encode(&self) -> String {
+        match self {
+            NewsBulletinType::Regular => "1\0",
+            NewsBulletinType::ExchangeUnavailable => "2\0",
+            NewsBulletinType::ExchangeAvailable => "3\0",
+        }
+        .to_string()
+    }
+}
+
+/// IB news bulletin, see `Client::request_news_bulletins`.
+#[derive(Debug, Clone)]
+pub struct NewsBulletin {
+    pub msg_id:          i32,
+    pub msg_type:        NewsBulletinType,
+    pub message:         String,
+    /// exchange the bulletin originates from
+    pub origin_exchange: String,
+}
+
+impl ParseIbkrFrame for NewsBulletin {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        _server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::NewsBulletins) {
+            tracing::error!("Unexpected Message (NewsBulletins): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        it.next(); // skip version
+        Ok(Self {
+            msg_id:          decode(it)?.unwrap_or_default(),
+            msg_type:        decode(it)?.unwrap_or(NewsBulletinType::Regular),
+            message:         decode(it)?.unwrap_or_default(),
+            origin_exchange: decode(it)?.unwrap_or_default(),
+        })
+    }
+}
+
+impl EncodeIbkrFrame for NewsBulletin {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = "1\0".to_string(); // version
+        code.push_str(&self.msg_id.encode());
+        code.push_str(&self.msg_type.encode());
+        code.push_str(&self.message.encode());
+        code.push_str(&self.origin_exchange.encode());
+        code
+    }
+}
+
+/// News headline of a market data subscription (generic tick 292).
+#[derive(Debug, Clone)]
+pub struct TickNews {
+    pub req_id:        RequestId,
+    pub time:          TimeStamp,
+    pub provider_code: String,
+    pub article_id:    String,
+    pub headline:      String,
+    pub extra_data:    Option<String>,
+}
+
+impl ParseIbkrFrame for TickNews {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        _server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::TickNews) {
+            tracing::error!("Unexpected Message (TickNews): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        let req_id = decode_required(it)?;
+        let millis: i64 = decode(it)?.unwrap_or_default();
+        Ok(Self {
+            req_id,
+            time: Utc
+                .timestamp_millis_opt(millis)
+                .single()
+                .ok_or_else(|| ParseError::InvalidValue(millis.to_string()))?,
+            provider_code: decode(it)?.unwrap_or_default(),
+            article_id: decode(it)?.unwrap_or_default(),
+            headline: decode(it)?.unwrap_or_default(),
+            extra_data: decode(it)?,
+        })
+    }
+}
+
+impl EncodeIbkrFrame for TickNews {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = self.req_id.encode();
+        code.push_str(&self.time.timestamp_millis().encode());
+        code.push_str(&self.provider_code.encode());
+        code.push_str(&self.article_id.encode());
+        code.push_str(&self.headline.encode());
+        code.push_str(&self.extra_data.encode());
+        code
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewsArticleType {
+    /// plain text or html
+    Text,
+    /// binary data, e.g. a pdf, encoded as base64
+    Binary,
+}
+
+impl FromStr for NewsArticleType {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(NewsArticleType::Text),
+            "1" => Ok(NewsArticleType::Binary),
+            &_ => Err(ParseEnumError),
+        }
+    }
+}
+impl Decodable for NewsArticleType {}
+
+impl Encodable for NewsArticleType {
+    fn encode(&self) -> String {
+        match self {
+            NewsArticleType::Text => "0\0",
+            NewsArticleType::Binary => "1\0",
+        }
+        .to_string()
+    }
+}
+
+/// Body of a news article, see `Client::request_news_article`.
+#[derive(Debug, Clone)]
+pub struct NewsArticle {
+    pub req_id:       RequestId,
+    pub article_type: NewsArticleType,
+    pub article_text: String,
+}
+
+impl ParseIbkrFrame for NewsArticle {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        _server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::NewsArticle) {
+            tracing::error!("Unexpected Message (NewsArticle): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        Ok(Self {
+            req_id:       decode_required(it)?,
+            article_type: decode(it)?.unwrap_or(NewsArticleType::Text),
+            article_text: decode(it)?.unwrap_or_default(),
+        })
+    }
+}
+
+impl EncodeIbkrFrame for NewsArticle {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = self.req_id.encode();
+        code.push_str(&self.article_type.encode());
+        code.push_str(&self.article_text.encode());
+        code
+    }
+}
+
+/// A single historical news headline.
+#[derive(Debug, Clone)]
+pub struct HistoricalNews {
+    pub req_id:        RequestId,
+    pub time:          TimeStamp,
+    pub provider_code: String,
+    pub article_id:    String,
+    pub headline:      String,
+}
+
+impl ParseIbkrFrame for HistoricalNews {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        _server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::HistoricalNews) {
+            tracing::error!("Unexpected Message (HistoricalNews): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        let req_id = decode_required(it)?;
+        // the time is sent as "yyyy-MM-dd HH:mm:ss.0" in UTC
+        let time: String = decode(it)?.unwrap_or_default();
+        let time = NaiveDateTime::parse_from_str(&time, "%Y-%m-%d %H:%M:%S%.f")
+            .map_err(|err| IbDecodeError::UnknownDateTime(err.into(), time.as_str().into()))?;
+        Ok(Self {
+            req_id,
+            time: Utc.from_utc_datetime(&time),
+            provider_code: decode(it)?.unwrap_or_default(),
+            article_id: decode(it)?.unwrap_or_default(),
+            headline: decode(it)?.unwrap_or_default(),
+        })
+    }
+}
+
+impl EncodeIbkrFrame for HistoricalNews {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = self.req_id.encode();
+        code.push_str(
+            &self
+                .time
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string()
+                .encode(),
+        );
+        code.push_str(&self.provider_code.encode());
+        code.push_str(&self.article_id.encode());
+        code.push_str(&self.headline.encode());
+        code
+    }
+}
+
+/// Result of `Client::request_historical_news`.
+#[derive(Debug, Clone, Default)]
+pub struct HistoricalNewsHeadlines {
+    pub headlines: Vec<HistoricalNews>,
+    /// `true` if more headlines than the requested `total_results` are
+    /// available
+    pub has_more:  bool,
+}
+
+/// Headlines of a historical news request as delivered by TWS.
+#[derive(Debug)]
+pub enum HistoricalNewsUpdate {
+    Headline(HistoricalNews),
+    End { has_more: bool },
+}
@@ -1,5 +1,3 @@
-use std::str::Split;
-
 use crossbeam::channel::{unbounded, Receiver, Sender};
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
@@ -8,10 +6,15 @@ use serde::{Deserialize, Serialize};
 
 use crate::{contract::{ComboLeg, Contract, DeltaNeutralContract, SecType},
             enums::*,
-            ib_frame::{ParseError, ParseIbkrFrame, ParseResult},
-            prelude::{ib_message::{decode, Decodable},
+            ib_frame::{EncodeIbkrFrame, ParseError, ParseIbkrFrame, ParseResult},
+            prelude::{ib_message::{decode,
+                                   decode_required,
+                                   decode_tag_values,
+                                   preallocate,
+                                   Decodable},
                       UsePriceMgmtAlgo},
-            utils::ib_message::Encodable,
+            server_versions::MinServerVersion,
+            utils::ib_message::{Encodable, Fields},
             AccountCode,
             ClientId,
             OrderId,
@@ -312,8 +315,64 @@ impl Order {
     }
 }
 
-impl Encodable for Order {
-    fn encode(&self) -> String {
+impl Order {
+    /// Fails with `Error::Unsupported` if the order uses a feature servers
+    /// with version `server_version` do not support.
+    fn check_server_version(&self, server_version: ServerVersion) -> crate::Result<()> {
+        let features = [
+            (
+                !self.total_qty.fract().is_zero(),
+                MinServerVersion::FractionalPositions,
+            ),
+            (
+                self.order_type == OrderType::PeggedToBenchmark
+                    || matches!(&self.conditions, Some(conds) if !conds.is_empty())
+                    || self.adjusted_order_type.is_some(),
+                MinServerVersion::PeggedToBenchmark,
+            ),
+            (self.model_code.is_some(), MinServerVersion::ModelsSupport),
+            (self.ext_operator.is_some(), MinServerVersion::ExtOperator),
+            (
+                self.soft_dollar_tier.is_some(),
+                MinServerVersion::SoftDollarTier,
+            ),
+            (self.cash_qty.is_some(), MinServerVersion::CashQty),
+            (
+                self.mifid_2_decision_maker.is_some() || self.mifid_2_decision_algo.is_some(),
+                MinServerVersion::DecisionMaker,
+            ),
+            (
+                self.mifid_2_execution_trader.is_some() || self.mifid_2_execution_algo.is_some(),
+                MinServerVersion::MifidExecution,
+            ),
+            (
+                self.dont_use_auto_price_for_hedge,
+                MinServerVersion::AutoPriceForHedge,
+            ),
+            (self.is_oms_container, MinServerVersion::OrderContainer),
+            (
+                self.discretionary_up_to_limit_price,
+                MinServerVersion::DPegOrders,
+            ),
+            (
+                self.use_price_mgmt_algo.is_some(),
+                MinServerVersion::PriceMgmtAlgo,
+            ),
+            (self.auto_cancel_parent, MinServerVersion::AutoCancelParent),
+        ];
+        for &(used, feature) in &features {
+            if used {
+                feature.check(server_version)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the order as expected by servers with version
+    /// `server_version`.
+    pub(crate) fn encode(&self, server_version: ServerVersion) -> crate::Result<String> {
+        self.check_server_version(server_version)?;
+        let supports = |feature: MinServerVersion| feature.is_supported(server_version);
         let mut code = String::new();
         code.push_str(&self.contract.encode_for_order());
         code.push_str(&self.action.encode());
@@ -381,8 +440,12 @@ impl Encodable for Order {
         code.push_str(&self.fa_group.encode());
         code.push_str(&self.fa_method.encode());
         code.push_str(&self.fa_percentage.encode());
-        code.push_str(&self.fa_profile.encode());
-        code.push_str(&self.model_code.encode());
+        if !supports(MinServerVersion::FaProfileDesupport) {
+            code.push_str(&self.fa_profile.encode());
+        }
+        if supports(MinServerVersion::ModelsSupport) {
+            code.push_str(&self.model_code.encode());
+        }
         code.push_str(&self.short_sale_slot.encode());
         code.push_str(&self.designated_location.encode());
         code.push_str(&self.exempt_code.encode());
@@ -473,54 +536,325 @@ impl Encodable for Order {
         code.push_str(&self.randomize_size.encode());
         code.push_str(&self.randomize_price.encode());
 
-        if self.order_type == OrderType::PeggedToBenchmark {
-            code.push_str(&self.reference_contract_id.encode());
-            code.push_str(&self.is_pegged_change_amount_decrease.encode());
-            code.push_str(&self.pegged_change_amount.encode());
-            code.push_str(&self.reference_change_amount.encode());
-            code.push_str(&self.reference_exchange_id.encode());
-        }
+        if supports(MinServerVersion::PeggedToBenchmark) {
+            if self.order_type == OrderType::PeggedToBenchmark {
+                code.push_str(&self.reference_contract_id.encode());
+                code.push_str(&self.is_pegged_change_amount_decrease.encode());
+                code.push_str(&self.pegged_change_amount.encode());
+                code.push_str(&self.reference_change_amount.encode());
+                code.push_str(&self.reference_exchange_id.encode());
+            }
 
-        match &self.conditions {
-            Some(conds) => {
-                code.push_str(&conds.len().encode());
-                for cond in conds {
-                    // C++ API has some facility for external notification here
-                    code.push_str(&cond.encode());
-                }
-                code.push_str(&self.conditions_ignore_rth.encode());
-                code.push_str(&self.conditions_cancel_order.encode());
-            },
-            None => code.push_str("0\0"),
+            match &self.conditions {
+                Some(conds) if !conds.is_empty() => {
+                    code.push_str(&conds.len().encode());
+                    for cond in conds {
+                        // C++ API has some facility for external notification here
+                        code.push_str(&cond.encode());
+                    }
+                    code.push_str(&self.conditions_ignore_rth.encode());
+                    code.push_str(&self.conditions_cancel_order.encode());
+                },
+                _ => code.push_str("0\0"),
+            }
+
+            code.push_str(&self.adjusted_order_type.encode());
+            code.push_str(&self.trigger_price.encode());
+            code.push_str(&self.lmt_price_offset.encode());
+            code.push_str(&self.adjusted_stop_price.encode());
+            code.push_str(&self.adjusted_stop_limit_price.encode());
+            code.push_str(&self.adjusted_trailing_amount.encode());
+            code.push_str(&self.adjustable_trailing_unit.encode());
+        }
+        if supports(MinServerVersion::ExtOperator) {
+            code.push_str(&self.ext_operator.encode());
+        }
+        if supports(MinServerVersion::SoftDollarTier) {
+            match &self.soft_dollar_tier {
+                Some(tier) => {
+                    code.push_str(&tier.name.encode());
+                    code.push_str(&tier.val.encode());
+                },
+                None => code.push_str("\0\0"),
+            }
+        }
+        if supports(MinServerVersion::CashQty) {
+            code.push_str(&self.cash_qty.encode());
         }
 
-        code.push_str(&self.adjusted_order_type.encode());
-        code.push_str(&self.trigger_price.encode());
-        code.push_str(&self.lmt_price_offset.encode());
-        code.push_str(&self.adjusted_stop_price.encode());
-        code.push_str(&self.adjusted_stop_limit_price.encode());
-        code.push_str(&self.adjusted_trailing_amount.encode());
-        code.push_str(&self.adjustable_trailing_unit.encode());
-        code.push_str(&self.ext_operator.encode());
-        match &self.soft_dollar_tier {
-            Some(tier) => {
-                code.push_str(&tier.name.encode());
-                code.push_str(&tier.val.encode());
-            },
-            None => code.push_str("\0\0"),
+        if supports(MinServerVersion::DecisionMaker) {
+            code.push_str(&self.mifid_2_decision_maker.encode());
+            code.push_str(&self.mifid_2_decision_algo.encode());
+        }
+        if supports(MinServerVersion::MifidExecution) {
+            code.push_str(&self.mifid_2_execution_trader.encode());
+            code.push_str(&self.mifid_2_execution_algo.encode());
         }
-        code.push_str(&self.cash_qty.encode());
 
-        code.push_str(&self.mifid_2_decision_maker.encode());
-        code.push_str(&self.mifid_2_decision_algo.encode());
-        code.push_str(&self.mifid_2_execution_trader.encode());
-        code.push_str(&self.mifid_2_execution_algo.encode());
+        if supports(MinServerVersion::AutoPriceForHedge) {
+            code.push_str(&self.dont_use_auto_price_for_hedge.encode());
+        }
+        if supports(MinServerVersion::OrderContainer) {
+            code.push_str(&self.is_oms_container.encode());
+        }
+        if supports(MinServerVersion::DPegOrders) {
+            code.push_str(&self.discretionary_up_to_limit_price.encode());
+        }
+        if supports(MinServerVersion::PriceMgmtAlgo) {
+            code.push_str(&self.use_price_mgmt_algo.encode());
+        }
+        if supports(MinServerVersion::Duration) {
+            // duration is not supported yet
+            code.push('\0');
+        }
+        if supports(MinServerVersion::PostToAts) {
+            // post to ATS is not supported yet
+            code.push('\0');
+        }
+        if supports(MinServerVersion::AutoCancelParent) {
+            code.push_str(&self.auto_cancel_parent.encode());
+        }
+        Ok(code)
+    }
 
-        code.push_str(&self.dont_use_auto_price_for_hedge.encode());
-        code.push_str(&self.is_oms_container.encode());
-        code.push_str(&self.discretionary_up_to_limit_price.encode());
-        code.push_str(&self.use_price_mgmt_algo.encode());
-        code
+    /// Decodes an order encoded for servers with version `server_version`,
+    /// the inverse of `encode`.
+    #[allow(clippy::cognitive_complexity)]
+    pub(crate) fn decode(it: &mut Fields<'_>, server_version: ServerVersion) -> ParseResult<Self> {
+        let supports = |feature: MinServerVersion| feature.is_supported(server_version);
+        let mut order = Order {
+            contract: Contract::decode_for_order(it)?,
+            action: decode_required(it)?,
+            total_qty: decode_required(it)?,
+            order_type: decode_required(it)?,
+            lmt_price: decode(it)?,
+            aux_price: decode(it)?,
+            tif: decode(it)?,
+            oca_group: decode(it)?,
+            account: decode(it)?,
+            open_close: decode(it)?,
+            origin: decode(it)?,
+            order_ref: decode(it)?,
+            transmit: decode_required(it)?,
+            parent_id: decode(it)?,
+            block_order: decode(it)?,
+            sweep_to_fill: decode_required(it)?,
+            display_size: decode(it)?,
+            trigger_method: decode(it)?,
+            outside_rth: decode_required(it)?,
+            hidden: decode_required(it)?,
+            ..Default::default()
+        };
+        if order.contract.sec_type == SecType::Combo {
+            let count: usize = decode(it)?.unwrap_or_default();
+            if count > 0 {
+                let mut legs = preallocate(count);
+                for _ in 0..count {
+                    legs.push(ComboLeg {
+                        con_id:              decode_required(it)?,
+                        ratio:               decode_required(it)?,
+                        action:              decode_required(it)?,
+                        exchange:            decode_required(it)?,
+                        open_close:          decode(it)?,
+                        shortsale_slot:      decode(it)?,
+                        designated_location: decode(it)?,
+                        exempt_code:         decode(it)?,
+                    });
+                }
+                order.contract.combo_legs = Some(legs);
+            }
+            let count: usize = decode(it)?.unwrap_or_default();
+            if count > 0 {
+                let mut legs = preallocate(count);
+                for _ in 0..count {
+                    legs.push(decode(it)?);
+                }
+                order.order_combo_legs = Some(legs);
+            }
+            let count: usize = decode(it)?.unwrap_or_default();
+            if count > 0 {
+                let mut params = preallocate(count);
+                for _ in 0..count {
+                    params.push((decode_required(it)?, decode_required(it)?));
+                }
+                order.smart_combo_routing_params = Some(params);
+            }
+        }
+        it.next(); // deprecated shares allocation field
+        order.discretionary_amt = decode(it)?.unwrap_or_default();
+        order.good_after_time = decode(it)?;
+        order.good_till_date = decode(it)?;
+        order.fa_group = decode(it)?;
+        order.fa_method = decode(it)?;
+        order.fa_percentage = decode(it)?;
+        if !supports(MinServerVersion::FaProfileDesupport) {
+            order.fa_profile = decode(it)?;
+        }
+        if supports(MinServerVersion::ModelsSupport) {
+            order.model_code = decode(it)?;
+        }
+        order.short_sale_slot = decode(it)?;
+        order.designated_location = decode(it)?;
+        order.exempt_code = decode(it)?;
+        order.oca_type = decode(it)?;
+        order.rule_80_a = decode(it)?;
+        order.settling_firm = decode(it)?;
+        order.all_or_none = decode_required(it)?;
+        order.min_qty = decode(it)?;
+        order.percent_offset = decode(it)?;
+        order.e_trade_only = decode(it)?;
+        order.firm_quote_only = decode(it)?;
+        order.nbbo_price_cap = decode(it)?;
+        order.auction_strategy = decode(it)?;
+        order.starting_price = decode(it)?;
+        order.stock_ref_price = decode(it)?;
+        order.delta = decode(it)?;
+        order.stock_range_lower = decode(it)?;
+        order.stock_range_upper = decode(it)?;
+        order.override_percentage_constraints = decode_required(it)?;
+        order.volatility = decode(it)?;
+        order.volatility_type = decode(it)?;
+        order.delta_neutral_order_type = decode(it)?;
+        order.delta_neutral_aux_price = decode(it)?;
+        if order.delta_neutral_order_type.is_some() {
+            order.delta_neutral_con_id = decode(it)?.unwrap_or_default();
+            order.delta_neutral_settling_firm = decode(it)?;
+            order.delta_neutral_clearing_account = decode(it)?;
+            order.delta_neutral_clearing_intent = decode(it)?;
+            order.delta_neutral_open_close = decode(it)?;
+            order.delta_neutral_short_sale = decode_required(it)?;
+            order.delta_neutral_designated_location = decode(it)?;
+        }
+        order.continuous_update = decode_required(it)?;
+        order.reference_price_type = decode(it)?;
+        order.trail_stop_price = decode(it)?;
+        order.trailing_percent = decode(it)?;
+        order.scale_init_level_size = decode(it)?;
+        order.scale_subs_level_size = decode(it)?;
+        order.scale_price_increment = decode(it)?;
+        if let Some(inc) = order.scale_price_increment {
+            if inc > dec!(0.0) {
+                order.scale_price_adjust_value = decode(it)?;
+                order.scale_price_adjust_interval = decode(it)?;
+                order.scale_profit_offset = decode(it)?;
+                order.scale_auto_reset = decode_required(it)?;
+                order.scale_init_position = decode(it)?;
+                order.scale_init_fill_qty = decode(it)?;
+                order.scale_random_percent = decode_required(it)?;
+            }
+        }
+        order.scale_table = decode(it)?;
+        order.active_start_time = decode(it)?;
+        order.active_stop_time = decode(it)?;
+        order.hedge_type = decode(it)?;
+        if order.hedge_type.is_some() {
+            order.hedge_param = decode(it)?;
+        }
+        order.opt_out_smart_routing = decode(it)?;
+        order.clearing_account = decode(it)?;
+        order.clearing_intent = decode(it)?;
+        order.not_held = decode_required(it)?;
+        if decode_required(it)? {
+            order.contract.delta_neutral_contract = Some(DeltaNeutralContract {
+                con_id: decode_required(it)?,
+                delta:  decode_required(it)?,
+                price:  decode_required(it)?,
+            });
+        }
+        order.algo_strategy = decode(it)?;
+        if order.algo_strategy.is_some() {
+            let count: usize = decode(it)?.unwrap_or_default();
+            if count > 0 {
+                let mut params = preallocate(count);
+                for _ in 0..count {
+                    params.push((decode_required(it)?, decode_required(it)?));
+                }
+                order.algo_params = Some(params);
+            }
+        }
+        order.algo_id = decode(it)?;
+        order.what_if = decode(it)?;
+        let misc_options = decode_tag_values(it)?;
+        if !misc_options.is_empty() {
+            order.order_misc_options = Some(misc_options);
+        }
+        order.solicited = decode_required(it)?;
+        order.randomize_size = decode_required(it)?;
+        order.randomize_price = decode_required(it)?;
+
+        if supports(MinServerVersion::PeggedToBenchmark) {
+            if order.order_type == OrderType::PeggedToBenchmark {
+                order.reference_contract_id = decode_required(it)?;
+                order.is_pegged_change_amount_decrease = decode_required(it)?;
+                order.pegged_change_amount = decode(it)?;
+                order.reference_change_amount = decode_required(it)?;
+                order.reference_exchange_id = decode(it)?;
+            }
+            let count: usize = decode(it)?.unwrap_or_default();
+            if count > 0 {
+                let mut conditions = preallocate(count);
+                for _ in 0..count {
+                    conditions.push(decode_required(it)?);
+                }
+                order.conditions = Some(conditions);
+                order.conditions_ignore_rth = decode_required(it)?;
+                order.conditions_cancel_order = decode_required(it)?;
+            }
+            order.adjusted_order_type = decode(it)?;
+            order.trigger_price = decode(it)?;
+            order.lmt_price_offset = decode(it)?;
+            order.adjusted_stop_price = decode(it)?;
+            order.adjusted_stop_limit_price = decode(it)?;
+            order.adjusted_trailing_amount = decode(it)?;
+            order.adjustable_trailing_unit = decode_required(it)?;
+        }
+        if supports(MinServerVersion::ExtOperator) {
+            order.ext_operator = decode(it)?;
+        }
+        if supports(MinServerVersion::SoftDollarTier) {
+            let name = decode(it)?;
+            let val = decode(it)?;
+            if name.is_some() || val.is_some() {
+                order.soft_dollar_tier = Some(SoftDollarTier {
+                    name,
+                    val,
+                    display_name: None,
+                });
+            }
+        }
+        if supports(MinServerVersion::CashQty) {
+            order.cash_qty = decode(it)?;
+        }
+        if supports(MinServerVersion::DecisionMaker) {
+            order.mifid_2_decision_maker = decode(it)?;
+            order.mifid_2_decision_algo = decode(it)?;
+        }
+        if supports(MinServerVersion::MifidExecution) {
+            order.mifid_2_execution_trader = decode(it)?;
+            order.mifid_2_execution_algo = decode(it)?;
+        }
+        if supports(MinServerVersion::AutoPriceForHedge) {
+            order.dont_use_auto_price_for_hedge = decode_required(it)?;
+        }
+        if supports(MinServerVersion::OrderContainer) {
+            order.is_oms_container = decode_required(it)?;
+        }
+        if supports(MinServerVersion::DPegOrders) {
+            order.discretionary_up_to_limit_price = decode_required(it)?;
+        }
+        if supports(MinServerVersion::PriceMgmtAlgo) {
+            order.use_price_mgmt_algo = decode(it)?;
+        }
+        if supports(MinServerVersion::Duration) {
+            it.next(); // duration
+        }
+        if supports(MinServerVersion::PostToAts) {
+            it.next(); // post to ATS
+        }
+        if supports(MinServerVersion::AutoCancelParent) {
+            order.auto_cancel_parent = decode_required(it)?;
+        }
+        Ok(order)
     }
 }
 
@@ -557,7 +891,7 @@ impl ParseIbkrFrame for OrderInformation {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -576,9 +910,9 @@ impl ParseIbkrFrame for OrderInformation {
         let mut order = Order {
             contract,
             order_id, //
-            action: decode(it)?.unwrap(),
-            total_qty: decode(it)?.unwrap(),
-            order_type: decode(it)?.unwrap(),
+            action: decode_required(it)?,
+            total_qty: decode_required(it)?,
+            order_type: decode_required(it)?,
             lmt_price: decode(it)?,
             aux_price: decode(it)?,
             tif: decode(it)?,
@@ -588,10 +922,10 @@ impl ParseIbkrFrame for OrderInformation {
             origin: decode(it)?,
             order_ref: decode(it)?,
             client_id: if !completed { decode(it)? } else { None },
-            perm_id: decode(it)?.unwrap(),
-            outside_rth: decode(it)?.unwrap(),
-            hidden: decode(it)?.unwrap(),
-            discretionary_amt: decode(it)?.unwrap(),
+            perm_id: decode_required(it)?,
+            outside_rth: decode_required(it)?,
+            hidden: decode_required(it)?,
+            discretionary_amt: decode_required(it)?,
             good_after_time: decode(it)?,
             fa_group: {
                 // skip deprecated sharesAllocation field
@@ -626,8 +960,8 @@ impl ParseIbkrFrame for OrderInformation {
             stock_range_upper: decode(it)?,
             display_size: decode(it)?,
             block_order: if !completed { decode(it)? } else { None },
-            sweep_to_fill: decode(it)?.unwrap(),
-            all_or_none: decode(it)?.unwrap(),
+            sweep_to_fill: decode_required(it)?,
+            all_or_none: decode_required(it)?,
             // all_or_none: decode(it)?.unwrap_or_default(),
             min_qty: decode(it)?,
             oca_type: decode(it)?,
@@ -643,16 +977,16 @@ impl ParseIbkrFrame for OrderInformation {
             ..Default::default()
         };
         if order.delta_neutral_order_type.is_some() {
-            order.delta_neutral_con_id = decode(it)?.unwrap();
+            order.delta_neutral_con_id = decode_required(it)?;
             order.delta_neutral_settling_firm = if !completed { decode(it)? } else { None };
             order.delta_neutral_clearing_account = if !completed { decode(it)? } else { None };
             order.delta_neutral_clearing_intent = if !completed { decode(it)? } else { None };
             order.delta_neutral_open_close = if !completed { decode(it)? } else { None };
-            order.delta_neutral_short_sale = decode(it)?.unwrap();
-            order.delta_neutral_short_sale_slot = decode(it)?.unwrap();
+            order.delta_neutral_short_sale = decode_required(it)?;
+            order.delta_neutral_short_sale_slot = decode_required(it)?;
             order.delta_neutral_designated_location = decode(it)?;
         }
-        order.continuous_update = decode(it)?.unwrap();
+        order.continuous_update = decode_required(it)?;
         order.reference_price_type = decode(it)?;
         order.trail_stop_price = decode(it)?;
         order.trailing_percent = decode(it)?;
@@ -661,13 +995,13 @@ impl ParseIbkrFrame for OrderInformation {
         order.contract.combo_legs_description = decode(it)?;
         let combo_legs_count: Option<usize> = decode(it)?;
         if let Some(n) = combo_legs_count {
-            let mut legs = Vec::with_capacity(n);
+            let mut legs = preallocate(n);
             for _i in 0..n {
                 legs.push(ComboLeg {
-                    con_id:              decode(it)?.unwrap(),
-                    ratio:               decode(it)?.unwrap(),
-                    action:              decode(it)?.unwrap(),
-                    exchange:            decode(it)?.unwrap(),
+                    con_id:              decode_required(it)?,
+                    ratio:               decode_required(it)?,
+                    action:              decode_required(it)?,
+                    exchange:            decode_required(it)?,
                     open_close:          decode(it)?,
                     shortsale_slot:      decode(it)?,
                     designated_location: decode(it)?,
@@ -678,7 +1012,7 @@ impl ParseIbkrFrame for OrderInformation {
         }
         let order_combo_legs_count: Option<usize> = decode(it)?;
         if let Some(n) = order_combo_legs_count {
-            let mut order_legs: Vec<Option<Decimal>> = Vec::with_capacity(n);
+            let mut order_legs: Vec<Option<Decimal>> = preallocate(n);
             for _i in 0..n {
                 order_legs.push(decode(it)?);
             }
@@ -686,10 +1020,11 @@ impl ParseIbkrFrame for OrderInformation {
         }
         let smart_combo_routing_params_count: Option<usize> = decode(it)?;
         if let Some(n) = smart_combo_routing_params_count {
-            let mut combo_params: Vec<(String, String)> = Vec::with_capacity(n);
+            let mut combo_params: Vec<(String, String)> = preallocate(n);
             for _i in 0..n {
-                combo_params.push((decode(it)?.unwrap(), decode(it)?.unwrap()));
+                combo_params.push((decode_required(it)?, decode_required(it)?));
             }
+            order.smart_combo_routing_params = Some(combo_params);
         }
         order.scale_init_level_size = decode(it)?;
         order.scale_subs_level_size = decode(it)?;
@@ -699,10 +1034,10 @@ impl ParseIbkrFrame for OrderInformation {
                 order.scale_price_adjust_value = decode(it)?;
                 order.scale_price_adjust_interval = decode(it)?;
                 order.scale_profit_offset = decode(it)?;
-                order.scale_auto_reset = decode(it)?.unwrap();
+                order.scale_auto_reset = decode_required(it)?;
                 order.scale_init_position = decode(it)?;
                 order.scale_init_fill_qty = decode(it)?;
-                order.scale_random_percent = decode(it)?.unwrap();
+                order.scale_random_percent = decode_required(it)?;
             }
         }
         order.hedge_type = decode(it)?;
@@ -714,14 +1049,14 @@ impl ParseIbkrFrame for OrderInformation {
         order.opt_out_smart_routing = if !completed { decode(it)? } else { None };
         order.clearing_account = decode(it)?;
         order.clearing_intent = decode(it)?;
-        order.not_held = decode(it)?.unwrap();
+        order.not_held = decode_required(it)?;
         let has_delta_neutral_contract: Option<bool> = decode(it)?;
         if let Some(has_dnc) = has_delta_neutral_contract {
             if has_dnc {
                 order.contract.delta_neutral_contract = Some(DeltaNeutralContract {
-                    con_id: decode(it)?.unwrap(),
-                    delta:  decode(it)?.unwrap(),
-                    price:  decode(it)?.unwrap(),
+                    con_id: decode_required(it)?,
+                    delta:  decode_required(it)?,
+                    price:  decode_required(it)?,
                 });
             }
         }
@@ -729,17 +1064,17 @@ impl ParseIbkrFrame for OrderInformation {
         if order.algo_strategy.is_some() {
             let params_count: Option<usize> = decode(it)?;
             if let Some(n) = params_count {
-                let mut params: Vec<(String, String)> = Vec::with_capacity(n);
+                let mut params: Vec<(String, String)> = preallocate(n);
                 for _i in 0..n {
-                    params.push((decode(it)?.unwrap(), decode(it)?.unwrap()));
+                    params.push((decode_required(it)?, decode_required(it)?));
                 }
                 order.algo_params = Some(params);
             }
         }
-        order.solicited = decode(it)?.unwrap();
+        order.solicited = decode_required(it)?;
         order.what_if = if !completed { decode(it)? } else { None };
         let mut order_state = OrderState {
-            status: decode(it)?.unwrap(),
+            status: decode_required(it)?,
             init_margin_before: if !completed { decode(it)? } else { None },
             maint_margin_before: if !completed { decode(it)? } else { None },
             equity_with_loan_value_before: if !completed { decode(it)? } else { None },
@@ -756,25 +1091,25 @@ impl ParseIbkrFrame for OrderInformation {
             warning_text: if !completed { decode(it)? } else { None },
             ..Default::default()
         };
-        order.randomize_size = decode(it)?.unwrap();
-        order.randomize_price = decode(it)?.unwrap();
+        order.randomize_size = decode_required(it)?;
+        order.randomize_price = decode_required(it)?;
         if order.order_type == OrderType::PeggedToBenchmark {
-            order.reference_contract_id = decode(it)?.unwrap();
-            order.is_pegged_change_amount_decrease = decode(it)?.unwrap();
+            order.reference_contract_id = decode_required(it)?;
+            order.is_pegged_change_amount_decrease = decode_required(it)?;
             order.pegged_change_amount = decode(it)?;
-            order.reference_change_amount = decode(it)?.unwrap();
+            order.reference_change_amount = decode_required(it)?;
             order.reference_exchange_id = decode(it)?;
         }
         let conditions_count: Option<usize> = decode(it)?;
         if let Some(n) = conditions_count {
             if n > 0 {
-                let mut conditions = Vec::with_capacity(n);
+                let mut conditions = preallocate(n);
                 for _i in 0..n {
-                    conditions.push(decode(it)?.unwrap());
+                    conditions.push(decode_required(it)?);
                 }
                 order.conditions = Some(conditions);
-                order.conditions_ignore_rth = decode(it)?.unwrap();
-                order.conditions_cancel_order = decode(it)?.unwrap();
+                order.conditions_ignore_rth = decode_required(it)?;
+                order.conditions_cancel_order = decode_required(it)?;
             }
         }
         if !completed {
@@ -787,7 +1122,7 @@ impl ParseIbkrFrame for OrderInformation {
             order.adjusted_stop_price = decode(it)?;
             order.adjusted_stop_limit_price = decode(it)?;
             order.adjusted_trailing_amount = decode(it)?;
-            order.adjustable_trailing_unit = decode(it)?.unwrap();
+            order.adjustable_trailing_unit = decode_required(it)?;
             let name: Option<String> = decode(it)?;
             let val: Option<String> = decode(it)?;
             let display_name: Option<String> = decode(it)?;
@@ -800,11 +1135,11 @@ impl ParseIbkrFrame for OrderInformation {
             }
         }
         order.cash_qty = decode(it)?;
-        order.dont_use_auto_price_for_hedge = decode(it)?.unwrap();
-        order.is_oms_container = decode(it)?.unwrap();
+        order.dont_use_auto_price_for_hedge = decode_required(it)?;
+        order.is_oms_container = decode_required(it)?;
 
         if !completed {
-            order.discretionary_up_to_limit_price = decode(it)?.unwrap();
+            order.discretionary_up_to_limit_price = decode_required(it)?;
             order.use_price_mgmt_algo = decode(it)?;
         }
 
@@ -812,10 +1147,10 @@ impl ParseIbkrFrame for OrderInformation {
             order.auto_cancel_date = decode(it)?;
             order.filled_quantity = decode(it)?;
             order.ref_futures_con_id = decode(it)?;
-            order.auto_cancel_parent = decode(it)?.unwrap();
+            order.auto_cancel_parent = decode_required(it)?;
             order.shareholder = decode(it)?;
-            order.imbalance_only = decode(it)?.unwrap();
-            order.route_marketable_to_bbo = decode(it)?.unwrap();
+            order.imbalance_only = decode_required(it)?;
+            order.route_marketable_to_bbo = decode_required(it)?;
             order.parent_perm_id = decode(it)?;
             order_state.completed_time = decode(it)?;
             order_state.completed_status = decode(it)?;
@@ -825,6 +1160,233 @@ impl ParseIbkrFrame for OrderInformation {
     }
 }
 
+impl EncodeIbkrFrame for OrderInformation {
+    fn encode_frame(&self, msg_id: Incoming, server_version: ServerVersion) -> String {
+        /// Encodes the count of a list TWS may leave out, followed by the
+        /// encoded items.
+        fn encode_list<T>(list: &Option<Vec<T>>, encode: impl Fn(&T) -> String) -> String {
+            list.as_ref().map_or_else(
+                || "\0".to_string(),
+                |items| {
+                    let mut code = items.len().encode();
+                    for item in items {
+                        code.push_str(&encode(item));
+                    }
+                    code
+                },
+            )
+        }
+        let tag_value = |(tag, value): &(String, String)| tag.encode() + &value.encode();
+        let completed = matches!(msg_id, Incoming::CompletedOrder);
+        let order = &self.order;
+        let state = &self.order_state;
+        let open_only = |code: &mut String, field: String| {
+            if !completed {
+                code.push_str(&field);
+            }
+        };
+        let mut code = String::new();
+        open_only(&mut code, order.order_id.encode());
+        code.push_str(&order.contract.encode_frame(msg_id, server_version));
+        code.push_str(&order.action.encode());
+        code.push_str(&order.total_qty.encode());
+        code.push_str(&order.order_type.encode());
+        code.push_str(&order.lmt_price.encode());
+        code.push_str(&order.aux_price.encode());
+        code.push_str(&order.tif.encode());
+        code.push_str(&order.oca_group.encode());
+        code.push_str(&order.account.encode());
+        code.push_str(&order.open_close.encode());
+        code.push_str(&order.origin.encode());
+        code.push_str(&order.order_ref.encode());
+        open_only(&mut code, order.client_id.encode());
+        code.push_str(&order.perm_id.encode());
+        code.push_str(&order.outside_rth.encode());
+        code.push_str(&order.hidden.encode());
+        code.push_str(&order.discretionary_amt.encode());
+        code.push_str(&order.good_after_time.encode());
+        open_only(&mut code, "\0".to_string()); // deprecated shares allocation field
+        code.push_str(&order.fa_group.encode());
+        code.push_str(&order.fa_method.encode());
+        code.push_str(&order.fa_percentage.encode());
+        code.push_str(&order.fa_profile.encode());
+        code.push_str(&order.model_code.encode());
+        code.push_str(&order.good_till_date.encode());
+        if server_version < 183 {
+            code.push_str(&order.rule_80_a.encode());
+        }
+        code.push_str(&order.percent_offset.encode());
+        code.push_str(&order.settling_firm.encode());
+        code.push_str(&order.short_sale_slot.encode());
+        code.push_str(&order.designated_location.encode());
+        code.push_str(&order.exempt_code.encode());
+        open_only(&mut code, order.auction_strategy.encode());
+        code.push_str(&order.starting_price.encode());
+        code.push_str(&order.stock_ref_price.encode());
+        code.push_str(&order.delta.encode());
+        code.push_str(&order.stock_range_lower.encode());
+        code.push_str(&order.stock_range_upper.encode());
+        code.push_str(&order.display_size.encode());
+        open_only(&mut code, order.block_order.encode());
+        code.push_str(&order.sweep_to_fill.encode());
+        code.push_str(&order.all_or_none.encode());
+        code.push_str(&order.min_qty.encode());
+        code.push_str(&order.oca_type.encode());
+        open_only(&mut code, order.e_trade_only.encode());
+        open_only(&mut code, order.firm_quote_only.encode());
+        open_only(&mut code, order.nbbo_price_cap.encode());
+        open_only(&mut code, order.parent_id.encode());
+        code.push_str(&order.trigger_method.encode());
+        code.push_str(&order.volatility.encode());
+        code.push_str(&order.volatility_type.encode());
+        code.push_str(&order.delta_neutral_order_type.encode());
+        code.push_str(&order.delta_neutral_aux_price.encode());
+        if order.delta_neutral_order_type.is_some() {
+            code.push_str(&order.delta_neutral_con_id.encode());
+            open_only(&mut code, order.delta_neutral_settling_firm.encode());
+            open_only(&mut code, order.delta_neutral_clearing_account.encode());
+            open_only(&mut code, order.delta_neutral_clearing_intent.encode());
+            open_only(&mut code, order.delta_neutral_open_close.encode());
+            code.push_str(&order.delta_neutral_short_sale.encode());
+            code.push_str(&order.delta_neutral_short_sale_slot.encode());
+            code.push_str(&order.delta_neutral_designated_location.encode());
+        }
+        code.push_str(&order.continuous_update.encode());
+        code.push_str(&order.reference_price_type.encode());
+        code.push_str(&order.trail_stop_price.encode());
+        code.push_str(&order.trailing_percent.encode());
+        open_only(&mut code, order.basis_points.encode());
+        open_only(&mut code, order.basis_points_type.encode());
+        code.push_str(&order.contract.combo_legs_description.encode());
+        code.push_str(&encode_list(&order.contract.combo_legs, |leg| {
+            let mut code = leg.con_id.encode();
+            code.push_str(&leg.ratio.encode());
+            code.push_str(&leg.action.encode());
+            code.push_str(&leg.exchange.encode());
+            code.push_str(&leg.open_close.encode());
+            code.push_str(&leg.shortsale_slot.encode());
+            code.push_str(&leg.designated_location.encode());
+            code.push_str(&leg.exempt_code.encode());
+            code
+        }));
+        code.push_str(&encode_list(&order.order_combo_legs, Encodable::encode));
+        code.push_str(&encode_list(&order.smart_combo_routing_params, tag_value));
+        code.push_str(&order.scale_init_level_size.encode());
+        code.push_str(&order.scale_subs_level_size.encode());
+        code.push_str(&order.scale_price_increment.encode());
+        if let Some(incr) = order.scale_price_increment {
+            if incr > dec!(0.0) {
+                code.push_str(&order.scale_price_adjust_value.encode());
+                code.push_str(&order.scale_price_adjust_interval.encode());
+                code.push_str(&order.scale_profit_offset.encode());
+                code.push_str(&order.scale_auto_reset.encode());
+                code.push_str(&order.scale_init_position.encode());
+                code.push_str(&order.scale_init_fill_qty.encode());
+                code.push_str(&order.scale_random_percent.encode());
+            }
+        }
+        code.push_str(&order.hedge_type.encode());
+        if matches!(&order.hedge_type, Some(ht) if *ht != HedgeType::Undefined) {
+            code.push_str(&order.hedge_param.encode());
+        }
+        open_only(&mut code, order.opt_out_smart_routing.encode());
+        code.push_str(&order.clearing_account.encode());
+        code.push_str(&order.clearing_intent.encode());
+        code.push_str(&order.not_held.encode());
+        match &order.contract.delta_neutral_contract {
+            Some(dn) => {
+                code.push_str("1\0");
+                code.push_str(&dn.con_id.encode());
+                code.push_str(&dn.delta.encode());
+                code.push_str(&dn.price.encode());
+            },
+            None => code.push_str("0\0"),
+        }
+        code.push_str(&order.algo_strategy.encode());
+        if order.algo_strategy.is_some() {
+            code.push_str(&encode_list(&order.algo_params, tag_value));
+        }
+        code.push_str(&order.solicited.encode());
+        open_only(&mut code, order.what_if.encode());
+        code.push_str(&state.status.encode());
+        for field in [
+            state.init_margin_before,
+            state.maint_margin_before,
+            state.equity_with_loan_value_before,
+            state.init_margin_change,
+            state.maint_margin_change,
+            state.equity_with_loan_change,
+            state.init_margin_after,
+            state.maint_margin_after,
+            state.equity_with_loan_after,
+            state.commission,
+            state.min_commission,
+            state.max_commission,
+        ] {
+            open_only(&mut code, field.encode());
+        }
+        open_only(&mut code, state.commission_currency.encode());
+        open_only(&mut code, state.warning_text.encode());
+        code.push_str(&order.randomize_size.encode());
+        code.push_str(&order.randomize_price.encode());
+        if order.order_type == OrderType::PeggedToBenchmark {
+            code.push_str(&order.reference_contract_id.encode());
+            code.push_str(&order.is_pegged_change_amount_decrease.encode());
+            code.push_str(&order.pegged_change_amount.encode());
+            code.push_str(&order.reference_change_amount.encode());
+            code.push_str(&order.reference_exchange_id.encode());
+        }
+        match &order.conditions {
+            Some(conditions) if !conditions.is_empty() => {
+                code.push_str(&conditions.len().encode());
+                for condition in conditions {
+                    code.push_str(&condition.encode());
+                }
+                code.push_str(&order.conditions_ignore_rth.encode());
+                code.push_str(&order.conditions_cancel_order.encode());
+            },
+            _ => code.push_str("0\0"),
+        }
+        open_only(&mut code, order.adjusted_order_type.encode());
+        open_only(&mut code, order.trigger_price.encode());
+        code.push_str(&order.trail_stop_price.encode());
+        code.push_str(&order.lmt_price_offset.encode());
+        if !completed {
+            code.push_str(&order.adjusted_stop_price.encode());
+            code.push_str(&order.adjusted_stop_limit_price.encode());
+            code.push_str(&order.adjusted_trailing_amount.encode());
+            code.push_str(&order.adjustable_trailing_unit.encode());
+            match &order.soft_dollar_tier {
+                Some(tier) => {
+                    code.push_str(&tier.name.encode());
+                    code.push_str(&tier.val.encode());
+                    code.push_str(&tier.display_name.encode());
+                },
+                None => code.push_str("\0\0\0"),
+            }
+        }
+        code.push_str(&order.cash_qty.encode());
+        code.push_str(&order.dont_use_auto_price_for_hedge.encode());
+        code.push_str(&order.is_oms_container.encode());
+        if completed {
+            code.push_str(&order.auto_cancel_date.encode());
+            code.push_str(&order.filled_quantity.encode());
+            code.push_str(&order.ref_futures_con_id.encode());
+            code.push_str(&order.auto_cancel_parent.encode());
+            code.push_str(&order.shareholder.encode());
+            code.push_str(&order.imbalance_only.encode());
+            code.push_str(&order.route_marketable_to_bbo.encode());
+            code.push_str(&order.parent_perm_id.encode());
+            code.push_str(&state.completed_time.encode());
+            code.push_str(&state.completed_status.encode());
+        } else {
+            code.push_str(&order.discretionary_up_to_limit_price.encode());
+            code.push_str(&order.use_price_mgmt_algo.encode());
+        }
+        code
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct OrderStatusUpdate {
     pub order_id:        OrderId,
@@ -842,7 +1404,7 @@ impl ParseIbkrFrame for OrderStatusUpdate {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -852,20 +1414,36 @@ impl ParseIbkrFrame for OrderStatusUpdate {
         }
         tracing::debug!("decode OrderStatus");
         Ok(Self {
-            order_id:        decode(it)?.unwrap(),
-            status:          decode(it)?.unwrap(),
-            filled:          decode(it)?.unwrap(),
-            remaining:       decode(it)?.unwrap(),
-            avg_fill_price:  decode(it)?.unwrap(),
-            perm_id:         decode(it)?.unwrap(),
-            parent_id:       decode(it)?.unwrap(),
-            last_fill_price: decode(it)?.unwrap(),
-            client_id:       decode(it)?.unwrap(),
+            order_id:        decode_required(it)?,
+            status:          decode_required(it)?,
+            filled:          decode_required(it)?,
+            remaining:       decode_required(it)?,
+            avg_fill_price:  decode_required(it)?,
+            perm_id:         decode_required(it)?,
+            parent_id:       decode_required(it)?,
+            last_fill_price: decode_required(it)?,
+            client_id:       decode_required(it)?,
             why_held:        decode(it)?,
         })
     }
 }
 
+impl EncodeIbkrFrame for OrderStatusUpdate {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = self.order_id.encode();
+        code.push_str(&self.status.encode());
+        code.push_str(&self.filled.encode());
+        code.push_str(&self.remaining.encode());
+        code.push_str(&self.avg_fill_price.encode());
+        code.push_str(&self.perm_id.encode());
+        code.push_str(&self.parent_id.encode());
+        code.push_str(&self.last_fill_price.encode());
+        code.push_str(&self.client_id.encode());
+        code.push_str(&self.why_held.encode());
+        code
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Execution {
     pub exec_id:        String,
@@ -893,7 +1471,7 @@ impl ParseIbkrFrame for Execution {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -902,24 +1480,24 @@ impl ParseIbkrFrame for Execution {
             return Err(ParseError::UnexpectedMessage);
         }
         it.next(); // skip version
-        let order_id: i32 = decode(it)?.unwrap();
+        let order_id: i32 = decode_required(it)?;
         let contract = Contract::try_parse_frame(msg_id, server_version, it)?;
 
         Ok(Self {
             order_id,
             contract,
-            exec_id: decode(it)?.unwrap(),
-            time: decode(it)?.unwrap(),
-            acct_number: decode(it)?.unwrap(),
+            exec_id: decode_required(it)?,
+            time: decode_required(it)?,
+            acct_number: decode_required(it)?,
             exchange: decode(it)?,
-            side: decode(it)?.unwrap(),
-            shares: decode(it)?.unwrap(),
-            price: decode(it)?.unwrap(),
-            perm_id: decode(it)?.unwrap(),
-            client_id: decode(it)?.unwrap(),
-            liquidation: decode(it)?.unwrap(),
-            cum_qty: decode(it)?.unwrap(),
-            avg_price: decode(it)?.unwrap(),
+            side: decode_required(it)?,
+            shares: decode_required(it)?,
+            price: decode_required(it)?,
+            perm_id: decode_required(it)?,
+            client_id: decode_required(it)?,
+            liquidation: decode_required(it)?,
+            cum_qty: decode_required(it)?,
+            avg_price: decode_required(it)?,
             order_ref: decode(it)?,
             ev_rule: decode(it)?,
             ev_multiplier: decode(it)?,
@@ -929,6 +1507,32 @@ impl ParseIbkrFrame for Execution {
     }
 }
 
+impl EncodeIbkrFrame for Execution {
+    fn encode_frame(&self, msg_id: Incoming, server_version: ServerVersion) -> String {
+        let mut code = "10\0".to_string(); // version
+        code.push_str(&self.order_id.encode());
+        code.push_str(&self.contract.encode_frame(msg_id, server_version));
+        code.push_str(&self.exec_id.encode());
+        code.push_str(&self.time.encode());
+        code.push_str(&self.acct_number.encode());
+        code.push_str(&self.exchange.encode());
+        code.push_str(&self.side.encode());
+        code.push_str(&self.shares.encode());
+        code.push_str(&self.price.encode());
+        code.push_str(&self.perm_id.encode());
+        code.push_str(&self.client_id.encode());
+        code.push_str(&self.liquidation.encode());
+        code.push_str(&self.cum_qty.encode());
+        code.push_str(&self.avg_price.encode());
+        code.push_str(&self.order_ref.encode());
+        code.push_str(&self.ev_rule.encode());
+        code.push_str(&self.ev_multiplier.encode());
+        code.push_str(&self.model_code.encode());
+        code.push_str(&self.last_liquidity.encode());
+        code
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct CommissionReport {
     pub exec_id:               String,
@@ -942,7 +1546,7 @@ impl ParseIbkrFrame for CommissionReport {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -952,9 +1556,9 @@ impl ParseIbkrFrame for CommissionReport {
         }
         it.next(); // skip version
         Ok(Self {
-            exec_id:               decode(it)?.unwrap(),
-            commission:            decode(it)?.unwrap(),
-            currency:              decode(it)?.unwrap(),
+            exec_id:               decode_required(it)?,
+            commission:            decode_required(it)?,
+            currency:              decode_required(it)?,
             realized_pnl:          decode(it)?,
             yield_amount:          decode(it)?,
             yield_redemption_date: decode(it)?,
@@ -962,6 +1566,19 @@ impl ParseIbkrFrame for CommissionReport {
     }
 }
 
+impl EncodeIbkrFrame for CommissionReport {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = "1\0".to_string(); // version
+        code.push_str(&self.exec_id.encode());
+        code.push_str(&self.commission.encode());
+        code.push_str(&self.currency.encode());
+        code.push_str(&self.realized_pnl.encode());
+        code.push_str(&self.yield_amount.encode());
+        code.push_str(&self.yield_redemption_date.encode());
+        code
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct ExecutionFilter {
     pub client_id:    Option<ClientId>,
@@ -1268,8 +1885,8 @@ impl FromStr for OrderType {
             "STP PRT" => Ok(OrderType::StopWithProtection),
             "TRAIL" => Ok(OrderType::TrailingStop),
             "TRAIL LIMIT" => Ok(OrderType::TrailingStopLimit),
-            "REL + LMT" => Ok(OrderType::RelativeLimit),
-            "REL + MKT" => Ok(OrderType::RelativeMarket),
+            "REL + LMT" | "Rel + LMT" => Ok(OrderType::RelativeLimit),
+            "REL + MKT" | "Rel + MKT" => Ok(OrderType::RelativeMarket),
             "VOL" => Ok(OrderType::Volatility),
             "PEG BENCH" => Ok(OrderType::PeggedToBenchmark),
             &_ => Err(ParseEnumError),
@@ -1361,7 +1978,7 @@ impl FromStr for TimeInForce {
             "DAY" => Ok(TimeInForce::Day),
             "GTC" => Ok(TimeInForce::GoodTillCancel),
             "IOC" => Ok(TimeInForce::ImmediateOrCancel),
-            "GTD" => Ok(TimeInForce::GoodTillCancel),
+            "GTD" => Ok(TimeInForce::GoodUntilDate),
             "OPG" => Ok(TimeInForce::GoodOnOpen),
             "FOK" => Ok(TimeInForce::FillOrKill),
             "DTC" => Ok(TimeInForce::DayUntilCancel),
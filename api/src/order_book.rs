@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use crate::{prelude::{DepthSide, MarketDepthOperation},
+            ticker::MarketDepthUpdate,
+            MarketDataValueType,
+            RequestId};
+
+/// A single row of one side of the order book.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthLevel {
+    pub price:        MarketDataValueType,
+    pub size:         MarketDataValueType,
+    pub market_maker: Option<String>,
+}
+
+/// Level 2 snapshot of a single market depth subscription.
+///
+/// Rows are ordered by their position in the book, i.e. `bids[0]` is the best
+/// bid and `asks[0]` the best ask.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DepthSnapshot {
+    pub bids: Vec<DepthLevel>,
+    pub asks: Vec<DepthLevel>,
+}
+
+impl DepthSnapshot {
+    /// Returns the rows of the given side of the book.
+    pub fn side(&self, side: DepthSide) -> &[DepthLevel] {
+        match side {
+            DepthSide::Bid => &self.bids,
+            DepthSide::Ask => &self.asks,
+        }
+    }
+
+    /// Applies a single insert/update/delete operation to the book.
+    ///
+    /// Updates for a row which does not exist yet are treated as inserts,
+    /// deletes of rows which do not exist are ignored.
+    pub fn apply(&mut self, update: &MarketDepthUpdate) {
+        let rows = match update.side {
+            DepthSide::Bid => &mut self.bids,
+            DepthSide::Ask => &mut self.asks,
+        };
+        let level = DepthLevel {
+            price:        update.price,
+            size:         update.size,
+            market_maker: update.market_maker.clone(),
+        };
+        match update.operation {
+            MarketDepthOperation::Insert => {
+                let position = update.position.min(rows.len());
+                rows.insert(position, level);
+            },
+            MarketDepthOperation::Update => {
+                match rows.get_mut(update.position) {
+                    Some(row) => *row = level,
+                    None => rows.push(level),
+                }
+            },
+            MarketDepthOperation::Remove => {
+                if update.position < rows.len() {
+                    rows.remove(update.position);
+                }
+            },
+        }
+    }
+}
+
+/// Maintains the order books of all market depth subscriptions.
+///
+/// Feed the `MarketDepthUpdate`s received on `MarketDataTracker::market_depth`
+/// into [`OrderBook::apply`] and read the current state of a subscription with
+/// [`OrderBook::snapshot`].
+#[derive(Clone, Debug, Default)]
+pub struct OrderBook {
+    books: HashMap<RequestId, DepthSnapshot>,
+}
+
+impl OrderBook {
+    pub fn new() -> Self { Self::default() }
+
+    /// Applies an update to the book of the request it belongs to.
+    pub fn apply(&mut self, update: &MarketDepthUpdate) {
+        self.books.entry(update.id).or_default().apply(update);
+    }
+
+    /// Returns the current L2 snapshot of the given request.
+    pub fn snapshot(&self, req_id: RequestId) -> Option<&DepthSnapshot> { self.books.get(&req_id) }
+
+    /// Removes the book of the given request, e.g. after the market depth
+    /// subscription was cancelled.
+    pub fn remove(&mut self, req_id: RequestId) -> Option<DepthSnapshot> {
+        self.books.remove(&req_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn update(
+        position: usize,
+        operation: MarketDepthOperation,
+        side: DepthSide,
+        price: MarketDataValueType,
+    ) -> MarketDepthUpdate {
+        MarketDepthUpdate {
+            id: 1,
+            position,
+            market_maker: None,
+            operation,
+            side,
+            price,
+            size: 100.0,
+            is_smart_depth: false,
+        }
+    }
+
+    fn prices(rows: &[DepthLevel]) -> Vec<MarketDataValueType> {
+        rows.iter().map(|row| row.price).collect()
+    }
+
+    #[test]
+    fn apply_insert_update_remove() {
+        let mut book = OrderBook::new();
+        book.apply(&update(0, MarketDepthOperation::Insert, DepthSide::Bid, 10.0));
+        book.apply(&update(0, MarketDepthOperation::Insert, DepthSide::Bid, 10.5));
+        book.apply(&update(2, MarketDepthOperation::Insert, DepthSide::Bid, 9.5));
+        book.apply(&update(0, MarketDepthOperation::Insert, DepthSide::Ask, 11.0));
+        let snapshot = book.snapshot(1).unwrap();
+        assert_eq!(vec![10.5, 10.0, 9.5], prices(&snapshot.bids));
+        assert_eq!(vec![11.0], prices(&snapshot.asks));
+
+        book.apply(&update(1, MarketDepthOperation::Update, DepthSide::Bid, 10.1));
+        book.apply(&update(0, MarketDepthOperation::Remove, DepthSide::Bid, 10.5));
+        book.apply(&update(5, MarketDepthOperation::Remove, DepthSide::Ask, 0.0));
+        let snapshot = book.snapshot(1).unwrap();
+        assert_eq!(vec![10.1, 9.5], prices(snapshot.side(DepthSide::Bid)));
+        assert_eq!(vec![11.0], prices(snapshot.side(DepthSide::Ask)));
+    }
+
+    #[test]
+    fn books_are_kept_per_request() {
+        let mut book = OrderBook::new();
+        book.apply(&update(0, MarketDepthOperation::Insert, DepthSide::Bid, 10.0));
+        book.apply(&MarketDepthUpdate {
+            id: 2,
+            ..update(0, MarketDepthOperation::Insert, DepthSide::Ask, 20.0)
+        });
+        assert_eq!(1, book.snapshot(1).unwrap().bids.len());
+        assert!(book.snapshot(1).unwrap().asks.is_empty());
+        assert_eq!(1, book.remove(2).unwrap().asks.len());
+        assert!(book.snapshot(2).is_none());
+    }
+}
@@ -0,0 +1,408 @@
+//! Pacing of the messages sent to TWS / IB Gateway.
+//!
+//! TWS disconnects clients which send more than about 50 messages per second,
+//! so every frame written by the client passes a token bucket [`RateLimit`].
+//! Historical data requests are subject to additional [`HistoricalPacing`]
+//! rules, requests violating them are queued until they may be sent.
+use std::{collections::{HashMap, VecDeque},
+          sync::atomic::{AtomicUsize, Ordering},
+          time::Duration};
+
+use bytes::Bytes;
+use tokio::{sync::Mutex,
+            time::{sleep_until, Instant}};
+
+use crate::{cmd::{HistoricalDataRequest, HistoricalTicksRequest, IntoIbkrFrame},
+            frame::Frame,
+            Result,
+            ServerVersion};
+
+/// Token bucket limit of the messages written to the socket.
+///
+/// Up to `max_messages` can be sent in a burst, afterwards the bucket is
+/// refilled with `max_messages` tokens per `interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_messages: u32,
+    pub interval:     Duration,
+}
+
+impl Default for RateLimit {
+    /// 50 messages per second, the limit enforced by TWS.
+    fn default() -> Self {
+        RateLimit {
+            max_messages: 50,
+            interval:     Duration::from_secs(1),
+        }
+    }
+}
+
+/// Pacing rules of historical data requests.
+///
+/// * `identical_interval` - minimum time between identical requests, i.e.
+///   requests which only differ in their request id.
+/// * `max_requests` - maximum number of requests sent within `interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoricalPacing {
+    pub identical_interval: Duration,
+    pub max_requests:       usize,
+    pub interval:           Duration,
+}
+
+impl Default for HistoricalPacing {
+    /// No identical requests within 15 seconds and at most 60 requests
+    /// within 10 minutes, as documented by IB.
+    fn default() -> Self {
+        HistoricalPacing {
+            identical_interval: Duration::from_secs(15),
+            max_requests:       60,
+            interval:           Duration::from_secs(600),
+        }
+    }
+}
+
+/// Number of messages currently held back by the pacing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacingMetrics {
+    /// messages waiting for a token of the rate limiter
+    pub rate_limited:      usize,
+    /// historical data requests queued because of the historical pacing
+    pub historical_queued: usize,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit:  RateLimit,
+    tokens: f64,
+    /// the time the tokens were last refilled, may lie in the future if
+    /// tokens have been borrowed
+    last:   Instant,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        Bucket {
+            limit,
+            tokens: f64::from(limit.max_messages),
+            last: now,
+        }
+    }
+
+    /// Takes a token and returns how long the caller has to wait before the
+    /// message may be sent.
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        let capacity = f64::from(self.limit.max_messages.max(1));
+        let rate = capacity / self.limit.interval.as_secs_f64();
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        if elapsed > 0.0 {
+            self.tokens = elapsed.mul_add(rate, self.tokens).min(capacity);
+            self.last = now;
+        }
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return None;
+        }
+        let wait = Duration::from_secs_f64((1.0 - self.tokens) / rate);
+        self.tokens = 0.0;
+        self.last = self.last.max(now) + wait;
+        Some(self.last - now)
+    }
+}
+
+/// Token bucket rate limiter shared by all clones of a `Writer`.
+///
+/// Callers are served in FIFO order, a caller waiting for a token blocks the
+/// ones behind it.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    bucket:  Mutex<Bucket>,
+    waiting: AtomicUsize,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            bucket:  Mutex::new(Bucket::new(limit, Instant::now())),
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    /// Waits until the next message may be sent.
+    #[allow(clippy::significant_drop_tightening)]
+    pub(crate) async fn acquire(&self) {
+        let _waiting = Counted::new(&self.waiting);
+        let mut bucket = self.bucket.lock().await;
+        let now = Instant::now();
+        if let Some(wait) = bucket.take(now) {
+            // keep the lock, so the callers queued behind have to wait as well
+            sleep_until(now + wait).await;
+        }
+    }
+
+    /// Replaces the limit, the bucket starts full.
+    pub(crate) async fn set_limit(&self, limit: RateLimit) {
+        *self.bucket.lock().await = Bucket::new(limit, Instant::now());
+    }
+
+    /// Number of messages waiting for a token.
+    pub(crate) fn waiting(&self) -> usize { self.waiting.load(Ordering::Relaxed) }
+}
+
+/// Counts a caller in `counter` until it is dropped, so callers which are
+/// cancelled while waiting are not counted anymore.
+struct Counted<'a>(&'a AtomicUsize);
+
+impl<'a> Counted<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Counted(counter)
+    }
+}
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) { self.0.fetch_sub(1, Ordering::Relaxed); }
+}
+
+#[derive(Debug, Default)]
+struct PacerState {
+    /// send times of the requests within the pacing interval
+    sent:          VecDeque<Instant>,
+    /// last send time of the requests within the identical interval
+    last_by_query: HashMap<Bytes, Instant>,
+}
+
+impl PacerState {
+    /// Returns the earliest time the request `query` may be sent.
+    fn next_slot(&mut self, pacing: &HistoricalPacing, query: &Bytes, now: Instant) -> Instant {
+        while matches!(self.sent.front(), Some(sent) if *sent + pacing.interval <= now) {
+            self.sent.pop_front();
+        }
+        self.last_by_query
+            .retain(|_, sent| *sent + pacing.identical_interval > now);
+
+        let mut slot = now;
+        if let Some(sent) = self.last_by_query.get(query) {
+            slot = slot.max(*sent + pacing.identical_interval);
+        }
+        if pacing.max_requests > 0 && self.sent.len() >= pacing.max_requests {
+            let oldest = self.sent[self.sent.len() - pacing.max_requests];
+            slot = slot.max(oldest + pacing.interval);
+        }
+        slot
+    }
+
+    fn record(&mut self, query: Bytes, sent: Instant) {
+        self.sent.push_back(sent);
+        self.last_by_query.insert(query, sent);
+    }
+}
+
+/// Queues historical data requests according to the `HistoricalPacing`.
+#[derive(Debug)]
+pub(crate) struct HistoricalPacer {
+    pacing: Mutex<HistoricalPacing>,
+    state:  Mutex<PacerState>,
+    queued: AtomicUsize,
+}
+
+impl HistoricalPacer {
+    pub(crate) fn new(pacing: HistoricalPacing) -> Self {
+        HistoricalPacer {
+            pacing: Mutex::new(pacing),
+            state:  Mutex::new(PacerState::default()),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    /// Waits until `request` may be sent to a server with version
+    /// `server_version` without violating the pacing rules.
+    #[allow(clippy::significant_drop_tightening)]
+    pub(crate) async fn acquire<R: PacedRequest>(
+        &self,
+        request: &R,
+        server_version: ServerVersion,
+    ) -> Result<()> {
+        let query = query_of(&request.without_req_id().into_frame(server_version)?);
+        let _queued = Counted::new(&self.queued);
+        let mut state = self.state.lock().await;
+        let pacing = *self.pacing.lock().await;
+        let slot = state.next_slot(&pacing, &query, Instant::now());
+        // keep the lock, so the requests queued behind have to wait as well
+        sleep_until(slot).await;
+        state.record(query, Instant::now());
+        Ok(())
+    }
+
+    /// Replaces the pacing rules, the requests sent so far still count.
+    pub(crate) async fn set_pacing(&self, pacing: HistoricalPacing) {
+        *self.pacing.lock().await = pacing;
+    }
+
+    /// Number of requests waiting to be sent.
+    pub(crate) fn queued(&self) -> usize { self.queued.load(Ordering::Relaxed) }
+}
+
+/// A request subject to the historical data pacing.
+pub(crate) trait PacedRequest: IntoIbkrFrame + Sync {
+    /// Returns the request with request id 0.
+    ///
+    /// Identical requests only differ in their request id, encoding them
+    /// without it yields the same query whatever fields the server version
+    /// adds.
+    fn without_req_id(&self) -> Self;
+}
+
+impl PacedRequest for HistoricalDataRequest {
+    fn without_req_id(&self) -> Self {
+        HistoricalDataRequest {
+            req_id: 0,
+            ..self.clone()
+        }
+    }
+}
+
+impl PacedRequest for HistoricalTicksRequest {
+    fn without_req_id(&self) -> Self {
+        HistoricalTicksRequest {
+            req_id: 0,
+            ..self.clone()
+        }
+    }
+}
+
+/// Returns the bytes of an encoded request, the key of identical requests.
+fn query_of(frame: &Frame) -> Bytes {
+    match frame {
+        Frame::Bulk(msg) => msg.clone(),
+        Frame::Array(frames) => {
+            frames
+                .iter()
+                .flat_map(|frame| query_of(frame).to_vec())
+                .collect::<Vec<_>>()
+                .into()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{contract::Contract,
+                prelude::{BarSize,
+                          HistoricalDataType,
+                          IntradayBarDateFormat,
+                          UseRegularTradingHoursOnly},
+                RequestId};
+
+    fn secs(secs: f64) -> Duration { Duration::from_secs_f64(secs) }
+
+    #[test]
+    fn token_bucket_allows_bursts_and_refills() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(
+            RateLimit {
+                max_messages: 2,
+                interval:     secs(1.0),
+            },
+            now,
+        );
+        assert_eq!(None, bucket.take(now));
+        assert_eq!(None, bucket.take(now));
+        assert_eq!(Some(secs(0.5)), bucket.take(now));
+        assert_eq!(Some(secs(1.0)), bucket.take(now));
+        // the borrowed tokens have to be paid back first
+        assert_eq!(Some(secs(0.5)), bucket.take(now + secs(1.0)));
+        assert_eq!(None, bucket.take(now + secs(2.5)));
+    }
+
+    #[test]
+    fn historical_pacing_delays_identical_and_excess_requests() {
+        let pacing = HistoricalPacing {
+            identical_interval: secs(15.0),
+            max_requests:       2,
+            interval:           secs(600.0),
+        };
+        let spy = Bytes::from("SPY");
+        let qqq = Bytes::from("QQQ");
+
+        let now = Instant::now();
+        let mut state = PacerState::default();
+        assert_eq!(now, state.next_slot(&pacing, &spy, now));
+        state.record(spy.clone(), now);
+        assert_eq!(now + secs(15.0), state.next_slot(&pacing, &spy, now));
+        assert_eq!(now, state.next_slot(&pacing, &qqq, now));
+        state.record(qqq.clone(), now + secs(1.0));
+        // the third request has to wait for the first to leave the interval
+        let later = now + secs(20.0);
+        assert_eq!(now + secs(600.0), state.next_slot(&pacing, &spy, later));
+        assert_eq!(
+            now + secs(600.0),
+            state.next_slot(&pacing, &qqq, now + secs(300.0))
+        );
+    }
+
+    fn bars(req_id: RequestId) -> HistoricalDataRequest {
+        HistoricalDataRequest {
+            req_id,
+            contract: Contract {
+                symbol: "SPY".to_string(),
+                ..Default::default()
+            },
+            end_date_time: Utc.with_ymd_and_hms(2023, 4, 3, 16, 0, 0).unwrap(),
+            duration: crate::prelude::Duration::Day(1),
+            bar_size_setting: BarSize::_1Min,
+            what_to_show: HistoricalDataType::Trades,
+            use_rth: UseRegularTradingHoursOnly::Use,
+            format_date: IntradayBarDateFormat::YYYYMMDD,
+            keep_up_to_date: false,
+            chart_options: vec![],
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn historical_pacer_queues_requests() {
+        let pacer = HistoricalPacer::new(HistoricalPacing::default());
+        let start = Instant::now();
+        pacer.acquire(&bars(1), 151).await.unwrap();
+        assert_eq!(start, Instant::now());
+        assert_eq!(0, pacer.queued());
+        pacer.acquire(&bars(2), 151).await.unwrap();
+        assert_eq!(start + secs(15.0), Instant::now());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn historical_pacer_detects_identical_requests_of_old_servers() {
+        // servers before `SyntRealtimeBars` expect a version field in front
+        // of the request id
+        let pacer = HistoricalPacer::new(HistoricalPacing::default());
+        let start = Instant::now();
+        pacer.acquire(&bars(1), 100).await.unwrap();
+        pacer.acquire(&bars(2), 100).await.unwrap();
+        assert_eq!(start + secs(15.0), Instant::now());
+        let mut other = bars(3);
+        other.contract.symbol = "QQQ".to_string();
+        pacer.acquire(&other, 100).await.unwrap();
+        assert_eq!(start + secs(15.0), Instant::now());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_callers_are_not_counted() {
+        let limiter = RateLimiter::new(RateLimit {
+            max_messages: 1,
+            interval:     secs(10.0),
+        });
+        limiter.acquire().await;
+        let waiting = tokio::time::timeout(secs(1.0), limiter.acquire()).await;
+        assert!(waiting.is_err());
+        assert_eq!(0, limiter.waiting());
+
+        let pacer = HistoricalPacer::new(HistoricalPacing::default());
+        pacer.acquire(&bars(1), 151).await.unwrap();
+        let queued = tokio::time::timeout(secs(1.0), pacer.acquire(&bars(2), 151)).await;
+        assert!(queued.is_err());
+        assert_eq!(0, pacer.queued());
+    }
+}
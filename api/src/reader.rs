@@ -1,10 +1,12 @@
-use std::io::Cursor;
+use std::io;
 
 use bytes::{Buf, BytesMut};
 use tokio::{io::{AsyncReadExt, BufReader},
             net::tcp::OwnedReadHalf};
 
 use crate::{ib_frame::{self, IBFrame},
+            recorder::{Direction, Recorder},
+            Error,
             ServerVersion};
 /// Send and receive `IBFrame` values from a remote peer.
 ///
@@ -33,20 +35,24 @@ pub struct Reader {
 
     //`true` if no message from server has been received yet, `false` otherwise.
     never_received: bool,
+
+    // Receives every message read from the socket, if the session is recorded.
+    recorder: Option<Recorder>,
 }
 
 impl Reader {
     /// Create a new `Connection`, backed by `socket`. Read and write buffers
-    /// are initialized.
-    pub fn new(socket: OwnedReadHalf) -> Reader {
+    /// are initialized. Every message read is recorded to `recorder`.
+    pub fn new(socket: OwnedReadHalf, recorder: Option<Recorder>) -> Reader {
         Reader {
-            stream:         BufReader::new(socket),
+            stream: BufReader::new(socket),
             // Default to a 4KB read buffer. For the use case of mini redis,
             // this is fine. However, real applications will want to tune this
             // value to their specific use case. There is a high likelihood that
             // a larger read buffer will work better.
-            buffer:         BytesMut::with_capacity(32 * 1024),
+            buffer: BytesMut::with_capacity(32 * 1024),
             never_received: true,
+            recorder,
         }
     }
 
@@ -86,7 +92,11 @@ impl Reader {
                 if self.buffer.is_empty() {
                     return Ok(None);
                 } else {
-                    return Err("connection reset by peer".into());
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionReset,
+                        "connection reset by peer",
+                    )
+                    .into());
                 }
             }
         }
@@ -94,56 +104,60 @@ impl Reader {
 
     /// Tries to parse a frame from the buffer. If the buffer contains enough
     /// data, the frame is returned and the data removed from the buffer. If not
-    /// enough data has been buffered yet, `Ok(None)` is returned. If the
-    /// buffered data does not represent a valid frame, `Err` is returned.
+    /// enough data has been buffered yet, `Ok(None)` is returned. Messages
+    /// which can not be decoded are logged and skipped. If the buffered data
+    /// does not represent a valid frame, `Err` is returned.
     fn parse_frame(
         &mut self,
         server_version: Option<ServerVersion>,
+    ) -> crate::prelude::Result<Option<IBFrame>> {
+        loop {
+            // the handshake can not continue without the server version
+            let handshake = self.never_received;
+            match self.try_parse_frame(server_version) {
+                Err(Error::Parse(err)) if !handshake => {
+                    tracing::warn!(cause = %err, "skipping message");
+                },
+                res => return res,
+            }
+        }
+    }
+
+    /// Parses the next frame, a message which can not be decoded is removed
+    /// from the buffer and returned as `Error::Parse`.
+    fn try_parse_frame(
+        &mut self,
+        server_version: Option<ServerVersion>,
     ) -> crate::prelude::Result<Option<IBFrame>> {
         use ib_frame::ParseError::Incomplete;
 
-        // Cursor is used to track the "current" location in the
-        // buffer. Cursor also implements `Buf` from the `bytes` crate
-        // which provides a number of helpful utilities for working
-        // with bytes.
-        let mut buf = Cursor::new(&self.buffer[..]);
-        tracing::trace!("{:?}", buf);
         // The first step is to check if enough data has been buffered to parse
-        // a single frame. This step is usually much faster than doing a full
-        // parse of the frame, and allows us to skip allocating data structures
-        // to hold the frame data unless we know the full frame has been
-        // received.
-        match IBFrame::check(&mut buf) {
-            Ok(_) => {
-                // The `check` function will have advanced the cursor until the
-                // end of the frame. Since the cursor had position set to zero
-                // before `IBFrame::check` was called, we obtain the length of the
-                // frame by checking the cursor position.
-                let len = buf.position() as usize;
+        // a single frame. Only the length prefix is read, which allows us to
+        // skip allocating data structures to hold the frame data unless we
+        // know the full frame has been received.
+        match IBFrame::message_len(&self.buffer) {
+            Ok(len) => {
                 tracing::trace!("length of the frame: {:?}", len);
-                // Reset the position to zero before passing the cursor to
-                // `IBFrame::parse`.
-                buf.set_position(0);
 
-                // Parse the frame from the buffer. This allocates the necessary
-                // structures to represent the frame and returns the frame
-                // value.
+                // Record the message before parsing it, so messages which can
+                // not be parsed are part of the capture.
+                if let Some(recorder) = &self.recorder {
+                    recorder.record(Direction::Inbound, [&self.buffer[..len]]);
+                }
+
+                // Parse the frame from the buffer. The fields are borrowed from
+                // the buffer, only the structures representing the frame are
+                // allocated.
                 //
                 // If the encoded frame representation is invalid, an error is
-                // returned. This should terminate the **current** connection
-                // but should not impact any other connected client.
+                // returned. An invalid server version terminates the
+                // connection, other messages are skipped by the caller.
                 let frame = if self.never_received {
                     // first message
                     self.never_received = false;
-                    IBFrame::parse_server_version(&mut buf)?
+                    IBFrame::parse_server_version_message(&self.buffer[4..len])
                 } else {
-                    match IBFrame::parse(&mut buf, server_version) {
-                        Ok(frame) => frame,
-                        Err(e) => {
-                            tracing::warn!("{}", e.to_string());
-                            return Ok(None);
-                        },
-                    }
+                    IBFrame::parse_message(&self.buffer[4..len], server_version)
                 };
                 // Discard the parsed data from the read buffer.
                 //
@@ -154,7 +168,7 @@ impl Reader {
                 self.buffer.advance(len);
 
                 // Return the parsed frame to the caller.
-                Ok(Some(frame))
+                Ok(Some(frame?))
             },
             // There is not enough data present in the read buffer to parse a
             // single frame. We must wait for more data to be received from the
@@ -168,7 +182,7 @@ impl Reader {
             // An error was encountered while parsing the frame. The connection
             // is now in an invalid state. Returning `Err` from here will result
             // in the connection being closed.
-            Err(e) => Err(e.into()),
+            Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e).into()),
         }
     }
 }
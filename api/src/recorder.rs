@@ -0,0 +1,409 @@
+//! Records the raw messages of a session to a capture file and replays them.
+//!
+//! A [`Recorder`] passed to
+//! [`ConnectOptions::record`](crate::client::ConnectOptions::record) receives
+//! every message read by the connection and every message written to it,
+//! exactly as they were sent on the wire. The capture starts with the
+//! header `IBKRREC` and the format version, followed by one record per
+//! message:
+//!
+//! | bytes | content                                              |
+//! |-------|------------------------------------------------------|
+//! | 1     | direction, `0` = received from TWS, `1` = sent to TWS |
+//! | 8     | microseconds since the unix epoch, big endian        |
+//! | 4     | length of the message, big endian                    |
+//! | n     | the message including its length prefix              |
+//!
+//! A [`ReplayReader`] parses the received messages of a capture again with
+//! the server version negotiated in the recorded handshake, e.g. to
+//! reproduce a parsing bug or to backtest on a recorded session.
+//!
+//! ```no_run
+//! # async fn example() -> ibkr_rust_api::Result<()> {
+//! use ibkr_rust_api::{client::{self, ConnectOptions},
+//!                     recorder::{Pace, Recorder, ReplayReader}};
+//!
+//! let recorder = Recorder::create("session.rec")?;
+//! let options = ConnectOptions::default().record(recorder.clone());
+//! let client = client::connect_with_options("localhost:4002", 1, options).await?;
+//! // ...
+//! drop(client);
+//! recorder.flush()?;
+//!
+//! let mut replay = ReplayReader::open("session.rec")?.pace(Pace::RealTime);
+//! while let Some(frame) = replay.read_frame().await? {
+//!     println!("{frame:?}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::{convert::TryFrom,
+          fmt,
+          fs::File,
+          io::{self, BufReader, BufWriter, Cursor, Read, Write},
+          path::Path,
+          sync::{Arc, Mutex, PoisonError}};
+
+use bytes::Bytes;
+use chrono::{TimeZone, Utc};
+use tokio::time::Instant;
+
+use crate::{ib_frame::IBFrame, Result, ServerVersion, TimeStamp};
+
+/// The first bytes of a capture file.
+const MAGIC: &[u8; 7] = b"IBKRREC";
+/// The version of the capture format written by the `Recorder`.
+const FORMAT_VERSION: u8 = 1;
+/// The prefix of the handshake, the next received message is the server
+/// version.
+const HANDSHAKE: &[u8] = b"API\0";
+
+/// The direction of a recorded message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// received from TWS
+    Inbound,
+    /// sent to TWS
+    Outbound,
+}
+
+/// A single message of a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub direction: Direction,
+    /// the time the message was read from or written to the socket
+    pub timestamp: TimeStamp,
+    /// the message as sent on the wire, including its length prefix
+    pub payload:   Bytes,
+}
+
+impl Record {
+    /// Appends the record to `dst`.
+    pub fn write_to(&self, dst: &mut impl Write) -> io::Result<()> {
+        let direction = match self.direction {
+            Direction::Inbound => 0,
+            Direction::Outbound => 1,
+        };
+        let len = u32::try_from(self.payload.len())
+            .map_err(|_| invalid_data("the message is too long to be recorded"))?;
+        dst.write_all(&[direction])?;
+        dst.write_all(&self.timestamp.timestamp_micros().to_be_bytes())?;
+        dst.write_all(&len.to_be_bytes())?;
+        dst.write_all(&self.payload)
+    }
+
+    /// Reads the next record of `src`, `None` at the end of the capture.
+    pub fn read_from(src: &mut impl Read) -> io::Result<Option<Record>> {
+        let mut direction = [0; 1];
+        if src.read(&mut direction)? == 0 {
+            return Ok(None);
+        }
+        let direction = match direction[0] {
+            0 => Direction::Inbound,
+            1 => Direction::Outbound,
+            other => return Err(invalid_data(format!("unknown direction {other}"))),
+        };
+        let mut timestamp = [0; 8];
+        src.read_exact(&mut timestamp)?;
+        let timestamp = Utc
+            .timestamp_micros(i64::from_be_bytes(timestamp))
+            .single()
+            .ok_or_else(|| invalid_data("invalid timestamp"))?;
+        let mut len = [0; 4];
+        src.read_exact(&mut len)?;
+        let mut payload = vec![0; u32::from_be_bytes(len) as usize];
+        src.read_exact(&mut payload)?;
+        Ok(Some(Record {
+            direction,
+            timestamp,
+            payload: payload.into(),
+        }))
+    }
+}
+
+/// Writes the messages of one or more connections to a capture.
+///
+/// Clones share the capture, the records are buffered until
+/// [`flush`](Recorder::flush) is called or the last clone is dropped.
+#[derive(Clone)]
+pub struct Recorder {
+    sink: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl Recorder {
+    /// Creates the capture file at `path`, replacing an existing file.
+    pub fn create(path: impl AsRef<Path>) -> Result<Recorder> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Writes the capture to `sink`.
+    pub fn new(mut sink: impl Write + Send + 'static) -> Result<Recorder> {
+        sink.write_all(MAGIC)?;
+        sink.write_all(&[FORMAT_VERSION])?;
+        Ok(Recorder {
+            sink: Arc::new(Mutex::new(Box::new(sink))),
+        })
+    }
+
+    /// Writes the buffered records to the capture.
+    pub fn flush(&self) -> Result<()> {
+        self.lock().flush()?;
+        Ok(())
+    }
+
+    /// Records a message consisting of `parts`. A failing capture does not
+    /// affect the connection, the error is logged.
+    pub fn record<'a>(
+        &self,
+        direction: Direction,
+        parts: impl IntoIterator<Item = &'a [u8]>,
+    ) {
+        let payload = parts.into_iter().flatten().copied().collect::<Vec<_>>();
+        let record = Record {
+            direction,
+            timestamp: Utc::now(),
+            payload: payload.into(),
+        };
+        let mut sink = self.lock();
+        if let Err(err) = record.write_to(&mut *sink) {
+            tracing::warn!(cause = %err, "could not record message");
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Box<dyn Write + Send>> {
+        self.sink.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Recorder {
+    /// Clones of a `Recorder` are equal, they write to the same capture.
+    fn eq(&self, other: &Self) -> bool { Arc::ptr_eq(&self.sink, &other.sink) }
+}
+
+impl Eq for Recorder {}
+
+/// The speed a `ReplayReader` returns the recorded messages with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pace {
+    /// without waiting between the messages
+    #[default]
+    AsFastAsPossible,
+    /// with the delays between the messages of the recorded session
+    RealTime,
+}
+
+/// Parses the received messages of a capture.
+#[derive(Debug)]
+pub struct ReplayReader<R = BufReader<File>> {
+    source:                  R,
+    pace:                    Pace,
+    server_version:          Option<ServerVersion>,
+    // `true` after the handshake was sent, the next received message is the
+    // server version
+    awaiting_server_version: bool,
+    // the timestamp of the first received message and the instant it was
+    // replayed at
+    start:                   Option<(TimeStamp, Instant)>,
+}
+
+impl ReplayReader {
+    /// Opens the capture file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<ReplayReader> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> ReplayReader<R> {
+    /// Replays the capture read from `source`, fails if it does not start
+    /// with the header of a capture.
+    pub fn new(mut source: R) -> Result<Self> {
+        let mut header = [0; 8];
+        source.read_exact(&mut header)?;
+        if &header[..7] != MAGIC || header[7] != FORMAT_VERSION {
+            return Err(invalid_data("not a capture of a supported format").into());
+        }
+        Ok(ReplayReader {
+            source,
+            pace: Pace::default(),
+            server_version: None,
+            awaiting_server_version: false,
+            start: None,
+        })
+    }
+
+    /// Sets the speed the messages are replayed with.
+    pub const fn pace(mut self, pace: Pace) -> Self {
+        self.pace = pace;
+        self
+    }
+
+    /// The server version of the recorded session, known once its server
+    /// version message was replayed.
+    pub const fn server_version(&self) -> Option<ServerVersion> { self.server_version }
+
+    /// Returns the next record of the capture without parsing or pacing it.
+    pub fn next_record(&mut self) -> Result<Option<Record>> {
+        Ok(Record::read_from(&mut self.source)?)
+    }
+
+    /// Parses the next received message of the capture, `None` at its end.
+    ///
+    /// A message which can not be parsed is returned as `Error::Parse`, the
+    /// next call continues with the following message.
+    pub async fn read_frame(&mut self) -> Result<Option<IBFrame>> {
+        while let Some(record) = self.next_record()? {
+            match record.direction {
+                Direction::Outbound => {
+                    if record.payload.starts_with(HANDSHAKE) {
+                        self.awaiting_server_version = true;
+                    }
+                },
+                Direction::Inbound => {
+                    self.wait(record.timestamp).await;
+                    let mut buf = Cursor::new(&record.payload[..]);
+                    if self.awaiting_server_version {
+                        self.awaiting_server_version = false;
+                        let frame = IBFrame::parse_server_version(&mut buf)?;
+                        if let IBFrame::ServerVersion { server_version, .. } = &frame {
+                            self.server_version = Some(*server_version);
+                        }
+                        return Ok(Some(frame));
+                    }
+                    return Ok(Some(IBFrame::parse(&mut buf, self.server_version)?));
+                },
+            }
+        }
+        Ok(None)
+    }
+
+    /// Waits until the message recorded at `timestamp` is due.
+    async fn wait(&mut self, timestamp: TimeStamp) {
+        if self.pace == Pace::AsFastAsPossible {
+            return;
+        }
+        let (start, started) = *self
+            .start
+            .get_or_insert_with(|| (timestamp, Instant::now()));
+        if let Ok(delay) = (timestamp - start).to_std() {
+            tokio::time::sleep_until(started + delay).await;
+        }
+    }
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{client::{self, ConnectOptions},
+                mock_tws::MockTws};
+
+    /// A capture file in the temporary directory, removed when dropped.
+    struct Capture(PathBuf);
+
+    impl Capture {
+        fn new(name: &str) -> Self {
+            Capture(std::env::temp_dir().join(format!("ibkr-{name}-{}.rec", std::process::id())))
+        }
+    }
+
+    impl Drop for Capture {
+        fn drop(&mut self) { let _ = std::fs::remove_file(&self.0); }
+    }
+
+    #[tokio::test]
+    async fn recorded_session_is_replayed() {
+        let capture = Capture::new("session");
+        let tws = MockTws::start().await.unwrap();
+        tws.set_next_order_id(42);
+        let recorder = Recorder::create(&capture.0).unwrap();
+        let options = ConnectOptions::default().record(recorder.clone());
+        let client = client::connect_with_options(tws.addr(), 1, options)
+            .await
+            .unwrap();
+        assert_eq!(
+            vec!["DU1234567".to_string()],
+            client.managed_accounts().await.unwrap()
+        );
+        drop(client);
+        recorder.flush().unwrap();
+
+        let mut replay = ReplayReader::open(&capture.0).unwrap();
+        assert!(matches!(
+            replay.read_frame().await.unwrap(),
+            Some(IBFrame::ServerVersion {
+                server_version: 163,
+                ..
+            })
+        ));
+        assert_eq!(Some(163), replay.server_version());
+        let mut frames = Vec::new();
+        while let Some(frame) = replay.read_frame().await.unwrap() {
+            frames.push(frame);
+        }
+        assert!(frames
+            .iter()
+            .any(|frame| matches!(frame, IBFrame::OrderId(42))));
+        assert!(frames.iter().any(
+            |frame| matches!(frame, IBFrame::AccountCode(accounts) if accounts == "DU1234567")
+        ));
+
+        // the handshake and the start of the API were recorded as well
+        let mut replay = ReplayReader::open(&capture.0).unwrap();
+        let first = replay.next_record().unwrap().unwrap();
+        assert_eq!(Direction::Outbound, first.direction);
+        assert_eq!(&b"API\0"[..], &first.payload[..]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn real_time_replay_keeps_the_delays() {
+        let start = Utc::now();
+        let record = |delay: i64, msg: &str| {
+            Record {
+                direction: Direction::Inbound,
+                timestamp: start + chrono::TimeDelta::try_seconds(delay).unwrap(),
+                payload:   [&(msg.len() as u32).to_be_bytes()[..], msg.as_bytes()]
+                    .concat()
+                    .into(),
+            }
+        };
+        let mut capture = MAGIC.to_vec();
+        capture.push(FORMAT_VERSION);
+        for record in [record(0, "9\01\042\0"), record(5, "9\01\043\0")] {
+            record.write_to(&mut capture).unwrap();
+        }
+
+        let mut replay = ReplayReader::new(&capture[..])
+            .unwrap()
+            .pace(Pace::RealTime);
+        let started = Instant::now();
+        assert!(matches!(
+            replay.read_frame().await.unwrap(),
+            Some(IBFrame::OrderId(42))
+        ));
+        assert!(matches!(
+            replay.read_frame().await.unwrap(),
+            Some(IBFrame::OrderId(43))
+        ));
+        assert_eq!(Duration::from_secs(5), started.elapsed());
+        assert!(replay.read_frame().await.unwrap().is_none());
+    }
+
+    #[test]
+    fn other_files_are_rejected() {
+        assert!(ReplayReader::new(&b"IBKRREC\x02"[..]).is_err());
+        assert!(ReplayReader::new(&b"API\0"[..]).is_err());
+    }
+}
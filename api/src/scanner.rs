@@ -0,0 +1,252 @@
+use crate::{cmd::TagValue,
+            contract::{Contract, ContractDetails},
+            enums::Incoming,
+            ib_frame::{EncodeIbkrFrame, ParseError, ParseIbkrFrame, ParseResult},
+            utils::ib_message::{decode, decode_required, Encodable, Fields},
+            ServerVersion};
+
+/// Defines a market scanner request.
+///
+/// Only `instrument`, `location_code` and `scan_code` are required, all the
+/// filters are optional. The valid values can be discovered with
+/// `Client::request_scanner_parameters`.
+///
+/// # Example
+///
+/// ```
+/// use ibkr_rust_api::{cmd::TagValue, scanner::ScannerSubscription};
+///
+/// let subscription = ScannerSubscription::new("STK", "STK.US.MAJOR", "TOP_PERC_GAIN")
+///     .number_of_rows(10)
+///     .above_price(5.0)
+///     .above_volume(100_000)
+///     .filter(TagValue::new("marketCapAbove1e6".to_string(), "1000".to_string()));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScannerSubscription {
+    pub number_of_rows:              Option<i32>,
+    pub instrument:                  String,
+    pub location_code:               String,
+    pub scan_code:                   String,
+    pub above_price:                 Option<f64>,
+    pub below_price:                 Option<f64>,
+    pub above_volume:                Option<i32>,
+    pub market_cap_above:            Option<f64>,
+    pub market_cap_below:            Option<f64>,
+    pub moody_rating_above:          Option<String>,
+    pub moody_rating_below:          Option<String>,
+    pub sp_rating_above:             Option<String>,
+    pub sp_rating_below:             Option<String>,
+    pub maturity_date_above:         Option<String>,
+    pub maturity_date_below:         Option<String>,
+    pub coupon_rate_above:           Option<f64>,
+    pub coupon_rate_below:           Option<f64>,
+    pub exclude_convertible:         bool,
+    pub average_option_volume_above: Option<i32>,
+    pub scanner_setting_pairs:       Option<String>,
+    pub stock_type_filter:           Option<String>,
+    /// Generic filters, see the `<AbstractField>` entries of the scanner
+    /// parameters XML.
+    pub filter_options:              Vec<TagValue>,
+}
+
+impl ScannerSubscription {
+    pub fn new(instrument: &str, location_code: &str, scan_code: &str) -> Self {
+        ScannerSubscription {
+            instrument: instrument.to_string(),
+            location_code: location_code.to_string(),
+            scan_code: scan_code.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Limits the number of rows returned by the scan (max. 50).
+    pub const fn number_of_rows(mut self, rows: i32) -> Self {
+        self.number_of_rows = Some(rows);
+        self
+    }
+
+    pub const fn above_price(mut self, price: f64) -> Self {
+        self.above_price = Some(price);
+        self
+    }
+
+    pub const fn below_price(mut self, price: f64) -> Self {
+        self.below_price = Some(price);
+        self
+    }
+
+    pub const fn above_volume(mut self, volume: i32) -> Self {
+        self.above_volume = Some(volume);
+        self
+    }
+
+    pub const fn market_cap_above(mut self, market_cap: f64) -> Self {
+        self.market_cap_above = Some(market_cap);
+        self
+    }
+
+    pub const fn market_cap_below(mut self, market_cap: f64) -> Self {
+        self.market_cap_below = Some(market_cap);
+        self
+    }
+
+    pub const fn average_option_volume_above(mut self, volume: i32) -> Self {
+        self.average_option_volume_above = Some(volume);
+        self
+    }
+
+    pub const fn exclude_convertible(mut self, exclude: bool) -> Self {
+        self.exclude_convertible = exclude;
+        self
+    }
+
+    pub fn stock_type_filter(mut self, filter: &str) -> Self {
+        self.stock_type_filter = Some(filter.to_string());
+        self
+    }
+
+    /// Adds a generic filter to the scan.
+    pub fn filter(mut self, filter: TagValue) -> Self {
+        self.filter_options.push(filter);
+        self
+    }
+}
+
+impl Encodable for ScannerSubscription {
+    fn encode(&self) -> String {
+        let mut code = String::new();
+        code.push_str(&self.number_of_rows.encode());
+        code.push_str(&self.instrument.encode());
+        code.push_str(&self.location_code.encode());
+        code.push_str(&self.scan_code.encode());
+        code.push_str(&self.above_price.encode());
+        code.push_str(&self.below_price.encode());
+        code.push_str(&self.above_volume.encode());
+        code.push_str(&self.market_cap_above.encode());
+        code.push_str(&self.market_cap_below.encode());
+        code.push_str(&self.moody_rating_above.encode());
+        code.push_str(&self.moody_rating_below.encode());
+        code.push_str(&self.sp_rating_above.encode());
+        code.push_str(&self.sp_rating_below.encode());
+        code.push_str(&self.maturity_date_above.encode());
+        code.push_str(&self.maturity_date_below.encode());
+        code.push_str(&self.coupon_rate_above.encode());
+        code.push_str(&self.coupon_rate_below.encode());
+        code.push_str(&self.exclude_convertible.encode());
+        code.push_str(&self.average_option_volume_above.encode());
+        code.push_str(&self.scanner_setting_pairs.encode());
+        code.push_str(&self.stock_type_filter.encode());
+        code
+    }
+}
+
+impl ScannerSubscription {
+    /// Decodes the fields written by `encode`, the generic filter options are
+    /// sent separately.
+    pub(crate) fn decode(it: &mut Fields<'_>) -> ParseResult<Self> {
+        Ok(ScannerSubscription {
+            number_of_rows:              decode(it)?,
+            instrument:                  decode(it)?.unwrap_or_default(),
+            location_code:               decode(it)?.unwrap_or_default(),
+            scan_code:                   decode(it)?.unwrap_or_default(),
+            above_price:                 decode(it)?,
+            below_price:                 decode(it)?,
+            above_volume:                decode(it)?,
+            market_cap_above:            decode(it)?,
+            market_cap_below:            decode(it)?,
+            moody_rating_above:          decode(it)?,
+            moody_rating_below:          decode(it)?,
+            sp_rating_above:             decode(it)?,
+            sp_rating_below:             decode(it)?,
+            maturity_date_above:         decode(it)?,
+            maturity_date_below:         decode(it)?,
+            coupon_rate_above:           decode(it)?,
+            coupon_rate_below:           decode(it)?,
+            exclude_convertible:         decode_required(it)?,
+            average_option_volume_above: decode(it)?,
+            scanner_setting_pairs:       decode(it)?,
+            stock_type_filter:           decode(it)?,
+            filter_options:              Vec::new(),
+        })
+    }
+}
+
+/// A single ranked row of a market scanner result.
+#[derive(Debug, Clone)]
+pub struct ScannerData {
+    pub rank:             i32,
+    pub contract_details: ContractDetails,
+    pub distance:         Option<String>,
+    pub benchmark:        Option<String>,
+    pub projection:       Option<String>,
+}
+
+impl ParseIbkrFrame for ScannerData {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        _server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::ScannerData) {
+            tracing::error!("Unexpected Message (ScannerData): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        let rank = decode_required(it)?;
+        let mut contract = Contract {
+            con_id: decode(it)?,
+            symbol: decode_required(it)?,
+            sec_type: decode_required(it)?,
+            last_trade_date_or_contract_month: decode(it)?,
+            strike: decode(it)?,
+            right: decode(it)?,
+            exchange: decode(it)?,
+            currency: decode_required(it)?,
+            local_symbol: decode(it)?,
+            ..Default::default()
+        };
+        let market_name = decode(it)?;
+        contract.trading_class = decode(it)?;
+        let distance = decode(it)?;
+        let benchmark = decode(it)?;
+        let projection = decode(it)?;
+        contract.combo_legs_description = decode(it)?;
+        Ok(Self {
+            rank,
+            contract_details: ContractDetails {
+                contract,
+                market_name,
+                ..Default::default()
+            },
+            distance,
+            benchmark,
+            projection,
+        })
+    }
+}
+
+impl EncodeIbkrFrame for ScannerData {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let contract = &self.contract_details.contract;
+        let mut code = self.rank.encode();
+        code.push_str(&contract.con_id.encode());
+        code.push_str(&contract.symbol.encode());
+        code.push_str(&contract.sec_type.encode());
+        code.push_str(&contract.last_trade_date_or_contract_month.encode());
+        code.push_str(&contract.strike.encode());
+        code.push_str(&contract.right.encode());
+        code.push_str(&contract.exchange.encode());
+        code.push_str(&contract.currency.encode());
+        code.push_str(&contract.local_symbol.encode());
+        code.push_str(&self.contract_details.market_name.encode());
+        code.push_str(&contract.trading_class.encode());
+        code.push_str(&self.distance.encode());
+        code.push_str(&self.benchmark.encode());
+        code.push_str(&self.projection.encode());
+        code.push_str(&contract.combo_legs_description.encode());
+        code
+    }
+}
@@ -0,0 +1,219 @@
+//! Minimum server versions of the features of the TWS API.
+//!
+//! The table mirrors `server_versions` of the official API. The server
+//! version negotiated during the handshake decides which fields a request
+//! carries, requests depending on a feature the server does not support fail
+//! with [`Error::Unsupported`] before they are sent.
+//!
+//! The handshake only accepts servers supporting `MIN_CLIENT_VER`, the
+//! features up to that version are always available.
+use std::fmt;
+
+use crate::{Error, Result, ServerVersion};
+
+macro_rules! min_server_versions {
+    ($($feature:ident => $name:ident = $version:literal,)+) => {
+        $(pub const $name: ServerVersion = $version;)+
+
+        /// A feature of the TWS API and the first server version supporting
+        /// it.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum MinServerVersion {
+            $($feature,)+
+        }
+
+        impl MinServerVersion {
+            /// The first server version supporting the feature.
+            pub const fn version(self) -> ServerVersion {
+                match self {
+                    $(MinServerVersion::$feature => $name,)+
+                }
+            }
+        }
+    };
+}
+
+min_server_versions! {
+    RealTimeBars => MIN_SERVER_VER_REAL_TIME_BARS = 34,
+    ScaleOrders => MIN_SERVER_VER_SCALE_ORDERS = 35,
+    SnapshotMktData => MIN_SERVER_VER_SNAPSHOT_MKT_DATA = 35,
+    SshortComboLegs => MIN_SERVER_VER_SSHORT_COMBO_LEGS = 35,
+    WhatIfOrders => MIN_SERVER_VER_WHAT_IF_ORDERS = 36,
+    ContractConid => MIN_SERVER_VER_CONTRACT_CONID = 37,
+    PtaOrders => MIN_SERVER_VER_PTA_ORDERS = 39,
+    FundamentalData => MIN_SERVER_VER_FUNDAMENTAL_DATA = 40,
+    DeltaNeutral => MIN_SERVER_VER_DELTA_NEUTRAL = 40,
+    ContractDataChain => MIN_SERVER_VER_CONTRACT_DATA_CHAIN = 40,
+    ScaleOrders2 => MIN_SERVER_VER_SCALE_ORDERS2 = 40,
+    AlgoOrders => MIN_SERVER_VER_ALGO_ORDERS = 41,
+    ExecutionDataChain => MIN_SERVER_VER_EXECUTION_DATA_CHAIN = 42,
+    NotHeld => MIN_SERVER_VER_NOT_HELD = 44,
+    SecIdType => MIN_SERVER_VER_SEC_ID_TYPE = 45,
+    PlaceOrderConid => MIN_SERVER_VER_PLACE_ORDER_CONID = 46,
+    ReqMktDataConid => MIN_SERVER_VER_REQ_MKT_DATA_CONID = 47,
+    ReqCalcImpliedVolat => MIN_SERVER_VER_REQ_CALC_IMPLIED_VOLAT = 49,
+    ReqCalcOptionPrice => MIN_SERVER_VER_REQ_CALC_OPTION_PRICE = 50,
+    SshortxOld => MIN_SERVER_VER_SSHORTX_OLD = 51,
+    Sshortx => MIN_SERVER_VER_SSHORTX = 52,
+    ReqGlobalCancel => MIN_SERVER_VER_REQ_GLOBAL_CANCEL = 53,
+    HedgeOrders => MIN_SERVER_VER_HEDGE_ORDERS = 54,
+    ReqMarketDataType => MIN_SERVER_VER_REQ_MARKET_DATA_TYPE = 55,
+    OptOutSmartRouting => MIN_SERVER_VER_OPT_OUT_SMART_ROUTING = 56,
+    SmartComboRoutingParams => MIN_SERVER_VER_SMART_COMBO_ROUTING_PARAMS = 57,
+    DeltaNeutralConid => MIN_SERVER_VER_DELTA_NEUTRAL_CONID = 58,
+    ScaleOrders3 => MIN_SERVER_VER_SCALE_ORDERS3 = 60,
+    OrderComboLegsPrice => MIN_SERVER_VER_ORDER_COMBO_LEGS_PRICE = 61,
+    TrailingPercent => MIN_SERVER_VER_TRAILING_PERCENT = 62,
+    DeltaNeutralOpenClose => MIN_SERVER_VER_DELTA_NEUTRAL_OPEN_CLOSE = 66,
+    Positions => MIN_SERVER_VER_POSITIONS = 67,
+    AccountSummary => MIN_SERVER_VER_ACCOUNT_SUMMARY = 67,
+    TradingClass => MIN_SERVER_VER_TRADING_CLASS = 68,
+    ScaleTable => MIN_SERVER_VER_SCALE_TABLE = 69,
+    Linking => MIN_SERVER_VER_LINKING = 70,
+    AlgoId => MIN_SERVER_VER_ALGO_ID = 71,
+    OptionalCapabilities => MIN_SERVER_VER_OPTIONAL_CAPABILITIES = 72,
+    OrderSolicited => MIN_SERVER_VER_ORDER_SOLICITED = 73,
+    LinkingAuth => MIN_SERVER_VER_LINKING_AUTH = 74,
+    Primaryexch => MIN_SERVER_VER_PRIMARYEXCH = 75,
+    RandomizeSizeAndPrice => MIN_SERVER_VER_RANDOMIZE_SIZE_AND_PRICE = 76,
+    FractionalPositions => MIN_SERVER_VER_FRACTIONAL_POSITIONS = 101,
+    PeggedToBenchmark => MIN_SERVER_VER_PEGGED_TO_BENCHMARK = 102,
+    ModelsSupport => MIN_SERVER_VER_MODELS_SUPPORT = 103,
+    SecDefOptParamsReq => MIN_SERVER_VER_SEC_DEF_OPT_PARAMS_REQ = 104,
+    ExtOperator => MIN_SERVER_VER_EXT_OPERATOR = 105,
+    SoftDollarTier => MIN_SERVER_VER_SOFT_DOLLAR_TIER = 106,
+    ReqFamilyCodes => MIN_SERVER_VER_REQ_FAMILY_CODES = 107,
+    ReqMatchingSymbols => MIN_SERVER_VER_REQ_MATCHING_SYMBOLS = 108,
+    PastLimit => MIN_SERVER_VER_PAST_LIMIT = 109,
+    MdSizeMultiplier => MIN_SERVER_VER_MD_SIZE_MULTIPLIER = 110,
+    CashQty => MIN_SERVER_VER_CASH_QTY = 111,
+    ReqMktDepthExchanges => MIN_SERVER_VER_REQ_MKT_DEPTH_EXCHANGES = 112,
+    TickNews => MIN_SERVER_VER_TICK_NEWS = 113,
+    ReqSmartComponents => MIN_SERVER_VER_REQ_SMART_COMPONENTS = 114,
+    ReqNewsProviders => MIN_SERVER_VER_REQ_NEWS_PROVIDERS = 115,
+    ReqNewsArticle => MIN_SERVER_VER_REQ_NEWS_ARTICLE = 116,
+    ReqHistoricalNews => MIN_SERVER_VER_REQ_HISTORICAL_NEWS = 117,
+    ReqHeadTimestamp => MIN_SERVER_VER_REQ_HEAD_TIMESTAMP = 118,
+    ReqHistogram => MIN_SERVER_VER_REQ_HISTOGRAM = 119,
+    ServiceDataType => MIN_SERVER_VER_SERVICE_DATA_TYPE = 120,
+    AggGroup => MIN_SERVER_VER_AGG_GROUP = 121,
+    UnderlyingInfo => MIN_SERVER_VER_UNDERLYING_INFO = 122,
+    CancelHeadtimestamp => MIN_SERVER_VER_CANCEL_HEADTIMESTAMP = 123,
+    SyntRealtimeBars => MIN_SERVER_VER_SYNT_REALTIME_BARS = 124,
+    CfdReroute => MIN_SERVER_VER_CFD_REROUTE = 125,
+    MarketRules => MIN_SERVER_VER_MARKET_RULES = 126,
+    Pnl => MIN_SERVER_VER_PNL = 127,
+    NewsQueryOrigins => MIN_SERVER_VER_NEWS_QUERY_ORIGINS = 128,
+    UnrealizedPnl => MIN_SERVER_VER_UNREALIZED_PNL = 129,
+    HistoricalTicks => MIN_SERVER_VER_HISTORICAL_TICKS = 130,
+    MarketCapPrice => MIN_SERVER_VER_MARKET_CAP_PRICE = 131,
+    PreOpenBidAsk => MIN_SERVER_VER_PRE_OPEN_BID_ASK = 132,
+    RealExpirationDate => MIN_SERVER_VER_REAL_EXPIRATION_DATE = 134,
+    RealizedPnl => MIN_SERVER_VER_REALIZED_PNL = 135,
+    LastLiquidity => MIN_SERVER_VER_LAST_LIQUIDITY = 136,
+    TickByTick => MIN_SERVER_VER_TICK_BY_TICK = 137,
+    DecisionMaker => MIN_SERVER_VER_DECISION_MAKER = 138,
+    MifidExecution => MIN_SERVER_VER_MIFID_EXECUTION = 139,
+    TickByTickIgnoreSize => MIN_SERVER_VER_TICK_BY_TICK_IGNORE_SIZE = 140,
+    AutoPriceForHedge => MIN_SERVER_VER_AUTO_PRICE_FOR_HEDGE = 141,
+    WhatIfExtFields => MIN_SERVER_VER_WHAT_IF_EXT_FIELDS = 142,
+    ScannerGenericOpts => MIN_SERVER_VER_SCANNER_GENERIC_OPTS = 143,
+    ApiBindOrder => MIN_SERVER_VER_API_BIND_ORDER = 144,
+    OrderContainer => MIN_SERVER_VER_ORDER_CONTAINER = 145,
+    SmartDepth => MIN_SERVER_VER_SMART_DEPTH = 146,
+    RemoveNullAllCasting => MIN_SERVER_VER_REMOVE_NULL_ALL_CASTING = 147,
+    DPegOrders => MIN_SERVER_VER_D_PEG_ORDERS = 148,
+    MktDepthPrimExchange => MIN_SERVER_VER_MKT_DEPTH_PRIM_EXCHANGE = 149,
+    CompletedOrders => MIN_SERVER_VER_COMPLETED_ORDERS = 150,
+    PriceMgmtAlgo => MIN_SERVER_VER_PRICE_MGMT_ALGO = 151,
+    StockType => MIN_SERVER_VER_STOCK_TYPE = 152,
+    EncodeMsgAscii7 => MIN_SERVER_VER_ENCODE_MSG_ASCII7 = 153,
+    SendAllFamilyCodes => MIN_SERVER_VER_SEND_ALL_FAMILY_CODES = 154,
+    NoDefaultOpenClose => MIN_SERVER_VER_NO_DEFAULT_OPEN_CLOSE = 155,
+    PriceBasedVolatility => MIN_SERVER_VER_PRICE_BASED_VOLATILITY = 156,
+    ReplaceFaEnd => MIN_SERVER_VER_REPLACE_FA_END = 157,
+    Duration => MIN_SERVER_VER_DURATION = 158,
+    MarketDataInShares => MIN_SERVER_VER_MARKET_DATA_IN_SHARES = 159,
+    PostToAts => MIN_SERVER_VER_POST_TO_ATS = 160,
+    WsheCalendar => MIN_SERVER_VER_WSHE_CALENDAR = 161,
+    AutoCancelParent => MIN_SERVER_VER_AUTO_CANCEL_PARENT = 162,
+    FractionalSizeSupport => MIN_SERVER_VER_FRACTIONAL_SIZE_SUPPORT = 163,
+    SizeRules => MIN_SERVER_VER_SIZE_RULES = 164,
+    HistoricalSchedule => MIN_SERVER_VER_HISTORICAL_SCHEDULE = 165,
+    AdvancedOrderReject => MIN_SERVER_VER_ADVANCED_ORDER_REJECT = 166,
+    UserInfo => MIN_SERVER_VER_USER_INFO = 167,
+    CryptoAggregatedTrades => MIN_SERVER_VER_CRYPTO_AGGREGATED_TRADES = 168,
+    ManualOrderTime => MIN_SERVER_VER_MANUAL_ORDER_TIME = 169,
+    PegbestPegmidOffsets => MIN_SERVER_VER_PEGBEST_PEGMID_OFFSETS = 170,
+    WshEventDataFilters => MIN_SERVER_VER_WSH_EVENT_DATA_FILTERS = 171,
+    IpoPrices => MIN_SERVER_VER_IPO_PRICES = 172,
+    WshEventDataFiltersDate => MIN_SERVER_VER_WSH_EVENT_DATA_FILTERS_DATE = 173,
+    InstrumentTimezone => MIN_SERVER_VER_INSTRUMENT_TIMEZONE = 174,
+    HmdsMarketDataInShares => MIN_SERVER_VER_HMDS_MARKET_DATA_IN_SHARES = 175,
+    BondIssuerid => MIN_SERVER_VER_BOND_ISSUERID = 176,
+    FaProfileDesupport => MIN_SERVER_VER_FA_PROFILE_DESUPPORT = 177,
+    PendingPriceRevision => MIN_SERVER_VER_PENDING_PRICE_REVISION = 178,
+    FundDataFields => MIN_SERVER_VER_FUND_DATA_FIELDS = 179,
+    ManualOrderTimeExerciseOptions => MIN_SERVER_VER_MANUAL_ORDER_TIME_EXERCISE_OPTIONS = 180,
+    OpenOrderAdStrategy => MIN_SERVER_VER_OPEN_ORDER_AD_STRATEGY = 181,
+    LastTradeDate => MIN_SERVER_VER_LAST_TRADE_DATE = 182,
+    CustomerAccount => MIN_SERVER_VER_CUSTOMER_ACCOUNT = 183,
+    ProfessionalCustomer => MIN_SERVER_VER_PROFESSIONAL_CUSTOMER = 184,
+    BondAccruedInterest => MIN_SERVER_VER_BOND_ACCRUED_INTEREST = 185,
+    IneligibilityReasons => MIN_SERVER_VER_INELIGIBILITY_REASONS = 186,
+    RfqFields => MIN_SERVER_VER_RFQ_FIELDS = 187,
+    BondTradingHours => MIN_SERVER_VER_BOND_TRADING_HOURS = 188,
+    IncludeOvernight => MIN_SERVER_VER_INCLUDE_OVERNIGHT = 189,
+    UndoRfqFields => MIN_SERVER_VER_UNDO_RFQ_FIELDS = 190,
+    PermIdAsLong => MIN_SERVER_VER_PERM_ID_AS_LONG = 191,
+    CmeTaggingFields => MIN_SERVER_VER_CME_TAGGING_FIELDS = 192,
+    CmeTaggingFieldsInOpenOrder => MIN_SERVER_VER_CME_TAGGING_FIELDS_IN_OPEN_ORDER = 193,
+    ErrorTime => MIN_SERVER_VER_ERROR_TIME = 194,
+}
+
+impl MinServerVersion {
+    /// Returns `true` if servers with version `server_version` support the
+    /// feature.
+    pub const fn is_supported(self, server_version: ServerVersion) -> bool {
+        server_version >= self.version()
+    }
+
+    /// Fails with `Error::Unsupported` if servers with version
+    /// `server_version` do not support the feature.
+    pub const fn check(self, server_version: ServerVersion) -> Result<()> {
+        if self.is_supported(server_version) {
+            Ok(())
+        } else {
+            Err(Error::Unsupported {
+                feature: self,
+                server_version,
+            })
+        }
+    }
+}
+
+impl fmt::Display for MinServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { fmt::Debug::fmt(self, f) }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn unsupported_features_are_rejected() {
+        assert_eq!(146, MinServerVersion::SmartDepth.version());
+        assert_eq!(
+            MIN_SERVER_VER_SMART_DEPTH,
+            MinServerVersion::SmartDepth.version()
+        );
+        assert!(MinServerVersion::SmartDepth.check(146).is_ok());
+        let error = MinServerVersion::SmartDepth.check(145).unwrap_err();
+        assert_eq!(
+            "SmartDepth requires server version 146, the server has version 145",
+            error.to_string()
+        );
+    }
+}
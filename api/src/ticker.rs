@@ -1,4 +1,4 @@
-use std::str::{FromStr, Split};
+use std::str::FromStr;
 
 use bitvec::prelude::*;
 use crossbeam::channel::{unbounded, Receiver, Sender};
@@ -7,9 +7,16 @@ use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 
 use crate::{bars::{HistoricalBars, HistoricalSchedule, RealtimeBar},
-            ib_frame::{ParseError, ParseIbkrFrame, ParseResult},
-            prelude::{constants::UNSET_INTEGER,
-                      ib_message::{decode, Decodable, Encodable},
+            ib_frame::{EncodeIbkrFrame, ParseError, ParseIbkrFrame, ParseResult},
+            prelude::{constants::{MIN_SERVER_VER_PRICE_BASED_VOLATILITY,
+                                   MIN_SERVER_VER_SMART_DEPTH,
+                                   UNSET_INTEGER},
+                      ib_message::{decode,
+                                   decode_required,
+                                   preallocate,
+                                   Decodable,
+                                   Encodable,
+                                   Fields},
                       DepthSide,
                       Incoming,
                       MarketDepthOperation,
@@ -19,6 +26,13 @@ use crate::{bars::{HistoricalBars, HistoricalSchedule, RealtimeBar},
             ServerVersion,
             TimeStamp};
 
+/// Returns the bit mask of the tick attributes.
+const fn attribute_mask(attributes: &TickAttribute) -> i32 {
+    attributes.can_auto_execute as i32
+        | (attributes.past_limit as i32) << 1
+        | (attributes.pre_open as i32) << 2
+}
+
 #[derive(Debug, Clone)]
 pub struct MarketDataTracker {
     pub tick_by_tick:        Receiver<Tick>,
@@ -87,13 +101,45 @@ pub enum Tick {
     String(TickString),
     //
     Generic(TickGeneric),
+    //
+    TickOptionComputation(TickOptionComputation),
+}
+
+impl Tick {
+    /// The request id of the tick.
+    pub const fn id(&self) -> RequestId {
+        match self {
+            Tick::TickByTickAllLast(tick) => tick.id,
+            Tick::TickByTickBidAsk(tick) => tick.id,
+            Tick::TickByTickMidPoint(tick) => tick.id,
+            Tick::Price(tick) => tick.id,
+            Tick::Size(tick) => tick.id,
+            Tick::String(tick) => tick.id,
+            Tick::Generic(tick) => tick.id,
+            Tick::TickOptionComputation(tick) => tick.id,
+        }
+    }
+
+    /// The id of the message the tick is sent with.
+    pub(crate) const fn msg_id(&self) -> Incoming {
+        match self {
+            Tick::TickByTickAllLast(_)
+            | Tick::TickByTickBidAsk(_)
+            | Tick::TickByTickMidPoint(_) => Incoming::TickByTick,
+            Tick::Price(_) => Incoming::TickPrice,
+            Tick::Size(_) => Incoming::TickSize,
+            Tick::String(_) => Incoming::TickString,
+            Tick::Generic(_) => Incoming::TickGeneric,
+            Tick::TickOptionComputation(_) => Incoming::TickOptionComputation,
+        }
+    }
 }
 
 impl ParseIbkrFrame for Tick {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -103,9 +149,9 @@ impl ParseIbkrFrame for Tick {
             return Err(ParseError::UnexpectedMessage);
         }
         // it.next(); // skip version
-        let id = decode(it)?.unwrap();
-        let tick_type = decode(it)?.unwrap();
-        let time = decode(it)?.unwrap();
+        let id = decode_required(it)?;
+        let tick_type = decode_required(it)?;
+        let time = decode_required(it)?;
 
         match tick_type {
             TickByTickType::NA =>
@@ -121,17 +167,17 @@ impl ParseIbkrFrame for Tick {
                     id,
                     tick_type,
                     time,
-                    price: decode(it)?.unwrap(),
-                    size: decode(it)?.unwrap(),
+                    price: decode_required(it)?,
+                    size: decode_required(it)?,
                     tick_attrib_last: {
-                        let mask: u32 = decode(it)?.unwrap();
+                        let mask: u32 = decode_required(it)?;
                         TickAttribLast {
                             past_limit: mask & 1 != 0,
                             unreported: mask & 2 != 0,
                         }
                     },
-                    exchange: decode(it)?.unwrap(),
-                    special_conditions: decode(it)?.unwrap(),
+                    exchange: decode_required(it)?,
+                    special_conditions: decode_required(it)?,
                 };
                 Ok(Self::TickByTickAllLast(tick))
             },
@@ -141,12 +187,12 @@ impl ParseIbkrFrame for Tick {
                 let tick = TickByTickBidAsk {
                     id,
                     time,
-                    bid_price: decode(it)?.unwrap(),
-                    ask_price: decode(it)?.unwrap(),
-                    bid_size: decode(it)?.unwrap(),
-                    ask_size: decode(it)?.unwrap(),
+                    bid_price: decode_required(it)?,
+                    ask_price: decode_required(it)?,
+                    bid_size: decode_required(it)?,
+                    ask_size: decode_required(it)?,
                     tick_attrib_bid_ask: {
-                        let mask: u32 = decode(it)?.unwrap();
+                        let mask: u32 = decode_required(it)?;
                         TickAttribBidAsk {
                             bid_past_low:  mask & 1 != 0,
                             ask_past_high: mask & 2 != 0,
@@ -161,7 +207,7 @@ impl ParseIbkrFrame for Tick {
                 let tick = TickByTickMidPoint {
                     id,
                     time,
-                    mid_point: decode(it)?.unwrap(),
+                    mid_point: decode_required(it)?,
                 };
                 Ok(Self::TickByTickMidPoint(tick)) // self.wrapper
             },
@@ -169,6 +215,47 @@ impl ParseIbkrFrame for Tick {
     }
 }
 
+impl EncodeIbkrFrame for Tick {
+    fn encode_frame(&self, msg_id: Incoming, server_version: ServerVersion) -> String {
+        let mut code = self.id().encode();
+        match self {
+            Tick::TickByTickAllLast(tick) => {
+                code.push_str(&(tick.tick_type as i32).encode());
+                code.push_str(&tick.time.encode());
+                code.push_str(&tick.price.encode());
+                code.push_str(&tick.size.encode());
+                let attributes = &tick.tick_attrib_last;
+                let mask = attributes.past_limit as i32 | (attributes.unreported as i32) << 1;
+                code.push_str(&mask.encode());
+                code.push_str(&tick.exchange.encode());
+                code.push_str(&tick.special_conditions.encode());
+            },
+            Tick::TickByTickBidAsk(tick) => {
+                code.push_str(&(TickByTickType::BidAsk as i32).encode());
+                code.push_str(&tick.time.encode());
+                code.push_str(&tick.bid_price.encode());
+                code.push_str(&tick.ask_price.encode());
+                code.push_str(&tick.bid_size.encode());
+                code.push_str(&tick.ask_size.encode());
+                let attributes = &tick.tick_attrib_bid_ask;
+                let mask = attributes.bid_past_low as i32 | (attributes.ask_past_high as i32) << 1;
+                code.push_str(&mask.encode());
+            },
+            Tick::TickByTickMidPoint(tick) => {
+                code.push_str(&(TickByTickType::MidPoint as i32).encode());
+                code.push_str(&tick.time.encode());
+                code.push_str(&tick.mid_point.encode());
+            },
+            Tick::Price(tick) => return tick.encode_frame(msg_id, server_version),
+            Tick::Size(tick) => return tick.encode_frame(msg_id, server_version),
+            Tick::String(tick) => return tick.encode_frame(msg_id, server_version),
+            Tick::Generic(tick) => return tick.encode_frame(msg_id, server_version),
+            Tick::TickOptionComputation(tick) => return tick.encode_frame(msg_id, server_version),
+        }
+        code
+    }
+}
+
 #[derive(Clone, Debug, From)]
 pub enum HistoricalTimeAndSales {
     BidAsk(HistoricalBidAsk),
@@ -283,7 +370,7 @@ impl ParseIbkrFrame for TickPrice {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -293,12 +380,12 @@ impl ParseIbkrFrame for TickPrice {
             return Err(ParseError::UnexpectedMessage);
         }
         it.next(); // skip version
-        let id = decode(it)?.unwrap();
-        let kind = decode(it)?.unwrap();
-        let price = decode(it)?.unwrap();
+        let id = decode_required(it)?;
+        let kind = decode_required(it)?;
+        let price = decode_required(it)?;
 
         let size = decode(it)?;
-        let mask: u32 = decode(it)?.unwrap();
+        let mask: u32 = decode_required(it)?;
         let bits = mask.view_bits::<LocalBits>();
         let attributes = TickAttribute {
             can_auto_execute: bits[0],
@@ -315,6 +402,18 @@ impl ParseIbkrFrame for TickPrice {
     }
 }
 
+impl EncodeIbkrFrame for TickPrice {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = "6\0".to_string(); // version
+        code.push_str(&self.id.encode());
+        code.push_str(&self.kind.encode());
+        code.push_str(&self.price.encode());
+        code.push_str(&self.size.encode());
+        code.push_str(&attribute_mask(&self.attributes).encode());
+        code
+    }
+}
+
 #[derive(Clone, Debug, Copy)]
 pub struct TickSize {
     pub id:   RequestId,
@@ -325,7 +424,7 @@ impl ParseIbkrFrame for TickSize {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -336,12 +435,21 @@ impl ParseIbkrFrame for TickSize {
         }
         it.next(); // skip version
         Ok(Self {
-            id:   decode(it)?.unwrap(),
-            kind: decode(it)?.unwrap(),
-            size: decode(it)?.unwrap(),
+            id:   decode_required(it)?,
+            kind: decode_required(it)?,
+            size: decode_required(it)?,
         })
     }
 }
+impl EncodeIbkrFrame for TickSize {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = "6\0".to_string(); // version
+        code.push_str(&self.id.encode());
+        code.push_str(&self.kind.encode());
+        code.push_str(&self.size.encode());
+        code
+    }
+}
 //
 #[derive(Clone, Debug)]
 pub struct TickString {
@@ -353,7 +461,7 @@ impl ParseIbkrFrame for TickString {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -364,12 +472,21 @@ impl ParseIbkrFrame for TickString {
         }
         it.next(); // skip version
         Ok(Self {
-            id:   decode(it)?.unwrap(),
-            kind: decode(it)?.unwrap(),
+            id:   decode_required(it)?,
+            kind: decode_required(it)?,
             val:  decode(it)?,
         })
     }
 }
+impl EncodeIbkrFrame for TickString {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = "6\0".to_string(); // version
+        code.push_str(&self.id.encode());
+        code.push_str(&self.kind.encode());
+        code.push_str(&self.val.encode());
+        code
+    }
+}
 //
 #[derive(Clone, Debug, Copy)]
 pub struct TickGeneric {
@@ -381,7 +498,7 @@ impl ParseIbkrFrame for TickGeneric {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -392,13 +509,148 @@ impl ParseIbkrFrame for TickGeneric {
         }
         it.next(); // skip version
         Ok(Self {
-            id:   decode(it)?.unwrap(),
-            kind: decode(it)?.unwrap(),
-            val:  decode(it)?.unwrap(),
+            id:   decode_required(it)?,
+            kind: decode_required(it)?,
+            val:  decode_required(it)?,
         })
     }
 }
 
+impl EncodeIbkrFrame for TickGeneric {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = "6\0".to_string(); // version
+        code.push_str(&self.id.encode());
+        code.push_str(&self.kind.encode());
+        code.push_str(&self.val.encode());
+        code
+    }
+}
+
+/// Option greeks, implied volatility and model price of an option.
+///
+/// Values which were not computed by TWS are `None`.
+#[derive(Clone, Debug, Copy)]
+pub struct TickOptionComputation {
+    pub id:          RequestId,
+    pub kind:        TickType,
+    /// 0 - return based, 1 - price based. Only sent by newer servers.
+    pub tick_attrib: Option<i32>,
+    pub implied_vol: Option<MarketDataValueType>,
+    pub delta:       Option<MarketDataValueType>,
+    pub opt_price:   Option<MarketDataValueType>,
+    pub pv_dividend: Option<MarketDataValueType>,
+    pub gamma:       Option<MarketDataValueType>,
+    pub vega:        Option<MarketDataValueType>,
+    pub theta:       Option<MarketDataValueType>,
+    pub und_price:   Option<MarketDataValueType>,
+}
+impl ParseIbkrFrame for TickOptionComputation {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        if !matches!(msg_id, Incoming::TickOptionComputation) {
+            tracing::error!("Unexpected Message (TickOptionComputation): {msg_id:?}");
+            return Err(ParseError::UnexpectedMessage);
+        }
+        let price_based_volatility =
+            matches!(server_version, Some(v) if v >= MIN_SERVER_VER_PRICE_BASED_VOLATILITY);
+        // newer servers don't send a version anymore
+        let version: i32 = if price_based_volatility {
+            i32::MAX
+        } else {
+            decode(it)?.unwrap_or_default()
+        };
+        let id = decode_required(it)?;
+        let kind = decode_required(it)?;
+        let tick_attrib = if price_based_volatility {
+            decode(it)?
+        } else {
+            None
+        };
+        let implied_vol = not_computed_price(decode(it)?);
+        let delta = not_computed_greek(decode(it)?);
+        let (opt_price, pv_dividend) =
+            if version >= 6 || matches!(kind, TickType::ModelOption | TickType::DelayedModelOption)
+            {
+                (
+                    not_computed_price(decode(it)?),
+                    not_computed_price(decode(it)?),
+                )
+            } else {
+                (None, None)
+            };
+        let (gamma, vega, theta, und_price) = if version >= 6 {
+            (
+                not_computed_greek(decode(it)?),
+                not_computed_greek(decode(it)?),
+                not_computed_greek(decode(it)?),
+                not_computed_price(decode(it)?),
+            )
+        } else {
+            (None, None, None, None)
+        };
+        Ok(Self {
+            id,
+            kind,
+            tick_attrib,
+            implied_vol,
+            delta,
+            opt_price,
+            pv_dividend,
+            gamma,
+            vega,
+            theta,
+            und_price,
+        })
+    }
+}
+
+impl EncodeIbkrFrame for TickOptionComputation {
+    fn encode_frame(&self, _msg_id: Incoming, server_version: ServerVersion) -> String {
+        let price = |val: Option<MarketDataValueType>| {
+            val.map_or_else(|| "-1\0".to_string(), |v| v.encode())
+        };
+        let greek = |val: Option<MarketDataValueType>| {
+            val.map_or_else(|| "-2\0".to_string(), |v| v.encode())
+        };
+        let price_based_volatility = server_version >= MIN_SERVER_VER_PRICE_BASED_VOLATILITY;
+        let mut code = String::new();
+        if !price_based_volatility {
+            code.push_str("6\0"); // version
+        }
+        code.push_str(&self.id.encode());
+        code.push_str(&self.kind.encode());
+        if price_based_volatility {
+            code.push_str(&self.tick_attrib.encode());
+        }
+        code.push_str(&price(self.implied_vol));
+        code.push_str(&greek(self.delta));
+        code.push_str(&price(self.opt_price));
+        code.push_str(&price(self.pv_dividend));
+        code.push_str(&greek(self.gamma));
+        code.push_str(&greek(self.vega));
+        code.push_str(&greek(self.theta));
+        code.push_str(&price(self.und_price));
+        code
+    }
+}
+
+/// TWS sends -1 for prices and volatilities which were not computed.
+fn not_computed_price(val: Option<MarketDataValueType>) -> Option<MarketDataValueType> {
+    val.filter(|v| *v >= 0.0)
+}
+
+/// TWS sends -2 for greeks which were not computed.
+#[allow(clippy::float_cmp)]
+fn not_computed_greek(val: Option<MarketDataValueType>) -> Option<MarketDataValueType> {
+    val.filter(|v| *v != -2.0)
+}
+
 #[derive(Clone, Debug, Copy)]
 pub struct HistoricalBidAsk {
     pub time:       TimeStamp,
@@ -412,7 +664,7 @@ impl ParseIbkrFrame for HistoricalBidAsk {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -421,8 +673,8 @@ impl ParseIbkrFrame for HistoricalBidAsk {
             tracing::error!("Unexpected Message (HistTickLast): {msg_id:?}");
             return Err(ParseError::UnexpectedMessage);
         }
-        let time = decode(it)?.unwrap();
-        let mask: u32 = decode(it)?.unwrap();
+        let time = decode_required(it)?;
+        let mask: u32 = decode_required(it)?;
         let bits = mask.view_bits::<LocalBits>();
         let attributes = TickAttribute {
             can_auto_execute: bits[0],
@@ -432,14 +684,26 @@ impl ParseIbkrFrame for HistoricalBidAsk {
         Ok(Self {
             time,
             attributes,
-            price_bid: decode(it)?.unwrap(),
-            price_ask: decode(it)?.unwrap(),
-            size_bid: decode(it)?.unwrap(),
-            size_ask: decode(it)?.unwrap(),
+            price_bid: decode_required(it)?,
+            price_ask: decode_required(it)?,
+            size_bid: decode_required(it)?,
+            size_ask: decode_required(it)?,
         })
     }
 }
 
+impl EncodeIbkrFrame for HistoricalBidAsk {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = self.time.encode();
+        code.push_str(&attribute_mask(&self.attributes).encode());
+        code.push_str(&self.price_bid.encode());
+        code.push_str(&self.price_ask.encode());
+        code.push_str(&self.size_bid.encode());
+        code.push_str(&self.size_ask.encode());
+        code
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct HistoricalTicks {
     pub id:    RequestId,
@@ -450,7 +714,7 @@ impl ParseIbkrFrame for HistoricalTicks {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -458,10 +722,10 @@ impl ParseIbkrFrame for HistoricalTicks {
         tracing::error!("Msg Id Historical Ticks {:#?}", &msg_id);
         match msg_id {
             Incoming::HistoricalTicks => {
-                let id = decode(it)?.unwrap();
-                let tick_count = decode(it)?.unwrap();
+                let id = decode_required(it)?;
+                let tick_count = decode_required(it)?;
                 let ticks = {
-                    let mut ticks = Vec::with_capacity(tick_count);
+                    let mut ticks = preallocate(tick_count);
                     for _i in 0..tick_count {
                         ticks.push(HistoricalTimeAndSales::Tick(
                             HistoricalTick::try_parse_frame(msg_id, server_version, it)?,
@@ -472,15 +736,15 @@ impl ParseIbkrFrame for HistoricalTicks {
                 Ok(Self {
                     id,
                     ticks,
-                    done: decode(it)?.unwrap(),
+                    done: decode_required(it)?,
                 })
             },
             Incoming::HistoricalTicksBidAsk => {
                 tracing::error!("Historical Ticks BidAsk {:#?}", &it);
-                let id = decode(it)?.unwrap();
-                let tick_count = decode(it)?.unwrap();
+                let id = decode_required(it)?;
+                let tick_count = decode_required(it)?;
                 let ticks = {
-                    let mut ticks = Vec::with_capacity(tick_count);
+                    let mut ticks = preallocate(tick_count);
                     for _i in 0..tick_count {
                         ticks.push(HistoricalTimeAndSales::BidAsk(
                             HistoricalBidAsk::try_parse_frame(msg_id, server_version, it)?,
@@ -492,15 +756,15 @@ impl ParseIbkrFrame for HistoricalTicks {
                 Ok(Self {
                     id,
                     ticks,
-                    done: decode(it)?.unwrap(),
+                    done: decode_required(it)?,
                 })
             },
             Incoming::HistoricalTicksLast => {
-                let id = decode(it)?.unwrap();
-                let tick_count = decode(it)?.unwrap();
+                let id = decode_required(it)?;
+                let tick_count = decode_required(it)?;
                 let ticks =
                     {
-                        let mut ticks = Vec::with_capacity(tick_count);
+                        let mut ticks = preallocate(tick_count);
                         for _i in 0..tick_count {
                             let tick = HistoricalTimeAndSales::Last(
                                 HistoricalLast::try_parse_frame(msg_id, server_version, it)?,
@@ -512,7 +776,7 @@ impl ParseIbkrFrame for HistoricalTicks {
                 Ok(Self {
                     id,
                     ticks,
-                    done: decode(it)?.unwrap(),
+                    done: decode_required(it)?,
                 })
             },
 
@@ -521,6 +785,40 @@ impl ParseIbkrFrame for HistoricalTicks {
     }
 }
 
+impl HistoricalTicks {
+    /// The id of the message the ticks are sent with, ticks of different
+    /// types are not sent together.
+    pub(crate) fn msg_id(&self) -> Incoming {
+        match self.ticks.first() {
+            Some(HistoricalTimeAndSales::BidAsk(_)) => Incoming::HistoricalTicksBidAsk,
+            Some(HistoricalTimeAndSales::Last(_)) => Incoming::HistoricalTicksLast,
+            Some(HistoricalTimeAndSales::Tick(_)) | None => Incoming::HistoricalTicks,
+        }
+    }
+}
+
+impl EncodeIbkrFrame for HistoricalTicks {
+    fn encode_frame(&self, msg_id: Incoming, server_version: ServerVersion) -> String {
+        let mut code = self.id.encode();
+        code.push_str(&self.ticks.len().encode());
+        for tick in &self.ticks {
+            match tick {
+                HistoricalTimeAndSales::BidAsk(tick) => {
+                    code.push_str(&tick.encode_frame(msg_id, server_version));
+                },
+                HistoricalTimeAndSales::Tick(tick) => {
+                    code.push_str(&tick.encode_frame(msg_id, server_version));
+                },
+                HistoricalTimeAndSales::Last(tick) => {
+                    code.push_str(&tick.encode_frame(msg_id, server_version));
+                },
+            }
+        }
+        code.push_str(&self.done.encode());
+        code
+    }
+}
+
 #[derive(Clone, Debug, Copy)]
 pub struct HistoricalTick {
     pub time:  TimeStamp,
@@ -531,7 +829,7 @@ impl ParseIbkrFrame for HistoricalTick {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -541,13 +839,22 @@ impl ParseIbkrFrame for HistoricalTick {
             return Err(ParseError::UnexpectedMessage);
         }
         Ok(Self {
-            time:  decode(it)?.unwrap(),
-            price: decode(it)?.unwrap(),
-            size:  decode(it)?.unwrap(),
+            time:  decode_required(it)?,
+            price: decode_required(it)?,
+            size:  decode_required(it)?,
         })
     }
 }
 
+impl EncodeIbkrFrame for HistoricalTick {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = self.time.encode();
+        code.push_str(&self.price.encode());
+        code.push_str(&self.size.encode());
+        code
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct HistoricalLast {
     pub time:               TimeStamp,
@@ -561,7 +868,7 @@ impl ParseIbkrFrame for HistoricalLast {
     fn try_parse_frame(
         msg_id: Incoming,
         server_version: Option<ServerVersion>,
-        it: &mut Split<&str>,
+        it: &mut Fields<'_>,
     ) -> ParseResult<Self>
     where
         Self: Sized,
@@ -570,8 +877,8 @@ impl ParseIbkrFrame for HistoricalLast {
             tracing::error!("Unexpected Message (HistTickLast): {msg_id:?}");
             return Err(ParseError::UnexpectedMessage);
         }
-        let time = decode(it)?.unwrap();
-        let mask: u32 = decode(it)?.unwrap();
+        let time = decode_required(it)?;
+        let mask: u32 = decode_required(it)?;
         let bits = mask.view_bits::<LocalBits>();
         let attributes = TickAttribute {
             can_auto_execute: bits[0],
@@ -579,16 +886,28 @@ impl ParseIbkrFrame for HistoricalLast {
             pre_open:         bits[2],
         };
         Ok(Self {
-            time, //: decode(it)?.unwrap(),
+            time, //: decode_required(it)?,
             attributes,
-            price: decode(it)?.unwrap(),
-            size: decode(it)?.unwrap(),
-            exchange: decode(it)?.unwrap(),
+            price: decode_required(it)?,
+            size: decode_required(it)?,
+            exchange: decode_required(it)?,
             special_conditions: decode(it)?,
         })
     }
 }
 
+impl EncodeIbkrFrame for HistoricalLast {
+    fn encode_frame(&self, _msg_id: Incoming, _server_version: ServerVersion) -> String {
+        let mut code = self.time.encode();
+        code.push_str(&attribute_mask(&self.attributes).encode());
+        code.push_str(&self.price.encode());
+        code.push_str(&self.size.encode());
+        code.push_str(&self.exchange.encode());
+        code.push_str(&self.special_conditions.encode());
+        code
+    }
+}
+
 #[derive(Clone, Debug, Copy)]
 pub struct HeadTimestamp {
     pub id:        RequestId,
@@ -598,12 +917,92 @@ pub struct HeadTimestamp {
 pub struct MarketDepthUpdate {
     pub id:             RequestId,
     pub position:       usize,
+    pub market_maker:   Option<String>,
     pub operation:      MarketDepthOperation,
     pub side:           DepthSide,
     pub price:          MarketDataValueType,
     pub size:           MarketDataValueType,
-    pub is_smart_depth: String,
+    pub is_smart_depth: bool,
 }
+impl ParseIbkrFrame for MarketDepthUpdate {
+    fn try_parse_frame(
+        msg_id: Incoming,
+        server_version: Option<ServerVersion>,
+        it: &mut Fields<'_>,
+    ) -> ParseResult<Self>
+    where
+        Self: Sized,
+    {
+        match msg_id {
+            Incoming::MarketDepth => {
+                it.next(); // skip version
+                Ok(Self {
+                    id:             decode_required(it)?,
+                    position:       decode_required(it)?,
+                    market_maker:   None,
+                    operation:      decode_required(it)?,
+                    side:           decode_required(it)?,
+                    price:          decode_required(it)?,
+                    size:           decode_required(it)?,
+                    is_smart_depth: false,
+                })
+            },
+            Incoming::MarketDepthL2 => {
+                it.next(); // skip version
+                Ok(Self {
+                    id:             decode_required(it)?,
+                    position:       decode_required(it)?,
+                    market_maker:   decode(it)?,
+                    operation:      decode_required(it)?,
+                    side:           decode_required(it)?,
+                    price:          decode_required(it)?,
+                    size:           decode_required(it)?,
+                    is_smart_depth: match server_version {
+                        Some(v) if v >= MIN_SERVER_VER_SMART_DEPTH => {
+                            decode(it)?.unwrap_or_default()
+                        },
+                        _ => false,
+                    },
+                })
+            },
+            _ => {
+                tracing::error!("Unexpected Message (MarketDepth): {msg_id:?}");
+                Err(ParseError::UnexpectedMessage)
+            },
+        }
+    }
+}
+impl MarketDepthUpdate {
+    /// The id of the message the update is sent with.
+    pub(crate) const fn msg_id(&self) -> Incoming {
+        if self.market_maker.is_some() || self.is_smart_depth {
+            Incoming::MarketDepthL2
+        } else {
+            Incoming::MarketDepth
+        }
+    }
+}
+
+impl EncodeIbkrFrame for MarketDepthUpdate {
+    fn encode_frame(&self, msg_id: Incoming, server_version: ServerVersion) -> String {
+        let l2 = matches!(msg_id, Incoming::MarketDepthL2);
+        let mut code = "1\0".to_string(); // version
+        code.push_str(&self.id.encode());
+        code.push_str(&self.position.encode());
+        if l2 {
+            code.push_str(&self.market_maker.encode());
+        }
+        code.push_str(&self.operation.encode());
+        code.push_str(&self.side.encode());
+        code.push_str(&self.price.encode());
+        code.push_str(&self.size.encode());
+        if l2 && server_version >= MIN_SERVER_VER_SMART_DEPTH {
+            code.push_str(&self.is_smart_depth.encode());
+        }
+        code
+    }
+}
+
 #[repr(i32)]
 #[derive(FromPrimitive, Debug, Clone, enum_ordinalize::Ordinalize, Copy)]
 pub enum TickType {
@@ -745,6 +1144,24 @@ impl Encodable for GenericTickType {
         .to_string()
     }
 }
+
+impl FromStr for GenericTickType {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let res = match s {
+            "236" => GenericTickType::ShortableData,
+            "165" => GenericTickType::HistoricData,
+            "10" => GenericTickType::OptionHistoricalVol,
+            "106" => GenericTickType::OptionImpliedVol,
+            "101" => GenericTickType::OptionOpenInterest,
+            "225" => GenericTickType::AuctionData,
+            "100" => GenericTickType::OptionVolume,
+            &_ => return Err(ParseEnumError),
+        };
+        Ok(res)
+    }
+}
 /// Tick by tick types
 #[derive(Clone, Debug, Copy)]
 pub enum TickByTickType {
@@ -760,10 +1177,10 @@ impl FromStr for TickByTickType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let res = match s {
             "0" => TickByTickType::NA,
-            "1" => TickByTickType::Last,
-            "2" => TickByTickType::AllLast,
-            "3" => TickByTickType::BidAsk,
-            "4" => TickByTickType::MidPoint,
+            "1" | "Last" => TickByTickType::Last,
+            "2" | "AllLast" => TickByTickType::AllLast,
+            "3" | "BidAsk" => TickByTickType::BidAsk,
+            "4" | "MidPoint" => TickByTickType::MidPoint,
             &_ => return Err(ParseEnumError),
         };
         Ok(res)
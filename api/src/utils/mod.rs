@@ -1,9 +1,9 @@
 pub mod dateparser;
 
 pub mod ib_message {
-    use std::{convert::TryInto, str};
+    use std::convert::{TryFrom, TryInto};
 
-    use chrono::{Local, NaiveDate, Utc};
+    use chrono::{Local, NaiveDate, TimeZone, Utc};
     use rust_decimal::prelude::*;
     use tracing::log::error;
 
@@ -53,6 +53,67 @@ pub mod ib_message {
 
         #[error("Unknown option right: '{}'", _0)]
         UnknownRight(Box<str>),
+
+        #[error("The message ended before all fields were decoded")]
+        MissingField,
+
+        #[error("Missing value of type {}", _0)]
+        MissingValue(&'static str),
+    }
+
+    /// The fields of a message, borrowed from the received bytes.
+    ///
+    /// Every field is terminated by a NUL, the iterator yields the same
+    /// fields as `msg.split('\0')`, including the empty field after the last
+    /// terminator.
+    #[derive(Debug, Clone)]
+    pub struct Fields<'a> {
+        // `None` once the last field was returned
+        rest: Option<&'a str>,
+    }
+
+    impl<'a> Fields<'a> {
+        pub const fn new(msg: &'a str) -> Self { Fields { rest: Some(msg) } }
+    }
+
+    impl<'a> Iterator for Fields<'a> {
+        type Item = &'a str;
+
+        fn next(&mut self) -> Option<&'a str> {
+            let rest = self.rest?;
+            // a NUL is never part of a multi byte character
+            match rest.bytes().position(|b| b == 0) {
+                Some(end) => {
+                    self.rest = Some(&rest[end + 1..]);
+                    Some(&rest[..end])
+                },
+                None => self.rest.take(),
+            }
+        }
+    }
+
+    /// Parses a plain decimal integer of at most 18 digits, `None` for
+    /// everything else.
+    ///
+    /// Most fields sent by TWS are ids, counts and enum ordinals, 18 digits
+    /// always fit into an `i64`.
+    fn parse_int(val: &str) -> Option<i64> {
+        let (negative, digits) = match val.as_bytes() {
+            [b'-', digits @ ..] => (true, digits),
+            digits => (false, digits),
+        };
+        if digits.is_empty() || digits.len() > 18 {
+            return None;
+        }
+        let mut n: i64 = 0;
+        for b in digits {
+            let digit = b.wrapping_sub(b'0');
+            if digit > 9 {
+                return None;
+            }
+            n = n * 10 + i64::from(digit);
+        }
+        Some(if negative { -n } else { n })
     }
 
     pub trait Decodable
@@ -64,10 +125,30 @@ pub mod ib_message {
         }
     }
 
-    impl Decodable for i32 {}
-    impl Decodable for u32 {}
-    impl Decodable for usize {}
-    impl Decodable for isize {}
+    /// Integers are parsed with `parse_int`, `FromStr` decides about the
+    /// values it rejects.
+    macro_rules! decodable_int {
+        ($($int:ty),*) => {
+            $(
+                impl Decodable for $int {
+                    fn decode_str(val: &str) -> Result<Self, IbDecodeError> {
+                        match parse_int(val).and_then(|n| <$int>::try_from(n).ok()) {
+                            Some(n) => Ok(n),
+                            None => {
+                                Self::from_str(val)
+                                    .map_err(|_| IbDecodeError::UnknownString(val.into()))
+                            },
+                        }
+                    }
+                }
+            )*
+        };
+    }
+
+    decodable_int!(i32, u32, usize, isize, i64);
+
+    // the standard library takes the fast path for the short prices and sizes
+    // sent by TWS
     impl Decodable for f64 {}
     impl Decodable for Decimal {
         fn decode_str(val: &str) -> Result<Self, IbDecodeError> {
@@ -82,7 +163,6 @@ pub mod ib_message {
     }
 
     impl Decodable for String {}
-    impl Decodable for i64 {}
 
     impl Decodable for bool {
         fn decode_str(val: &str) -> Result<Self, IbDecodeError> {
@@ -95,6 +175,14 @@ pub mod ib_message {
     }
     impl Decodable for TimeStamp {
         fn decode_str(val: &str) -> Result<Self, IbDecodeError> {
+            // the times of ticks are sent as seconds since the epoch
+            if val.len() == 10 && val.bytes().all(|b| b.is_ascii_digit()) {
+                if let Some(time) =
+                    parse_int(val).and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+                {
+                    return Ok(time);
+                }
+            }
             super::dateparser::Parse::new(&Local, Utc::now().time())
                 .parse(val)
                 .map_err(|err| IbDecodeError::UnknownDateTime(err, val.into()))
@@ -113,23 +201,17 @@ pub mod ib_message {
 
     impl Decodable for OptionRight {
         fn decode_str(val: &str) -> Result<Self, IbDecodeError> {
-            tracing::debug!("decode value {} for OptionRight", val,);
             OptionRight::from_str(val)
                 .map_or_else(|_| Err(IbDecodeError::UnknownRight(val.into())), Ok)
         }
     }
 
-    pub fn decode<T>(stream: &mut std::str::Split<'_, &str>) -> Result<Option<T>, IbDecodeError>
+    pub fn decode<T>(stream: &mut Fields<'_>) -> Result<Option<T>, IbDecodeError>
     where
         T: FromStr + Sized + Decodable,
         <T as FromStr>::Err: std::fmt::Debug,
     {
-        let str_val = stream.next().unwrap();
-        tracing::debug!(
-            "decode value {} for type {}",
-            str_val,
-            std::any::type_name::<T>()
-        );
+        let str_val = stream.next().ok_or(IbDecodeError::MissingField)?;
         match str_val {
             "" | "1.7976931348623157E308" | "2147483647" => Ok(None),
             _ => {
@@ -145,10 +227,33 @@ pub mod ib_message {
         }
     }
 
+    /// Decodes the next field, which has to be set.
+    pub fn decode_required<T>(stream: &mut Fields<'_>) -> Result<T, IbDecodeError>
+    where
+        T: FromStr + Sized + Decodable,
+        <T as FromStr>::Err: std::fmt::Debug,
+    {
+        decode(stream)?.ok_or_else(|| IbDecodeError::MissingValue(std::any::type_name::<T>()))
+    }
+
+    /// The maximum number of items preallocated by `preallocate`.
+    const MAX_PREALLOCATED: usize = 1024;
+
+    /// Returns a vector for `count` decoded items.
+    ///
+    /// The count is sent by TWS, the preallocation is limited so a corrupt
+    /// count can not exhaust the memory.
+    pub fn preallocate<T>(count: usize) -> Vec<T> {
+        Vec::with_capacity(count.min(MAX_PREALLOCATED))
+    }
+
     pub trait Encodable {
         fn encode(&self) -> String;
     }
 
+    impl Encodable for f32 {
+        fn encode(&self) -> String { self.to_string() + "\0" }
+    }
     impl Encodable for f64 {
         fn encode(&self) -> String { self.to_string() + "\0" }
     }
@@ -161,6 +266,9 @@ pub mod ib_message {
     impl Encodable for Decimal {
         fn encode(&self) -> String { self.to_string() + "\0" }
     }
+    impl Encodable for isize {
+        fn encode(&self) -> String { self.to_string() + "\0" }
+    }
     impl Encodable for usize {
         fn encode(&self) -> String { self.to_string() + "\0" }
     }
@@ -171,6 +279,14 @@ pub mod ib_message {
         fn encode(&self) -> String { self.to_string() + "\0" }
     }
 
+    impl Encodable for TimeStamp {
+        /// Encodes the time as unix timestamp in seconds.
+        fn encode(&self) -> String { self.timestamp().encode() }
+    }
+    impl Encodable for NaiveDate {
+        fn encode(&self) -> String { self.format("%Y%m%d").to_string() + "\0" }
+    }
+
     impl Encodable for bool {
         fn encode(&self) -> String {
             if *self {
@@ -205,6 +321,23 @@ pub mod ib_message {
         }
     }
 
+    /// Decodes a list of "tag=value;" pairs, the inverse of the `Encodable`
+    /// implementation of `Vec<(String, String)>`.
+    pub fn decode_tag_values(
+        stream: &mut Fields<'_>,
+    ) -> Result<Vec<(String, String)>, IbDecodeError> {
+        let str_val = stream.next().ok_or(IbDecodeError::MissingField)?;
+        str_val
+            .split(';')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                pair.split_once('=')
+                    .map(|(tag, value)| (tag.to_string(), value.to_string()))
+                    .ok_or_else(|| IbDecodeError::UnknownString(pair.into()))
+            })
+            .collect()
+    }
+
     // pub fn push_enc<T: Encodable>(str: &mut String, val: T) {
     // str.push_str(&val.encode()); }
 }
@@ -300,6 +433,47 @@ mod tests {
         );
         tracing::debug!("test {:?}", timestamp);
     }
+    #[test]
+    fn fields_are_split_like_str_split() {
+        for msg in [
+            "",
+            "\0",
+            "1",
+            "1\0",
+            "1\02\0",
+            "1\0\03\0",
+            "Zürich\0€\0",
+            "a\0b",
+        ] {
+            let fields: Vec<_> = Fields::new(msg).collect();
+            let expected: Vec<_> = msg.split('\0').collect();
+            assert_eq!(expected, fields, "{msg:?}");
+        }
+    }
+
+    #[test]
+    fn decode_integer() {
+        assert_eq!(42, i32::decode_str("42").unwrap());
+        assert_eq!(-42, i64::decode_str("-42").unwrap());
+        assert_eq!(7, usize::decode_str("+7").unwrap());
+        assert_eq!(i64::MAX, i64::decode_str("9223372036854775807").unwrap());
+        assert!(i32::decode_str("2147483648").is_err());
+        assert!(u32::decode_str("-1").is_err());
+        assert!(i32::decode_str("1.5").is_err());
+        assert!(i32::decode_str("-").is_err());
+    }
+
+    #[test]
+    fn decode_timestamp_epoch() {
+        let timestamp: TimeStamp = TimeStamp::decode_str("1700000000").unwrap();
+        assert_eq!(Utc.timestamp_opt(1_700_000_000, 0).unwrap(), timestamp);
+        let timestamp: TimeStamp = TimeStamp::decode_str("1700000000123").unwrap();
+        assert_eq!(
+            Utc.timestamp_millis_opt(1_700_000_000_123).unwrap(),
+            timestamp
+        );
+    }
+
     #[test]
     fn decode_decimal() {
         let val = "1234.3";
@@ -1,12 +1,36 @@
-use std::io;
+use std::{io, sync::Arc};
 
 // use bytes::BytesMut;
 use tokio::{
     io::{AsyncWriteExt, BufWriter},
     net::tcp::OwnedWriteHalf,
+    sync::{mpsc, oneshot},
 };
 
-use crate::frame::Frame;
+use crate::{frame::Frame,
+            pacing::{RateLimit, RateLimiter},
+            recorder::{Direction, Recorder}};
+
+/// Commands processed by the writer task.
+#[derive(Debug)]
+enum Command {
+    /// write a frame and report the result
+    Frame {
+        frame: Frame,
+        done:  oneshot::Sender<io::Result<()>>,
+    },
+    /// write raw bytes, i.e. the handshake prefix, and report the result
+    Raw {
+        msg:  Vec<u8>,
+        done: oneshot::Sender<io::Result<()>>,
+    },
+    /// hand the socket over to another writer task, ending this one
+    Detach {
+        socket: oneshot::Sender<BufWriter<OwnedWriteHalf>>,
+    },
+    /// continue with `socket`, dropping the previous one
+    Attach { socket: BufWriter<OwnedWriteHalf> },
+}
 
 /// Send and receive `IBFrame` values from a remote peer.
 ///
@@ -20,12 +44,22 @@ use crate::frame::Frame;
 ///
 /// When sending frames, the frame is first encoded into the write buffer.
 /// The contents of the write buffer are then written to the socket.
-#[derive(Debug)]
+///
+/// The socket is owned by a writer task, a `Writer` is a cheap handle sending
+/// the frames to that task through a channel. Clones of a `Writer` share the
+/// task, which lets the connection handler swap in a new socket after a
+/// reconnect while every `Client` handle keeps writing through its own
+/// `Writer`.
+#[derive(Debug, Clone)]
 pub struct Writer {
-    // The `TcpStream`. It is decorated with a `BufWriter`, which provides write
-    // level buffering. The `BufWriter` implementation provided by Tokio is
-    // sufficient for our needs.
-    stream: BufWriter<OwnedWriteHalf>,
+    // Sends the frames to the writer task, which owns the `TcpStream`. It is
+    // decorated with a `BufWriter`, which provides write level buffering. The
+    // `BufWriter` implementation provided by Tokio is sufficient for our
+    // needs.
+    commands: mpsc::UnboundedSender<Command>,
+    // Paces the written frames, so TWS does not disconnect the client for
+    // sending too many messages.
+    limiter:  Arc<RateLimiter>,
     // The buffer for reading frames. Unfortunately, Tokio's `BufReader`
     // currently requires you to empty its buffer before you can ask it to
     // retrieve more data from the underlying stream, so we have to manually
@@ -37,11 +71,15 @@ pub struct Writer {
 }
 
 impl Writer {
-    /// Create a new `Connection`, backed by `socket`. Read and write buffers
-    /// are initialized.
-    pub fn new(socket: OwnedWriteHalf) -> Writer {
+    /// Create a new `Connection`, backed by `socket`. The socket is handed to
+    /// a newly spawned writer task, which runs until all clones of the
+    /// `Writer` are dropped. Every message written is recorded to `recorder`.
+    pub fn new(socket: OwnedWriteHalf, recorder: Option<Recorder>) -> Writer {
+        let (commands, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(Self::run(BufWriter::new(socket), receiver, recorder));
         Writer {
-            stream: BufWriter::new(socket),
+            commands,
+            limiter: Arc::new(RateLimiter::new(RateLimit::default())),
             // Default to a 4KB read buffer. For the use case of mini redis,
             // this is fine. However, real applications will want to tune this
             // value to their specific use case. There is a high likelihood that
@@ -51,6 +89,91 @@ impl Writer {
         }
     }
 
+    /// Write a single `IBFrame` value to the underlying stream.
+    ///
+    /// The frame is paced by the rate limiter and then written by the writer
+    /// task, the returned future completes once it was flushed to the
+    /// socket. Frames written through different handles are never
+    /// interleaved.
+    pub async fn write_frame(&self, frame: &Frame) -> io::Result<()> {
+        tracing::trace!("writing frame: {:?}", frame);
+        self.limiter.acquire().await;
+        let (done, result) = oneshot::channel();
+        self.send(Command::Frame {
+            frame: frame.clone(),
+            done,
+        })?;
+        result.await.unwrap_or_else(|_| Err(closed()))
+    }
+
+    /// Write raw bytes to the underlying stream, bypassing the rate limiter.
+    pub async fn write_raw(&self, msg: &[u8]) -> io::Result<()> {
+        let (done, result) = oneshot::channel();
+        self.send(Command::Raw {
+            msg: msg.to_vec(),
+            done,
+        })?;
+        result.await.unwrap_or_else(|_| Err(closed()))
+    }
+
+    /// Replaces the socket shared by all clones of this `Writer` with the
+    /// socket of `other`, e.g. after a reconnect. The writer task of `other`
+    /// ends and the previous socket is dropped.
+    pub(crate) async fn replace(&self, other: Writer) {
+        let (socket, detached) = oneshot::channel();
+        if other.send(Command::Detach { socket }).is_err() {
+            return;
+        }
+        if let Ok(socket) = detached.await {
+            // the task only ends once all clones of `self` are dropped
+            let _ = self.send(Command::Attach { socket });
+        }
+    }
+
+    /// The rate limiter shared by all clones of this `Writer`.
+    pub(crate) fn rate_limiter(&self) -> &RateLimiter { &self.limiter }
+
+    fn send(&self, command: Command) -> io::Result<()> {
+        self.commands.send(command).map_err(|_| closed())
+    }
+
+    /// The writer task, processes the commands until all `Writer` handles
+    /// are dropped or the socket was handed over. The written messages are
+    /// recorded to `recorder`.
+    async fn run(
+        mut stream: BufWriter<OwnedWriteHalf>,
+        mut commands: mpsc::UnboundedReceiver<Command>,
+        recorder: Option<Recorder>,
+    ) {
+        while let Some(command) = commands.recv().await {
+            match command {
+                Command::Frame { frame, done } => {
+                    let res = Self::write(&mut stream, &frame).await;
+                    if let (Ok(()), Some(recorder)) = (&res, &recorder) {
+                        recorder.record(Direction::Outbound, frame.parts());
+                    }
+                    // the caller may have given up waiting
+                    let _ = done.send(res);
+                },
+                Command::Raw { msg, done } => {
+                    let res = match stream.write_all(&msg).await {
+                        Ok(()) => stream.flush().await,
+                        Err(err) => Err(err),
+                    };
+                    if let (Ok(()), Some(recorder)) = (&res, &recorder) {
+                        recorder.record(Direction::Outbound, [&msg[..]]);
+                    }
+                    let _ = done.send(res);
+                },
+                Command::Detach { socket } => {
+                    let _ = socket.send(stream);
+                    return;
+                },
+                Command::Attach { socket } => stream = socket,
+            }
+        }
+    }
+
     /// Write a single `IBFrame` value to the underlying stream.
     ///
     /// The `IBFrame` value is written to the socket using the various `write_*`
@@ -59,48 +182,34 @@ impl Writer {
     /// of syscalls. However, it is fine to call these functions on a
     /// *buffered* write stream. The data will be written to the buffer.
     /// Once the buffer is full, it is flushed to the underlying socket.
-    pub async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
+    async fn write(stream: &mut BufWriter<OwnedWriteHalf>, frame: &Frame) -> io::Result<()> {
         // Arrays are encoded by encoding each entry. All other frame types are
         // considered literals. For now, mini-redis is not able to encode
         // recursive frame structures. See below for more details.
-        tracing::trace!("writing frame: {:?}", frame);
         match frame {
             Frame::Array(val) => {
                 // Iterate and encode each entry in the array.
                 for entry in &**val {
-                    self.write_value(entry).await?;
+                    Self::write_value(stream, entry).await?;
                 }
             },
             // The frame type is a literal. Encode the value directly.
-            _ => self.write_value(frame).await?,
+            _ => Self::write_value(stream, frame).await?,
         }
 
         // Ensure the encoded frame is written to the socket. The calls above
         // are to the buffered stream and writes. Calling `flush` writes the
         // remaining contents of the buffer to the socket.
-        self.stream.flush().await
-    }
-
-    /// Write a single `IBFrame` value to the underlying stream.
-    ///
-    /// The `IBFrame` value is written to the socket using the various `write_*`
-    /// functions provided by `AsyncWrite`. Calling these functions directly on
-    /// a `TcpStream` is **not** advised, as this will result in a large number
-    /// of syscalls. However, it is fine to call these functions on a
-    /// *buffered* write stream. The data will be written to the buffer.
-    /// Once the buffer is full, it is flushed to the underlying socket.
-    pub async fn write_raw(&mut self, msg: &[u8]) -> io::Result<()> {
-        self.stream.write_all(msg).await?;
-        self.stream.flush().await
+        stream.flush().await
     }
 
     /// Write a frame literal to the stream
-    async fn write_value(&mut self, frame: &Frame) -> io::Result<()> {
+    async fn write_value(stream: &mut BufWriter<OwnedWriteHalf>, frame: &Frame) -> io::Result<()> {
         match frame {
             Frame::Bulk(val) => {
                 let _len = val.len();
 
-                self.stream.write_all(val).await?;
+                stream.write_all(val).await?;
             },
             // Encoding an `Array` from within a value cannot be done using a
             // recursive strategy. In general, async fns do not support
@@ -112,3 +221,54 @@ impl Writer {
         Ok(())
     }
 }
+
+/// The error returned once the writer task has ended.
+fn closed() -> io::Error { io::Error::new(io::ErrorKind::BrokenPipe, "the writer task has ended") }
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use pretty_assertions::assert_eq;
+    use tokio::{io::AsyncReadExt,
+                net::{TcpListener, TcpStream}};
+
+    use super::*;
+
+    /// Returns a `Writer` and the peer reading its socket.
+    async fn connected() -> (Writer, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let socket = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (peer, _) = listener.accept().await.unwrap();
+        let (_, write_half) = socket.into_split();
+        (Writer::new(write_half, None), peer)
+    }
+
+    async fn read(peer: &mut TcpStream, len: usize) -> String {
+        let mut buf = vec![0; len];
+        peer.read_exact(&mut buf).await.unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[tokio::test]
+    async fn clones_share_the_writer_task() {
+        let (writer, mut peer) = connected().await;
+        let clone = writer.clone();
+        let frame = |msg: &'static str| Frame::Bulk(Bytes::from(msg));
+        let (first, second) = (frame("first"), frame("second"));
+        let (first, second) = tokio::join!(writer.write_frame(&first), clone.write_frame(&second));
+        first.unwrap();
+        second.unwrap();
+        let written = read(&mut peer, 11).await;
+        assert!(written == "firstsecond" || written == "secondfirst");
+
+        // after replacing the socket all clones write to the new one
+        let (other, mut other_peer) = connected().await;
+        writer.replace(other).await;
+        clone.write_frame(&frame("third")).await.unwrap();
+        assert_eq!("third", read(&mut other_peer, 5).await);
+        // the previous socket was dropped
+        assert_eq!(0, peer.read(&mut [0; 1]).await.unwrap());
+    }
+}
@@ -0,0 +1,124 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::RequestId;
+
+/// Selects the Wall Street Horizon events requested with
+/// `Client::request_wsh_event_data`.
+///
+/// Either `con_id` or `filter` has to be set. The filter, watchlist/portfolio
+/// flags and the date range are only supported by servers with version 171
+/// (173 for the date range) and later, requests using them fail with
+/// `Error::Unsupported` on older servers.
+#[derive(Debug, Clone, Default)]
+pub struct WshEventDataFilter {
+    pub con_id:           Option<i32>,
+    /// JSON encoded filter, see the meta data for the available filters
+    pub filter:           Option<String>,
+    pub fill_watchlist:   bool,
+    pub fill_portfolio:   bool,
+    pub fill_competitors: bool,
+    /// start date of the events, formatted as "yyyyMMdd"
+    pub start_date:       Option<String>,
+    /// end date of the events, formatted as "yyyyMMdd"
+    pub end_date:         Option<String>,
+    pub total_limit:      Option<i32>,
+}
+
+impl WshEventDataFilter {
+    pub fn with_con_id(con_id: i32) -> Self {
+        WshEventDataFilter {
+            con_id: Some(con_id),
+            ..Default::default()
+        }
+    }
+}
+
+/// Raw Wall Street Horizon event data as sent by TWS.
+#[derive(Debug, Clone)]
+pub struct WshEventData {
+    pub req_id:    RequestId,
+    pub data_json: String,
+}
+
+impl WshEventData {
+    /// Decodes the JSON payload into events.
+    ///
+    /// TWS sends either a single event object or an array of events.
+    pub fn events(&self) -> Result<Vec<WshEvent>, serde_json::Error> {
+        match serde_json::from_str::<Value>(&self.data_json)? {
+            Value::Array(events) => events.into_iter().map(serde_json::from_value).collect(),
+            event => Ok(vec![serde_json::from_value(event)?]),
+        }
+    }
+}
+
+/// A single Wall Street Horizon event, e.g. an earnings date.
+///
+/// Only the event type is decoded into a typed field, all other attributes
+/// are kept in `fields` as they differ between the event types.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WshEvent {
+    #[serde(default)]
+    pub event_type: Option<String>,
+    #[serde(flatten)]
+    pub fields:     Map<String, Value>,
+}
+
+impl WshEvent {
+    /// Returns the attribute `name` of the event.
+    ///
+    /// Attributes nested in the `data` object of the event are found as well.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.fields.get(name).or_else(|| {
+            self.fields
+                .get("data")
+                .and_then(Value::as_object)
+                .and_then(|data| data.get(name))
+        })
+    }
+
+    /// Returns the earnings date of an earnings event.
+    pub fn earnings_date(&self) -> Option<NaiveDate> {
+        let date = self.field("earnings_date")?.as_str()?;
+        NaiveDate::parse_from_str(date, "%Y%m%d")
+            .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn decode_wsh_events() {
+        let data = WshEventData {
+            req_id:    1,
+            data_json: r#"[
+                {"event_type":"wshe_ed","data":{"earnings_date":"20240201","time_of_day":"AMC"}},
+                {"event_type":"wshe_div","ex_date":"2024-02-09"}
+            ]"#
+            .to_string(),
+        };
+        let events = data.events().unwrap();
+        assert_eq!(2, events.len());
+        assert_eq!(Some("wshe_ed".to_string()), events[0].event_type);
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2024, 2, 1),
+            events[0].earnings_date()
+        );
+        assert_eq!(
+            Some("AMC"),
+            events[0].field("time_of_day").and_then(Value::as_str)
+        );
+        assert_eq!(None, events[1].earnings_date());
+        assert_eq!(
+            Some("2024-02-09"),
+            events[1].field("ex_date").and_then(Value::as_str)
+        );
+    }
+}
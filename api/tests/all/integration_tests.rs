@@ -1,58 +1,67 @@
-use std::{net::{IpAddr, Ipv4Addr, SocketAddr},
-          thread};
-
+use futures::StreamExt;
 use ibkr_rust_api::{client,
+                    mock_tws::{Message, MockTws},
                     prelude::{Contract, *}};
-fn get_client_addr() -> SocketAddr {
-    // SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 62)), 4444)
-    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 62)), 1111)
-}
-#[tokio::test]
+
+#[tokio::test(flavor = "multi_thread")]
 async fn request_account_updates() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 99).await?;
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqAcctData, |_| {
+        vec![
+            Message::new(Incoming::AcctValue).field(2).fields([
+                "NetLiquidation",
+                "100000.25",
+                "USD",
+                "DU3293378",
+            ]),
+            Message::new(Incoming::AcctUpdateTime)
+                .field(1)
+                .field("09:45"),
+        ]
+    });
+    let client = client::connect(tws.addr(), 99).await?;
     client
         .request_account_updates(true, "DU3293378".to_string())
         .await?;
-    // tokio::spawn(async move {
-    //    let mut account_receiver = client.account_tracker;
-    //    while let summary = account_receiver.recv() {
-    //        //tracing::debug!(
-    //        //    "{:?}\t-\tunrealized pnl:  {:?}",
-    //        //    account_receiver.unrealized_pnl()
-    //        //);
-    //    }
-    //});
-    thread::spawn(move || {
-        let account_receiver = client.account_tracker;
-        loop {
-            tracing::debug!("got updates at:  {:?}", account_receiver.recv());
-        }
-        // tracing::debug!("got order with state:  {:?}", order_state);
-    });
-    tokio::time::sleep(std::time::Duration::from_secs(180)).await;
-    // tracing::debug!("next valid order id: {:?}", order_id);
+    let account = client.account_tracker.recv().unwrap();
+    assert_eq!("DU3293378", account.account);
+    assert_eq!("100000.25", account.value);
+    assert_eq!("USD", account.currency);
+    assert!(client.account_update_tracker.recv().is_ok());
     Ok(())
 }
 
 #[tokio::test]
 async fn request_next_valid_order_id() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 99).await?;
-    let order_id = &client.request_ids().await?;
-    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-    tracing::debug!("next valid order id: {:?}", order_id);
+    let tws = MockTws::start().await?;
+    tws.set_next_order_id(1234);
+    let client = client::connect(tws.addr(), 99).await?;
+    let order_id = client.get_next_valid_order_id().await?;
+    assert_eq!(1234, order_id);
     Ok(())
 }
-#[tokio::test]
+
+/// The contract details of AMD as sent by TWS.
+fn amd_contract_details(req_id: RequestId) -> Message {
+    Message::new(Incoming::ContractData)
+        .field(req_id)
+        .fields(["AMD", "STK", "", "0", "", "SMART", "USD", "AMD"])
+        .fields(["NMS", "NMS", "4391", "0.01", ""])
+        .fields(["ACTIVETIM,AD,ADJUST,ALERT", "SMART,AMEX,NYSE", "1", "0"])
+        .fields(["ADVANCED MICRO DEVICES", "NASDAQ", "", "Technology"])
+        .fields(["Semiconductors", "Electronic Compo-Semicon", "US/Eastern"])
+        .fields(["", "", "", "", "1", "ISIN", "US0079031078", "1", "", "", ""])
+        .fields(["26,26", "", "COMMON"])
+}
+
+#[tokio::test(flavor = "multi_thread")]
 async fn request_contract_details() -> Result<()> {
-    // Open a connection to the mini-redis address.
-    let mut client = client::connect(get_client_addr(), 1).await?;
-    let contracts = client.subscribe_contract_details().clone();
-    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-    thread::spawn(move || {
-        while let Ok(contract) = contracts.recv() {
-            tracing::error!("got contracts:  {:#?}", contract);
-        }
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqContractData, |_| {
+        vec![amd_contract_details(1), Message::contract_data_end(1)]
     });
+    let client = client::connect(tws.addr(), 1).await?;
+    let contracts = client.subscribe_contract_details();
     let _contract2 = Contract {
         symbol: "AMD".to_string(),
         exchange: Some("SMART".to_string()),
@@ -61,22 +70,34 @@ async fn request_contract_details() -> Result<()> {
         ..Default::default()
     };
 
-    let _ = &client.request_contract_details(1, _contract2).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    client.request_contract_details(1, _contract2).await?;
+    let details = contracts.recv().unwrap();
+    assert_eq!(1, details.req_id);
+    let details = details.response.unwrap();
+    assert_eq!("AMD", details.contract.symbol);
+    assert_eq!(Some(4391), details.contract.con_id);
+    assert_eq!(
+        Some("NASDAQ".to_string()),
+        details.contract.primary_exchange
+    );
+    assert_eq!(
+        Some("ADVANCED MICRO DEVICES".to_string()),
+        details.long_name
+    );
+    assert!(tws
+        .requests()
+        .iter()
+        .any(|request| request.is(Outgoing::ReqContractData) && request.field(3) == Some("AMD")));
     Ok(())
 }
 
 #[tokio::test]
 async fn request_contract_details_stream() -> Result<()> {
-    // Open a connection to the mini-redis address.
-    let mut client = client::connect(get_client_addr(), 1).await?;
-    let contracts = client.subscribe_contract_details().clone();
-    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-    thread::spawn(move || {
-        while let Ok(contract) = contracts.recv() {
-            tracing::error!("got contracts:  {:#?}", contract);
-        }
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqContractData, |_| {
+        vec![amd_contract_details(1), Message::contract_data_end(1)]
     });
+    let client = client::connect(tws.addr(), 1).await?;
     let _contract2 = Contract {
         symbol: "AMD".to_string(),
         exchange: Some("SMART".to_string()),
@@ -85,106 +106,97 @@ async fn request_contract_details_stream() -> Result<()> {
         ..Default::default()
     };
 
-    let _ = &client.request_contract_details(1, _contract2).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    let details = client.get_contract_details(1, _contract2).await;
+    futures::pin_mut!(details);
+    let amd = details.next().await.unwrap()?;
+    assert_eq!("AMD", amd.contract.symbol);
+    // the stream ends after the last contract
+    assert!(details.next().await.is_none());
     Ok(())
 }
 
-// async fn contract_details_async(mut client: Client, contract: Contract) ->
-// ContractDetails {     let contracts =
-// client.subscribe_contract_details().clone();     let _ =
-// &client.request_contract_details(1, _contract2).await?;     contracts.
-// try_iter().filter(|c| c.callable) }
-
 #[tokio::test]
-async fn orders_auto_open() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 0).await?;
-    let orders = client.order_tracker.order.clone();
-    let executions = client.order_tracker.executions.clone();
-    thread::spawn(move || {
-        while let Ok(order) = orders.recv() {
-            tracing::debug!("got order:  {:?}", order);
-        }
+async fn request_contract_details_rejected() -> Result<()> {
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqContractData, |_| {
+        vec![Message::error(
+            Some(1),
+            200,
+            "No security definition has been found for the request",
+        )]
     });
-    thread::spawn(move || {
-        while let Ok(order) = executions.recv() {
-            tracing::debug!("got executions:  {:?}", order);
-        }
+    let client = client::connect(tws.addr(), 1).await?;
+
+    let details = client.get_contract_details(1, Contract::default()).await;
+    futures::pin_mut!(details);
+    match details.next().await {
+        Some(Err(Error::Tws(message))) => assert_eq!(Some(1), message.req_id),
+        other => panic!("unexpected response: {:?}", other),
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn orders_auto_open() -> Result<()> {
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqAutoOpenOrders, |_| {
+        vec![Message::order_status(7, "Submitted", 0.0, 100.0, 0.0)]
     });
+    let client = client::connect(tws.addr(), 0).await?;
     let _ = client.request_auto_open_orders(true).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+    let status = client.order_tracker.order_status.recv().unwrap();
+    assert_eq!(7, status.order_id);
+    assert!(matches!(status.status, OrderStatus::Submitted));
     Ok(())
 }
 
-#[tokio::test]
+#[tokio::test(flavor = "multi_thread")]
 async fn orders_all_open() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 0).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-    let rec_order = client.subscribe_orders().order.clone();
-    let rec_state = client.subscribe_orders().order_state.clone();
-    let rec_status = client.subscribe_orders().order_status.clone();
-    thread::spawn(move || {
-        while let Ok(order) = rec_order.recv() {
-            tracing::debug!("got order:  {:#?}", order);
-        }
-    });
-    thread::spawn(move || {
-        while let Ok(state) = rec_state.recv() {
-            tracing::debug!("got state:  {:#?}", state);
-        }
-    });
-    thread::spawn(move || {
-        while let Ok(status) = rec_status.recv() {
-            tracing::debug!("got status:  {:?}", status);
-        }
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqAllOpenOrders, |_| {
+        vec![
+            Message::order_status(8, "PreSubmitted", 0.0, 10.0, 0.0),
+            Message::order_status(9, "Filled", 5.0, 0.0, 101.5),
+            Message::new(Incoming::OpenOrderEnd).field(1),
+        ]
     });
+    let client = client::connect(tws.addr(), 0).await?;
+    let rec_status = client.subscribe_orders().order_status.clone();
     let _ = client.request_all_open_orders().await?;
-    tokio::time::sleep(std::time::Duration::from_secs(60)).await;
-    // signal::ctrl_c().await.expect("failed to listen for event");
+    let first = rec_status.recv().unwrap();
+    assert_eq!(8, first.order_id);
+    let second = rec_status.recv().unwrap();
+    assert_eq!(9, second.order_id);
+    assert!(matches!(second.status, OrderStatus::Filled));
+    assert_eq!(rust_decimal_macros::dec!(101.5), second.avg_fill_price);
     Ok(())
 }
 
 #[tokio::test]
 async fn orders_completed() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 0).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-    let rec_order = client.subscribe_orders().order.clone();
-    let rec_state = client.subscribe_orders().order_state.clone();
-    let rec_status = client.subscribe_orders().order_status.clone();
-    thread::spawn(move || {
-        loop {
-            tracing::error!("got order:  {:#?}", rec_order.recv());
-        }
-        // while let Ok(order) = rec_order.recv() {
-        //     tracing::error!("got order:  {:#?}", order);
-        // }
-    });
-    thread::spawn(move || {
-        while let Ok(state) = rec_state.recv() {
-            tracing::error!("got state:  {:#?}", state);
-        }
-    });
-    thread::spawn(move || {
-        while let Ok(status) = rec_status.recv() {
-            tracing::error!("got status:  {:?}", status);
-        }
-    });
+    let tws = MockTws::start().await?;
+    let client = client::connect(tws.addr(), 0).await?;
+    // the order id sent after the API was started must not answer the
+    // request below
+    client.managed_accounts().await?;
     let _ = client.request_completed_orders(false).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(60)).await;
-    // signal::ctrl_c().await.expect("failed to listen for event");
+    // the requests are answered in order, the order id confirms the request
+    // was received
+    client.get_next_valid_order_id().await?;
+    let request = tws
+        .requests()
+        .into_iter()
+        .find(|request| request.is(Outgoing::ReqCompletedOrders))
+        .unwrap();
+    assert_eq!(Some("0"), request.field(0));
     Ok(())
 }
+
 #[tokio::test]
 async fn executions_filtered() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 0).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-    let receiver = client.order_tracker.executions.clone();
-    thread::spawn(move || {
-        while let Ok(fill) = receiver.recv() {
-            tracing::error!("got order:  {:?}", fill);
-            // tracing::debug!("got order with state:  {:?}", order_state);
-        }
-    });
+    let tws = MockTws::start().await?;
+    let client = client::connect(tws.addr(), 0).await?;
+    client.managed_accounts().await?;
     let filter = Some(ExecutionFilter {
         client_id: None,
         account_code: "U11213636".to_string(),
@@ -193,14 +205,23 @@ async fn executions_filtered() -> Result<()> {
         ..Default::default()
     });
     client.request_executions(1, filter).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(60)).await;
-    // signal::ctrl_c().await.expect("failed to listen for event");
+    client.get_next_valid_order_id().await?;
+    let request = tws
+        .requests()
+        .into_iter()
+        .find(|request| request.is(Outgoing::ReqExecutions))
+        .unwrap();
+    assert!(request.fields.iter().any(|field| field == "U11213636"));
+    assert!(request
+        .fields
+        .iter()
+        .any(|field| field == "20220824-00:12:59"));
     Ok(())
 }
 
 #[tokio::test]
 async fn place_market_order() -> Result<()> {
-    // let mut client = client::connect(get_client_addr(), 2).await?;
+    // let client = client::connect(get_client_addr(), 2).await?;
     //// client.request_auto_open_orders(true).await?;
     // let order_id = &client.get_next_valid_order_id().await?;
     // let contract = Contract {
@@ -230,7 +251,7 @@ async fn place_market_order() -> Result<()> {
 
 //#[tokio::test]
 // async fn request_orders() {
-//    let mut client = match IBClient::connect("192.168.1.96".to_string(), 4444,
+//    let client = match IBClient::connect("192.168.1.96".to_string(), 4444,
 // 0, "").await {        Ok(client) => client,
 //        Err(_error) => panic!("Connection not successful!"),
 //    };
@@ -256,7 +277,7 @@ async fn place_market_order() -> Result<()> {
 //
 //#[tokio::test]
 // async fn place_spread_market_order() {
-//    let mut client = match IBClient::connect("192.168.1.96".to_string(),4444,
+//    let client = match IBClient::connect("192.168.1.96".to_string(),4444,
 // 1, "").await {        Ok(client) => client,
 //        Err(_error) => panic!("Connection not successful!")
 //    };
@@ -285,7 +306,7 @@ async fn place_market_order() -> Result<()> {
 //
 //#[tokio::test]
 // async fn market_data() {
-//    let mut client = match IBClient::connect("192.168.1.96".to_string(), 4444,
+//    let client = match IBClient::connect("192.168.1.96".to_string(), 4444,
 // 1, "").await {        Ok(client) => client,
 //        Err(_error) => panic!("Connection not successful!"),
 //    };
@@ -316,7 +337,7 @@ async fn place_market_order() -> Result<()> {
 //
 //#[tokio::test]
 // async fn historical_data() {
-//    let mut client = match IBClient::connect("192.168.1.96".to_string(),4444,
+//    let client = match IBClient::connect("192.168.1.96".to_string(),4444,
 // 1, "").await {        Ok(client) => client,
 //        Err(_error) => panic!("Connection not successful!")
 //    };
@@ -1,17 +1,38 @@
-// use futures_util::{pin_mut, stream::StreamExt};
-use std::{net::{IpAddr, Ipv4Addr, SocketAddr},
-          thread};
-
 use chrono::Utc;
+use futures::StreamExt;
 use ibkr_rust_api::{client,
+                    mock_tws::{Message, MockTws},
                     prelude::{Contract, *}};
-fn get_client_addr() -> SocketAddr {
-    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 62)), 1111)
+
+fn amd() -> Contract {
+    Contract {
+        symbol: "AMD".to_string(),
+        exchange: Some("SMART".to_string()),
+        sec_type: SecType::Stock,
+        currency: "USD".to_string(),
+        ..Default::default()
+    }
 }
+
 #[tokio::test]
 async fn market_data_realtime_bars() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 10).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqRealTimeBars, |_| {
+        vec![Message::new(Incoming::RealTimeBars)
+            .field(3)
+            .field(1000)
+            .fields([
+                "1704205800",
+                "185.1",
+                "185.5",
+                "184.9",
+                "185.2",
+                "1200",
+                "185.25",
+                "17",
+            ])]
+    });
+    let client = client::connect(tws.addr(), 10).await?;
     let contract = Contract {
         symbol: "AAPL".to_string(),
         exchange: Some("SMART".to_string()),
@@ -19,17 +40,7 @@ async fn market_data_realtime_bars() -> Result<()> {
         currency: "USD".to_string(),
         ..Default::default()
     };
-    let receiver = client.market_data_tracker.bars.clone();
-    thread::spawn(move || {
-        let mut count: i32 = 0;
-        while let Ok(tick) = receiver.recv() {
-            tracing::error!("received: {:?}", tick);
-            count += 1;
-            if count > 3 {}
-        }
-        assert!(count > 0);
-    });
-    let _orders = client
+    let mut bars = client
         .request_realtime_bars(&RealtimeBarRequest {
             req_id: 1000,
             contract,
@@ -39,17 +50,27 @@ async fn market_data_realtime_bars() -> Result<()> {
             real_time_bars_options: vec![],
         })
         .await?;
-    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    let bar = bars.next().await.unwrap()?;
+    assert_eq!(1000, bar.id);
+    assert_eq!(17, bar.data.count);
     let _ = client
         .cancel_realtime_bars(&CancelRealtimeBars { req_id: 1000 })
         .await?;
+    // the stream ends once the request was cancelled
+    assert!(bars.next().await.is_none());
     Ok(())
 }
 
 #[tokio::test]
 async fn market_data_market_data() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 10).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqMktData, |_| {
+        vec![
+            Message::tick_price(1000, 1, 1.0845, 1_000_000),
+            Message::tick_price(1000, 2, 1.0846, 2_000_000),
+        ]
+    });
+    let client = client::connect(tws.addr(), 10).await?;
     let contract = Contract {
         symbol: "EUR".to_string(),
         exchange: Some("IDEALPRO".to_string()),
@@ -57,17 +78,7 @@ async fn market_data_market_data() -> Result<()> {
         currency: "USD".to_string(),
         ..Default::default()
     };
-    let receiver = client.market_data_tracker.tick_by_tick.clone();
-    thread::spawn(move || {
-        let mut count: i32 = 0;
-        while let Ok(tick) = receiver.recv() {
-            tracing::error!("received: {:?}", tick);
-            count += 1;
-            if count > 3 {}
-        }
-        assert!(count > 0);
-    });
-    let _orders = client
+    let mut ticks = client
         .request_market_data(&MarketDataRequest {
             req_id: 1000,
             contract,
@@ -75,19 +86,95 @@ async fn market_data_market_data() -> Result<()> {
             snapshot: false,
             regulatory: false,
             additional_data: vec![],
-        }) //           1000, contract, false, false, vec![])
+        })
         .await?;
-    tokio::time::sleep(std::time::Duration::from_secs(50)).await;
+    for _ in 0..2 {
+        match ticks.next().await.unwrap()? {
+            Tick::Price(tick) => assert_eq!(1000, tick.id),
+            tick => panic!("unexpected tick: {:?}", tick),
+        }
+    }
+    tws.push(&Message::tick_size(1000, 0, 3_000_000));
+    assert!(matches!(ticks.next().await.unwrap()?, Tick::Size(_)));
     let _ = client
         .cancel_market_data(&CancelMarketDataRequest { req_id: 1000 })
         .await?;
     Ok(())
 }
 
+#[tokio::test]
+async fn market_data_snapshot_ends() -> Result<()> {
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqMktData, |_| {
+        vec![
+            Message::tick_price(1001, 1, 1.0845, 1_000_000),
+            Message::new(Incoming::TickSnapshotEnd).field(1).field(1001),
+        ]
+    });
+    let client = client::connect(tws.addr(), 10).await?;
+    let mut ticks = client
+        .request_market_data(&MarketDataRequest {
+            req_id:            1001,
+            contract:          amd(),
+            generic_tick_list: vec![],
+            snapshot:          true,
+            regulatory:        false,
+            additional_data:   vec![],
+        })
+        .await?;
+    assert!(matches!(ticks.next().await.unwrap()?, Tick::Price(_)));
+    // the stream ends with the snapshot
+    assert!(ticks.next().await.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn market_data_ignores_order_errors() -> Result<()> {
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqMktData, |_| {
+        vec![Message::tick_price(7, 1, 1.0845, 1_000_000)]
+    });
+    let client = client::connect(tws.addr(), 10).await?;
+    let mut ticks = client
+        .request_market_data(&MarketDataRequest {
+            req_id:            7,
+            contract:          amd(),
+            generic_tick_list: vec![],
+            snapshot:          false,
+            regulatory:        false,
+            additional_data:   vec![],
+        })
+        .await?;
+    assert!(matches!(ticks.next().await.unwrap()?, Tick::Price(_)));
+    // the rejection of order 7 does not end the subscription of request 7
+    tws.push(&Message::error(Some(7), 201, "Order rejected"));
+    tws.push(&Message::tick_size(7, 0, 3_000_000));
+    assert!(matches!(ticks.next().await.unwrap()?, Tick::Size(_)));
+    Ok(())
+}
+
+/// A single one second bar answering the historical data request `req_id`.
+fn historical_bars(req_id: RequestId) -> Message {
+    Message::new(Incoming::HistoricalData)
+        .field(req_id)
+        .fields(["1704205800", "1704207600", "1"])
+        .fields([
+            "1704205800",
+            "137.1",
+            "137.3",
+            "137.0",
+            "137.2",
+            "500",
+            "137.15",
+            "4",
+        ])
+}
+
 #[tokio::test]
 async fn market_data_historical_data() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 10).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqHistoricalData, |_| vec![historical_bars(1010)]);
+    let client = client::connect(tws.addr(), 10).await?;
     let contract = Contract {
         con_id: Some(76792991),
         // symbol: "TSLA".to_string(),
@@ -96,18 +183,7 @@ async fn market_data_historical_data() -> Result<()> {
         // currency: "USD".to_string(),
         ..Default::default()
     };
-    let receiver = client.market_data_tracker.historical_bars.clone();
-    thread::spawn(move || {
-        let mut count: i32 = 0;
-        while let Ok(_fill) = receiver.recv() {
-            tracing::error!("received: {:?}", _fill);
-            count += 1;
-            if count > 3 {}
-        }
-        tracing::error!("received: {:?}", count);
-        assert!(count > 0);
-    });
-    let _ = client
+    let mut bars = client
         .request_historical_data(&HistoricalDataRequest {
             req_id: 1010,
             contract,
@@ -121,34 +197,25 @@ async fn market_data_historical_data() -> Result<()> {
             chart_options: vec![],
         })
         .await?;
-
-    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    let series = bars.next().await.unwrap()?;
+    assert_eq!(1010, series.id);
+    assert_eq!(1, series.data.bars.len());
+    // the request is answered by a single message
+    assert!(bars.next().await.is_none());
     Ok(())
 }
 
 #[tokio::test]
 async fn market_data_historical_schedule() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 10).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    let tws = MockTws::start().await?;
+    let client = client::connect(tws.addr(), 10).await?;
     let contract = Contract {
-        symbol: "AMD".to_string(),
         exchange: Some("ISLAND".to_string()),
-        sec_type: SecType::Stock,
-        currency: "USD".to_string(),
-        ..Default::default()
+        ..amd()
     };
-    let receiver = client.market_data_tracker.historical_schedule.clone();
-    thread::spawn(move || {
-        let mut count: i32 = 0;
-        while let Ok(_fill) = receiver.recv() {
-            count += 1;
-            tracing::error!("received: {:#?}", _fill);
-            if count > 3 {}
-        }
-        tracing::error!("received: {:?}", count);
-        assert!(count > 0);
-    });
-    let _ = client
+    // the schedule needs server version 166, newer than the client versions
+    // supported by the crate
+    let result = client
         .request_historical_data(&HistoricalDataRequest {
             req_id: 1010,
             contract,
@@ -161,35 +228,31 @@ async fn market_data_historical_schedule() -> Result<()> {
             keep_up_to_date: false,
             chart_options: vec![],
         })
-        .await?;
-
-    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+        .await;
+    assert!(matches!(result, Err(Error::Unsupported { .. })));
+    assert!(!tws
+        .requests()
+        .iter()
+        .any(|request| request.is(Outgoing::ReqHistoricalData)));
     Ok(())
 }
 
 #[tokio::test]
 async fn market_data_historical_tick() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 10).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-    let contract = Contract {
-        symbol: "AMD".to_string(),
-        exchange: Some("SMART".to_string()),
-        sec_type: SecType::Stock,
-        currency: "USD".to_string(),
-        ..Default::default()
-    };
-    let receiver = client.market_data_tracker.historical_ticks.clone();
-    thread::spawn(move || {
-        let mut count: i32 = 0;
-        while let Ok(_tick) = receiver.recv() {
-            count += 1;
-        }
-        assert!(count > 0);
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqHistoricalTicks, |_| {
+        vec![Message::new(Incoming::HistoricalTicksLast)
+            .field(1020)
+            .field(2)
+            .fields(["1704205800", "0", "137.1", "100", "NASDAQ", ""])
+            .fields(["1704205801", "0", "137.2", "200", "ARCA", "I"])
+            .field(1)]
     });
-    let _ = client
+    let client = client::connect(tws.addr(), 10).await?;
+    let mut ticks = client
         .request_historical_ticks(&HistoricalTicksRequest {
             req_id:          1020,
-            contract:        contract.clone(),
+            contract:        amd(),
             date_time:       HistoricalTickDateTime::End(Utc::now()),
             number_of_ticks: 10,
             what_to_show:    HistoricalDataType::Trades,
@@ -198,33 +261,22 @@ async fn market_data_historical_tick() -> Result<()> {
             misc_options:    Vec::new(),
         })
         .await?;
-
-    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    let batch = ticks.next().await.unwrap()?;
+    assert_eq!(2, batch.ticks.len());
+    assert!(batch.done);
+    // the stream ends after the last batch
+    assert!(ticks.next().await.is_none());
     Ok(())
 }
 #[tokio::test]
 async fn market_data_historical_bars() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 10).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-    let contract = Contract {
-        symbol: "AMD".to_string(),
-        exchange: Some("SMART".to_string()),
-        sec_type: SecType::Stock,
-        currency: "USD".to_string(),
-        ..Default::default()
-    };
-    let receiver = client.market_data_tracker.historical_bars.clone();
-    thread::spawn(move || {
-        let mut count: i32 = 0;
-        while let Ok(_tick) = receiver.recv() {
-            count += 1;
-        }
-        assert!(count > 0);
-    });
-    let _ = client
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqHistoricalData, |_| vec![historical_bars(1020)]);
+    let client = client::connect(tws.addr(), 10).await?;
+    let mut bars = client
         .request_historical_data(&HistoricalDataRequest {
             req_id:           1020,
-            contract:         contract.clone(),
+            contract:         amd(),
             end_date_time:    Utc::now(),
             duration:         Duration::Seconds(1800),
             bar_size_setting: BarSize::_1Secs,
@@ -235,82 +287,75 @@ async fn market_data_historical_bars() -> Result<()> {
             use_rth:          UseRegularTradingHoursOnly::Use,
         })
         .await?;
-
-    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    let series = bars.next().await.unwrap()?;
+    assert_eq!(1020, series.id);
+    assert_eq!(4, series.data.bars[0].count);
     Ok(())
 }
 #[tokio::test]
 async fn market_data_historical_head_timestamp() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 10).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-    let contract = Contract {
-        symbol: "AMD".to_string(),
-        exchange: Some("SMART".to_string()),
-        sec_type: SecType::Stock,
-        currency: "USD".to_string(),
-        ..Default::default()
-    };
-    let receiver = client.market_data_tracker.head_timestamp.clone();
-    thread::spawn(move || {
-        let mut count: i32 = 0;
-        while let Ok(time) = receiver.recv() {
-            count += 1;
-            tracing::error!("head timestamp: {:#?}", time);
-        }
-        assert!(count > 0);
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqHeadTimestamp, |_| {
+        vec![Message::new(Incoming::HeadTimestamp)
+            .field(1010)
+            .field("1704205800")]
     });
-    let _ = client
+    let client = client::connect(tws.addr(), 10).await?;
+    let mut timestamp = client
         .request_head_time_stamp(&HeadTimestampRequest {
-            req_id: 1010,
-            contract,
+            req_id:       1010,
+            contract:     amd(),
             what_to_show: HistoricalDataType::Trades,
-            use_rth: UseRegularTradingHoursOnly::DontUse,
-            format_date: IntradayBarDateFormat::YYYYMMDD,
+            use_rth:      UseRegularTradingHoursOnly::DontUse,
+            format_date:  IntradayBarDateFormat::UnixEpochSeconds,
         })
         .await?;
-
-    tokio::time::sleep(std::time::Duration::from_secs(6)).await;
+    let head = timestamp.next().await.unwrap()?;
+    assert_eq!(1010, head.id);
+    assert_eq!(1_704_205_800, head.timestamp.timestamp());
     Ok(())
 }
 #[tokio::test]
 async fn market_data_tick_by_tick() -> Result<()> {
-    let mut client = client::connect(get_client_addr(), 10).await?;
-    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-    let contract = Contract {
-        symbol: "AMD".to_string(),
-        exchange: Some("SMART".to_string()),
-        sec_type: SecType::Stock,
-        currency: "USD".to_string(),
-        ..Default::default()
-    };
-    let receiver = client.market_data_tracker.tick_by_tick.clone();
-    thread::spawn(move || {
-        let mut count: i32 = 0;
-        while let Ok(tick) = receiver.recv() {
-            count += 3;
-            tracing::error!("got tick: {:#?}", tick);
-        }
-        assert!(count > 0);
+    let tws = MockTws::start().await?;
+    tws.on(Outgoing::ReqTickByTickData, |_| {
+        vec![Message::new(Incoming::TickByTick)
+            .field(200)
+            .field(3)
+            .fields(["1704205800", "137.1", "137.2", "300", "400", "0"])]
     });
-    let receiver = client.market_data_tracker.historical_ticks.clone();
-    thread::spawn(move || {
-        let mut count: i32 = 0;
-        while let Ok(tick) = receiver.recv() {
-            count += 3;
-            tracing::error!("got tick: {:#?}", tick);
-        }
-        assert!(count > 0);
-    });
-    let _ = client
+    let client = client::connect(tws.addr(), 10).await?;
+    let mut ticks = client
         .request_tick_by_tick_data(&TickByTickRequest {
-            req_id: 200,
-            contract,
-            tick_type: TickByTickType::BidAsk,
+            req_id:          200,
+            contract:        amd(),
+            tick_type:       TickByTickType::BidAsk,
             number_of_ticks: 200,
-            ignore_size: false,
+            ignore_size:     false,
         })
         .await?;
+    match ticks.next().await.unwrap()? {
+        Tick::TickByTickBidAsk(tick) => assert_eq!(200, tick.id),
+        tick => panic!("unexpected tick: {:?}", tick),
+    }
+    Ok(())
+}
 
-    tokio::time::sleep(std::time::Duration::from_secs(6)).await;
+#[tokio::test]
+async fn market_data_unsupported_by_server_version() -> Result<()> {
+    let tws = MockTws::start().await?;
+    // tick by tick data needs server version 137
+    tws.set_server_version(136);
+    let client = client::connect(tws.addr(), 10).await?;
+    let result = client
+        .request_tick_by_tick_data(&TickByTickRequest {
+            req_id:          200,
+            contract:        amd(),
+            tick_type:       TickByTickType::BidAsk,
+            number_of_ticks: 0,
+            ignore_size:     false,
+        })
+        .await;
+    assert!(matches!(result, Err(Error::Unsupported { .. })));
     Ok(())
 }
@@ -8,7 +8,7 @@ pub async fn main() -> Result<()> {
 
     tracing_subscriber::fmt::init();
 
-    let mut client = client::connect(get_client_addr(), 99).await?;
+    let client = client::connect(get_client_addr(), 99).await?;
 
 
     Ok(())